@@ -1,6 +1,6 @@
 //! Command-line argument parsing
 
-use crate::config::{AppConfig, RoutingRule};
+use crate::config::{AppConfig, RoutingRule, DEFAULT_DOCKER_SOCKET};
 use crate::error::ConfigError;
 use clap::Parser;
 
@@ -26,6 +26,48 @@ pub struct CliArgs {
     #[arg(long, help = "Path to Docker socket")]
     pub docker_socket: Option<String>,
 
+    /// Docker daemon address for the http and ssl connection methods
+    #[arg(long, help = "Docker daemon address, e.g. tcp://host:2376")]
+    pub docker_host: Option<String>,
+
+    /// CA certificate for the ssl connection method
+    #[arg(long, help = "Path to the CA certificate verifying the Docker daemon")]
+    pub docker_tls_ca: Option<String>,
+
+    /// Client certificate for the ssl connection method
+    #[arg(long, help = "Path to the client certificate for the Docker daemon")]
+    pub docker_tls_cert: Option<String>,
+
+    /// Client key for the ssl connection method
+    #[arg(long, help = "Path to the client certificate's private key")]
+    pub docker_tls_key: Option<String>,
+
+    /// Docker API version
+    #[arg(long, help = "Docker API version, e.g. 1.41 (negotiated if unset)")]
+    pub docker_api_version: Option<String>,
+
+    /// Docker API request timeout in seconds
+    #[arg(long, help = "Seconds a Docker API request may take")]
+    pub docker_timeout: Option<u64>,
+
+    /// Container runtime behind the socket (docker, podman)
+    #[arg(long, help = "Container runtime serving the Docker API")]
+    pub container_runtime: Option<String>,
+
+    /// Rootless Docker
+    #[arg(
+        long,
+        help = "Use a rootless Docker daemon: its socket under XDG_RUNTIME_DIR and rootlesskit's namespaces"
+    )]
+    pub docker_rootless: bool,
+
+    /// Server-side event filtering by label
+    #[arg(
+        long,
+        help = "Only stream Docker events of containers with the target or warp role label"
+    )]
+    pub docker_event_label_filter: bool,
+
     /// Warp container name pattern
     #[arg(
         long,
@@ -44,10 +86,117 @@ pub struct CliArgs {
     )]
     pub network_preference_label: Option<String>,
 
-    /// Routing rules in format "dest:proto:port_range"
+    /// Route attribute label prefix
+    #[arg(
+        long,
+        help = "Label prefix used to set route attributes (mtu, advmss, ...) on warp containers"
+    )]
+    pub route_attribute_label_prefix: Option<String>,
+
+    /// Route metric label name
+    #[arg(
+        long,
+        help = "Label name used to override the route metric on target containers"
+    )]
+    pub route_metric_label: Option<String>,
+
+    /// Warp selector label name
+    #[arg(
+        long,
+        help = "Label name holding a selector for the containers a warp container serves"
+    )]
+    pub warp_selector_label: Option<String>,
+
+    /// Warp role label name
+    #[arg(
+        long,
+        help = "Label name marking containers as warp containers regardless of their name"
+    )]
+    pub warp_role_label: Option<String>,
+
+    /// Route precedence policy (metric, override)
+    #[arg(
+        long,
+        help = "Whether existing kernel routes win by metric or are overridden by warp routes"
+    )]
+    pub route_precedence: Option<String>,
+
+    /// Metric for network routes
+    #[arg(long, help = "Metric used for network routes")]
+    pub route_metric_network: Option<u32>,
+
+    /// Metric for default routes
+    #[arg(long, help = "Metric used for default routes")]
+    pub route_metric_default: Option<u32>,
+
+    /// Metric for host routes
+    #[arg(long, help = "Metric used for host routes")]
+    pub route_metric_host: Option<u32>,
+
+    /// Refresh interval for hostname rules
+    #[arg(
+        long,
+        help = "Seconds between hostname rule lookups, whatever the DNS record TTL"
+    )]
+    pub hostname_refresh_interval: Option<u64>,
+
+    /// Number of answers kept per hostname rule
+    #[arg(
+        long,
+        help = "Number of recent lookups an address must be missing from before its route is removed"
+    )]
+    pub hostname_history_size: Option<usize>,
+
+    /// Paused warp policy (ignore, failover, kill-switch)
+    #[arg(
+        long,
+        help = "Whether the targets of a paused warp keep their routes, fail over or lose them"
+    )]
+    pub paused_warp_policy: Option<String>,
+
+    /// Fan-out concurrency
+    #[arg(
+        long,
+        help = "Number of targets handled at a time when a warp starts or on startup"
+    )]
+    pub fanout_concurrency: Option<usize>,
+
+    /// Fan-out target timeout
+    #[arg(
+        long,
+        help = "Seconds a single target may take when a warp starts or on startup"
+    )]
+    pub fanout_target_timeout: Option<u64>,
+
+    /// Inventory resync interval
+    #[arg(long, help = "Seconds between full resyncs of the container inventory")]
+    pub inventory_resync_interval: Option<u64>,
+
+    /// Enable forwarding and masquerading in warp containers
+    #[arg(
+        long,
+        help = "Enable IP forwarding and masquerading in warp containers that have routed targets"
+    )]
+    pub warp_forwarding: bool,
+
+    /// Warp egress interface
+    #[arg(
+        long,
+        help = "Interface of the warp container that forwarded traffic is masqueraded out of"
+    )]
+    pub warp_egress_interface: Option<String>,
+
+    /// Allow modifying host-network containers
+    #[arg(
+        long,
+        help = "Allow modifying containers in the host's network namespace (dangerous: routes apply to the host)"
+    )]
+    pub dangerously_allow_host_network: bool,
+
+    /// Routing rules in format "dest:proto:port_range:attributes"
     #[arg(
         long,
-        help = "Routing rules in format 'dest1:proto1:port1-port2,dest2:proto2:port3-port4'"
+        help = "Routing rules in format 'dest1:proto1:port1-port2:key=value;key=value,dest2:proto2:port3-port4'"
     )]
     pub routing_rules: Option<String>,
 
@@ -82,12 +231,116 @@ impl CliArgs {
             base_config.network_preference_label = label.clone();
         }
 
+        if let Some(ref prefix) = self.route_attribute_label_prefix {
+            base_config.route_attribute_label_prefix = prefix.clone();
+        }
+
+        if let Some(ref label) = self.route_metric_label {
+            base_config.route_metric_label = label.clone();
+        }
+
+        if let Some(ref label) = self.warp_selector_label {
+            base_config.warp_selector_label = label.clone();
+        }
+
+        if let Some(ref label) = self.warp_role_label {
+            base_config.warp_role_label = label.clone();
+        }
+
+        if let Some(ref precedence) = self.route_precedence {
+            base_config.route_precedence = precedence.clone();
+        }
+
+        if let Some(metric) = self.route_metric_network {
+            base_config.route_metric_network = metric;
+        }
+
+        if let Some(metric) = self.route_metric_default {
+            base_config.route_metric_default = metric;
+        }
+
+        if let Some(metric) = self.route_metric_host {
+            base_config.route_metric_host = metric;
+        }
+
+        if self.warp_forwarding {
+            base_config.warp_forwarding = true;
+        }
+
+        if let Some(ref interface) = self.warp_egress_interface {
+            base_config.warp_egress_interface = interface.clone();
+        }
+
+        if self.dangerously_allow_host_network {
+            base_config.dangerously_allow_host_network = true;
+        }
+
+        if let Some(interval) = self.hostname_refresh_interval {
+            base_config.hostname_refresh_interval = interval;
+        }
+
+        if let Some(size) = self.hostname_history_size {
+            base_config.hostname_history_size = size;
+        }
+
+        if let Some(ref policy) = self.paused_warp_policy {
+            base_config.paused_warp_policy = policy.clone();
+        }
+
+        if let Some(concurrency) = self.fanout_concurrency {
+            base_config.fanout_concurrency = concurrency;
+        }
+
+        if let Some(timeout) = self.fanout_target_timeout {
+            base_config.fanout_target_timeout = timeout;
+        }
+
+        if let Some(interval) = self.inventory_resync_interval {
+            base_config.inventory_resync_interval = interval;
+        }
+
         if let Some(ref level) = self.log_level {
             base_config.log_level = level.clone();
         }
 
         if let Some(ref socket) = self.docker_socket {
-            base_config.docker_socket = socket.clone();
+            base_config.docker_socket = Some(socket.clone());
+        }
+
+        if let Some(ref host) = self.docker_host {
+            base_config.docker_host = Some(host.clone());
+        }
+
+        if let Some(ref ca) = self.docker_tls_ca {
+            base_config.docker_tls_ca = Some(ca.clone());
+        }
+
+        if let Some(ref cert) = self.docker_tls_cert {
+            base_config.docker_tls_cert = Some(cert.clone());
+        }
+
+        if let Some(ref key) = self.docker_tls_key {
+            base_config.docker_tls_key = Some(key.clone());
+        }
+
+        if let Some(ref version) = self.docker_api_version {
+            base_config.docker_api_version = Some(version.clone());
+        }
+
+        if let Some(timeout) = self.docker_timeout {
+            base_config.docker_timeout = timeout;
+        }
+
+        if let Some(ref runtime) = self.container_runtime {
+            base_config.container_runtime = runtime.clone();
+        }
+
+        if self.docker_rootless {
+            base_config.docker_rootless = true;
+        }
+
+        if self.docker_event_label_filter {
+            base_config.docker_event_label_filter = true;
         }
 
         if let Some(ref rules_str) = self.routing_rules {
@@ -99,7 +352,7 @@ impl CliArgs {
 }
 
 /// Parse routing rules from CLI argument string
-/// Same format as environment variables: "dest1:proto1:port1-port2,dest2:proto2:port3-port4"
+/// Same format as environment variables: "dest1:proto1:port1-port2:attrs1,dest2:proto2:port3-port4"
 fn parse_routing_rules_from_cli(rules_str: &str) -> Result<Vec<RoutingRule>, ConfigError> {
     // Reuse the same parsing logic as environment variables
     crate::config::env::parse_routing_rules_from_env(rules_str)
@@ -136,13 +389,111 @@ pub fn print_default_config() {
         default_config.network_preference_label
     );
     println!();
+    println!("# Label prefix used to set route attributes on warp containers,");
+    println!("# e.g. <prefix>mtu=1280, <prefix>advmss, <prefix>scope, <prefix>onlink,");
+    println!("# <prefix>prefsrc, <prefix>protocol");
+    println!(
+        "route_attribute_label_prefix = \"{}\"",
+        default_config.route_attribute_label_prefix
+    );
+    println!();
+    println!("# Label name used to override the route metric on target containers");
+    println!(
+        "route_metric_label = \"{}\"",
+        default_config.route_metric_label
+    );
+    println!();
+    println!("# Label name holding a selector (e.g. app=frontend,env=prod) on warp");
+    println!("# containers; matching containers without a target label are routed through it");
+    println!(
+        "warp_selector_label = \"{}\"",
+        default_config.warp_selector_label
+    );
+    println!("# Label name marking containers as warp containers, whatever their name");
+    println!("warp_role_label = \"{}\"", default_config.warp_role_label);
+    println!();
+    println!("# Allow modifying containers that run in the host's network namespace.");
+    println!("# Dangerous: a default route via a warp would apply to the whole host");
+    println!(
+        "dangerously_allow_host_network = {}",
+        default_config.dangerously_allow_host_network
+    );
+    println!();
     println!("[logging]");
     println!("# Log level: trace, debug, info, warn, error");
     println!("level = \"{}\"", default_config.log_level);
     println!();
     println!("[docker]");
-    println!("# Path to Docker socket");
-    println!("socket = \"{}\"", default_config.docker_socket);
+    println!("# Path to Docker socket; when unset, DOCKER_HOST or the current docker");
+    println!("# context is used if there is one, and this socket otherwise");
+    println!("# socket = \"{}\"", DEFAULT_DOCKER_SOCKET);
+    println!("# Docker API version, e.g. \"1.41\"; negotiated with the daemon when unset");
+    println!("# api_version = \"1.41\"");
+    println!("# Seconds a Docker API request may take");
+    println!("timeout = {}", default_config.docker_timeout);
+    println!("# Container runtime behind the socket: docker or podman");
+    println!("runtime = \"{}\"", default_config.container_runtime);
+    println!("# Rootless Docker: use $XDG_RUNTIME_DIR/docker.sock unless socket is changed,");
+    println!("# and route from inside rootlesskit's user and network namespaces");
+    println!("rootless = {}", default_config.docker_rootless);
+    println!("# Only stream events of containers with the target or warp role label; warps");
+    println!("# matched by name only, and containers served through selectors, are missed");
+    println!(
+        "event_label_filter = {}",
+        default_config.docker_event_label_filter
+    );
+    println!("# Daemon address, CA and client certificate for the ssl connection method");
+    println!("# host = \"tcp://docker.example.com:2376\"");
+    println!("# tls_ca = \"/etc/docker-network-warp/ca.pem\"");
+    println!("# tls_cert = \"/etc/docker-network-warp/cert.pem\"");
+    println!("# tls_key = \"/etc/docker-network-warp/key.pem\"");
+    println!();
+    println!("[routing]");
+    println!("# Route precedence: 'metric' keeps existing kernel routes and lets the lowest");
+    println!("# metric win; 'override' replaces existing routes with the same destination");
+    println!("# and metric (the default metric must then be 0 to replace Docker's)");
+    println!("precedence = \"{}\"", default_config.route_precedence);
+    println!("network_metric = {}", default_config.route_metric_network);
+    println!("default_metric = {}", default_config.route_metric_default);
+    println!("host_metric = {}", default_config.route_metric_host);
+    println!("# Hostname rules are looked up again every hostname_refresh_interval seconds,");
+    println!("# whatever the TTL of their DNS records, and an address keeps its route until");
+    println!("# it is missing from the last hostname_history_size answers");
+    println!(
+        "hostname_refresh_interval = {}",
+        default_config.hostname_refresh_interval
+    );
+    println!(
+        "hostname_history_size = {}",
+        default_config.hostname_history_size
+    );
+    println!("# Targets of a paused warp: 'ignore' keeps their routes, 'failover' moves");
+    println!("# them to another running warp serving them if there is one, 'kill-switch'");
+    println!("# drops their traffic until the warp is unpaused");
+    println!("paused_warp = \"{}\"", default_config.paused_warp_policy);
+    println!();
+    println!("[fanout]");
+    println!("# Targets handled at a time when a warp starts or on startup reconciliation,");
+    println!("# and seconds each target may take");
+    println!("concurrency = {}", default_config.fanout_concurrency);
+    println!("target_timeout = {}", default_config.fanout_target_timeout);
+    println!();
+    println!("[inventory]");
+    println!("# Seconds between full resyncs of the event-driven container inventory,");
+    println!("# repairing it after missed events");
+    println!(
+        "resync_interval = {}",
+        default_config.inventory_resync_interval
+    );
+    println!();
+    println!("[forwarding]");
+    println!("# Enable IP forwarding in warp containers and masquerade traffic of their");
+    println!("# targets out of egress_interface (requires nft in the host's PATH)");
+    println!("enabled = {}", default_config.warp_forwarding);
+    println!(
+        "egress_interface = \"{}\"",
+        default_config.warp_egress_interface
+    );
     println!();
     println!(
         "# Routing rules - traffic matching these rules will be routed through warp containers"
@@ -156,6 +507,7 @@ pub fn print_default_config() {
         if let Some((start, end)) = rule.port_range {
             println!("port_range = [{}, {}]", start, end);
         }
+        println!("# Optional: metric, mtu, advmss, scope, onlink, prefsrc, route_protocol");
         if i < default_config.routing_rules.len() - 1 {
             println!();
         }
@@ -179,12 +531,53 @@ mod tests {
             "http",
             "--docker-socket",
             "/custom/docker.sock",
+            "--docker-host",
+            "tcp://docker.example.com:2376",
+            "--docker-tls-ca",
+            "/certs/ca.pem",
+            "--docker-api-version",
+            "1.41",
+            "--docker-timeout",
+            "60",
             "--warp-container-pattern",
             "proxy-*",
             "--target-container-label",
             "app.proxy.target",
             "--network-preference-label",
             "app.proxy.network",
+            "--route-attribute-label-prefix",
+            "app.proxy.route.",
+            "--route-metric-label",
+            "app.proxy.metric",
+            "--warp-selector-label",
+            "app.proxy.serve",
+            "--warp-role-label",
+            "app.proxy.role",
+            "--route-precedence",
+            "override",
+            "--route-metric-network",
+            "10",
+            "--route-metric-default",
+            "0",
+            "--route-metric-host",
+            "5",
+            "--hostname-refresh-interval",
+            "60",
+            "--hostname-history-size",
+            "4",
+            "--paused-warp-policy",
+            "failover",
+            "--fanout-concurrency",
+            "8",
+            "--fanout-target-timeout",
+            "15",
+            "--inventory-resync-interval",
+            "120",
+            "--warp-forwarding",
+            "--docker-rootless",
+            "--docker-event-label-filter",
+            "--warp-egress-interface",
+            "wg0",
             "--routing-rules",
             "10.0.0.0/8:tcp:80-443,192.168.0.0/16::53-53",
             "--validate-config",
@@ -198,6 +591,14 @@ mod tests {
         assert_eq!(args.log_level, Some("debug".to_string()));
         assert_eq!(args.docker_connection_method, Some("http".to_string()));
         assert_eq!(args.docker_socket, Some("/custom/docker.sock".to_string()));
+        assert_eq!(
+            args.docker_host,
+            Some("tcp://docker.example.com:2376".to_string())
+        );
+        assert_eq!(args.docker_tls_ca, Some("/certs/ca.pem".to_string()));
+        assert_eq!(args.docker_tls_cert, None);
+        assert_eq!(args.docker_api_version, Some("1.41".to_string()));
+        assert_eq!(args.docker_timeout, Some(60));
         assert_eq!(args.warp_container_pattern, Some("proxy-*".to_string()));
         assert_eq!(
             args.target_container_label,
@@ -207,6 +608,33 @@ mod tests {
             args.network_preference_label,
             Some("app.proxy.network".to_string())
         );
+        assert_eq!(
+            args.route_attribute_label_prefix,
+            Some("app.proxy.route.".to_string())
+        );
+        assert_eq!(
+            args.route_metric_label,
+            Some("app.proxy.metric".to_string())
+        );
+        assert_eq!(
+            args.warp_selector_label,
+            Some("app.proxy.serve".to_string())
+        );
+        assert_eq!(args.warp_role_label, Some("app.proxy.role".to_string()));
+        assert_eq!(args.route_precedence, Some("override".to_string()));
+        assert_eq!(args.route_metric_network, Some(10));
+        assert_eq!(args.route_metric_default, Some(0));
+        assert_eq!(args.route_metric_host, Some(5));
+        assert_eq!(args.hostname_refresh_interval, Some(60));
+        assert_eq!(args.hostname_history_size, Some(4));
+        assert_eq!(args.paused_warp_policy, Some("failover".to_string()));
+        assert_eq!(args.fanout_concurrency, Some(8));
+        assert_eq!(args.fanout_target_timeout, Some(15));
+        assert_eq!(args.inventory_resync_interval, Some(120));
+        assert!(args.warp_forwarding);
+        assert!(args.docker_rootless);
+        assert!(args.docker_event_label_filter);
+        assert_eq!(args.warp_egress_interface, Some("wg0".to_string()));
         assert_eq!(
             args.routing_rules,
             Some("10.0.0.0/8:tcp:80-443,192.168.0.0/16::53-53".to_string())
@@ -226,6 +654,10 @@ mod tests {
         assert_eq!(args.warp_container_pattern, None);
         assert_eq!(args.target_container_label, None);
         assert_eq!(args.network_preference_label, None);
+        assert_eq!(args.route_attribute_label_prefix, None);
+        assert_eq!(args.route_metric_label, None);
+        assert_eq!(args.route_precedence, None);
+        assert_eq!(args.route_metric_default, None);
         assert_eq!(args.routing_rules, None);
         assert!(!args.validate_config);
         assert!(!args.print_default_config);
@@ -238,10 +670,36 @@ mod tests {
             log_level: Some("trace".to_string()),
             docker_connection_method: Some("ssl".to_string()),
             docker_socket: Some("/test/docker.sock".to_string()),
+            docker_host: Some("tcp://docker.example.com:2376".to_string()),
+            docker_tls_ca: Some("/test/ca.pem".to_string()),
+            docker_tls_cert: Some("/test/cert.pem".to_string()),
+            docker_tls_key: Some("/test/key.pem".to_string()),
+            docker_api_version: Some("1.43".to_string()),
+            docker_timeout: Some(15),
+            container_runtime: Some("podman".to_string()),
+            docker_rootless: true,
+            docker_event_label_filter: true,
             warp_container_pattern: Some("test-*".to_string()),
             target_container_label: Some("test.label".to_string()),
             network_preference_label: Some("test.network".to_string()),
-            routing_rules: Some("172.16.0.0/12:udp:53-53".to_string()),
+            route_attribute_label_prefix: Some("test.route.".to_string()),
+            route_metric_label: Some("test.metric".to_string()),
+            warp_selector_label: Some("test.serve".to_string()),
+            warp_role_label: Some("test.role".to_string()),
+            route_precedence: Some("override".to_string()),
+            route_metric_network: Some(10),
+            route_metric_default: Some(0),
+            route_metric_host: Some(5),
+            hostname_refresh_interval: Some(30),
+            hostname_history_size: Some(2),
+            paused_warp_policy: Some("kill-switch".to_string()),
+            fanout_concurrency: Some(2),
+            fanout_target_timeout: Some(5),
+            inventory_resync_interval: Some(90),
+            warp_forwarding: true,
+            warp_egress_interface: Some("wg0".to_string()),
+            dangerously_allow_host_network: true,
+            routing_rules: Some("172.16.0.0/12:udp:53-53:mtu=1400".to_string()),
             validate_config: false,
             print_default_config: false,
         };
@@ -251,15 +709,45 @@ mod tests {
 
         assert_eq!(config.docker_connection_method, "ssl");
         assert_eq!(config.log_level, "trace");
-        assert_eq!(config.docker_socket, "/test/docker.sock");
+        assert_eq!(config.docker_socket.as_deref(), Some("/test/docker.sock"));
+        assert_eq!(config.container_runtime, "podman");
+        assert!(config.docker_rootless);
+        assert!(config.docker_event_label_filter);
+        assert_eq!(
+            config.docker_host,
+            Some("tcp://docker.example.com:2376".to_string())
+        );
+        assert_eq!(config.docker_tls_ca, Some("/test/ca.pem".to_string()));
+        assert_eq!(config.docker_tls_cert, Some("/test/cert.pem".to_string()));
+        assert_eq!(config.docker_tls_key, Some("/test/key.pem".to_string()));
+        assert_eq!(config.docker_api_version, Some("1.43".to_string()));
+        assert_eq!(config.docker_timeout, 15);
         assert_eq!(config.warp_container_pattern, "test-*");
         assert_eq!(config.target_container_label, "test.label");
         assert_eq!(config.network_preference_label, "test.network");
+        assert_eq!(config.route_attribute_label_prefix, "test.route.");
+        assert_eq!(config.route_metric_label, "test.metric");
+        assert_eq!(config.warp_selector_label, "test.serve");
+        assert_eq!(config.warp_role_label, "test.role");
+        assert_eq!(config.route_precedence, "override");
+        assert_eq!(config.route_metric_network, 10);
+        assert_eq!(config.route_metric_default, 0);
+        assert_eq!(config.route_metric_host, 5);
+        assert_eq!(config.hostname_refresh_interval, 30);
+        assert_eq!(config.hostname_history_size, 2);
+        assert_eq!(config.paused_warp_policy, "kill-switch");
+        assert_eq!(config.fanout_concurrency, 2);
+        assert_eq!(config.fanout_target_timeout, 5);
+        assert_eq!(config.inventory_resync_interval, 90);
+        assert!(config.warp_forwarding);
+        assert_eq!(config.warp_egress_interface, "wg0");
+        assert!(config.dangerously_allow_host_network);
 
         assert_eq!(config.routing_rules.len(), 1);
         assert_eq!(config.routing_rules[0].destination, "172.16.0.0/12");
         assert_eq!(config.routing_rules[0].protocol, Some("udp".to_string()));
         assert_eq!(config.routing_rules[0].port_range, Some((53, 53)));
+        assert_eq!(config.routing_rules[0].mtu, Some(1400));
     }
 
     #[test]
@@ -269,9 +757,35 @@ mod tests {
             log_level: None,
             docker_connection_method: None,
             docker_socket: None,
+            docker_host: None,
+            docker_tls_ca: None,
+            docker_tls_cert: None,
+            docker_tls_key: None,
+            docker_api_version: None,
+            docker_timeout: None,
+            container_runtime: None,
+            docker_rootless: false,
+            docker_event_label_filter: false,
             warp_container_pattern: None,
             target_container_label: None,
             network_preference_label: None,
+            route_attribute_label_prefix: None,
+            route_metric_label: None,
+            warp_selector_label: None,
+            warp_role_label: None,
+            route_precedence: None,
+            route_metric_network: None,
+            route_metric_default: None,
+            route_metric_host: None,
+            hostname_refresh_interval: None,
+            hostname_history_size: None,
+            paused_warp_policy: None,
+            fanout_concurrency: None,
+            fanout_target_timeout: None,
+            inventory_resync_interval: None,
+            warp_forwarding: false,
+            warp_egress_interface: None,
+            dangerously_allow_host_network: false,
             routing_rules: None,
             validate_config: false,
             print_default_config: false,
@@ -288,6 +802,12 @@ mod tests {
         );
         assert_eq!(config.log_level, original_config.log_level);
         assert_eq!(config.docker_socket, original_config.docker_socket);
+        assert_eq!(config.container_runtime, original_config.container_runtime);
+        assert!(!config.docker_rootless);
+        assert!(!config.docker_event_label_filter);
+        assert_eq!(config.docker_host, None);
+        assert_eq!(config.docker_tls_key, None);
+        assert_eq!(config.docker_timeout, original_config.docker_timeout);
         assert_eq!(
             config.warp_container_pattern,
             original_config.warp_container_pattern
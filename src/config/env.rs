@@ -2,7 +2,9 @@
 
 use crate::config::{AppConfig, RoutingRule};
 use crate::error::ConfigError;
+use crate::routing::resolver::is_hostname;
 use std::env;
+use std::str::FromStr;
 
 /// Environment variable prefix
 const ENV_PREFIX: &str = "DOCKER_NETWORK_WARP_";
@@ -26,12 +28,116 @@ pub fn apply_env_config(mut base_config: AppConfig) -> Result<AppConfig, ConfigE
         base_config.network_preference_label = label;
     }
 
+    if let Ok(prefix) = env::var(format!("{}ROUTE_ATTRIBUTE_LABEL_PREFIX", ENV_PREFIX)) {
+        base_config.route_attribute_label_prefix = prefix;
+    }
+
+    if let Ok(label) = env::var(format!("{}ROUTE_METRIC_LABEL", ENV_PREFIX)) {
+        base_config.route_metric_label = label;
+    }
+
+    if let Ok(label) = env::var(format!("{}WARP_SELECTOR_LABEL", ENV_PREFIX)) {
+        base_config.warp_selector_label = label;
+    }
+
+    if let Ok(label) = env::var(format!("{}WARP_ROLE_LABEL", ENV_PREFIX)) {
+        base_config.warp_role_label = label;
+    }
+
+    if let Ok(precedence) = env::var(format!("{}ROUTE_PRECEDENCE", ENV_PREFIX)) {
+        base_config.route_precedence = precedence;
+    }
+
+    if let Some(metric) = parse_integer_env_var("ROUTE_METRIC_NETWORK")? {
+        base_config.route_metric_network = metric;
+    }
+
+    if let Some(metric) = parse_integer_env_var("ROUTE_METRIC_DEFAULT")? {
+        base_config.route_metric_default = metric;
+    }
+
+    if let Some(metric) = parse_integer_env_var("ROUTE_METRIC_HOST")? {
+        base_config.route_metric_host = metric;
+    }
+
+    if let Some(interval) = parse_integer_env_var("HOSTNAME_REFRESH_INTERVAL")? {
+        base_config.hostname_refresh_interval = interval;
+    }
+
+    if let Some(size) = parse_integer_env_var("HOSTNAME_HISTORY_SIZE")? {
+        base_config.hostname_history_size = size;
+    }
+
+    if let Ok(policy) = env::var(format!("{}PAUSED_WARP_POLICY", ENV_PREFIX)) {
+        base_config.paused_warp_policy = policy;
+    }
+
+    if let Some(concurrency) = parse_integer_env_var("FANOUT_CONCURRENCY")? {
+        base_config.fanout_concurrency = concurrency;
+    }
+
+    if let Some(timeout) = parse_integer_env_var("FANOUT_TARGET_TIMEOUT")? {
+        base_config.fanout_target_timeout = timeout;
+    }
+
+    if let Some(interval) = parse_integer_env_var("INVENTORY_RESYNC_INTERVAL")? {
+        base_config.inventory_resync_interval = interval;
+    }
+
+    if let Some(enabled) = parse_bool_env_var("WARP_FORWARDING")? {
+        base_config.warp_forwarding = enabled;
+    }
+
+    if let Ok(interface) = env::var(format!("{}WARP_EGRESS_INTERFACE", ENV_PREFIX)) {
+        base_config.warp_egress_interface = interface;
+    }
+
+    if let Some(allow) = parse_bool_env_var("DANGEROUSLY_ALLOW_HOST_NETWORK")? {
+        base_config.dangerously_allow_host_network = allow;
+    }
+
     if let Ok(level) = env::var(format!("{}LOG_LEVEL", ENV_PREFIX)) {
         base_config.log_level = level;
     }
 
     if let Ok(socket) = env::var(format!("{}DOCKER_SOCKET", ENV_PREFIX)) {
-        base_config.docker_socket = socket;
+        base_config.docker_socket = Some(socket);
+    }
+
+    if let Ok(host) = env::var(format!("{}DOCKER_HOST", ENV_PREFIX)) {
+        base_config.docker_host = Some(host);
+    }
+
+    if let Ok(ca) = env::var(format!("{}DOCKER_TLS_CA", ENV_PREFIX)) {
+        base_config.docker_tls_ca = Some(ca);
+    }
+
+    if let Ok(cert) = env::var(format!("{}DOCKER_TLS_CERT", ENV_PREFIX)) {
+        base_config.docker_tls_cert = Some(cert);
+    }
+
+    if let Ok(key) = env::var(format!("{}DOCKER_TLS_KEY", ENV_PREFIX)) {
+        base_config.docker_tls_key = Some(key);
+    }
+
+    if let Ok(version) = env::var(format!("{}DOCKER_API_VERSION", ENV_PREFIX)) {
+        base_config.docker_api_version = Some(version);
+    }
+
+    if let Some(timeout) = parse_integer_env_var("DOCKER_TIMEOUT")? {
+        base_config.docker_timeout = timeout;
+    }
+
+    if let Ok(runtime) = env::var(format!("{}CONTAINER_RUNTIME", ENV_PREFIX)) {
+        base_config.container_runtime = runtime;
+    }
+
+    if let Some(rootless) = parse_bool_env_var("DOCKER_ROOTLESS")? {
+        base_config.docker_rootless = rootless;
+    }
+
+    if let Some(filter) = parse_bool_env_var("DOCKER_EVENT_LABEL_FILTER")? {
+        base_config.docker_event_label_filter = filter;
     }
 
     // Parse routing rules from environment variables
@@ -43,13 +149,45 @@ pub fn apply_env_config(mut base_config: AppConfig) -> Result<AppConfig, ConfigE
     Ok(base_config)
 }
 
+/// Parse an optional unsigned integer environment variable
+fn parse_integer_env_var<T: FromStr>(name: &str) -> Result<Option<T>, ConfigError> {
+    match env::var(format!("{}{}", ENV_PREFIX, name)) {
+        Ok(value) => value.trim().parse::<T>().map(Some).map_err(|_| {
+            ConfigError::InvalidFormat(format!(
+                "Invalid value for {}{}: '{}'. Expected a non-negative integer",
+                ENV_PREFIX, name, value
+            ))
+        }),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Parse an optional boolean environment variable
+fn parse_bool_env_var(name: &str) -> Result<Option<bool>, ConfigError> {
+    match env::var(format!("{}{}", ENV_PREFIX, name)) {
+        Ok(value) => match value.trim().to_lowercase().as_str() {
+            "true" | "1" | "yes" => Ok(Some(true)),
+            "false" | "0" | "no" => Ok(Some(false)),
+            _ => Err(ConfigError::InvalidFormat(format!(
+                "Invalid value for {}{}: '{}'. Expected true or false",
+                ENV_PREFIX, name, value
+            ))),
+        },
+        Err(_) => Ok(None),
+    }
+}
+
 /// Parse routing rules from environment variable string
-/// Format: "dest1:proto1:port1-port2,dest2:proto2:port3-port4"
+/// Format: "dest1:proto1:port1-port2:attrs1,dest2:proto2:port3-port4:attrs2"
+/// where attrs is a ';'-separated list of route attributes (metric, mtu, advmss,
+/// scope, onlink, prefsrc, route_protocol) in `key=value` form
 /// Examples:
 /// - "0.0.0.0/0" (destination only)
 /// - "10.0.0.0/8:tcp" (destination and protocol)
 /// - "192.168.0.0/16:tcp:80-443" (destination, protocol, and port range)
 /// - "172.16.0.0/12::53-53" (destination and port range, no protocol)
+/// - "api.example.com:tcp" (hostname destination, resolved to host routes)
+/// - "0.0.0.0/0:::mtu=1280;advmss=1240;onlink=true" (destination and route attributes)
 pub fn parse_routing_rules_from_env(rules_str: &str) -> Result<Vec<RoutingRule>, ConfigError> {
     if rules_str.trim().is_empty() {
         return Ok(vec![]);
@@ -63,20 +201,21 @@ pub fn parse_routing_rules_from_env(rules_str: &str) -> Result<Vec<RoutingRule>,
             continue;
         }
 
-        let parts: Vec<&str> = rule_str.split(':').collect();
+        // Split at most 4 ways so the attribute segment is kept intact
+        let parts: Vec<&str> = rule_str.splitn(4, ':').collect();
 
         if parts.is_empty() || parts[0].trim().is_empty() {
             return Err(ConfigError::InvalidFormat(
-                format!("Invalid routing rule format: '{}'. Expected format: 'destination[:protocol[:port_start-port_end]]'", rule_str)
+                format!("Invalid routing rule format: '{}'. Expected format: 'destination[:protocol[:port_start-port_end[:attributes]]]'", rule_str)
             ));
         }
 
         let destination = parts[0].trim().to_string();
 
-        // Validate CIDR format
-        if !destination.contains('/') {
+        // Validate CIDR or hostname format
+        if !destination.contains('/') && !is_hostname(&destination) {
             return Err(ConfigError::InvalidFormat(
-                format!("Invalid destination '{}' in routing rule. Must be in CIDR format (e.g., '10.0.0.0/8')", destination)
+                format!("Invalid destination '{}' in routing rule. Must be in CIDR format (e.g., '10.0.0.0/8') or a hostname", destination)
             ));
         }
 
@@ -92,16 +231,72 @@ pub fn parse_routing_rules_from_env(rules_str: &str) -> Result<Vec<RoutingRule>,
             None
         };
 
-        rules.push(RoutingRule {
+        let mut rule = RoutingRule {
             destination,
             protocol,
             port_range,
-        });
+            ..Default::default()
+        };
+
+        if parts.len() > 3 {
+            for attribute in parts[3].split(';') {
+                let attribute = attribute.trim();
+                if attribute.is_empty() {
+                    continue;
+                }
+                let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+                apply_route_attribute(&mut rule, key.trim(), value.trim())?;
+            }
+        }
+
+        rules.push(rule);
     }
 
     Ok(rules)
 }
 
+/// Apply a single `key=value` route attribute to a routing rule
+fn apply_route_attribute(
+    rule: &mut RoutingRule,
+    key: &str,
+    value: &str,
+) -> Result<(), ConfigError> {
+    let parse_u32 = |value: &str| {
+        value
+            .parse::<u32>()
+            .map_err(|_| ConfigError::InvalidFormat(format!("Invalid {} value: '{}'", key, value)))
+    };
+
+    match key.to_lowercase().as_str() {
+        "metric" => rule.metric = Some(parse_u32(value)?),
+        "mtu" => rule.mtu = Some(parse_u32(value)?),
+        "advmss" => rule.advmss = Some(parse_u32(value)?),
+        "scope" => rule.scope = Some(value.to_string()),
+        "onlink" => {
+            rule.onlink = Some(match value.to_lowercase().as_str() {
+                "" | "true" | "yes" | "1" => true,
+                "false" | "no" | "0" => false,
+                _ => {
+                    return Err(ConfigError::InvalidFormat(format!(
+                        "Invalid onlink value: '{}'",
+                        value
+                    )))
+                }
+            })
+        }
+        "prefsrc" | "src" => rule.prefsrc = Some(value.to_string()),
+        "route_protocol" | "proto" => rule.route_protocol = Some(value.to_string()),
+        _ => {
+            return Err(ConfigError::InvalidFormat(format!(
+                "Unknown route attribute '{}' in routing rule. Expected one of: metric, mtu, advmss, scope, onlink, prefsrc, route_protocol",
+                key
+            )))
+        }
+    }
+
+    Ok(())
+}
+
 /// Parse port range from string format "start-end" or "port"
 fn parse_port_range(port_str: &str) -> Result<(u16, u16), ConfigError> {
     if port_str.contains('-') {
@@ -155,8 +350,40 @@ mod tests {
             "DOCKER_NETWORK_WARP_NETWORK_PREFERENCE_LABEL",
             "app.proxy.network",
         );
+        env::set_var(
+            "DOCKER_NETWORK_WARP_ROUTE_ATTRIBUTE_LABEL_PREFIX",
+            "app.proxy.route.",
+        );
+        env::set_var("DOCKER_NETWORK_WARP_ROUTE_METRIC_LABEL", "app.proxy.metric");
+        env::set_var("DOCKER_NETWORK_WARP_WARP_SELECTOR_LABEL", "app.proxy.serve");
+        env::set_var("DOCKER_NETWORK_WARP_WARP_ROLE_LABEL", "app.proxy.role");
+        env::set_var("DOCKER_NETWORK_WARP_ROUTE_PRECEDENCE", "override");
+        env::set_var("DOCKER_NETWORK_WARP_ROUTE_METRIC_NETWORK", "10");
+        env::set_var("DOCKER_NETWORK_WARP_ROUTE_METRIC_DEFAULT", "0");
+        env::set_var("DOCKER_NETWORK_WARP_ROUTE_METRIC_HOST", "5");
+        env::set_var("DOCKER_NETWORK_WARP_HOSTNAME_REFRESH_INTERVAL", "60");
+        env::set_var("DOCKER_NETWORK_WARP_HOSTNAME_HISTORY_SIZE", "5");
+        env::set_var("DOCKER_NETWORK_WARP_PAUSED_WARP_POLICY", "failover");
+        env::set_var("DOCKER_NETWORK_WARP_FANOUT_CONCURRENCY", "4");
+        env::set_var("DOCKER_NETWORK_WARP_FANOUT_TARGET_TIMEOUT", "10");
+        env::set_var("DOCKER_NETWORK_WARP_INVENTORY_RESYNC_INTERVAL", "60");
+        env::set_var("DOCKER_NETWORK_WARP_WARP_FORWARDING", "true");
+        env::set_var("DOCKER_NETWORK_WARP_WARP_EGRESS_INTERFACE", "wg0");
+        env::set_var("DOCKER_NETWORK_WARP_DANGEROUSLY_ALLOW_HOST_NETWORK", "yes");
         env::set_var("DOCKER_NETWORK_WARP_LOG_LEVEL", "debug");
         env::set_var("DOCKER_NETWORK_WARP_DOCKER_SOCKET", "/custom/docker.sock");
+        env::set_var(
+            "DOCKER_NETWORK_WARP_DOCKER_HOST",
+            "tcp://docker.example.com:2376",
+        );
+        env::set_var("DOCKER_NETWORK_WARP_DOCKER_TLS_CA", "/certs/ca.pem");
+        env::set_var("DOCKER_NETWORK_WARP_DOCKER_TLS_CERT", "/certs/cert.pem");
+        env::set_var("DOCKER_NETWORK_WARP_DOCKER_TLS_KEY", "/certs/key.pem");
+        env::set_var("DOCKER_NETWORK_WARP_DOCKER_API_VERSION", "1.41");
+        env::set_var("DOCKER_NETWORK_WARP_DOCKER_TIMEOUT", "60");
+        env::set_var("DOCKER_NETWORK_WARP_DOCKER_ROOTLESS", "true");
+        env::set_var("DOCKER_NETWORK_WARP_DOCKER_EVENT_LABEL_FILTER", "true");
+        env::set_var("DOCKER_NETWORK_WARP_CONTAINER_RUNTIME", "podman");
         env::set_var(
             "DOCKER_NETWORK_WARP_ROUTING_RULES",
             "10.0.0.0/8:tcp:80-443,192.168.0.0/16::53-53,172.16.0.0/12",
@@ -169,8 +396,34 @@ mod tests {
         env::remove_var("DOCKER_NETWORK_WARP_WARP_CONTAINER_PATTERN");
         env::remove_var("DOCKER_NETWORK_WARP_TARGET_CONTAINER_LABEL");
         env::remove_var("DOCKER_NETWORK_WARP_NETWORK_PREFERENCE_LABEL");
+        env::remove_var("DOCKER_NETWORK_WARP_ROUTE_ATTRIBUTE_LABEL_PREFIX");
+        env::remove_var("DOCKER_NETWORK_WARP_ROUTE_METRIC_LABEL");
+        env::remove_var("DOCKER_NETWORK_WARP_WARP_SELECTOR_LABEL");
+        env::remove_var("DOCKER_NETWORK_WARP_WARP_ROLE_LABEL");
+        env::remove_var("DOCKER_NETWORK_WARP_ROUTE_PRECEDENCE");
+        env::remove_var("DOCKER_NETWORK_WARP_ROUTE_METRIC_NETWORK");
+        env::remove_var("DOCKER_NETWORK_WARP_ROUTE_METRIC_DEFAULT");
+        env::remove_var("DOCKER_NETWORK_WARP_ROUTE_METRIC_HOST");
+        env::remove_var("DOCKER_NETWORK_WARP_HOSTNAME_REFRESH_INTERVAL");
+        env::remove_var("DOCKER_NETWORK_WARP_HOSTNAME_HISTORY_SIZE");
+        env::remove_var("DOCKER_NETWORK_WARP_PAUSED_WARP_POLICY");
+        env::remove_var("DOCKER_NETWORK_WARP_FANOUT_CONCURRENCY");
+        env::remove_var("DOCKER_NETWORK_WARP_FANOUT_TARGET_TIMEOUT");
+        env::remove_var("DOCKER_NETWORK_WARP_INVENTORY_RESYNC_INTERVAL");
+        env::remove_var("DOCKER_NETWORK_WARP_WARP_FORWARDING");
+        env::remove_var("DOCKER_NETWORK_WARP_WARP_EGRESS_INTERFACE");
+        env::remove_var("DOCKER_NETWORK_WARP_DANGEROUSLY_ALLOW_HOST_NETWORK");
         env::remove_var("DOCKER_NETWORK_WARP_LOG_LEVEL");
         env::remove_var("DOCKER_NETWORK_WARP_DOCKER_SOCKET");
+        env::remove_var("DOCKER_NETWORK_WARP_DOCKER_HOST");
+        env::remove_var("DOCKER_NETWORK_WARP_DOCKER_TLS_CA");
+        env::remove_var("DOCKER_NETWORK_WARP_DOCKER_TLS_CERT");
+        env::remove_var("DOCKER_NETWORK_WARP_DOCKER_TLS_KEY");
+        env::remove_var("DOCKER_NETWORK_WARP_DOCKER_API_VERSION");
+        env::remove_var("DOCKER_NETWORK_WARP_DOCKER_TIMEOUT");
+        env::remove_var("DOCKER_NETWORK_WARP_DOCKER_ROOTLESS");
+        env::remove_var("DOCKER_NETWORK_WARP_DOCKER_EVENT_LABEL_FILTER");
+        env::remove_var("DOCKER_NETWORK_WARP_CONTAINER_RUNTIME");
         env::remove_var("DOCKER_NETWORK_WARP_ROUTING_RULES");
     }
 
@@ -186,8 +439,37 @@ mod tests {
         assert_eq!(config.warp_container_pattern, "proxy-*");
         assert_eq!(config.target_container_label, "app.proxy.target");
         assert_eq!(config.network_preference_label, "app.proxy.network");
+        assert_eq!(config.route_attribute_label_prefix, "app.proxy.route.");
+        assert_eq!(config.route_metric_label, "app.proxy.metric");
+        assert_eq!(config.warp_selector_label, "app.proxy.serve");
+        assert_eq!(config.warp_role_label, "app.proxy.role");
+        assert_eq!(config.route_precedence, "override");
+        assert_eq!(config.route_metric_network, 10);
+        assert_eq!(config.route_metric_default, 0);
+        assert_eq!(config.route_metric_host, 5);
+        assert_eq!(config.hostname_refresh_interval, 60);
+        assert_eq!(config.hostname_history_size, 5);
+        assert_eq!(config.paused_warp_policy, "failover");
+        assert_eq!(config.fanout_concurrency, 4);
+        assert_eq!(config.fanout_target_timeout, 10);
+        assert_eq!(config.inventory_resync_interval, 60);
+        assert!(config.warp_forwarding);
+        assert_eq!(config.warp_egress_interface, "wg0");
+        assert!(config.dangerously_allow_host_network);
         assert_eq!(config.log_level, "debug");
-        assert_eq!(config.docker_socket, "/custom/docker.sock");
+        assert_eq!(config.docker_socket.as_deref(), Some("/custom/docker.sock"));
+        assert_eq!(
+            config.docker_host,
+            Some("tcp://docker.example.com:2376".to_string())
+        );
+        assert_eq!(config.docker_tls_ca, Some("/certs/ca.pem".to_string()));
+        assert_eq!(config.docker_tls_cert, Some("/certs/cert.pem".to_string()));
+        assert_eq!(config.docker_tls_key, Some("/certs/key.pem".to_string()));
+        assert_eq!(config.docker_api_version, Some("1.41".to_string()));
+        assert_eq!(config.docker_timeout, 60);
+        assert_eq!(config.container_runtime, "podman");
+        assert!(config.docker_rootless);
+        assert!(config.docker_event_label_filter);
 
         assert_eq!(config.routing_rules.len(), 3);
 
@@ -256,6 +538,35 @@ mod tests {
         assert_eq!(rules[2].port_range, None);
     }
 
+    #[test]
+    fn test_parse_routing_rules_with_attributes() {
+        let rules = parse_routing_rules_from_env(
+            "0.0.0.0/0:::metric=10;mtu=1280;advmss=1240;scope=universe;onlink;proto=static,10.0.0.0/8:tcp::src=172.17.0.2",
+        )
+        .unwrap();
+
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].destination, "0.0.0.0/0");
+        assert_eq!(rules[0].protocol, None);
+        assert_eq!(rules[0].port_range, None);
+        assert_eq!(rules[0].metric, Some(10));
+        assert_eq!(rules[0].mtu, Some(1280));
+        assert_eq!(rules[0].advmss, Some(1240));
+        assert_eq!(rules[0].scope, Some("universe".to_string()));
+        assert_eq!(rules[0].onlink, Some(true));
+        assert_eq!(rules[0].route_protocol, Some("static".to_string()));
+
+        assert_eq!(rules[1].destination, "10.0.0.0/8");
+        assert_eq!(rules[1].protocol, Some("tcp".to_string()));
+        assert_eq!(rules[1].prefsrc, Some("172.17.0.2".to_string()));
+
+        let result = parse_routing_rules_from_env("0.0.0.0/0:::mtu=big");
+        assert!(matches!(result, Err(ConfigError::InvalidFormat(_))));
+
+        let result = parse_routing_rules_from_env("0.0.0.0/0:::color=blue");
+        assert!(matches!(result, Err(ConfigError::InvalidFormat(_))));
+    }
+
     #[test]
     fn test_parse_routing_rules_empty() {
         let rules = parse_routing_rules_from_env("").unwrap();
@@ -271,6 +582,23 @@ mod tests {
         assert!(matches!(result, Err(ConfigError::InvalidFormat(_))));
     }
 
+    #[test]
+    fn test_parse_routing_rules_hostname() {
+        let rules =
+            parse_routing_rules_from_env("api.example.com:tcp:443,cdn.example.com:::mtu=1280")
+                .unwrap();
+
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].destination, "api.example.com");
+        assert_eq!(rules[0].hostname(), Some("api.example.com"));
+        assert_eq!(rules[0].port_range, Some((443, 443)));
+        assert_eq!(rules[1].hostname(), Some("cdn.example.com"));
+        assert_eq!(rules[1].mtu, Some(1280));
+
+        let result = parse_routing_rules_from_env("bad_host.example.com");
+        assert!(matches!(result, Err(ConfigError::InvalidFormat(_))));
+    }
+
     #[test]
     fn test_parse_routing_rules_invalid_port_range() {
         let result = parse_routing_rules_from_env("10.0.0.0/8:tcp:443-80");
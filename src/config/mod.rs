@@ -3,7 +3,17 @@
 //! Handles loading configuration from multiple sources with proper precedence:
 //! CLI arguments > environment variables > TOML files > defaults
 
-use crate::error::ConfigError;
+use crate::docker::classifier::ContainerClassifier;
+use crate::docker::fanout::FanOut;
+use crate::docker::{
+    ApiVersion, ContainerRuntime, DockerApiConfig, DockerTlsConfig, DEFAULT_API_TIMEOUT,
+    MINIMUM_API_VERSION,
+};
+use crate::error::{ConfigError, RouteError};
+use crate::routing::resolver::is_hostname;
+use crate::routing::{
+    validate_metric, PausedWarpPolicy, RouteAttributes, RouteMetrics, RoutePrecedence,
+};
 use serde::{Deserialize, Serialize};
 
 pub mod cli;
@@ -14,9 +24,30 @@ pub mod toml;
 pub const DEFAULT_WARP_CONTAINER_PATTERN: &str = "warp-*";
 pub const DEFAULT_TARGET_CONTAINER_LABEL: &str = "network.warp.target";
 pub const DEFAULT_NETWORK_PREFERENCE_LABEL: &str = "network.warp.network";
+pub const DEFAULT_ROUTE_ATTRIBUTE_LABEL_PREFIX: &str = "network.warp.route.";
+pub const DEFAULT_ROUTE_METRIC_LABEL: &str = "network.warp.metric";
+pub const DEFAULT_WARP_SELECTOR_LABEL: &str = "network.warp.serve";
+pub const DEFAULT_WARP_ROLE_LABEL: &str = "network.warp.role";
+pub const DEFAULT_ROUTE_PRECEDENCE: &str = "metric";
+pub const DEFAULT_ROUTE_METRIC_NETWORK: u32 = 100;
+pub const DEFAULT_ROUTE_METRIC_DEFAULT: u32 = 200;
+pub const DEFAULT_ROUTE_METRIC_HOST: u32 = 50;
+pub const DEFAULT_HOSTNAME_REFRESH_INTERVAL: u64 = 300;
+pub const DEFAULT_HOSTNAME_HISTORY_SIZE: usize = 3;
+pub const DEFAULT_PAUSED_WARP_POLICY: &str = "ignore";
+pub const DEFAULT_FANOUT_CONCURRENCY: usize = 16;
+pub const DEFAULT_FANOUT_TARGET_TIMEOUT: u64 = 30;
+pub const DEFAULT_INVENTORY_RESYNC_INTERVAL: u64 = 300;
+pub const DEFAULT_WARP_FORWARDING: bool = false;
+pub const DEFAULT_WARP_EGRESS_INTERFACE: &str = "CloudflareWARP";
+pub const DEFAULT_DANGEROUSLY_ALLOW_HOST_NETWORK: bool = false;
 pub const DEFAULT_LOG_LEVEL: &str = "info";
 pub const DEFAULT_DOCKER_SOCKET: &str = "/var/run/docker.sock";
 pub const DEFAULT_DOCKER_CONNECTION_METHOD: &str = "socket";
+pub const DEFAULT_DOCKER_TIMEOUT: u64 = DEFAULT_API_TIMEOUT;
+pub const DEFAULT_CONTAINER_RUNTIME: &str = "docker";
+pub const DEFAULT_DOCKER_ROOTLESS: bool = false;
+pub const DEFAULT_DOCKER_EVENT_LABEL_FILTER: bool = false;
 
 /// Main configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,18 +55,111 @@ pub struct AppConfig {
     pub warp_container_pattern: String,
     pub target_container_label: String,
     pub network_preference_label: String,
+    /// Prefix of warp container labels carrying route attributes, e.g. `network.warp.route.mtu`
+    pub route_attribute_label_prefix: String,
+    /// Label on target containers overriding the metric of their routes
+    pub route_metric_label: String,
+    /// Label on warp containers holding a selector for the containers they serve
+    pub warp_selector_label: String,
+    /// Label marking containers as warps regardless of their name
+    pub warp_role_label: String,
+    /// Route precedence policy: metric or override (see `RoutePrecedence`)
+    pub route_precedence: String,
+    pub route_metric_network: u32,
+    pub route_metric_default: u32,
+    pub route_metric_host: u32,
+    /// Seconds between lookups of the hostnames of hostname rules. The system resolver
+    /// doesn't report record TTLs, so this fixed interval applies whatever the TTL.
+    pub hostname_refresh_interval: u64,
+    /// Number of recent answers an address of a hostname rule must be missing from to be unrouted
+    pub hostname_history_size: usize,
+    /// What happens to the targets of a paused warp: ignore, failover or kill-switch
+    /// (see `PausedWarpPolicy`)
+    pub paused_warp_policy: String,
+    /// Number of targets handled at a time when a warp starts or on startup reconciliation
+    pub fanout_concurrency: usize,
+    /// Seconds a single target may take during a fan-out
+    pub fanout_target_timeout: u64,
+    /// Seconds between full resyncs of the container inventory with the Docker API
+    pub inventory_resync_interval: u64,
+    /// Enable IP forwarding and masquerading in warp containers with routed targets
+    pub warp_forwarding: bool,
+    /// Interface of the warp container that forwarded traffic is masqueraded out of
+    pub warp_egress_interface: String,
+    /// Allow modifying containers that use the host's network namespace
+    pub dangerously_allow_host_network: bool,
     pub routing_rules: Vec<RoutingRule>,
     pub log_level: String,
-    pub docker_socket: String,
+    /// Socket path set explicitly; without one, `DOCKER_HOST`, a docker context or the
+    /// rootless socket is looked up before falling back to the default socket
+    pub docker_socket: Option<String>,
     pub docker_connection_method: String,
+    /// Daemon address for the http and ssl connection methods, e.g. `tcp://host:2376`
+    pub docker_host: Option<String>,
+    /// CA certificate verifying the daemon for the ssl connection method
+    pub docker_tls_ca: Option<String>,
+    /// Client certificate for the ssl connection method
+    pub docker_tls_cert: Option<String>,
+    /// Private key of the client certificate for the ssl connection method
+    pub docker_tls_key: Option<String>,
+    /// Docker API version, e.g. `1.41`; negotiated with the daemon if unset
+    pub docker_api_version: Option<String>,
+    /// Seconds a Docker API request may take
+    pub docker_timeout: u64,
+    /// Container runtime behind the API socket: docker or podman (see `ContainerRuntime`)
+    pub container_runtime: String,
+    /// Talk to a rootless Docker daemon and work inside rootlesskit's namespaces
+    pub docker_rootless: bool,
+    /// Only stream events of containers carrying the target or warp role label
+    pub docker_event_label_filter: bool,
 }
 
 /// Routing rule configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RoutingRule {
-    pub destination: String, // CIDR notation
+    pub destination: String, // CIDR notation or hostname
     pub protocol: Option<String>,
     pub port_range: Option<(u16, u16)>,
+    /// Metric overriding the global metric for this rule's routes
+    pub metric: Option<u32>,
+    pub mtu: Option<u32>,
+    pub advmss: Option<u32>,
+    pub scope: Option<String>,
+    pub onlink: Option<bool>,
+    pub prefsrc: Option<String>,
+    /// Kernel route protocol (`ip route ... proto`), not to be confused with `protocol`
+    pub route_protocol: Option<String>,
+}
+
+impl RoutingRule {
+    /// Get the destination hostname if this rule targets a hostname instead of a CIDR
+    pub fn hostname(&self) -> Option<&str> {
+        let destination = self.destination.trim();
+        is_hostname(destination).then_some(destination)
+    }
+
+    /// Parse the kernel route attributes configured on this rule
+    pub fn route_attributes(&self) -> Result<RouteAttributes, RouteError> {
+        let mut attributes = RouteAttributes {
+            mtu: self.mtu,
+            advmss: self.advmss,
+            onlink: self.onlink,
+            ..Default::default()
+        };
+
+        if let Some(ref scope) = self.scope {
+            attributes.set("scope", scope)?;
+        }
+        if let Some(ref prefsrc) = self.prefsrc {
+            attributes.set("prefsrc", prefsrc)?;
+        }
+        if let Some(ref protocol) = self.route_protocol {
+            attributes.set("protocol", protocol)?;
+        }
+
+        attributes.validate()?;
+        Ok(attributes)
+    }
 }
 
 /// Configuration manager trait
@@ -52,6 +176,18 @@ pub trait ConfigurationManager {
     /// Get the network preference label name
     fn get_network_preference_label(&self) -> &str;
 
+    /// Get the route attribute label prefix
+    fn get_route_attribute_label_prefix(&self) -> &str;
+
+    /// Get the route metric label name
+    fn get_route_metric_label(&self) -> &str;
+
+    /// Get the warp selector label name
+    fn get_warp_selector_label(&self) -> &str;
+
+    /// Get the warp role label name
+    fn get_warp_role_label(&self) -> &str;
+
     /// Get the routing rules
     fn get_routing_rules(&self) -> &[RoutingRule];
 
@@ -66,14 +202,39 @@ impl Default for AppConfig {
             warp_container_pattern: DEFAULT_WARP_CONTAINER_PATTERN.to_string(),
             target_container_label: DEFAULT_TARGET_CONTAINER_LABEL.to_string(),
             network_preference_label: DEFAULT_NETWORK_PREFERENCE_LABEL.to_string(),
+            route_attribute_label_prefix: DEFAULT_ROUTE_ATTRIBUTE_LABEL_PREFIX.to_string(),
+            route_metric_label: DEFAULT_ROUTE_METRIC_LABEL.to_string(),
+            warp_selector_label: DEFAULT_WARP_SELECTOR_LABEL.to_string(),
+            warp_role_label: DEFAULT_WARP_ROLE_LABEL.to_string(),
+            route_precedence: DEFAULT_ROUTE_PRECEDENCE.to_string(),
+            route_metric_network: DEFAULT_ROUTE_METRIC_NETWORK,
+            route_metric_default: DEFAULT_ROUTE_METRIC_DEFAULT,
+            route_metric_host: DEFAULT_ROUTE_METRIC_HOST,
+            hostname_refresh_interval: DEFAULT_HOSTNAME_REFRESH_INTERVAL,
+            hostname_history_size: DEFAULT_HOSTNAME_HISTORY_SIZE,
+            paused_warp_policy: DEFAULT_PAUSED_WARP_POLICY.to_string(),
+            fanout_concurrency: DEFAULT_FANOUT_CONCURRENCY,
+            fanout_target_timeout: DEFAULT_FANOUT_TARGET_TIMEOUT,
+            inventory_resync_interval: DEFAULT_INVENTORY_RESYNC_INTERVAL,
+            warp_forwarding: DEFAULT_WARP_FORWARDING,
+            warp_egress_interface: DEFAULT_WARP_EGRESS_INTERFACE.to_string(),
+            dangerously_allow_host_network: DEFAULT_DANGEROUSLY_ALLOW_HOST_NETWORK,
             routing_rules: vec![RoutingRule {
                 destination: "0.0.0.0/0".to_string(),
-                protocol: None,
-                port_range: None,
+                ..Default::default()
             }],
             log_level: DEFAULT_LOG_LEVEL.to_string(),
-            docker_socket: DEFAULT_DOCKER_SOCKET.to_string(),
+            docker_socket: None,
             docker_connection_method: DEFAULT_DOCKER_CONNECTION_METHOD.to_string(),
+            docker_host: None,
+            docker_tls_ca: None,
+            docker_tls_cert: None,
+            docker_tls_key: None,
+            docker_api_version: None,
+            docker_timeout: DEFAULT_DOCKER_TIMEOUT,
+            container_runtime: DEFAULT_CONTAINER_RUNTIME.to_string(),
+            docker_rootless: DEFAULT_DOCKER_ROOTLESS,
+            docker_event_label_filter: DEFAULT_DOCKER_EVENT_LABEL_FILTER,
         }
     }
 }
@@ -84,6 +245,89 @@ impl AppConfig {
         Self::default()
     }
 
+    /// Get the global route metrics
+    pub fn route_metrics(&self) -> RouteMetrics {
+        RouteMetrics {
+            network: self.route_metric_network,
+            default_route: self.route_metric_default,
+            host: self.route_metric_host,
+        }
+    }
+
+    /// Parse the route precedence policy
+    pub fn route_precedence(&self) -> Result<RoutePrecedence, ConfigError> {
+        self.route_precedence
+            .parse()
+            .map_err(|e: RouteError| ConfigError::ValidationError(e.to_string()))
+    }
+
+    /// Parse the policy for the targets of paused warps
+    pub fn paused_warp_policy(&self) -> Result<PausedWarpPolicy, ConfigError> {
+        self.paused_warp_policy
+            .parse()
+            .map_err(|e: RouteError| ConfigError::ValidationError(e.to_string()))
+    }
+
+    /// Get the fan-out used when a warp starts and on startup reconciliation
+    pub fn fan_out(&self) -> FanOut {
+        FanOut::new(
+            self.fanout_concurrency,
+            std::time::Duration::from_secs(self.fanout_target_timeout),
+        )
+    }
+
+    /// Get the labels Docker should filter events by: the classifier's event labels
+    /// if server-side filtering is enabled, none otherwise
+    pub fn event_label_filter(&self, classifier: &dyn ContainerClassifier) -> Vec<String> {
+        if self.docker_event_label_filter {
+            classifier.event_labels()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Parse the container runtime
+    pub fn container_runtime(&self) -> Result<ContainerRuntime, ConfigError> {
+        self.container_runtime.parse().map_err(|e| match e {
+            ConfigError::InvalidFormat(message) => ConfigError::ValidationError(message),
+            e => e,
+        })
+    }
+
+    /// Get the client certificate and CA for the ssl connection method, if all are set
+    pub fn docker_tls(&self) -> Option<DockerTlsConfig> {
+        match (
+            &self.docker_tls_ca,
+            &self.docker_tls_cert,
+            &self.docker_tls_key,
+        ) {
+            (Some(ca), Some(cert), Some(key)) => Some(DockerTlsConfig {
+                ca: ca.into(),
+                cert: cert.into(),
+                key: key.into(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Parse the Docker API version and request timeout
+    pub fn docker_api(&self) -> Result<DockerApiConfig, ConfigError> {
+        let version = match self.docker_api_version.as_deref().map(str::trim) {
+            Some(version) if !version.is_empty() => {
+                Some(version.parse::<ApiVersion>().map_err(|e| match e {
+                    ConfigError::InvalidFormat(message) => ConfigError::ValidationError(message),
+                    e => e,
+                })?)
+            }
+            _ => None,
+        };
+
+        Ok(DockerApiConfig {
+            version,
+            timeout: self.docker_timeout,
+        })
+    }
+
     /// Validate the configuration
     pub fn validate(&self) -> Result<(), ConfigError> {
         // Validate warp container pattern is not empty
@@ -107,6 +351,94 @@ impl AppConfig {
             ));
         }
 
+        // Validate route attribute label prefix is not empty
+        if self.route_attribute_label_prefix.trim().is_empty() {
+            return Err(ConfigError::ValidationError(
+                "Route attribute label prefix cannot be empty".to_string(),
+            ));
+        }
+
+        // Validate route metric label is not empty
+        if self.route_metric_label.trim().is_empty() {
+            return Err(ConfigError::ValidationError(
+                "Route metric label cannot be empty".to_string(),
+            ));
+        }
+
+        // Validate warp selector label is not empty
+        if self.warp_selector_label.trim().is_empty() {
+            return Err(ConfigError::ValidationError(
+                "Warp selector label cannot be empty".to_string(),
+            ));
+        }
+
+        // Validate warp role label is not empty
+        if self.warp_role_label.trim().is_empty() {
+            return Err(ConfigError::ValidationError(
+                "Warp role label cannot be empty".to_string(),
+            ));
+        }
+
+        // Validate global route metrics against the route precedence policy
+        let precedence = self.route_precedence()?;
+        let metric_error = |kind: &str, e: RouteError| {
+            ConfigError::ValidationError(format!("Invalid {} route metric: {}", kind, e))
+        };
+        validate_metric(precedence, self.route_metric_network, false)
+            .map_err(|e| metric_error("network", e))?;
+        validate_metric(precedence, self.route_metric_default, true)
+            .map_err(|e| metric_error("default", e))?;
+        validate_metric(precedence, self.route_metric_host, false)
+            .map_err(|e| metric_error("host", e))?;
+
+        // Validate hostname rule refresh settings
+        if self.hostname_refresh_interval == 0 {
+            return Err(ConfigError::ValidationError(
+                "Hostname refresh interval must be greater than zero".to_string(),
+            ));
+        }
+        if self.hostname_history_size == 0 {
+            return Err(ConfigError::ValidationError(
+                "Hostname history size must be greater than zero".to_string(),
+            ));
+        }
+
+        // Validate the paused warp policy
+        self.paused_warp_policy()?;
+
+        // Validate fan-out limits
+        if self.fanout_concurrency == 0 {
+            return Err(ConfigError::ValidationError(
+                "Fan-out concurrency must be greater than zero".to_string(),
+            ));
+        }
+        if self.fanout_target_timeout == 0 {
+            return Err(ConfigError::ValidationError(
+                "Fan-out target timeout must be greater than zero".to_string(),
+            ));
+        }
+
+        // Validate the inventory resync interval
+        if self.inventory_resync_interval == 0 {
+            return Err(ConfigError::ValidationError(
+                "Inventory resync interval must be greater than zero".to_string(),
+            ));
+        }
+
+        // Validate the warp egress interface name, which ends up in nftables rules
+        let interface = &self.warp_egress_interface;
+        if interface.is_empty()
+            || interface.len() > 15
+            || interface
+                .chars()
+                .any(|c| c.is_whitespace() || c == '/' || c == ':' || c == '"')
+        {
+            return Err(ConfigError::ValidationError(format!(
+                "Invalid warp egress interface name: '{}'",
+                interface
+            )));
+        }
+
         // Validate log level
         match self.log_level.to_lowercase().as_str() {
             "trace" | "debug" | "info" | "warn" | "error" => {}
@@ -129,6 +461,49 @@ impl AppConfig {
             }
         }
 
+        // Validate the daemon address and certificates of the ssl connection method
+        if self.docker_connection_method.eq_ignore_ascii_case("ssl") {
+            if self
+                .docker_host
+                .as_deref()
+                .is_none_or(|host| host.trim().is_empty())
+            {
+                return Err(ConfigError::ValidationError(
+                    "Docker host is required for the ssl connection method".to_string(),
+                ));
+            }
+            for (name, path) in [
+                ("CA certificate", &self.docker_tls_ca),
+                ("client certificate", &self.docker_tls_cert),
+                ("client key", &self.docker_tls_key),
+            ] {
+                if path.as_deref().is_none_or(|path| path.trim().is_empty()) {
+                    return Err(ConfigError::ValidationError(format!(
+                        "Docker TLS {} is required for the ssl connection method",
+                        name
+                    )));
+                }
+            }
+        }
+
+        // Validate the Docker API version and request timeout
+        if let Some(version) = self.docker_api()?.version {
+            if version < MINIMUM_API_VERSION {
+                return Err(ConfigError::ValidationError(format!(
+                    "Docker API version {} is older than the minimum supported version {}",
+                    version, MINIMUM_API_VERSION
+                )));
+            }
+        }
+        if self.docker_timeout == 0 {
+            return Err(ConfigError::ValidationError(
+                "Docker timeout must be greater than zero".to_string(),
+            ));
+        }
+
+        // Validate container runtime
+        self.container_runtime()?;
+
         // Validate routing rules
         for (i, rule) in self.routing_rules.iter().enumerate() {
             if rule.destination.trim().is_empty() {
@@ -139,9 +514,9 @@ impl AppConfig {
             }
 
             // Basic CIDR validation - check if it contains '/' and has valid format
-            if !rule.destination.contains('/') {
+            if !rule.destination.contains('/') && rule.hostname().is_none() {
                 return Err(ConfigError::ValidationError(format!(
-                    "Routing rule {} destination '{}' is not in CIDR format",
+                    "Routing rule {} destination '{}' is neither in CIDR format nor a hostname",
                     i, rule.destination
                 )));
             }
@@ -155,6 +530,29 @@ impl AppConfig {
                     )));
                 }
             }
+
+            // Validate the rule's metric against the route precedence policy
+            if let Some(metric) = rule.metric {
+                let is_default_route = rule
+                    .destination
+                    .parse::<ipnetwork::IpNetwork>()
+                    .map(|network| network.prefix() == 0)
+                    .unwrap_or(false);
+                validate_metric(precedence, metric, is_default_route).map_err(|e| {
+                    ConfigError::ValidationError(format!(
+                        "Routing rule {} has invalid metric: {}",
+                        i, e
+                    ))
+                })?;
+            }
+
+            // Validate kernel route attributes
+            rule.route_attributes().map_err(|e| {
+                ConfigError::ValidationError(format!(
+                    "Routing rule {} has invalid route attributes: {}",
+                    i, e
+                ))
+            })?;
         }
 
         Ok(())
@@ -209,6 +607,7 @@ impl DefaultConfigurationManager {
     }
 
     /// Create a configuration manager with defaults only (for testing)
+    #[allow(clippy::should_implement_trait)]
     pub fn default() -> Result<Self, ConfigError> {
         let config = AppConfig::default();
         config.validate()?;
@@ -238,6 +637,22 @@ impl ConfigurationManager for DefaultConfigurationManager {
         &self.config.network_preference_label
     }
 
+    fn get_route_attribute_label_prefix(&self) -> &str {
+        &self.config.route_attribute_label_prefix
+    }
+
+    fn get_route_metric_label(&self) -> &str {
+        &self.config.route_metric_label
+    }
+
+    fn get_warp_selector_label(&self) -> &str {
+        &self.config.warp_selector_label
+    }
+
+    fn get_warp_role_label(&self) -> &str {
+        &self.config.warp_role_label
+    }
+
     fn get_routing_rules(&self) -> &[RoutingRule] {
         &self.config.routing_rules
     }
@@ -250,6 +665,7 @@ impl ConfigurationManager for DefaultConfigurationManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::docker::classifier::DefaultContainerClassifier;
     use defer;
     use std::env;
     use std::io::Write;
@@ -263,8 +679,10 @@ mod tests {
 
     #[test]
     fn test_app_config_validation_empty_pattern() {
-        let mut config = AppConfig::default();
-        config.warp_container_pattern = "".to_string();
+        let config = AppConfig {
+            warp_container_pattern: "".to_string(),
+            ..Default::default()
+        };
         assert!(matches!(
             config.validate(),
             Err(ConfigError::ValidationError(_))
@@ -273,8 +691,10 @@ mod tests {
 
     #[test]
     fn test_app_config_validation_invalid_log_level() {
-        let mut config = AppConfig::default();
-        config.log_level = "invalid".to_string();
+        let config = AppConfig {
+            log_level: "invalid".to_string(),
+            ..Default::default()
+        };
         assert!(matches!(
             config.validate(),
             Err(ConfigError::ValidationError(_))
@@ -283,8 +703,10 @@ mod tests {
 
     #[test]
     fn test_app_config_validation_invalid_docker_method() {
-        let mut config = AppConfig::default();
-        config.docker_connection_method = "invalid".to_string();
+        let config = AppConfig {
+            docker_connection_method: "invalid".to_string(),
+            ..Default::default()
+        };
         assert!(matches!(
             config.validate(),
             Err(ConfigError::ValidationError(_))
@@ -293,26 +715,389 @@ mod tests {
 
     #[test]
     fn test_app_config_validation_invalid_cidr() {
-        let mut config = AppConfig::default();
-        config.routing_rules = vec![RoutingRule {
-            destination: "10.0.0.0".to_string(), // Missing /mask
-            protocol: None,
-            port_range: None,
-        }];
+        let config = AppConfig {
+            routing_rules: vec![RoutingRule {
+                destination: "10.0.0.0".to_string(), // Missing /mask
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
         assert!(matches!(
             config.validate(),
             Err(ConfigError::ValidationError(_))
         ));
     }
 
+    #[test]
+    fn test_app_config_validation_hostname_rules() {
+        let config = AppConfig {
+            routing_rules: vec![RoutingRule {
+                destination: "api.example.com".to_string(),
+                metric: Some(10),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+        assert_eq!(config.routing_rules[0].hostname(), Some("api.example.com"));
+
+        let config = AppConfig {
+            routing_rules: vec![RoutingRule {
+                destination: "not a host".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+
+        let config = AppConfig {
+            hostname_refresh_interval: 0,
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+
+        let config = AppConfig {
+            hostname_history_size: 0,
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_app_config_validation_warp_egress_interface() {
+        let config = AppConfig {
+            warp_forwarding: true,
+            warp_egress_interface: "wg0".to_string(),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+
+        for interface in ["", "a-very-long-interface", "wg0\" masquerade", "eth 0"] {
+            let config = AppConfig {
+                warp_egress_interface: interface.to_string(),
+                ..Default::default()
+            };
+            assert!(
+                config.validate().is_err(),
+                "{:?} should be rejected",
+                interface
+            );
+        }
+    }
+
     #[test]
     fn test_app_config_validation_invalid_port_range() {
-        let mut config = AppConfig::default();
-        config.routing_rules = vec![RoutingRule {
-            destination: "10.0.0.0/8".to_string(),
-            protocol: Some("tcp".to_string()),
-            port_range: Some((443, 80)), // start > end
-        }];
+        let config = AppConfig {
+            routing_rules: vec![RoutingRule {
+                destination: "10.0.0.0/8".to_string(),
+                protocol: Some("tcp".to_string()),
+                port_range: Some((443, 80)), // start > end
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_app_config_validation_route_attributes() {
+        let config = AppConfig {
+            routing_rules: vec![RoutingRule {
+                destination: "10.0.0.0/8".to_string(),
+                mtu: Some(1280),
+                advmss: Some(1240),
+                scope: Some("universe".to_string()),
+                onlink: Some(true),
+                prefsrc: Some("172.17.0.2".to_string()),
+                route_protocol: Some("static".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+
+        let config = AppConfig {
+            routing_rules: vec![RoutingRule {
+                destination: "10.0.0.0/8".to_string(),
+                scope: Some("galaxy".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::ValidationError(_))
+        ));
+
+        let config = AppConfig {
+            routing_rules: vec![RoutingRule {
+                destination: "10.0.0.0/8".to_string(),
+                mtu: Some(1280),
+                advmss: Some(1400), // larger than the MTU
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_app_config_validation_inventory() {
+        let config = AppConfig {
+            inventory_resync_interval: 0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_app_config_validation_fanout() {
+        let config = AppConfig {
+            fanout_concurrency: 0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::ValidationError(_))
+        ));
+
+        let config = AppConfig {
+            fanout_target_timeout: 0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_app_config_validation_ssl_connection() {
+        let config = AppConfig {
+            docker_connection_method: "ssl".to_string(),
+            docker_host: Some("tcp://docker.example.com:2376".to_string()),
+            docker_tls_ca: Some("/certs/ca.pem".to_string()),
+            docker_tls_cert: Some("/certs/cert.pem".to_string()),
+            ..Default::default()
+        };
+        assert!(config.docker_tls().is_none());
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::ValidationError(message)) if message.contains("client key")
+        ));
+
+        let config = AppConfig {
+            docker_tls_key: Some("/certs/key.pem".to_string()),
+            ..config
+        };
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.docker_tls(),
+            Some(DockerTlsConfig {
+                ca: "/certs/ca.pem".into(),
+                cert: "/certs/cert.pem".into(),
+                key: "/certs/key.pem".into(),
+            })
+        );
+
+        let config = AppConfig {
+            docker_host: None,
+            ..config
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::ValidationError(message)) if message.contains("Docker host")
+        ));
+    }
+
+    #[test]
+    fn test_app_config_validation_docker_api() {
+        let config = AppConfig {
+            docker_api_version: Some("1.41".to_string()),
+            docker_timeout: 30,
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.docker_api().unwrap(),
+            DockerApiConfig {
+                version: Some(ApiVersion {
+                    major: 1,
+                    minor: 41
+                }),
+                timeout: 30,
+            }
+        );
+        assert_eq!(AppConfig::default().docker_api().unwrap().version, None);
+
+        for version in ["latest", "1.23"] {
+            let config = AppConfig {
+                docker_api_version: Some(version.to_string()),
+                ..Default::default()
+            };
+            assert!(matches!(
+                config.validate(),
+                Err(ConfigError::ValidationError(_))
+            ));
+        }
+
+        let config = AppConfig {
+            docker_timeout: 0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_app_config_validation_container_runtime() {
+        let config = AppConfig {
+            container_runtime: "containerd".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::ValidationError(_))
+        ));
+
+        let config = AppConfig {
+            container_runtime: "podman".to_string(),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.container_runtime().unwrap(),
+            ContainerRuntime::Podman
+        );
+    }
+
+    #[test]
+    fn test_app_config_validation_route_precedence() {
+        let config = AppConfig {
+            route_precedence: "whatever".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::ValidationError(_))
+        ));
+
+        // Metric 0 collides with Docker's routes unless our routes override them
+        let config = AppConfig {
+            route_metric_network: 0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::ValidationError(_))
+        ));
+
+        // Overriding Docker's default route requires metric 0
+        let config = AppConfig {
+            route_precedence: "override".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::ValidationError(_))
+        ));
+
+        let config = AppConfig {
+            route_precedence: "override".to_string(),
+            route_metric_default: 0,
+            route_metric_network: 0,
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.route_precedence().unwrap(),
+            RoutePrecedence::Override
+        );
+        assert_eq!(config.route_metrics().default_route, 0);
+
+        // Per-rule metrics follow the same policy
+        let config = AppConfig {
+            route_precedence: "override".to_string(),
+            route_metric_default: 0,
+            routing_rules: vec![RoutingRule {
+                destination: "0.0.0.0/0".to_string(),
+                metric: Some(10),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::ValidationError(_))
+        ));
+
+        let config = AppConfig {
+            routing_rules: vec![RoutingRule {
+                destination: "10.0.0.0/8".to_string(),
+                metric: Some(0),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_event_label_filter() {
+        let classifier = DefaultContainerClassifier::with_simple_pattern(
+            "warp-*".to_string(),
+            DEFAULT_TARGET_CONTAINER_LABEL.to_string(),
+            DEFAULT_NETWORK_PREFERENCE_LABEL.to_string(),
+        );
+        assert!(AppConfig::default()
+            .event_label_filter(&classifier)
+            .is_empty());
+
+        let config = AppConfig {
+            docker_event_label_filter: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            config.event_label_filter(&classifier),
+            vec![DEFAULT_TARGET_CONTAINER_LABEL, DEFAULT_WARP_ROLE_LABEL]
+        );
+    }
+
+    #[test]
+    fn test_app_config_validation_paused_warp_policy() {
+        assert_eq!(
+            AppConfig::default().paused_warp_policy().unwrap(),
+            PausedWarpPolicy::Ignore
+        );
+
+        let config = AppConfig {
+            paused_warp_policy: "kill-switch".to_string(),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.paused_warp_policy().unwrap(),
+            PausedWarpPolicy::KillSwitch
+        );
+
+        let config = AppConfig {
+            paused_warp_policy: "panic".to_string(),
+            ..Default::default()
+        };
         assert!(matches!(
             config.validate(),
             Err(ConfigError::ValidationError(_))
@@ -337,7 +1122,7 @@ mod tests {
             DEFAULT_NETWORK_PREFERENCE_LABEL
         );
         assert_eq!(config.log_level, DEFAULT_LOG_LEVEL);
-        assert_eq!(config.docker_socket, DEFAULT_DOCKER_SOCKET);
+        assert_eq!(config.docker_socket, None);
         assert_eq!(
             config.docker_connection_method,
             DEFAULT_DOCKER_CONNECTION_METHOD
@@ -367,7 +1152,7 @@ socket = "/custom/docker.sock"
         assert_eq!(config.warp_container_pattern, "custom-*");
         assert_eq!(config.target_container_label, "custom.label");
         assert_eq!(config.log_level, "debug");
-        assert_eq!(config.docker_socket, "/custom/docker.sock");
+        assert_eq!(config.docker_socket.as_deref(), Some("/custom/docker.sock"));
     }
 
     // Clean up any existing env vars first
@@ -404,9 +1189,35 @@ level = "warn"
             log_level: None, // Should use env var (trace)
             docker_connection_method: None,
             docker_socket: None,
+            docker_host: None,
+            docker_tls_ca: None,
+            docker_tls_cert: None,
+            docker_tls_key: None,
+            docker_api_version: None,
+            docker_timeout: None,
+            container_runtime: None,
+            docker_rootless: false,
+            docker_event_label_filter: false,
             warp_container_pattern: Some("cli-*".to_string()), // Should override env and toml
             target_container_label: None,                      // Should use toml value
             network_preference_label: None,
+            route_attribute_label_prefix: None,
+            route_metric_label: None,
+            warp_selector_label: None,
+            warp_role_label: None,
+            route_precedence: None,
+            route_metric_network: None,
+            route_metric_default: None,
+            route_metric_host: None,
+            hostname_refresh_interval: None,
+            hostname_history_size: None,
+            paused_warp_policy: None,
+            fanout_concurrency: None,
+            fanout_target_timeout: None,
+            inventory_resync_interval: None,
+            warp_forwarding: false,
+            warp_egress_interface: None,
+            dangerously_allow_host_network: false,
             routing_rules: None,
             validate_config: false,
             print_default_config: false,
@@ -12,9 +12,18 @@ pub struct TomlConfig {
     pub warp_container_name_pattern: Option<String>,
     pub target_container_label: Option<String>,
     pub network_preference_label: Option<String>,
+    pub route_attribute_label_prefix: Option<String>,
+    pub route_metric_label: Option<String>,
+    pub warp_selector_label: Option<String>,
+    pub warp_role_label: Option<String>,
+    pub dangerously_allow_host_network: Option<bool>,
     pub routing_rules: Option<Vec<TomlRoutingRule>>,
     pub logging: Option<LoggingConfig>,
     pub docker: Option<DockerConfig>,
+    pub routing: Option<RoutingConfig>,
+    pub forwarding: Option<ForwardingConfig>,
+    pub fanout: Option<FanOutConfig>,
+    pub inventory: Option<InventoryConfig>,
 }
 
 /// TOML routing rule configuration
@@ -23,6 +32,13 @@ pub struct TomlRoutingRule {
     pub destination: String,
     pub protocol: Option<String>,
     pub port_range: Option<(u16, u16)>,
+    pub metric: Option<u32>,
+    pub mtu: Option<u32>,
+    pub advmss: Option<u32>,
+    pub scope: Option<String>,
+    pub onlink: Option<bool>,
+    pub prefsrc: Option<String>,
+    pub route_protocol: Option<String>,
 }
 
 /// Logging configuration
@@ -37,6 +53,50 @@ pub struct LoggingConfig {
 pub struct DockerConfig {
     pub socket: Option<String>,
     pub api_version: Option<String>,
+    pub runtime: Option<String>,
+    pub rootless: Option<bool>,
+    /// Only stream events of containers with the target or warp role label
+    pub event_label_filter: Option<bool>,
+    /// Daemon address for the http and ssl connection methods
+    pub host: Option<String>,
+    pub tls_ca: Option<String>,
+    pub tls_cert: Option<String>,
+    pub tls_key: Option<String>,
+    /// Seconds a Docker API request may take
+    pub timeout: Option<u64>,
+}
+
+/// Route metric and precedence configuration
+#[derive(Debug, Deserialize)]
+pub struct RoutingConfig {
+    pub precedence: Option<String>,
+    pub network_metric: Option<u32>,
+    pub default_metric: Option<u32>,
+    pub host_metric: Option<u32>,
+    pub hostname_refresh_interval: Option<u64>,
+    pub hostname_history_size: Option<usize>,
+    /// Policy for the targets of paused warps
+    pub paused_warp: Option<String>,
+}
+
+/// Fan-out limits for warp starts and startup reconciliation
+#[derive(Debug, Deserialize)]
+pub struct FanOutConfig {
+    pub concurrency: Option<usize>,
+    pub target_timeout: Option<u64>,
+}
+
+/// Container inventory configuration
+#[derive(Debug, Deserialize)]
+pub struct InventoryConfig {
+    pub resync_interval: Option<u64>,
+}
+
+/// Warp forwarding configuration
+#[derive(Debug, Deserialize)]
+pub struct ForwardingConfig {
+    pub enabled: Option<bool>,
+    pub egress_interface: Option<String>,
 }
 
 impl TomlConfig {
@@ -60,6 +120,26 @@ impl TomlConfig {
             config.network_preference_label = label.clone();
         }
 
+        if let Some(ref prefix) = self.route_attribute_label_prefix {
+            config.route_attribute_label_prefix = prefix.clone();
+        }
+
+        if let Some(ref label) = self.route_metric_label {
+            config.route_metric_label = label.clone();
+        }
+
+        if let Some(ref label) = self.warp_selector_label {
+            config.warp_selector_label = label.clone();
+        }
+
+        if let Some(ref label) = self.warp_role_label {
+            config.warp_role_label = label.clone();
+        }
+
+        if let Some(allow) = self.dangerously_allow_host_network {
+            config.dangerously_allow_host_network = allow;
+        }
+
         if let Some(ref rules) = self.routing_rules {
             config.routing_rules = rules
                 .iter()
@@ -67,6 +147,13 @@ impl TomlConfig {
                     destination: r.destination.clone(),
                     protocol: r.protocol.clone(),
                     port_range: r.port_range,
+                    metric: r.metric,
+                    mtu: r.mtu,
+                    advmss: r.advmss,
+                    scope: r.scope.clone(),
+                    onlink: r.onlink,
+                    prefsrc: r.prefsrc.clone(),
+                    route_protocol: r.route_protocol.clone(),
                 })
                 .collect();
         }
@@ -79,7 +166,82 @@ impl TomlConfig {
 
         if let Some(ref docker) = self.docker {
             if let Some(ref socket) = docker.socket {
-                config.docker_socket = socket.clone();
+                config.docker_socket = Some(socket.clone());
+            }
+            if let Some(ref version) = docker.api_version {
+                config.docker_api_version = Some(version.clone());
+            }
+            if let Some(timeout) = docker.timeout {
+                config.docker_timeout = timeout;
+            }
+            if let Some(ref runtime) = docker.runtime {
+                config.container_runtime = runtime.clone();
+            }
+            if let Some(rootless) = docker.rootless {
+                config.docker_rootless = rootless;
+            }
+            if let Some(filter) = docker.event_label_filter {
+                config.docker_event_label_filter = filter;
+            }
+            if let Some(ref host) = docker.host {
+                config.docker_host = Some(host.clone());
+            }
+            if let Some(ref ca) = docker.tls_ca {
+                config.docker_tls_ca = Some(ca.clone());
+            }
+            if let Some(ref cert) = docker.tls_cert {
+                config.docker_tls_cert = Some(cert.clone());
+            }
+            if let Some(ref key) = docker.tls_key {
+                config.docker_tls_key = Some(key.clone());
+            }
+        }
+
+        if let Some(ref routing) = self.routing {
+            if let Some(ref precedence) = routing.precedence {
+                config.route_precedence = precedence.clone();
+            }
+            if let Some(metric) = routing.network_metric {
+                config.route_metric_network = metric;
+            }
+            if let Some(metric) = routing.default_metric {
+                config.route_metric_default = metric;
+            }
+            if let Some(metric) = routing.host_metric {
+                config.route_metric_host = metric;
+            }
+            if let Some(interval) = routing.hostname_refresh_interval {
+                config.hostname_refresh_interval = interval;
+            }
+            if let Some(size) = routing.hostname_history_size {
+                config.hostname_history_size = size;
+            }
+            if let Some(ref policy) = routing.paused_warp {
+                config.paused_warp_policy = policy.clone();
+            }
+        }
+
+        if let Some(ref fanout) = self.fanout {
+            if let Some(concurrency) = fanout.concurrency {
+                config.fanout_concurrency = concurrency;
+            }
+            if let Some(timeout) = fanout.target_timeout {
+                config.fanout_target_timeout = timeout;
+            }
+        }
+
+        if let Some(ref inventory) = self.inventory {
+            if let Some(interval) = inventory.resync_interval {
+                config.inventory_resync_interval = interval;
+            }
+        }
+
+        if let Some(ref forwarding) = self.forwarding {
+            if let Some(enabled) = forwarding.enabled {
+                config.warp_forwarding = enabled;
+            }
+            if let Some(ref interface) = forwarding.egress_interface {
+                config.warp_egress_interface = interface.clone();
             }
         }
 
@@ -129,6 +291,7 @@ docker_connection_method = "socket"
 warp_container_name_pattern = "proxy-*"
 target_container_label = "app.proxy.target"
 network_preference_label = "app.proxy.network"
+warp_role_label = "app.proxy.role"
 
 [logging]
 level = "debug"
@@ -137,14 +300,49 @@ format = "json"
 [docker]
 socket = "/var/run/docker.sock"
 api_version = "1.41"
+runtime = "podman"
+rootless = true
+event_label_filter = true
+host = "tcp://docker.example.com:2376"
+tls_ca = "/etc/docker-warp/ca.pem"
+tls_cert = "/etc/docker-warp/cert.pem"
+tls_key = "/etc/docker-warp/key.pem"
+timeout = 60
+
+[routing]
+precedence = "override"
+network_metric = 10
+default_metric = 0
+host_metric = 5
+hostname_refresh_interval = 60
+hostname_history_size = 4
+paused_warp = "kill-switch"
+
+[fanout]
+concurrency = 32
+target_timeout = 5
+
+[inventory]
+resync_interval = 600
+
+[forwarding]
+enabled = true
+egress_interface = "wg0"
 
 [[routing_rules]]
 destination = "10.0.0.0/8"
 protocol = "tcp"
 port_range = [80, 443]
+metric = 20
 
 [[routing_rules]]
 destination = "192.168.0.0/16"
+mtu = 1280
+advmss = 1240
+scope = "universe"
+onlink = true
+prefsrc = "172.17.0.2"
+route_protocol = "static"
 "#;
 
         let mut temp_file = NamedTempFile::new().unwrap();
@@ -165,6 +363,7 @@ destination = "192.168.0.0/16"
             config.network_preference_label,
             Some("app.proxy.network".to_string())
         );
+        assert_eq!(config.warp_role_label, Some("app.proxy.role".to_string()));
 
         let logging = config.logging.unwrap();
         assert_eq!(logging.level, Some("debug".to_string()));
@@ -173,15 +372,56 @@ destination = "192.168.0.0/16"
         let docker = config.docker.unwrap();
         assert_eq!(docker.socket, Some("/var/run/docker.sock".to_string()));
         assert_eq!(docker.api_version, Some("1.41".to_string()));
+        assert_eq!(docker.runtime, Some("podman".to_string()));
+        assert_eq!(docker.rootless, Some(true));
+        assert_eq!(docker.event_label_filter, Some(true));
+        assert_eq!(
+            docker.host,
+            Some("tcp://docker.example.com:2376".to_string())
+        );
+        assert_eq!(docker.tls_ca, Some("/etc/docker-warp/ca.pem".to_string()));
+        assert_eq!(
+            docker.tls_cert,
+            Some("/etc/docker-warp/cert.pem".to_string())
+        );
+        assert_eq!(docker.tls_key, Some("/etc/docker-warp/key.pem".to_string()));
+        assert_eq!(docker.timeout, Some(60));
+
+        let routing = config.routing.unwrap();
+        assert_eq!(routing.precedence, Some("override".to_string()));
+        assert_eq!(routing.network_metric, Some(10));
+        assert_eq!(routing.default_metric, Some(0));
+        assert_eq!(routing.host_metric, Some(5));
+        assert_eq!(routing.hostname_refresh_interval, Some(60));
+        assert_eq!(routing.hostname_history_size, Some(4));
+        assert_eq!(routing.paused_warp, Some("kill-switch".to_string()));
+
+        let fanout = config.fanout.unwrap();
+        assert_eq!(fanout.concurrency, Some(32));
+        assert_eq!(fanout.target_timeout, Some(5));
+        assert_eq!(config.inventory.unwrap().resync_interval, Some(600));
+
+        let forwarding = config.forwarding.unwrap();
+        assert_eq!(forwarding.enabled, Some(true));
+        assert_eq!(forwarding.egress_interface, Some("wg0".to_string()));
 
         let rules = config.routing_rules.unwrap();
         assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].metric, Some(20));
+        assert_eq!(rules[1].metric, None);
         assert_eq!(rules[0].destination, "10.0.0.0/8");
         assert_eq!(rules[0].protocol, Some("tcp".to_string()));
         assert_eq!(rules[0].port_range, Some((80, 443)));
         assert_eq!(rules[1].destination, "192.168.0.0/16");
         assert_eq!(rules[1].protocol, None);
         assert_eq!(rules[1].port_range, None);
+        assert_eq!(rules[0].mtu, None);
+        assert_eq!(rules[1].mtu, Some(1280));
+        assert_eq!(rules[1].advmss, Some(1240));
+        assert_eq!(rules[1].scope, Some("universe".to_string()));
+        assert_eq!(rules[1].onlink, Some(true));
+        assert_eq!(rules[1].prefsrc, Some("172.17.0.2".to_string()));
+        assert_eq!(rules[1].route_protocol, Some("static".to_string()));
     }
 
     #[test]
@@ -229,10 +469,22 @@ invalid toml content [[[
             warp_container_name_pattern: Some("custom-*".to_string()),
             target_container_label: Some("custom.label".to_string()),
             network_preference_label: Some("custom.network".to_string()),
+            route_attribute_label_prefix: Some("custom.route.".to_string()),
+            route_metric_label: Some("custom.metric".to_string()),
+            warp_selector_label: Some("custom.serve".to_string()),
+            warp_role_label: Some("custom.role".to_string()),
+            dangerously_allow_host_network: Some(true),
             routing_rules: Some(vec![TomlRoutingRule {
                 destination: "172.16.0.0/12".to_string(),
                 protocol: Some("udp".to_string()),
                 port_range: Some((53, 53)),
+                metric: Some(30),
+                mtu: Some(1400),
+                advmss: None,
+                scope: None,
+                onlink: Some(true),
+                prefsrc: None,
+                route_protocol: None,
             }]),
             logging: Some(LoggingConfig {
                 level: Some("trace".to_string()),
@@ -241,6 +493,34 @@ invalid toml content [[[
             docker: Some(DockerConfig {
                 socket: Some("/custom/docker.sock".to_string()),
                 api_version: Some("1.40".to_string()),
+                runtime: Some("podman".to_string()),
+                rootless: None,
+                event_label_filter: Some(true),
+                host: Some("tcp://docker.example.com:2376".to_string()),
+                tls_ca: Some("/certs/ca.pem".to_string()),
+                tls_cert: None,
+                tls_key: None,
+                timeout: Some(45),
+            }),
+            routing: Some(RoutingConfig {
+                precedence: Some("override".to_string()),
+                network_metric: None,
+                default_metric: Some(0),
+                host_metric: None,
+                hostname_refresh_interval: Some(120),
+                hostname_history_size: None,
+                paused_warp: Some("failover".to_string()),
+            }),
+            forwarding: Some(ForwardingConfig {
+                enabled: Some(true),
+                egress_interface: None,
+            }),
+            fanout: Some(FanOutConfig {
+                concurrency: Some(4),
+                target_timeout: None,
+            }),
+            inventory: Some(InventoryConfig {
+                resync_interval: Some(30),
             }),
         };
 
@@ -251,8 +531,49 @@ invalid toml content [[[
         assert_eq!(app_config.warp_container_pattern, "custom-*");
         assert_eq!(app_config.target_container_label, "custom.label");
         assert_eq!(app_config.network_preference_label, "custom.network");
+        assert_eq!(app_config.route_attribute_label_prefix, "custom.route.");
+        assert_eq!(app_config.route_metric_label, "custom.metric");
+        assert_eq!(app_config.warp_selector_label, "custom.serve");
+        assert_eq!(app_config.warp_role_label, "custom.role");
+        assert!(app_config.docker_event_label_filter);
+        assert!(app_config.dangerously_allow_host_network);
+        assert_eq!(app_config.route_precedence, "override");
+        assert_eq!(app_config.route_metric_default, 0);
+        assert_eq!(
+            app_config.route_metric_network,
+            crate::config::DEFAULT_ROUTE_METRIC_NETWORK
+        );
+        assert_eq!(app_config.hostname_refresh_interval, 120);
+        assert_eq!(app_config.paused_warp_policy, "failover");
+        assert_eq!(app_config.fanout_concurrency, 4);
+        assert_eq!(
+            app_config.fanout_target_timeout,
+            crate::config::DEFAULT_FANOUT_TARGET_TIMEOUT
+        );
+        assert_eq!(app_config.inventory_resync_interval, 30);
+        assert!(app_config.warp_forwarding);
+        assert_eq!(
+            app_config.warp_egress_interface,
+            crate::config::DEFAULT_WARP_EGRESS_INTERFACE
+        );
+        assert_eq!(
+            app_config.hostname_history_size,
+            crate::config::DEFAULT_HOSTNAME_HISTORY_SIZE
+        );
         assert_eq!(app_config.log_level, "trace");
-        assert_eq!(app_config.docker_socket, "/custom/docker.sock");
+        assert_eq!(
+            app_config.docker_socket.as_deref(),
+            Some("/custom/docker.sock")
+        );
+        assert_eq!(app_config.container_runtime, "podman");
+        assert_eq!(
+            app_config.docker_host,
+            Some("tcp://docker.example.com:2376".to_string())
+        );
+        assert_eq!(app_config.docker_tls_ca, Some("/certs/ca.pem".to_string()));
+        assert_eq!(app_config.docker_tls_cert, None);
+        assert_eq!(app_config.docker_api_version, Some("1.40".to_string()));
+        assert_eq!(app_config.docker_timeout, 45);
         assert_eq!(app_config.routing_rules.len(), 1);
         assert_eq!(app_config.routing_rules[0].destination, "172.16.0.0/12");
         assert_eq!(
@@ -260,5 +581,8 @@ invalid toml content [[[
             Some("udp".to_string())
         );
         assert_eq!(app_config.routing_rules[0].port_range, Some((53, 53)));
+        assert_eq!(app_config.routing_rules[0].metric, Some(30));
+        assert_eq!(app_config.routing_rules[0].mtu, Some(1400));
+        assert_eq!(app_config.routing_rules[0].onlink, Some(true));
     }
 }
@@ -0,0 +1,349 @@
+//! Typed event bus between the event monitor and its subscribers
+//!
+//! Every subscriber gets a queue of its own, which publishing fills without
+//! waiting on anyone. A subscriber is registered by the time `subscribe`
+//! returns, so it sees every event published afterwards, and a slow subscriber
+//! only holds up its own queue.
+//!
+//! Handlers subscribed through [`EventBus::subscribe_handler`] each run in their
+//! own task, processing their events in order from an unbounded queue, so they
+//! never miss one and leave routes stale. They are expected to keep up by handing
+//! work off, as the work scheduler does, whose queued reconciles absorb repeated
+//! events. Receivers from [`EventBus::subscribe`] have a bounded queue instead:
+//! once it's full, events for them are dropped and counted in
+//! [`EventBus::dropped`], and it's up to them to catch up.
+
+use crate::docker::{ContainerEvent, EventHandler};
+use crate::error::EventError;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::runtime::Handle;
+use tokio::sync::mpsc::{self, error::TrySendError, Receiver, Sender, UnboundedSender};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, warn};
+
+/// Events queued for a receiver by default before further ones are dropped
+pub const DEFAULT_SUBSCRIBER_QUEUE_CAPACITY: usize = 1024;
+
+/// Queue of a subscriber
+enum Subscriber {
+    /// A receiver, dropping events once full
+    Bounded(Sender<ContainerEvent>),
+    /// A handler's task, which must not miss events
+    Unbounded(UnboundedSender<ContainerEvent>),
+}
+
+/// Broadcasts container events to any number of subscribers
+#[derive(Clone)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+    capacity: usize,
+    dropped: Arc<AtomicU64>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_SUBSCRIBER_QUEUE_CAPACITY)
+    }
+}
+
+impl EventBus {
+    /// Create a bus without subscribers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a bus queueing up to `capacity` events per receiver
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            capacity: capacity.max(1),
+            dropped: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Subscribe to the events published from now on. Dropping the receiver
+    /// unsubscribes.
+    pub fn subscribe(&self) -> Receiver<ContainerEvent> {
+        let (sender, receiver) = mpsc::channel(self.capacity);
+        self.subscribers
+            .lock()
+            .expect("event bus lock poisoned")
+            .push(Subscriber::Bounded(sender));
+        receiver
+    }
+
+    /// Subscribe a handler, run in a task of its own on the current runtime until
+    /// the bus is dropped
+    pub fn subscribe_handler(
+        &self,
+        handler: Box<dyn EventHandler>,
+    ) -> Result<JoinHandle<()>, EventError> {
+        let runtime = Handle::try_current().map_err(|e| {
+            EventError::ProcessingFailed(format!("Cannot run event handler: {}", e))
+        })?;
+        let (sender, mut events) = mpsc::unbounded_channel();
+        self.subscribers
+            .lock()
+            .expect("event bus lock poisoned")
+            .push(Subscriber::Unbounded(sender));
+        Ok(runtime.spawn(async move {
+            while let Some(event) = events.recv().await {
+                dispatch(handler.as_ref(), event).await;
+            }
+        }))
+    }
+
+    /// Publish an event to every subscriber, returning how many it was queued for
+    pub fn publish(&self, event: ContainerEvent) -> usize {
+        let mut subscribers = self.subscribers.lock().expect("event bus lock poisoned");
+        let mut queued = 0;
+        subscribers.retain(|subscriber| {
+            let sent = match subscriber {
+                Subscriber::Bounded(sender) => sender.try_send(event.clone()),
+                Subscriber::Unbounded(sender) => sender
+                    .send(event.clone())
+                    .map_err(|e| TrySendError::Closed(e.0)),
+            };
+            match sent {
+                Ok(()) => {
+                    queued += 1;
+                    true
+                }
+                Err(TrySendError::Full(_)) => {
+                    let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                    warn!(
+                        "Dropped event for container {}: a receiver's queue of {} is full ({} dropped so far)",
+                        event.container().id,
+                        self.capacity,
+                        dropped
+                    );
+                    true
+                }
+                Err(TrySendError::Closed(_)) => false,
+            }
+        });
+        debug!(
+            "Published event for container {} to {} subscribers",
+            event.container().id,
+            queued
+        );
+        queued
+    }
+
+    /// Number of events dropped for receivers whose queue was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Number of subscribers, not counting those gone since the last publish
+    pub fn subscribers(&self) -> usize {
+        self.subscribers
+            .lock()
+            .expect("event bus lock poisoned")
+            .len()
+    }
+}
+
+/// Hand an event to a handler, logging rather than propagating its failure
+async fn dispatch(handler: &dyn EventHandler, event: ContainerEvent) {
+    match event {
+        ContainerEvent::Start(event) => {
+            if let Err(e) = handler.handle_container_start(event).await {
+                error!("Handler failed to process container start event: {}", e);
+            }
+        }
+        ContainerEvent::Lifecycle(event) => {
+            let action = event.action.clone();
+            if let Err(e) = handler.handle_container_lifecycle(event).await {
+                error!(
+                    "Handler failed to process container {} event: {}",
+                    action, e
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker::{
+        ContainerInfo, ContainerLifecycleEvent, ContainerStartEvent, ContainerState,
+        LifecycleAction,
+    };
+    use crate::error::HandlerError;
+    use std::collections::HashMap;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::time::Duration;
+    use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+    use tokio::sync::Semaphore;
+    use tokio::time::timeout;
+
+    /// Handler forwarding the IDs of started containers, after taking a permit if gated
+    struct ForwardingHandler {
+        started: UnboundedSender<String>,
+        gate: Option<Arc<Semaphore>>,
+        fail: bool,
+    }
+
+    impl EventHandler for ForwardingHandler {
+        fn handle_container_start(
+            &self,
+            event: ContainerStartEvent,
+        ) -> Pin<Box<dyn Future<Output = Result<(), HandlerError>> + Send + '_>> {
+            Box::pin(async move {
+                if let Some(gate) = &self.gate {
+                    gate.acquire().await.unwrap().forget();
+                }
+                self.started.send(event.container.id).unwrap();
+                if self.fail {
+                    return Err(HandlerError::ExecutionFailed("boom".to_string()));
+                }
+                Ok(())
+            })
+        }
+    }
+
+    fn forwarding_handler(
+        gate: Option<Arc<Semaphore>>,
+        fail: bool,
+    ) -> (Box<ForwardingHandler>, UnboundedReceiver<String>) {
+        let (started, receiver) = mpsc::unbounded_channel();
+        let handler = ForwardingHandler {
+            started,
+            gate,
+            fail,
+        };
+        (Box::new(handler), receiver)
+    }
+
+    fn start_event(id: &str) -> ContainerEvent {
+        ContainerEvent::Start(ContainerStartEvent {
+            container: ContainerInfo {
+                id: id.to_string(),
+                name: id.to_string(),
+                labels: HashMap::new(),
+                networks: vec![],
+                state: ContainerState::Running,
+                pid: Some(30),
+                network_mode: None,
+                sandbox_key: None,
+                started_at: None,
+            },
+        })
+    }
+
+    async fn next(receiver: &mut UnboundedReceiver<String>) -> String {
+        timeout(Duration::from_secs(1), receiver.recv())
+            .await
+            .expect("handler did not receive the event")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_publish_to_subscribers() {
+        let bus = EventBus::new();
+        assert_eq!(bus.publish(start_event("early")), 0);
+
+        let mut first = bus.subscribe();
+        let second = bus.subscribe();
+        assert_eq!(bus.subscribers(), 2);
+        assert_eq!(bus.publish(start_event("web")), 2);
+        assert_eq!(first.try_recv().unwrap().container().id, "web");
+        assert!(first.try_recv().is_err());
+
+        // Dropped subscribers are pruned on the next publish
+        drop(second);
+        let lifecycle = ContainerEvent::Lifecycle(ContainerLifecycleEvent {
+            container: start_event("db").container().clone(),
+            action: LifecycleAction::Restart,
+        });
+        assert_eq!(bus.publish(lifecycle), 1);
+        assert!(matches!(
+            first.try_recv().unwrap(),
+            ContainerEvent::Lifecycle(ContainerLifecycleEvent {
+                action: LifecycleAction::Restart,
+                ..
+            })
+        ));
+        assert!(first.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_drops_events() {
+        let bus = EventBus::with_capacity(2);
+        let mut slow = bus.subscribe();
+        let mut fast = bus.subscribe();
+
+        assert_eq!(bus.publish(start_event("web")), 2);
+        assert_eq!(fast.try_recv().unwrap().container().id, "web");
+        assert_eq!(bus.publish(start_event("db")), 2);
+        assert_eq!(fast.try_recv().unwrap().container().id, "db");
+
+        // The slow subscriber's queue is full; it misses the event but stays subscribed
+        assert_eq!(bus.publish(start_event("api")), 1);
+        assert_eq!(bus.dropped(), 1);
+        assert_eq!(bus.subscribers(), 2);
+        assert_eq!(fast.try_recv().unwrap().container().id, "api");
+
+        assert_eq!(slow.try_recv().unwrap().container().id, "web");
+        assert_eq!(slow.try_recv().unwrap().container().id, "db");
+        assert!(slow.try_recv().is_err());
+        assert_eq!(bus.publish(start_event("cache")), 2);
+        assert_eq!(slow.try_recv().unwrap().container().id, "cache");
+    }
+
+    #[tokio::test]
+    async fn test_slow_handler_does_not_hold_up_others() {
+        let bus = EventBus::new();
+        let gate = Arc::new(Semaphore::new(0));
+        let (slow, mut slow_started) = forwarding_handler(Some(Arc::clone(&gate)), false);
+        let (fast, mut fast_started) = forwarding_handler(None, true);
+        bus.subscribe_handler(slow).unwrap();
+        bus.subscribe_handler(fast).unwrap();
+
+        // Registered before subscribe_handler returns, without yielding
+        assert_eq!(bus.publish(start_event("web")), 2);
+        assert_eq!(bus.publish(start_event("db")), 2);
+
+        // The failing handler keeps receiving events while the slow one is stuck
+        assert_eq!(next(&mut fast_started).await, "web");
+        assert_eq!(next(&mut fast_started).await, "db");
+        assert!(slow_started.try_recv().is_err());
+
+        // The slow handler catches up in order
+        gate.add_permits(2);
+        assert_eq!(next(&mut slow_started).await, "web");
+        assert_eq!(next(&mut slow_started).await, "db");
+    }
+
+    #[tokio::test]
+    async fn test_slow_handler_misses_no_events() {
+        let bus = EventBus::with_capacity(1);
+        let gate = Arc::new(Semaphore::new(0));
+        let (slow, mut slow_started) = forwarding_handler(Some(Arc::clone(&gate)), false);
+        bus.subscribe_handler(slow).unwrap();
+
+        // Beyond the capacity of a receiver's queue, nothing is dropped for a handler
+        for id in ["web", "db", "api"] {
+            assert_eq!(bus.publish(start_event(id)), 1);
+        }
+        assert_eq!(bus.dropped(), 0);
+
+        gate.add_permits(3);
+        for id in ["web", "db", "api"] {
+            assert_eq!(next(&mut slow_started).await, id);
+        }
+    }
+
+    #[test]
+    fn test_subscribe_handler_outside_runtime() {
+        let (handler, _) = forwarding_handler(None, false);
+        assert!(matches!(
+            EventBus::new().subscribe_handler(handler),
+            Err(EventError::ProcessingFailed(_))
+        ));
+    }
+}
@@ -1,7 +1,20 @@
 //! Container classification logic
 
-use crate::docker::ContainerInfo;
+use crate::config::{
+    DEFAULT_ROUTE_ATTRIBUTE_LABEL_PREFIX, DEFAULT_ROUTE_METRIC_LABEL, DEFAULT_WARP_ROLE_LABEL,
+    DEFAULT_WARP_SELECTOR_LABEL,
+};
+use crate::docker::inventory::ContainerInventory;
+use crate::docker::selector::LabelSelector;
+use crate::docker::{ContainerInfo, ContainerState};
+use crate::routing::RouteAttributes;
 use regex::Regex;
+use tracing::{debug, warn};
+
+/// Label Docker Compose sets to the project a container belongs to
+pub const COMPOSE_PROJECT_LABEL: &str = "com.docker.compose.project";
+/// Label Docker Compose sets to the service a container belongs to
+pub const COMPOSE_SERVICE_LABEL: &str = "com.docker.compose.service";
 
 /// Container type classification
 #[derive(Debug, Clone, PartialEq)]
@@ -16,6 +29,10 @@ pub enum ContainerType {
 pub struct WarpContainerInfo {
     pub container: ContainerInfo,
     pub target_network: Option<String>,
+    /// Route attributes requested through the warp container's labels
+    pub route_attributes: RouteAttributes,
+    /// Selector for the containers this warp serves, from the warp selector label
+    pub selector: Option<LabelSelector>,
 }
 
 /// Target container information
@@ -23,6 +40,8 @@ pub struct WarpContainerInfo {
 pub struct TargetContainerInfo {
     pub container: ContainerInfo,
     pub warp_target: String,
+    /// Route metric requested through the target container's labels
+    pub route_metric: Option<u32>,
 }
 
 /// Container classifier trait
@@ -36,11 +55,153 @@ pub trait ContainerClassifier {
     /// Extract network preference from container labels
     fn extract_network_preference(&self, container: &ContainerInfo) -> Option<String>;
 
+    /// Extract route attributes from container labels
+    fn extract_route_attributes(&self, container: &ContainerInfo) -> RouteAttributes;
+
+    /// Extract route metric from container labels
+    fn extract_route_metric(&self, container: &ContainerInfo) -> Option<u32>;
+
+    /// Extract the selector of served containers from warp container labels
+    fn extract_target_selector(&self, container: &ContainerInfo) -> Option<LabelSelector>;
+
+    /// Find the warp whose selector serves a container without a target label
+    fn select_warp_by_selector<'a>(
+        &self,
+        container: &ContainerInfo,
+        warps: &'a [WarpContainerInfo],
+    ) -> Option<&'a WarpContainerInfo>;
+
+    /// Find the running containers a warp serves through its selector
+    fn select_targets_for_warp(
+        &self,
+        warp: &WarpContainerInfo,
+        containers: &[ContainerInfo],
+        warps: &[WarpContainerInfo],
+    ) -> Vec<TargetContainerInfo>;
+
+    /// Find the warp container a target container refers to
+    fn find_warp_for_target<'a>(
+        &self,
+        target: &TargetContainerInfo,
+        warps: &'a [WarpContainerInfo],
+    ) -> Option<&'a WarpContainerInfo>;
+
+    /// Find the warp containers of an inventory a target container's label may
+    /// refer to. Only the containers the target label can name are classified.
+    fn find_warps_in_inventory(
+        &self,
+        target: &TargetContainerInfo,
+        inventory: &ContainerInventory,
+    ) -> Vec<WarpContainerInfo> {
+        let mut candidates = inventory.in_service(&target.warp_target);
+        if let Some(project) = target.container.labels.get(COMPOSE_PROJECT_LABEL) {
+            candidates.extend(inventory.in_project(project));
+        }
+        candidates.extend(inventory.get_by_name(&target.warp_target));
+
+        candidates
+            .iter()
+            .filter_map(|container| match self.classify_container(container) {
+                ContainerType::WarpContainer(warp) => Some(warp),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Find the warp container a target container refers to among an inventory's
+    /// containers
+    fn find_warp_in_inventory(
+        &self,
+        target: &TargetContainerInfo,
+        inventory: &ContainerInventory,
+    ) -> Option<WarpContainerInfo> {
+        let warps = self.find_warps_in_inventory(target, inventory);
+        self.find_warp_for_target(target, &warps).cloned()
+    }
+
+    /// Find the warps of an inventory that serve containers through a selector,
+    /// through its index of containers carrying one. The inventory should index the
+    /// classifier's selector label.
+    fn find_selector_warps_in_inventory(
+        &self,
+        inventory: &ContainerInventory,
+    ) -> Vec<WarpContainerInfo> {
+        inventory
+            .selecting()
+            .iter()
+            .filter(|candidate| self.extract_target_selector(candidate).is_some())
+            .filter_map(|candidate| match self.classify_container(candidate) {
+                ContainerType::WarpContainer(warp) => Some(warp),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Find the running containers a warp serves among an inventory's containers:
+    /// those whose target label refers to it, through the target label index, and
+    /// those its selector picks
+    fn find_targets_in_inventory(
+        &self,
+        warp: &WarpContainerInfo,
+        inventory: &ContainerInventory,
+    ) -> Vec<TargetContainerInfo> {
+        let container = &warp.container;
+        let mut names = vec![container.name.clone()];
+        names.extend(container.labels.get(COMPOSE_SERVICE_LABEL).cloned());
+        names.extend(container.swarm_task().map(|task| task.service_name));
+        names.sort();
+        names.dedup();
+
+        let mut targets: Vec<TargetContainerInfo> = names
+            .iter()
+            .flat_map(|name| inventory.targets_of(name))
+            .filter(|candidate| candidate.state.is_active())
+            .filter_map(|candidate| match self.classify_container(&candidate) {
+                ContainerType::TargetContainer(target) => Some(target),
+                _ => None,
+            })
+            // The same name may refer to another warp, e.g. in another compose project
+            .filter(|target| {
+                self.find_warp_in_inventory(target, inventory)
+                    .is_some_and(|found| found.container.id == container.id)
+            })
+            .collect();
+
+        if warp.selector.is_some() {
+            let warps = self.find_selector_warps_in_inventory(inventory);
+            targets.extend(self.select_targets_for_warp(warp, &inventory.containers(), &warps));
+        }
+        targets
+    }
+
     /// Check if a container name matches the warp pattern
     fn is_warp_container(&self, container: &ContainerInfo) -> bool;
 
     /// Check if a container has target labels
     fn is_target_container(&self, container: &ContainerInfo) -> bool;
+
+    /// Labels that make a container a warp or a target, for server-side event filtering
+    fn event_labels(&self) -> Vec<String>;
+
+    /// Check whether a container known only by name and labels, e.g. from an event's
+    /// actor attributes, may matter for routing: a warp, a target, or a container a
+    /// warp of the inventory serves through its selector. Without an inventory the
+    /// selectors are unknown, so any container may matter.
+    fn is_relevant(
+        &self,
+        container: &ContainerInfo,
+        inventory: Option<&ContainerInventory>,
+    ) -> bool {
+        if self.is_warp_container(container) || self.is_target_container(container) {
+            return true;
+        }
+        let Some(inventory) = inventory else {
+            return true;
+        };
+
+        let warps = self.find_selector_warps_in_inventory(inventory);
+        self.select_warp_by_selector(container, &warps).is_some()
+    }
 }
 
 /// Default container classifier implementation with regex support
@@ -49,6 +210,11 @@ pub struct DefaultContainerClassifier {
     warp_regex: Option<Regex>,
     target_label: String,
     network_preference_label: String,
+    route_attribute_label_prefix: String,
+    route_metric_label: String,
+    warp_selector_label: String,
+    warp_role_label: String,
+    local_node_id: Option<String>,
 }
 
 impl DefaultContainerClassifier {
@@ -72,6 +238,11 @@ impl DefaultContainerClassifier {
             warp_regex,
             target_label,
             network_preference_label,
+            route_attribute_label_prefix: DEFAULT_ROUTE_ATTRIBUTE_LABEL_PREFIX.to_string(),
+            route_metric_label: DEFAULT_ROUTE_METRIC_LABEL.to_string(),
+            warp_selector_label: DEFAULT_WARP_SELECTOR_LABEL.to_string(),
+            warp_role_label: DEFAULT_WARP_ROLE_LABEL.to_string(),
+            local_node_id: None,
         })
     }
 
@@ -86,6 +257,54 @@ impl DefaultContainerClassifier {
             warp_regex: None,
             target_label,
             network_preference_label,
+            route_attribute_label_prefix: DEFAULT_ROUTE_ATTRIBUTE_LABEL_PREFIX.to_string(),
+            route_metric_label: DEFAULT_ROUTE_METRIC_LABEL.to_string(),
+            warp_selector_label: DEFAULT_WARP_SELECTOR_LABEL.to_string(),
+            warp_role_label: DEFAULT_WARP_ROLE_LABEL.to_string(),
+            local_node_id: None,
+        }
+    }
+
+    /// Set the label prefix used to read route attributes from warp containers
+    pub fn with_route_attribute_label_prefix(mut self, prefix: String) -> Self {
+        self.route_attribute_label_prefix = prefix;
+        self
+    }
+
+    /// Set the label used to read the route metric from target containers
+    pub fn with_route_metric_label(mut self, label: String) -> Self {
+        self.route_metric_label = label;
+        self
+    }
+
+    /// Set the label used to read the selector of served containers from warp containers
+    pub fn with_warp_selector_label(mut self, label: String) -> Self {
+        self.warp_selector_label = label;
+        self
+    }
+
+    /// Set the label marking containers as warps regardless of their name
+    pub fn with_warp_role_label(mut self, label: String) -> Self {
+        self.warp_role_label = label;
+        self
+    }
+
+    /// Set the swarm node ID of the local daemon, so that tasks of other nodes are ignored
+    pub fn with_local_node_id(mut self, node_id: Option<String>) -> Self {
+        self.local_node_id = node_id;
+        self
+    }
+
+    /// Check whether a container's namespace lives on the local node.
+    ///
+    /// Only swarm tasks scheduled on another node than the local daemon's are remote.
+    fn is_local(&self, container: &ContainerInfo) -> bool {
+        match (
+            container.swarm_task().and_then(|task| task.node_id),
+            &self.local_node_id,
+        ) {
+            (Some(task_node), Some(local_node)) => task_node == *local_node,
+            _ => true,
         }
     }
 
@@ -143,26 +362,36 @@ impl DefaultContainerClassifier {
 
 impl ContainerClassifier for DefaultContainerClassifier {
     fn classify_container(&self, container: &ContainerInfo) -> ContainerType {
+        // Namespaces of tasks on other swarm nodes can't be entered from here
+        if !self.is_local(container) {
+            debug!(
+                "Ignoring container {} running on another swarm node",
+                container.name
+            );
+            return ContainerType::Ignored;
+        }
+
         // Check if it's a warp container by name pattern
-        if self.is_warp_container(container) {
-            if self.validate_warp_container(container) {
-                let target_network = self.extract_network_preference(container);
-                return ContainerType::WarpContainer(WarpContainerInfo {
-                    container: container.clone(),
-                    target_network,
-                });
-            }
+        if self.is_warp_container(container) && self.validate_warp_container(container) {
+            let target_network = self.extract_network_preference(container);
+            let route_attributes = self.extract_route_attributes(container);
+            let selector = self.extract_target_selector(container);
+            return ContainerType::WarpContainer(WarpContainerInfo {
+                container: container.clone(),
+                target_network,
+                route_attributes,
+                selector,
+            });
         }
 
         // Check if it's a target container by label
-        if self.is_target_container(container) {
-            if self.validate_target_container(container) {
-                if let Some(warp_target) = self.extract_warp_target(container) {
-                    return ContainerType::TargetContainer(TargetContainerInfo {
-                        container: container.clone(),
-                        warp_target,
-                    });
-                }
+        if self.is_target_container(container) && self.validate_target_container(container) {
+            if let Some(warp_target) = self.extract_warp_target(container) {
+                return ContainerType::TargetContainer(TargetContainerInfo {
+                    container: container.clone(),
+                    warp_target,
+                    route_metric: self.extract_route_metric(container),
+                });
             }
         }
 
@@ -180,19 +409,192 @@ impl ContainerClassifier for DefaultContainerClassifier {
             .cloned()
     }
 
+    fn extract_route_attributes(&self, container: &ContainerInfo) -> RouteAttributes {
+        let mut attributes = RouteAttributes::default();
+
+        for (label, value) in &container.labels {
+            if let Some(key) = label.strip_prefix(&self.route_attribute_label_prefix) {
+                // Invalid values are skipped so that a typo doesn't take the warp out of service
+                if let Err(e) = attributes.set(key, value) {
+                    warn!(
+                        "Ignoring route attribute label {} on container {}: {}",
+                        label, container.name, e
+                    );
+                }
+            }
+        }
+
+        if let Err(e) = attributes.validate() {
+            warn!(
+                "Ignoring route attribute labels on container {}: {}",
+                container.name, e
+            );
+            return RouteAttributes::default();
+        }
+
+        attributes
+    }
+
+    fn extract_route_metric(&self, container: &ContainerInfo) -> Option<u32> {
+        let value = container.labels.get(&self.route_metric_label)?;
+        match value.trim().parse::<u32>() {
+            Ok(metric) => Some(metric),
+            Err(_) => {
+                warn!(
+                    "Ignoring route metric label {} on container {}: invalid metric '{}'",
+                    self.route_metric_label, container.name, value
+                );
+                None
+            }
+        }
+    }
+
+    fn extract_target_selector(&self, container: &ContainerInfo) -> Option<LabelSelector> {
+        let value = container.labels.get(&self.warp_selector_label)?;
+        match value.parse() {
+            Ok(selector) => Some(selector),
+            Err(e) => {
+                warn!(
+                    "Ignoring warp selector label {} on container {}: {}",
+                    self.warp_selector_label, container.name, e
+                );
+                None
+            }
+        }
+    }
+
+    /// An explicit target label always takes precedence over warp selectors, and
+    /// warp containers are never selected. When several selectors match, the most
+    /// specific one (most requirements) wins, with ties broken by warp name.
+    fn select_warp_by_selector<'a>(
+        &self,
+        container: &ContainerInfo,
+        warps: &'a [WarpContainerInfo],
+    ) -> Option<&'a WarpContainerInfo> {
+        if self.is_target_container(container) || self.is_warp_container(container) {
+            return None;
+        }
+
+        warps
+            .iter()
+            .filter(|warp| warp.container.id != container.id)
+            .filter_map(|warp| {
+                let selector = warp.selector.as_ref()?;
+                selector
+                    .matches(&container.labels)
+                    .then_some((selector.specificity(), warp))
+            })
+            .min_by(|(a_specificity, a), (b_specificity, b)| {
+                b_specificity
+                    .cmp(a_specificity)
+                    .then_with(|| a.container.name.cmp(&b.container.name))
+            })
+            .map(|(_, warp)| warp)
+    }
+
+    fn select_targets_for_warp(
+        &self,
+        warp: &WarpContainerInfo,
+        containers: &[ContainerInfo],
+        warps: &[WarpContainerInfo],
+    ) -> Vec<TargetContainerInfo> {
+        if warp.selector.is_none() {
+            return Vec::new();
+        }
+
+        containers
+            .iter()
+            .filter(|container| container.state.is_active() && !container.networks.is_empty())
+            .filter(|container| {
+                self.select_warp_by_selector(container, warps)
+                    .is_some_and(|selected| selected.container.id == warp.container.id)
+            })
+            .map(|container| TargetContainerInfo {
+                container: container.clone(),
+                warp_target: warp.container.name.clone(),
+                route_metric: self.extract_route_metric(container),
+            })
+            .collect()
+    }
+
+    /// The target label is first matched against compose service names within the
+    /// target's compose project, so `network.warp.target=warp` finds `project-warp-1`
+    /// without colliding with other stacks' `warp` services. Otherwise it's matched
+    /// against swarm service names, then container names.
+    fn find_warp_for_target<'a>(
+        &self,
+        target: &TargetContainerInfo,
+        warps: &'a [WarpContainerInfo],
+    ) -> Option<&'a WarpContainerInfo> {
+        if let Some(project) = target.container.labels.get(COMPOSE_PROJECT_LABEL) {
+            // With scaled services, pick a replica deterministically
+            let service_match = pick_replica(warps.iter().filter(|warp| {
+                let labels = &warp.container.labels;
+                labels.get(COMPOSE_PROJECT_LABEL) == Some(project)
+                    && labels.get(COMPOSE_SERVICE_LABEL) == Some(&target.warp_target)
+            }));
+            if service_match.is_some() {
+                return service_match;
+            }
+        }
+
+        // Swarm task containers are named `<service>.<slot>.<task id>`, and exited
+        // tasks are kept around next to their replacements
+        let task_match = pick_replica(warps.iter().filter(|warp| {
+            warp.container
+                .swarm_task()
+                .is_some_and(|task| task.service_name == target.warp_target)
+        }));
+        if task_match.is_some() {
+            return task_match;
+        }
+
+        warps
+            .iter()
+            .find(|warp| warp.container.name == target.warp_target)
+    }
+
+    /// Swarm task containers also match by their service name, and any container
+    /// with the warp role label is a warp
     fn is_warp_container(&self, container: &ContainerInfo) -> bool {
-        self.matches_warp_pattern(&container.name)
+        container.labels.contains_key(&self.warp_role_label)
+            || self.matches_warp_pattern(&container.name)
+            || container
+                .swarm_task()
+                .is_some_and(|task| self.matches_warp_pattern(&task.service_name))
     }
 
     fn is_target_container(&self, container: &ContainerInfo) -> bool {
         container.labels.contains_key(&self.target_label)
     }
+
+    fn event_labels(&self) -> Vec<String> {
+        vec![self.target_label.clone(), self.warp_role_label.clone()]
+    }
+}
+
+/// Pick one of the replicas of a scaled warp: the first by name among the running
+/// ones, else the paused ones. Stopped replicas keep their networks, so they are
+/// only picked when no other is left.
+fn pick_replica<'a>(
+    replicas: impl Iterator<Item = &'a WarpContainerInfo>,
+) -> Option<&'a WarpContainerInfo> {
+    replicas.min_by_key(|warp| {
+        let rank = match warp.container.state {
+            ContainerState::Running => 0,
+            ContainerState::Paused => 1,
+            _ => 2,
+        };
+        (rank, &warp.container.name)
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::docker::{ContainerState, NetworkInfo};
+    use crate::docker::{
+        NetworkInfo, SWARM_NODE_ID_LABEL, SWARM_SERVICE_NAME_LABEL, SWARM_TASK_ID_LABEL,
+    };
     use ipnetwork::IpNetwork;
     use std::collections::HashMap;
     use std::net::IpAddr;
@@ -210,6 +612,9 @@ mod tests {
             networks,
             state: ContainerState::Running,
             pid: Some(30),
+            network_mode: None,
+            sandbox_key: None,
+            started_at: None,
         }
     }
 
@@ -432,6 +837,48 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_warp_container_with_route_attributes() {
+        let classifier = DefaultContainerClassifier::with_simple_pattern(
+            "warp-*".to_string(),
+            "warp.target".to_string(),
+            "warp.network".to_string(),
+        )
+        .with_route_attribute_label_prefix("warp.route.".to_string());
+
+        let mut labels = HashMap::new();
+        labels.insert("warp.route.mtu".to_string(), "1280".to_string());
+        labels.insert("warp.route.advmss".to_string(), "1240".to_string());
+        labels.insert("warp.route.onlink".to_string(), "true".to_string());
+        labels.insert("warp.route.scope".to_string(), "galaxy".to_string()); // invalid, ignored
+        let container = create_test_container(
+            "warp-proxy-1",
+            labels,
+            vec![create_test_network("bridge", "172.17.0.2")],
+        );
+
+        match classifier.classify_container(&container) {
+            ContainerType::WarpContainer(info) => {
+                assert_eq!(info.route_attributes.mtu, Some(1280));
+                assert_eq!(info.route_attributes.advmss, Some(1240));
+                assert_eq!(info.route_attributes.onlink, Some(true));
+                assert_eq!(info.route_attributes.scope, None);
+            }
+            _ => panic!("Expected WarpContainer classification"),
+        }
+
+        // Inconsistent attributes are dropped as a whole
+        let mut labels = HashMap::new();
+        labels.insert("warp.route.mtu".to_string(), "1280".to_string());
+        labels.insert("warp.route.advmss".to_string(), "1400".to_string());
+        let container = create_test_container(
+            "warp-proxy-2",
+            labels,
+            vec![create_test_network("bridge", "172.17.0.3")],
+        );
+        assert!(classifier.extract_route_attributes(&container).is_empty());
+    }
+
     #[test]
     fn test_target_container_classification() {
         let classifier = DefaultContainerClassifier::with_simple_pattern(
@@ -587,11 +1034,15 @@ mod tests {
         let warp_info1 = WarpContainerInfo {
             container: container1.clone(),
             target_network: None,
+            route_attributes: RouteAttributes::default(),
+            selector: None,
         };
 
         let warp_info2 = WarpContainerInfo {
             container: container2.clone(),
             target_network: None,
+            route_attributes: RouteAttributes::default(),
+            selector: None,
         };
 
         assert_eq!(
@@ -602,11 +1053,13 @@ mod tests {
         let target_info1 = TargetContainerInfo {
             container: container1,
             warp_target: "warp-1".to_string(),
+            route_metric: None,
         };
 
         let target_info2 = TargetContainerInfo {
             container: container2,
             warp_target: "warp-1".to_string(),
+            route_metric: None,
         };
 
         assert_eq!(
@@ -617,6 +1070,508 @@ mod tests {
         assert_eq!(ContainerType::Ignored, ContainerType::Ignored);
     }
 
+    #[test]
+    fn test_target_container_with_route_metric() {
+        let classifier = DefaultContainerClassifier::with_simple_pattern(
+            "warp-*".to_string(),
+            "warp.target".to_string(),
+            "warp.network".to_string(),
+        )
+        .with_route_metric_label("warp.metric".to_string());
+
+        let mut labels = HashMap::new();
+        labels.insert("warp.target".to_string(), "warp-1".to_string());
+        labels.insert("warp.metric".to_string(), "25".to_string());
+        let container = create_test_container(
+            "app",
+            labels,
+            vec![create_test_network("bridge", "172.17.0.3")],
+        );
+
+        match classifier.classify_container(&container) {
+            ContainerType::TargetContainer(info) => assert_eq!(info.route_metric, Some(25)),
+            _ => panic!("Expected TargetContainer classification"),
+        }
+
+        // Invalid metrics are ignored
+        let mut labels = HashMap::new();
+        labels.insert("warp.target".to_string(), "warp-1".to_string());
+        labels.insert("warp.metric".to_string(), "high".to_string());
+        let container = create_test_container(
+            "app",
+            labels,
+            vec![create_test_network("bridge", "172.17.0.3")],
+        );
+
+        match classifier.classify_container(&container) {
+            ContainerType::TargetContainer(info) => assert_eq!(info.route_metric, None),
+            _ => panic!("Expected TargetContainer classification"),
+        }
+    }
+
+    fn create_compose_container(name: &str, project: &str, service: &str) -> ContainerInfo {
+        let mut labels = HashMap::new();
+        labels.insert(COMPOSE_PROJECT_LABEL.to_string(), project.to_string());
+        labels.insert(COMPOSE_SERVICE_LABEL.to_string(), service.to_string());
+        create_test_container(
+            name,
+            labels,
+            vec![create_test_network("bridge", "172.17.0.2")],
+        )
+    }
+
+    fn as_warp(container: ContainerInfo) -> WarpContainerInfo {
+        WarpContainerInfo {
+            container,
+            target_network: None,
+            route_attributes: RouteAttributes::default(),
+            selector: None,
+        }
+    }
+
+    fn as_target(container: ContainerInfo, warp_target: &str) -> TargetContainerInfo {
+        TargetContainerInfo {
+            container,
+            warp_target: warp_target.to_string(),
+            route_metric: None,
+        }
+    }
+
+    #[test]
+    fn test_find_warp_for_target_compose_project() {
+        let classifier = DefaultContainerClassifier::with_simple_pattern(
+            "*warp*".to_string(),
+            "warp.target".to_string(),
+            "warp.network".to_string(),
+        );
+        let warps = vec![
+            as_warp(create_compose_container("shop-warp-1", "shop", "warp")),
+            as_warp(create_compose_container("blog-warp-2", "blog", "warp")),
+            as_warp(create_compose_container("blog-warp-1", "blog", "warp")),
+            as_warp(create_test_container(
+                "warp",
+                HashMap::new(),
+                vec![create_test_network("bridge", "172.17.0.9")],
+            )),
+        ];
+
+        // Service name resolved within the target's own project
+        let target = as_target(
+            create_compose_container("shop-app-1", "shop", "app"),
+            "warp",
+        );
+        let warp = classifier.find_warp_for_target(&target, &warps).unwrap();
+        assert_eq!(warp.container.name, "shop-warp-1");
+
+        // Scaled services resolve to the same replica every time
+        let target = as_target(
+            create_compose_container("blog-app-1", "blog", "app"),
+            "warp",
+        );
+        let warp = classifier.find_warp_for_target(&target, &warps).unwrap();
+        assert_eq!(warp.container.name, "blog-warp-1");
+
+        // Running replicas are preferred over stopped and paused ones
+        for state in [ContainerState::Stopped, ContainerState::Paused] {
+            let mut warps = warps.clone();
+            warps[2].container.state = state;
+            let warp = classifier.find_warp_for_target(&target, &warps).unwrap();
+            assert_eq!(warp.container.name, "blog-warp-2");
+        }
+
+        // Projects without a warp service fall back to the container name
+        let target = as_target(
+            create_compose_container("wiki-app-1", "wiki", "app"),
+            "warp",
+        );
+        let warp = classifier.find_warp_for_target(&target, &warps).unwrap();
+        assert_eq!(warp.container.name, "warp");
+
+        // Exact container names work across projects
+        let target = as_target(
+            create_compose_container("wiki-app-1", "wiki", "app"),
+            "shop-warp-1",
+        );
+        let warp = classifier.find_warp_for_target(&target, &warps).unwrap();
+        assert_eq!(warp.container.name, "shop-warp-1");
+
+        // Services of other projects are not matched
+        let warps = vec![as_warp(create_compose_container(
+            "shop-warp-1",
+            "shop",
+            "warp",
+        ))];
+        let target = as_target(
+            create_compose_container("blog-app-1", "blog", "app"),
+            "warp",
+        );
+        assert!(classifier.find_warp_for_target(&target, &warps).is_none());
+
+        // Targets outside of compose only match container names
+        let target = as_target(
+            create_test_container(
+                "app",
+                HashMap::new(),
+                vec![create_test_network("bridge", "172.17.0.3")],
+            ),
+            "warp",
+        );
+        assert!(classifier.find_warp_for_target(&target, &warps).is_none());
+    }
+
+    #[test]
+    fn test_find_in_inventory() {
+        let classifier = DefaultContainerClassifier::with_simple_pattern(
+            "*-warp-1".to_string(),
+            "warp.target".to_string(),
+            "warp.network".to_string(),
+        );
+        let inventory = ContainerInventory::new("warp.target");
+        let shop_warp = create_compose_container("shop-warp-1", "shop", "warp");
+        let mut shop_app = create_compose_container("shop-app-1", "shop", "app");
+        shop_app
+            .labels
+            .insert("warp.target".to_string(), "warp".to_string());
+        let mut blog_app = create_compose_container("blog-app-1", "blog", "app");
+        blog_app
+            .labels
+            .insert("warp.target".to_string(), "warp".to_string());
+        for container in [
+            shop_warp.clone(),
+            create_compose_container("blog-warp-1", "blog", "warp"),
+            shop_app.clone(),
+            blog_app,
+        ] {
+            inventory.upsert(container);
+        }
+
+        let target = as_target(shop_app, "warp");
+        let warp = classifier
+            .find_warp_in_inventory(&target, &inventory)
+            .unwrap();
+        assert_eq!(warp.container.name, "shop-warp-1");
+
+        // Targets of the same service name in other projects belong to their own warp
+        let targets = classifier.find_targets_in_inventory(&as_warp(shop_warp), &inventory);
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].container.name, "shop-app-1");
+
+        let target = as_target(
+            create_compose_container("wiki-app-1", "wiki", "app"),
+            "warp",
+        );
+        assert!(classifier
+            .find_warp_in_inventory(&target, &inventory)
+            .is_none());
+    }
+
+    #[test]
+    fn test_warp_role_label_and_relevance() {
+        let classifier = DefaultContainerClassifier::with_simple_pattern(
+            "warp-*".to_string(),
+            "warp.target".to_string(),
+            "warp.network".to_string(),
+        )
+        .with_warp_role_label("warp.role".to_string())
+        .with_warp_selector_label("warp.serve".to_string());
+        assert_eq!(classifier.event_labels(), vec!["warp.target", "warp.role"]);
+
+        // The role label makes a warp whatever the name
+        let network = vec![create_test_network("bridge", "172.17.0.2")];
+        let labelled = create_test_container(
+            "vpn",
+            HashMap::from([
+                ("warp.role".to_string(), "warp".to_string()),
+                ("warp.serve".to_string(), "app=frontend".to_string()),
+            ]),
+            network.clone(),
+        );
+        assert!(classifier.is_warp_container(&labelled));
+        assert!(matches!(
+            classifier.classify_container(&labelled),
+            ContainerType::WarpContainer(_)
+        ));
+
+        let inventory = ContainerInventory::new("warp.target").with_selector_label("warp.serve");
+        let frontend = create_test_container(
+            "front",
+            HashMap::from([("app".to_string(), "frontend".to_string())]),
+            vec![],
+        );
+        let other = create_test_container("db", HashMap::new(), vec![]);
+        let target = create_test_container(
+            "app",
+            HashMap::from([("warp.target".to_string(), "vpn".to_string())]),
+            vec![],
+        );
+
+        // Without an inventory, selectors can't be ruled out
+        assert!(classifier.is_relevant(&other, None));
+        assert!(classifier.is_relevant(&target, Some(&inventory)));
+        assert!(!classifier.is_relevant(&frontend, Some(&inventory)));
+
+        inventory.upsert(labelled);
+        assert!(classifier.is_relevant(&frontend, Some(&inventory)));
+        assert!(!classifier.is_relevant(&other, Some(&inventory)));
+    }
+
+    #[test]
+    fn test_find_selected_targets_in_inventory() {
+        let classifier = DefaultContainerClassifier::with_simple_pattern(
+            "warp-*".to_string(),
+            "warp.target".to_string(),
+            "warp.network".to_string(),
+        )
+        .with_warp_selector_label("warp.serve".to_string());
+        let warp = create_labeled_container("warp-1", &[("warp.serve", "app=frontend")]);
+        let specific =
+            create_labeled_container("warp-2", &[("warp.serve", "app=frontend,tier=edge")]);
+        let mut stopped = create_labeled_container("stopped", &[("app", "frontend")]);
+        stopped.state = ContainerState::Stopped;
+        let inventory = ContainerInventory::new("warp.target").with_selector_label("warp.serve");
+        for container in [
+            warp.clone(),
+            specific,
+            create_labeled_container("front", &[("app", "frontend")]),
+            create_labeled_container("edge", &[("app", "frontend"), ("tier", "edge")]),
+            create_labeled_container("named", &[("warp.target", "warp-1")]),
+            create_labeled_container("db", &[]),
+            stopped,
+        ] {
+            inventory.upsert(container);
+        }
+
+        let ContainerType::WarpContainer(warp) = classifier.classify_container(&warp) else {
+            panic!("Expected a warp container");
+        };
+        let mut names: Vec<String> = classifier
+            .find_targets_in_inventory(&warp, &inventory)
+            .into_iter()
+            .map(|target| {
+                assert_eq!(target.warp_target, "warp-1");
+                target.container.name
+            })
+            .collect();
+        names.sort();
+        // The more specific selector of warp-2 takes the edge container
+        assert_eq!(names, vec!["front", "named"]);
+    }
+
+    fn as_selecting_warp(name: &str, selector: &str) -> WarpContainerInfo {
+        let mut warp = as_warp(create_test_container(
+            name,
+            HashMap::new(),
+            vec![create_test_network("bridge", "172.17.0.2")],
+        ));
+        warp.selector = Some(selector.parse().unwrap());
+        warp
+    }
+
+    fn create_labeled_container(name: &str, pairs: &[(&str, &str)]) -> ContainerInfo {
+        let labels = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        create_test_container(
+            name,
+            labels,
+            vec![create_test_network("bridge", "172.17.0.3")],
+        )
+    }
+
+    #[test]
+    fn test_warp_container_with_selector() {
+        let classifier = DefaultContainerClassifier::with_simple_pattern(
+            "warp-*".to_string(),
+            "warp.target".to_string(),
+            "warp.network".to_string(),
+        )
+        .with_warp_selector_label("warp.serve".to_string());
+
+        let container = create_labeled_container("warp-1", &[("warp.serve", "app=frontend")]);
+        match classifier.classify_container(&container) {
+            ContainerType::WarpContainer(info) => {
+                assert_eq!(info.selector.unwrap().to_string(), "app=frontend");
+            }
+            _ => panic!("Expected WarpContainer classification"),
+        }
+
+        // Invalid selectors are ignored
+        let container = create_labeled_container("warp-1", &[("warp.serve", " , ")]);
+        match classifier.classify_container(&container) {
+            ContainerType::WarpContainer(info) => assert!(info.selector.is_none()),
+            _ => panic!("Expected WarpContainer classification"),
+        }
+    }
+
+    #[test]
+    fn test_select_warp_by_selector_precedence() {
+        let classifier = DefaultContainerClassifier::with_simple_pattern(
+            "warp-*".to_string(),
+            "warp.target".to_string(),
+            "warp.network".to_string(),
+        );
+        let warps = vec![
+            as_selecting_warp("warp-b", "app=frontend"),
+            as_selecting_warp("warp-prod", "app=frontend,env=prod"),
+            as_selecting_warp("warp-a", "app=frontend"),
+        ];
+
+        // The most specific selector wins
+        let container = create_labeled_container("web", &[("app", "frontend"), ("env", "prod")]);
+        let warp = classifier
+            .select_warp_by_selector(&container, &warps)
+            .unwrap();
+        assert_eq!(warp.container.name, "warp-prod");
+
+        // Equally specific selectors are broken by warp name
+        let container = create_labeled_container("web", &[("app", "frontend"), ("env", "dev")]);
+        let warp = classifier
+            .select_warp_by_selector(&container, &warps)
+            .unwrap();
+        assert_eq!(warp.container.name, "warp-a");
+
+        // An explicit target label takes precedence over selectors
+        let container =
+            create_labeled_container("web", &[("app", "frontend"), ("warp.target", "warp-other")]);
+        assert!(classifier
+            .select_warp_by_selector(&container, &warps)
+            .is_none());
+
+        // Warp containers are never selected
+        let container = create_labeled_container("warp-c", &[("app", "frontend")]);
+        assert!(classifier
+            .select_warp_by_selector(&container, &warps)
+            .is_none());
+
+        let container = create_labeled_container("db", &[("app", "backend")]);
+        assert!(classifier
+            .select_warp_by_selector(&container, &warps)
+            .is_none());
+    }
+
+    #[test]
+    fn test_select_targets_for_warp() {
+        let classifier = DefaultContainerClassifier::with_simple_pattern(
+            "warp-*".to_string(),
+            "warp.target".to_string(),
+            "warp.network".to_string(),
+        );
+        let warps = vec![
+            as_selecting_warp("warp-web", "app=frontend"),
+            as_selecting_warp("warp-prod", "app=frontend,env=prod"),
+        ];
+
+        let mut stopped = create_labeled_container("web-old", &[("app", "frontend")]);
+        stopped.state = ContainerState::Stopped;
+        let containers = vec![
+            create_labeled_container("web-dev", &[("app", "frontend"), ("env", "dev")]),
+            create_labeled_container("web-prod", &[("app", "frontend"), ("env", "prod")]),
+            create_labeled_container(
+                "web-pinned",
+                &[("app", "frontend"), ("warp.target", "warp-prod")],
+            ),
+            create_labeled_container("db", &[("app", "backend")]),
+            stopped,
+        ];
+
+        let targets = classifier.select_targets_for_warp(&warps[0], &containers, &warps);
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].container.name, "web-dev");
+        assert_eq!(targets[0].warp_target, "warp-web");
+
+        let targets = classifier.select_targets_for_warp(&warps[1], &containers, &warps);
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].container.name, "web-prod");
+
+        // Warps without a selector serve no containers this way
+        let plain = as_warp(create_labeled_container("warp-plain", &[]));
+        assert!(classifier
+            .select_targets_for_warp(&plain, &containers, &warps)
+            .is_empty());
+    }
+
+    fn create_swarm_task(service: &str, slot: u32, task_id: &str, node: &str) -> ContainerInfo {
+        let mut labels = HashMap::new();
+        labels.insert(SWARM_SERVICE_NAME_LABEL.to_string(), service.to_string());
+        labels.insert(SWARM_TASK_ID_LABEL.to_string(), task_id.to_string());
+        labels.insert(SWARM_NODE_ID_LABEL.to_string(), node.to_string());
+        create_test_container(
+            &format!("{}.{}.{}", service, slot, task_id),
+            labels,
+            vec![create_test_network("overlay-net", "10.0.1.5")],
+        )
+    }
+
+    #[test]
+    fn test_swarm_task_classification() {
+        let classifier = DefaultContainerClassifier::with_simple_pattern(
+            "warp".to_string(),
+            "warp.target".to_string(),
+            "warp.network".to_string(),
+        )
+        .with_local_node_id(Some("node-a".to_string()));
+
+        // Task containers match the warp pattern by service name
+        let task = create_swarm_task("warp", 1, "x1y2z3", "node-a");
+        assert!(matches!(
+            classifier.classify_container(&task),
+            ContainerType::WarpContainer(_)
+        ));
+
+        // Tasks on other nodes are ignored
+        let task = create_swarm_task("warp", 2, "a1b2c3", "node-b");
+        assert_eq!(classifier.classify_container(&task), ContainerType::Ignored);
+
+        let mut task = create_swarm_task("app", 1, "d4e5f6", "node-b");
+        task.labels
+            .insert("warp.target".to_string(), "warp".to_string());
+        assert_eq!(classifier.classify_container(&task), ContainerType::Ignored);
+
+        // Without a known local node, tasks are assumed local
+        let classifier = DefaultContainerClassifier::with_simple_pattern(
+            "warp".to_string(),
+            "warp.target".to_string(),
+            "warp.network".to_string(),
+        );
+        assert!(matches!(
+            classifier.classify_container(&task),
+            ContainerType::TargetContainer(_)
+        ));
+    }
+
+    #[test]
+    fn test_find_warp_for_target_swarm_service() {
+        let classifier = DefaultContainerClassifier::with_simple_pattern(
+            "warp".to_string(),
+            "warp.target".to_string(),
+            "warp.network".to_string(),
+        );
+        let warps = vec![
+            as_warp(create_swarm_task("warp", 2, "b2", "node-a")),
+            as_warp(create_swarm_task("warp", 1, "a1", "node-a")),
+            as_warp(create_swarm_task("other", 1, "c3", "node-a")),
+        ];
+
+        let target = as_target(create_swarm_task("app", 1, "d4", "node-a"), "warp");
+        let warp = classifier.find_warp_for_target(&target, &warps).unwrap();
+        assert_eq!(warp.container.name, "warp.1.a1");
+
+        // An exited task of the slot, replaced by a running one, isn't picked
+        let mut exited = as_warp(create_swarm_task("warp", 1, "a0", "node-a"));
+        exited.container.state = ContainerState::Stopped;
+        let replaced = vec![
+            exited,
+            as_warp(create_swarm_task("warp", 1, "z9", "node-a")),
+        ];
+        let warp = classifier.find_warp_for_target(&target, &replaced).unwrap();
+        assert_eq!(warp.container.name, "warp.1.z9");
+
+        let target = as_target(create_swarm_task("app", 1, "d4", "node-a"), "warp.2.b2");
+        let warp = classifier.find_warp_for_target(&target, &warps).unwrap();
+        assert_eq!(warp.container.name, "warp.2.b2");
+    }
+
     #[test]
     fn test_invalid_regex_pattern() {
         let result = DefaultContainerClassifier::new(
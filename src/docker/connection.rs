@@ -0,0 +1,783 @@
+//! Resolution of the Docker daemon to connect to
+//!
+//! Operators usually have the docker CLI set up already, through `DOCKER_HOST`
+//! or a docker context, so the daemon is looked up the same way unless this
+//! application is configured with an address of its own:
+//!
+//! 1. `docker_connection_method = "ssl"` or an explicit `docker_host`
+//! 2. An explicit `docker_socket`
+//! 3. `DOCKER_HOST`, over TLS when `DOCKER_TLS_VERIFY` is set, with the
+//!    certificates in `DOCKER_CERT_PATH`
+//! 4. The context named by `DOCKER_CONTEXT`, or the current context of
+//!    `~/.docker/config.json`
+//! 5. With `docker_rootless`, `$XDG_RUNTIME_DIR/docker.sock`
+//! 6. The default socket
+//!
+//! The docker CLI environment is only a fallback, so a `DOCKER_HOST` or context
+//! that can't be used is skipped with a warning rather than failing startup.
+
+use crate::config::{AppConfig, DEFAULT_DOCKER_SOCKET};
+use crate::docker::DockerTlsConfig;
+use crate::error::ConfigError;
+use serde_json::Value;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// Port of a plain TCP daemon when the address has none
+const DEFAULT_HTTP_PORT: u16 = 2375;
+/// Port of a TLS daemon when the address has none
+const DEFAULT_TLS_PORT: u16 = 2376;
+/// Context that stands for the local daemon rather than stored settings
+const DEFAULT_CONTEXT: &str = "default";
+/// Socket of a rootless daemon, relative to the user's runtime directory
+const ROOTLESS_SOCKET: &str = "docker.sock";
+
+/// Daemon endpoint and how to connect to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DockerEndpoint {
+    /// Unix socket path
+    Socket(String),
+    /// Plain TCP address, `tcp://host:port`
+    Http(String),
+    /// TLS address, `tcp://host:port`, with the client certificate to present
+    Ssl(String, DockerTlsConfig),
+}
+
+impl fmt::Display for DockerEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DockerEndpoint::Socket(path) => write!(f, "unix://{}", path),
+            DockerEndpoint::Http(address) => write!(f, "{}", address),
+            DockerEndpoint::Ssl(address, tls) => write!(
+                f,
+                "{} (TLS, CA {}, certificate {})",
+                address,
+                tls.ca.display(),
+                tls.cert.display()
+            ),
+        }
+    }
+}
+
+/// Where the endpoint was taken from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionSource {
+    /// The `docker_host` and TLS settings of this application
+    Settings,
+    /// The `DOCKER_HOST` environment variable
+    Environment,
+    /// A docker context, by name
+    Context(String),
+    /// The socket of a rootless daemon in the user's runtime directory
+    Rootless,
+    /// The `docker_socket` setting
+    Socket,
+    /// The default socket, without any setting or environment naming a daemon
+    Default,
+}
+
+impl fmt::Display for ConnectionSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionSource::Settings => f.write_str("docker_host setting"),
+            ConnectionSource::Environment => f.write_str("DOCKER_HOST"),
+            ConnectionSource::Context(name) => write!(f, "docker context '{}'", name),
+            ConnectionSource::Rootless => f.write_str("rootless Docker"),
+            ConnectionSource::Socket => f.write_str("docker_socket setting"),
+            ConnectionSource::Default => f.write_str("default socket"),
+        }
+    }
+}
+
+/// Effective Docker connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedConnection {
+    pub endpoint: DockerEndpoint,
+    pub source: ConnectionSource,
+}
+
+impl fmt::Display for ResolvedConnection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (from {})", self.endpoint, self.source)
+    }
+}
+
+/// Docker CLI environment the connection is resolved from
+#[derive(Debug, Clone, Default)]
+pub struct DockerEnvironment {
+    /// `DOCKER_HOST`
+    pub host: Option<String>,
+    /// Whether `DOCKER_TLS_VERIFY` is set
+    pub tls_verify: bool,
+    /// `DOCKER_CERT_PATH`
+    pub cert_path: Option<PathBuf>,
+    /// `DOCKER_CONTEXT`
+    pub context: Option<String>,
+    /// `DOCKER_CONFIG`, or `~/.docker`
+    pub config_dir: Option<PathBuf>,
+    /// `XDG_RUNTIME_DIR`
+    pub runtime_dir: Option<PathBuf>,
+}
+
+impl DockerEnvironment {
+    /// Read the environment of the current process
+    pub fn from_env() -> Self {
+        // Like the docker CLI, empty variables count as unset
+        let var = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+
+        Self {
+            host: var("DOCKER_HOST"),
+            tls_verify: var("DOCKER_TLS_VERIFY").is_some(),
+            cert_path: var("DOCKER_CERT_PATH").map(PathBuf::from),
+            context: var("DOCKER_CONTEXT"),
+            config_dir: var("DOCKER_CONFIG")
+                .map(PathBuf::from)
+                .or_else(|| var("HOME").map(|home| Path::new(&home).join(".docker"))),
+            runtime_dir: var("XDG_RUNTIME_DIR").map(PathBuf::from),
+        }
+    }
+
+    /// Directory holding `ca.pem`, `cert.pem` and `key.pem` for `DOCKER_HOST`
+    fn cert_dir(&self) -> Option<&Path> {
+        self.cert_path.as_deref().or(self.config_dir.as_deref())
+    }
+}
+
+/// Resolve the daemon to connect to from the settings and the docker CLI environment
+pub fn resolve_connection(
+    config: &AppConfig,
+    environment: &DockerEnvironment,
+) -> Result<ResolvedConnection, ConfigError> {
+    let method = config.docker_connection_method.to_lowercase();
+    let resolved = |endpoint, source| Ok(ResolvedConnection { endpoint, source });
+
+    if let Some(host) = config
+        .docker_host
+        .as_deref()
+        .filter(|h| !h.trim().is_empty())
+    {
+        let address = parse_host(host)?;
+        let tls = match address {
+            HostAddress::Tcp { https, .. } if https || method == "ssl" => {
+                Some(match config.docker_tls() {
+                    Some(tls) => tls,
+                    None => certificates_in(environment.cert_dir())?,
+                })
+            }
+            HostAddress::Unix(_) if method == "ssl" => {
+                return Err(ConfigError::ValidationError(format!(
+                    "Docker host {} is not a TCP address, as the ssl connection method requires",
+                    host
+                )))
+            }
+            _ => None,
+        };
+        return resolved(address.into_endpoint(tls), ConnectionSource::Settings);
+    }
+
+    if method != "http" {
+        if let Some(ref socket) = config.docker_socket {
+            return resolved(
+                DockerEndpoint::Socket(socket.clone()),
+                ConnectionSource::Socket,
+            );
+        }
+    }
+
+    match environment_connection(environment) {
+        Ok(Some(connection)) => return Ok(connection),
+        Ok(None) => {}
+        Err(e) => warn!("Ignoring the docker CLI environment: {}", e),
+    }
+
+    if method == "http" {
+        return Err(ConfigError::MissingRequired {
+            field: "docker_host (or DOCKER_HOST) for the http connection method".to_string(),
+        });
+    }
+    if config.docker_rootless {
+        let runtime_dir =
+            environment
+                .runtime_dir
+                .as_deref()
+                .ok_or_else(|| ConfigError::MissingRequired {
+                    field: "XDG_RUNTIME_DIR for rootless Docker".to_string(),
+                })?;
+        return resolved(
+            DockerEndpoint::Socket(runtime_dir.join(ROOTLESS_SOCKET).display().to_string()),
+            ConnectionSource::Rootless,
+        );
+    }
+    resolved(
+        DockerEndpoint::Socket(DEFAULT_DOCKER_SOCKET.to_string()),
+        ConnectionSource::Default,
+    )
+}
+
+/// Resolve the daemon the docker CLI would use through `DOCKER_HOST` or a context, if any
+fn environment_connection(
+    environment: &DockerEnvironment,
+) -> Result<Option<ResolvedConnection>, ConfigError> {
+    let resolved = |endpoint, source| Ok(Some(ResolvedConnection { endpoint, source }));
+
+    if let Some(ref host) = environment.host {
+        let address = parse_host(host)?;
+        let tls = match address {
+            HostAddress::Tcp { https, .. } if https || environment.tls_verify => {
+                Some(certificates_in(environment.cert_dir())?)
+            }
+            _ => None,
+        };
+        return resolved(address.into_endpoint(tls), ConnectionSource::Environment);
+    }
+
+    let Some(context) = current_context(environment)? else {
+        return Ok(None);
+    };
+    let config_dir = environment.config_dir.as_deref().ok_or_else(|| {
+        ConfigError::ValidationError(format!(
+            "Docker context '{}' is selected, but the docker config directory is unknown",
+            context
+        ))
+    })?;
+    let (host, tls_dir) = load_context(config_dir, &context)?;
+    let address = parse_host(&host)?;
+    let tls = match address {
+        HostAddress::Tcp { https, .. } if https || tls_dir.is_some() => {
+            Some(certificates_in(tls_dir.as_deref())?)
+        }
+        _ => None,
+    };
+    resolved(
+        address.into_endpoint(tls),
+        ConnectionSource::Context(context),
+    )
+}
+
+/// Daemon address in one of the forms `DOCKER_HOST` accepts on Linux
+#[derive(Debug, PartialEq)]
+enum HostAddress {
+    Unix(String),
+    /// `host` or `host:port`; `https` if given with that scheme
+    Tcp {
+        address: String,
+        https: bool,
+    },
+}
+
+impl HostAddress {
+    fn into_endpoint(self, tls: Option<DockerTlsConfig>) -> DockerEndpoint {
+        match (self, tls) {
+            (HostAddress::Unix(path), _) => DockerEndpoint::Socket(path),
+            (HostAddress::Tcp { address, .. }, Some(tls)) => {
+                DockerEndpoint::Ssl(with_port(&address, DEFAULT_TLS_PORT), tls)
+            }
+            (HostAddress::Tcp { address, .. }, None) => {
+                DockerEndpoint::Http(with_port(&address, DEFAULT_HTTP_PORT))
+            }
+        }
+    }
+}
+
+/// Parse `unix://`, `tcp://`, `http://` and `https://` addresses, bare socket paths and `host:port`
+fn parse_host(host: &str) -> Result<HostAddress, ConfigError> {
+    let host = host.trim();
+    let invalid = |reason: &str| {
+        ConfigError::InvalidFormat(format!("Invalid Docker host '{}': {}", host, reason))
+    };
+
+    if let Some(path) = host.strip_prefix("unix://") {
+        if path.is_empty() {
+            return Err(invalid("missing socket path"));
+        }
+        return Ok(HostAddress::Unix(path.to_string()));
+    }
+    if host.starts_with('/') {
+        return Ok(HostAddress::Unix(host.to_string()));
+    }
+
+    let (address, https) = if let Some(address) = host.strip_prefix("tcp://") {
+        (address, false)
+    } else if let Some(address) = host.strip_prefix("http://") {
+        (address, false)
+    } else if let Some(address) = host.strip_prefix("https://") {
+        (address, true)
+    } else if let Some((scheme, _)) = host.split_once("://") {
+        return Err(invalid(&format!("unsupported scheme '{}'", scheme)));
+    } else {
+        (host, false)
+    };
+
+    // A path after the address is meaningless to the API client
+    let address = address.split('/').next().unwrap_or_default();
+    if address.is_empty() || address.starts_with(':') {
+        return Err(invalid("missing host name"));
+    }
+
+    Ok(HostAddress::Tcp {
+        address: address.to_string(),
+        https,
+    })
+}
+
+/// Format a TCP address, adding the default port if it has none
+fn with_port(address: &str, default_port: u16) -> String {
+    let has_port = match address.rsplit_once(':') {
+        // `[::1]` has colons but no port, `[::1]:2376` has one
+        Some((host, port)) => {
+            port.parse::<u16>().is_ok() && (!host.contains(':') || host.ends_with(']'))
+        }
+        None => false,
+    };
+
+    if has_port {
+        format!("tcp://{}", address)
+    } else {
+        format!("tcp://{}:{}", address, default_port)
+    }
+}
+
+/// Get the client certificate and CA stored in a directory the way the docker CLI expects
+fn certificates_in(dir: Option<&Path>) -> Result<DockerTlsConfig, ConfigError> {
+    let dir = dir.ok_or_else(|| ConfigError::MissingRequired {
+        field: "DOCKER_CERT_PATH for a TLS Docker host".to_string(),
+    })?;
+
+    let tls = DockerTlsConfig {
+        ca: dir.join("ca.pem"),
+        cert: dir.join("cert.pem"),
+        key: dir.join("key.pem"),
+    };
+    for path in [&tls.ca, &tls.cert, &tls.key] {
+        if !path.is_file() {
+            return Err(ConfigError::FileNotFound {
+                path: path.display().to_string(),
+            });
+        }
+    }
+    Ok(tls)
+}
+
+/// Name of the selected docker context, unless it is the default one
+fn current_context(environment: &DockerEnvironment) -> Result<Option<String>, ConfigError> {
+    let context = match environment.context {
+        Some(ref context) => Some(context.clone()),
+        None => match environment.config_dir {
+            Some(ref config_dir) => read_json(&config_dir.join("config.json"))?
+                .and_then(|config| config["currentContext"].as_str().map(str::to_string)),
+            None => None,
+        },
+    };
+
+    Ok(context.filter(|name| !name.is_empty() && name != DEFAULT_CONTEXT))
+}
+
+/// Look up the docker endpoint of a context and its TLS material directory, if any
+fn load_context(config_dir: &Path, name: &str) -> Result<(String, Option<PathBuf>), ConfigError> {
+    // Contexts are stored by digest of their name; match on the name inside instead
+    let meta_dir = config_dir.join("contexts").join("meta");
+    let entries = fs::read_dir(&meta_dir).map_err(|_| {
+        ConfigError::ValidationError(format!("Docker context '{}' not found", name))
+    })?;
+
+    for entry in entries.flatten() {
+        let Some(meta) = read_json(&entry.path().join("meta.json"))? else {
+            continue;
+        };
+        if meta["Name"].as_str() != Some(name) {
+            continue;
+        }
+
+        let host = meta["Endpoints"]["docker"]["Host"]
+            .as_str()
+            .filter(|host| !host.is_empty())
+            .ok_or_else(|| {
+                ConfigError::ValidationError(format!(
+                    "Docker context '{}' has no docker endpoint",
+                    name
+                ))
+            })?;
+        let tls_dir = config_dir
+            .join("contexts")
+            .join("tls")
+            .join(entry.file_name())
+            .join("docker");
+        debug!(
+            "Found docker context '{}' in {}",
+            name,
+            entry.path().display()
+        );
+
+        return Ok((host.to_string(), tls_dir.is_dir().then_some(tls_dir)));
+    }
+
+    Err(ConfigError::ValidationError(format!(
+        "Docker context '{}' not found",
+        name
+    )))
+}
+
+/// Read a JSON file, or None if it doesn't exist
+fn read_json(path: &Path) -> Result<Option<Value>, ConfigError> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(ConfigError::InvalidFormat(format!(
+                "Failed to read {}: {}",
+                path.display(),
+                e
+            )))
+        }
+    };
+
+    serde_json::from_str(&content).map(Some).map_err(|e| {
+        ConfigError::InvalidFormat(format!("Failed to parse {}: {}", path.display(), e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_certificates(dir: &Path) {
+        fs::create_dir_all(dir).unwrap();
+        for name in ["ca.pem", "cert.pem", "key.pem"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+    }
+
+    fn write_context(config_dir: &Path, digest: &str, name: &str, host: &str) {
+        let meta_dir = config_dir.join("contexts").join("meta").join(digest);
+        fs::create_dir_all(&meta_dir).unwrap();
+        fs::write(
+            meta_dir.join("meta.json"),
+            format!(
+                r#"{{"Name":"{}","Metadata":{{}},"Endpoints":{{"docker":{{"Host":"{}","SkipTLSVerify":false}}}}}}"#,
+                name, host
+            ),
+        )
+        .unwrap();
+    }
+
+    fn environment(config_dir: &TempDir) -> DockerEnvironment {
+        DockerEnvironment {
+            config_dir: Some(config_dir.path().to_path_buf()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_host_forms() {
+        assert_eq!(
+            parse_host("unix:///run/docker.sock").unwrap(),
+            HostAddress::Unix("/run/docker.sock".to_string())
+        );
+        assert_eq!(
+            parse_host("/run/podman/podman.sock").unwrap(),
+            HostAddress::Unix("/run/podman/podman.sock".to_string())
+        );
+        assert_eq!(
+            parse_host("tcp://10.0.0.5:2375").unwrap(),
+            HostAddress::Tcp {
+                address: "10.0.0.5:2375".to_string(),
+                https: false
+            }
+        );
+        assert_eq!(
+            parse_host("https://docker.example.com/").unwrap(),
+            HostAddress::Tcp {
+                address: "docker.example.com".to_string(),
+                https: true
+            }
+        );
+        assert_eq!(
+            parse_host("docker.example.com:2376").unwrap(),
+            HostAddress::Tcp {
+                address: "docker.example.com:2376".to_string(),
+                https: false
+            }
+        );
+
+        assert!(parse_host("ssh://user@docker.example.com").is_err());
+        assert!(parse_host("npipe:////./pipe/docker_engine").is_err());
+        assert!(parse_host("unix://").is_err());
+        assert!(parse_host("tcp://:2375").is_err());
+    }
+
+    #[test]
+    fn test_default_ports() {
+        assert_eq!(with_port("10.0.0.5", 2375), "tcp://10.0.0.5:2375");
+        assert_eq!(with_port("10.0.0.5:8080", 2375), "tcp://10.0.0.5:8080");
+        assert_eq!(with_port("[::1]", 2376), "tcp://[::1]:2376");
+        assert_eq!(with_port("[::1]:2377", 2376), "tcp://[::1]:2377");
+    }
+
+    #[test]
+    fn test_resolve_defaults_to_socket_setting() {
+        let config_dir = TempDir::new().unwrap();
+        let config = AppConfig::default();
+
+        let connection = resolve_connection(&config, &environment(&config_dir)).unwrap();
+        assert_eq!(
+            connection.endpoint,
+            DockerEndpoint::Socket(DEFAULT_DOCKER_SOCKET.to_string())
+        );
+        assert_eq!(connection.source, ConnectionSource::Default);
+        assert_eq!(
+            connection.to_string(),
+            "unix:///var/run/docker.sock (from default socket)"
+        );
+
+        let config = AppConfig {
+            docker_connection_method: "http".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            resolve_connection(&config, &environment(&config_dir)),
+            Err(ConfigError::MissingRequired { .. })
+        ));
+    }
+
+    #[test]
+    fn test_resolve_rootless_socket() {
+        let config_dir = TempDir::new().unwrap();
+        let environment = DockerEnvironment {
+            runtime_dir: Some(PathBuf::from("/run/user/1000")),
+            ..environment(&config_dir)
+        };
+        let config = AppConfig {
+            docker_rootless: true,
+            ..Default::default()
+        };
+
+        let connection = resolve_connection(&config, &environment).unwrap();
+        assert_eq!(
+            connection.to_string(),
+            "unix:///run/user/1000/docker.sock (from rootless Docker)"
+        );
+
+        // A socket set explicitly is kept
+        let config = AppConfig {
+            docker_rootless: true,
+            docker_socket: Some("/srv/docker.sock".to_string()),
+            ..Default::default()
+        };
+        let connection = resolve_connection(&config, &environment).unwrap();
+        assert_eq!(connection.source, ConnectionSource::Socket);
+
+        // DOCKER_HOST still takes precedence
+        let environment = DockerEnvironment {
+            host: Some("unix:///run/user/1000/podman/podman.sock".to_string()),
+            ..environment
+        };
+        let config = AppConfig {
+            docker_rootless: true,
+            ..Default::default()
+        };
+        let connection = resolve_connection(&config, &environment).unwrap();
+        assert_eq!(connection.source, ConnectionSource::Environment);
+
+        // Without XDG_RUNTIME_DIR, the rootless socket can't be found
+        let environment = DockerEnvironment {
+            host: None,
+            runtime_dir: None,
+            ..environment
+        };
+        assert!(matches!(
+            resolve_connection(&config, &environment),
+            Err(ConfigError::MissingRequired { .. })
+        ));
+    }
+
+    #[test]
+    fn test_resolve_docker_host() {
+        let config_dir = TempDir::new().unwrap();
+        let config = AppConfig::default();
+
+        let env = DockerEnvironment {
+            host: Some("tcp://10.0.0.5".to_string()),
+            ..environment(&config_dir)
+        };
+        let connection = resolve_connection(&config, &env).unwrap();
+        assert_eq!(
+            connection.endpoint,
+            DockerEndpoint::Http("tcp://10.0.0.5:2375".to_string())
+        );
+        assert_eq!(connection.source, ConnectionSource::Environment);
+
+        // DOCKER_TLS_VERIFY needs the certificates of DOCKER_CERT_PATH
+        let cert_dir = config_dir.path().join("certs");
+        let env = DockerEnvironment {
+            tls_verify: true,
+            cert_path: Some(cert_dir.clone()),
+            ..env
+        };
+        assert!(matches!(
+            environment_connection(&env),
+            Err(ConfigError::FileNotFound { .. })
+        ));
+        let connection = resolve_connection(&config, &env).unwrap();
+        assert_eq!(connection.source, ConnectionSource::Default);
+
+        write_certificates(&cert_dir);
+        let connection = resolve_connection(&config, &env).unwrap();
+        assert_eq!(
+            connection.endpoint,
+            DockerEndpoint::Ssl(
+                "tcp://10.0.0.5:2376".to_string(),
+                DockerTlsConfig {
+                    ca: cert_dir.join("ca.pem"),
+                    cert: cert_dir.join("cert.pem"),
+                    key: cert_dir.join("key.pem"),
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn test_resolve_settings_take_precedence() {
+        let config_dir = TempDir::new().unwrap();
+        let env = DockerEnvironment {
+            host: Some("tcp://10.0.0.5:2375".to_string()),
+            context: Some("remote".to_string()),
+            ..environment(&config_dir)
+        };
+
+        let config = AppConfig {
+            docker_host: Some("unix:///run/user/1000/docker.sock".to_string()),
+            ..Default::default()
+        };
+        let connection = resolve_connection(&config, &env).unwrap();
+        assert_eq!(
+            connection.endpoint,
+            DockerEndpoint::Socket("/run/user/1000/docker.sock".to_string())
+        );
+        assert_eq!(connection.source, ConnectionSource::Settings);
+
+        let config = AppConfig {
+            docker_connection_method: "ssl".to_string(),
+            docker_host: Some("docker.example.com".to_string()),
+            docker_tls_ca: Some("/certs/ca.pem".to_string()),
+            docker_tls_cert: Some("/certs/cert.pem".to_string()),
+            docker_tls_key: Some("/certs/key.pem".to_string()),
+            ..Default::default()
+        };
+        let connection = resolve_connection(&config, &env).unwrap();
+        assert_eq!(
+            connection.endpoint,
+            DockerEndpoint::Ssl(
+                "tcp://docker.example.com:2376".to_string(),
+                config.docker_tls().unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn test_resolve_current_context() {
+        let config_dir = TempDir::new().unwrap();
+        let config = AppConfig::default();
+        write_context(config_dir.path(), "0a1b", "plain", "tcp://10.0.0.7:2375");
+        write_context(config_dir.path(), "2c3d", "secure", "tcp://10.0.0.8");
+        write_certificates(
+            &config_dir
+                .path()
+                .join("contexts")
+                .join("tls")
+                .join("2c3d")
+                .join("docker"),
+        );
+
+        // The current context of config.json
+        fs::write(
+            config_dir.path().join("config.json"),
+            r#"{"auths":{},"currentContext":"plain"}"#,
+        )
+        .unwrap();
+        let connection = resolve_connection(&config, &environment(&config_dir)).unwrap();
+        assert_eq!(
+            connection.endpoint,
+            DockerEndpoint::Http("tcp://10.0.0.7:2375".to_string())
+        );
+        assert_eq!(
+            connection.source,
+            ConnectionSource::Context("plain".to_string())
+        );
+
+        // DOCKER_CONTEXT overrides it, and TLS material of the context is used
+        let env = DockerEnvironment {
+            context: Some("secure".to_string()),
+            ..environment(&config_dir)
+        };
+        let connection = resolve_connection(&config, &env).unwrap();
+        assert!(matches!(
+            connection.endpoint,
+            DockerEndpoint::Ssl(ref address, _) if address == "tcp://10.0.0.8:2376"
+        ));
+
+        // The default context means the local daemon
+        let env = DockerEnvironment {
+            context: Some("default".to_string()),
+            ..environment(&config_dir)
+        };
+        let connection = resolve_connection(&config, &env).unwrap();
+        assert_eq!(connection.source, ConnectionSource::Default);
+
+        // A context that can't be loaded is skipped rather than failing startup
+        let env = DockerEnvironment {
+            context: Some("missing".to_string()),
+            ..environment(&config_dir)
+        };
+        let connection = resolve_connection(&config, &env).unwrap();
+        assert_eq!(connection.source, ConnectionSource::Default);
+    }
+
+    #[test]
+    fn test_resolve_explicit_socket_beats_environment() {
+        let config_dir = TempDir::new().unwrap();
+        write_context(config_dir.path(), "0a1b", "plain", "tcp://10.0.0.7:2375");
+        let config = AppConfig {
+            docker_socket: Some("/srv/docker.sock".to_string()),
+            ..Default::default()
+        };
+
+        for env in [
+            DockerEnvironment {
+                host: Some("tcp://10.0.0.5:2375".to_string()),
+                ..environment(&config_dir)
+            },
+            DockerEnvironment {
+                context: Some("plain".to_string()),
+                ..environment(&config_dir)
+            },
+            // Neither an unsupported DOCKER_HOST nor a broken context is consulted
+            DockerEnvironment {
+                host: Some("ssh://user@docker.example.com".to_string()),
+                ..environment(&config_dir)
+            },
+            DockerEnvironment {
+                context: Some("missing".to_string()),
+                ..environment(&config_dir)
+            },
+        ] {
+            let connection = resolve_connection(&config, &env).unwrap();
+            assert_eq!(
+                connection.endpoint,
+                DockerEndpoint::Socket("/srv/docker.sock".to_string())
+            );
+            assert_eq!(connection.source, ConnectionSource::Socket);
+        }
+
+        // Without a socket setting, an unsupported DOCKER_HOST falls back to the default
+        let env = DockerEnvironment {
+            host: Some("ssh://user@docker.example.com".to_string()),
+            ..environment(&config_dir)
+        };
+        let connection = resolve_connection(&AppConfig::default(), &env).unwrap();
+        assert_eq!(connection.source, ConnectionSource::Default);
+    }
+}
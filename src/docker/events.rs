@@ -1,34 +1,123 @@
 //! Docker event monitoring and processing
 
+use crate::docker::bus::EventBus;
+use crate::docker::classifier::ContainerClassifier;
+use crate::docker::connection::DockerEndpoint;
+use crate::docker::inventory::ContainerInventory;
 use crate::docker::{
-    BollardDockerClient, ContainerStartEvent, DockerClient, EventHandler, EventMonitor,
+    connect_docker, ApiVersion, BollardDockerClient, ContainerEvent, ContainerInfo,
+    ContainerLifecycleEvent, ContainerRuntime, ContainerStartEvent, ContainerState,
+    DockerApiConfig, DockerClient, DockerTlsConfig, EventHandler, EventMonitor, LifecycleAction,
 };
 use crate::error::{DockerError, EventError};
 use bollard::models::EventMessage;
 use bollard::query_parameters::EventsOptions;
 use bollard::secret::EventMessageTypeEnum;
 use bollard::Docker;
-use futures_util::stream::StreamExt;
-use std::collections::HashMap;
+use futures_util::stream::{self, BoxStream, StreamExt};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::RwLock;
 use tokio::time::sleep;
 use tracing::{debug, error, info, warn};
 
+/// Stream of events from one connection to the daemon
+pub type EventStream = BoxStream<'static, Result<EventMessage, EventError>>;
+
+/// Source of Docker events, opened again whenever the monitor reconnects
+pub trait EventSource: Send + Sync {
+    /// Open an event stream limited by Docker event filters, e.g. `type` and `event`
+    fn events(&self, filters: HashMap<String, Vec<String>>) -> Result<EventStream, EventError>;
+}
+
+/// Number of recent events remembered to drop copies delivered on another stream
+const RECENT_EVENTS: usize = 256;
+
+/// Container ID, action and time in nanoseconds of an event
+type EventKey = (Option<String>, Option<String>, i64);
+
+/// Events recently received, to drop the copies of an event matching several
+/// label-filtered streams, e.g. a warp carrying both the role and the target label
+#[derive(Default)]
+struct RecentEvents {
+    seen: HashSet<EventKey>,
+    order: VecDeque<EventKey>,
+}
+
+impl RecentEvents {
+    /// Remember an event, returning whether it was received already. Events
+    /// without a timestamp are never taken for a copy.
+    fn repeated(&mut self, event: &EventMessage) -> bool {
+        let Some(time) = event.time_nano else {
+            return false;
+        };
+        let id = event.actor.as_ref().and_then(|actor| actor.id.clone());
+        let key = (id, event.action.clone(), time);
+        if !self.seen.insert(key.clone()) {
+            return true;
+        }
+
+        self.order.push_back(key);
+        if self.order.len() > RECENT_EVENTS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        false
+    }
+}
+
 /// Docker connection configuration for event monitoring
 #[derive(Debug, Clone)]
 enum DockerConnection {
-    Socket(String),
-    Http(String),
+    Endpoint(DockerEndpoint),
     Default,
 }
 
+/// Event source streaming from the Docker API through bollard
+pub struct BollardEventSource {
+    connection: DockerConnection,
+    api: DockerApiConfig,
+}
+
+impl EventSource for BollardEventSource {
+    fn events(&self, filters: HashMap<String, Vec<String>>) -> Result<EventStream, EventError> {
+        // Create a new Docker connection for event streaming using the same configuration
+        let docker = match &self.connection {
+            DockerConnection::Endpoint(endpoint) => {
+                connect_docker(endpoint, &self.api).map_err(|e| {
+                    EventError::StartFailed(format!(
+                        "Failed to connect to Docker at {}: {}",
+                        endpoint, e
+                    ))
+                })?
+            }
+            DockerConnection::Default => Docker::connect_with_socket_defaults().map_err(|e| {
+                EventError::StartFailed(format!("Failed to connect to Docker: {}", e))
+            })?,
+        };
+
+        let options = EventsOptions {
+            since: None,
+            until: None,
+            filters: Some(filters),
+        };
+
+        Ok(docker
+            .events(Some(options))
+            .map(|result| result.map_err(|e| EventError::StreamError(e.to_string())))
+            .boxed())
+    }
+}
+
 /// Docker event monitor implementation with retry logic and event filtering
-pub struct DockerEventMonitor {
-    docker_client: BollardDockerClient,
-    docker_connection: DockerConnection,
-    handlers: Arc<RwLock<Vec<Arc<dyn EventHandler>>>>,
+pub struct DockerEventMonitor<C = BollardDockerClient, S = BollardEventSource> {
+    docker_client: C,
+    source: S,
+    bus: EventBus,
+    inventory: Option<Arc<ContainerInventory>>,
+    classifier: Option<Arc<dyn ContainerClassifier + Send + Sync>>,
+    label_filter: Vec<String>,
     retry_delay: Duration,
     max_retries: u32,
 }
@@ -37,37 +126,116 @@ impl DockerEventMonitor {
     /// Create a new Docker event monitor with default connection
     pub fn new() -> Result<Self, DockerError> {
         let docker_client = BollardDockerClient::new()?;
-        Ok(Self {
+        Ok(Self::with_source(
             docker_client,
-            docker_connection: DockerConnection::Default,
-            handlers: Arc::new(RwLock::new(Vec::new())),
-            retry_delay: Duration::from_secs(5),
-            max_retries: 10,
-        })
+            BollardEventSource {
+                connection: DockerConnection::Default,
+                api: DockerApiConfig::default(),
+            },
+        ))
     }
 
     /// Create a new Docker event monitor with custom socket path
     pub fn with_socket(socket_path: &str) -> Result<Self, DockerError> {
-        let docker_client = BollardDockerClient::with_socket(socket_path)?;
-        Ok(Self {
-            docker_client,
-            docker_connection: DockerConnection::Socket(socket_path.to_string()),
-            handlers: Arc::new(RwLock::new(Vec::new())),
-            retry_delay: Duration::from_secs(5),
-            max_retries: 10,
-        })
+        Self::connect(
+            &DockerEndpoint::Socket(socket_path.to_string()),
+            &DockerApiConfig::default(),
+        )
     }
 
     /// Create a new Docker event monitor with HTTP connection
     pub fn with_http(url: &str) -> Result<Self, DockerError> {
-        let docker_client = BollardDockerClient::with_http(url)?;
-        Ok(Self {
+        Self::connect(
+            &DockerEndpoint::Http(url.to_string()),
+            &DockerApiConfig::default(),
+        )
+    }
+
+    /// Create a new Docker event monitor with a TLS connection authenticated by client certificate
+    pub fn with_ssl(address: &str, tls: &DockerTlsConfig) -> Result<Self, DockerError> {
+        Self::connect(
+            &DockerEndpoint::Ssl(address.to_string(), tls.clone()),
+            &DockerApiConfig::default(),
+        )
+    }
+
+    /// Create a new Docker event monitor for a resolved endpoint
+    pub fn connect(endpoint: &DockerEndpoint, api: &DockerApiConfig) -> Result<Self, DockerError> {
+        let docker_client = BollardDockerClient::connect(endpoint, api)?;
+        Ok(Self::with_source(
+            docker_client,
+            BollardEventSource {
+                connection: DockerConnection::Endpoint(endpoint.clone()),
+                api: *api,
+            },
+        ))
+    }
+
+    /// Check the daemon's API version and settle on the version to use, for the
+    /// client and for event stream connections alike
+    pub async fn negotiate_api_version(&mut self) -> Result<ApiVersion, DockerError> {
+        let version = self.docker_client.negotiate_api_version().await?;
+        self.source.api.version = Some(version);
+        Ok(version)
+    }
+
+    /// Set the container runtime behind the API, to normalize its events and inspect payloads
+    pub fn with_runtime(mut self, runtime: ContainerRuntime) -> Self {
+        self.docker_client = self.docker_client.with_runtime(runtime);
+        self
+    }
+}
+
+impl<C: DockerClient, S: EventSource> DockerEventMonitor<C, S> {
+    /// Create a Docker event monitor inspecting containers through a client and
+    /// reading events from a source
+    pub fn with_source(docker_client: C, source: S) -> Self {
+        Self {
             docker_client,
-            docker_connection: DockerConnection::Http(url.to_string()),
-            handlers: Arc::new(RwLock::new(Vec::new())),
+            source,
+            bus: EventBus::new(),
+            inventory: None,
+            classifier: None,
+            label_filter: Vec::new(),
             retry_delay: Duration::from_secs(5),
             max_retries: 10,
-        })
+        }
+    }
+
+    /// Publish events on a bus shared with other publishers or subscribers
+    pub fn with_bus(mut self, bus: EventBus) -> Self {
+        self.bus = bus;
+        self
+    }
+
+    /// Bus the monitor publishes container events on
+    pub fn bus(&self) -> &EventBus {
+        &self.bus
+    }
+
+    /// Keep a container inventory current from the monitored events
+    pub fn with_inventory(mut self, inventory: Arc<ContainerInventory>) -> Self {
+        self.inventory = Some(inventory);
+        self
+    }
+
+    /// Skip events of containers the classifier finds irrelevant from the event's
+    /// actor attributes, without inspecting them
+    pub fn with_classifier(
+        mut self,
+        classifier: Arc<dyn ContainerClassifier + Send + Sync>,
+    ) -> Self {
+        self.classifier = Some(classifier);
+        self
+    }
+
+    /// Only stream events of containers carrying one of these labels, e.g. the
+    /// classifier's `event_labels`. Docker requires events to match every label
+    /// filter, so each label is streamed on a connection of its own; an event
+    /// arriving on several of them is processed once.
+    pub fn with_label_filter(mut self, labels: Vec<String>) -> Self {
+        self.label_filter = labels;
+        self
     }
 
     /// Set retry configuration
@@ -77,8 +245,26 @@ impl DockerEventMonitor {
         self
     }
 
-    /// Process a Docker event and notify handlers
+    /// Process a Docker event and publish it to subscribers
     async fn process_event(&self, event: EventMessage) -> Result<(), EventError> {
+        let event = normalize_event(event, self.docker_client.runtime());
+        let relevant = self.is_relevant(&event);
+
+        // The inventory follows every event, as a warp's selector may pick any container
+        // later, but only inspects started containers that matter
+        let mut inspected = None;
+        if let Some(inventory) = &self.inventory {
+            if relevant {
+                inspected = inventory.apply_event(&event, &self.docker_client).await;
+            } else {
+                inventory.record_event(&event);
+            }
+        }
+        if !relevant {
+            debug!("Skipping event of irrelevant container: {:?}", event.actor);
+            return Ok(());
+        }
+
         // Filter for container events
         if let Some(event_type) = &event.typ {
             if *event_type != EventMessageTypeEnum::CONTAINER {
@@ -86,12 +272,30 @@ impl DockerEventMonitor {
             }
         }
 
-        // Filter for start events
-        if let Some(action) = &event.action {
-            if action != "start" {
-                return Ok(());
-            }
-        }
+        let attribute = |name: &str| {
+            event
+                .actor
+                .as_ref()
+                .and_then(|actor| actor.attributes.as_ref())
+                .and_then(|attributes| attributes.get(name))
+                .cloned()
+        };
+
+        // Filter for start and lifecycle events
+        let action = match event.action.as_deref() {
+            Some("start") => None,
+            Some("stop") => Some(LifecycleAction::Stop),
+            Some("die") => Some(LifecycleAction::Die {
+                exit_code: attribute("exitCode"),
+            }),
+            Some("pause") => Some(LifecycleAction::Pause),
+            Some("unpause") => Some(LifecycleAction::Unpause),
+            Some("restart") => Some(LifecycleAction::Restart),
+            Some("kill") => Some(LifecycleAction::Kill {
+                signal: attribute("signal"),
+            }),
+            _ => return Ok(()),
+        };
 
         // Extract container ID
         let container_id = match &event.actor {
@@ -108,32 +312,135 @@ impl DockerEventMonitor {
             }
         };
 
-        debug!("Processing container start event for: {}", container_id);
+        match &action {
+            Some(action) => debug!(
+                "Processing container {} event for: {}",
+                action, container_id
+            ),
+            None => debug!("Processing container start event for: {}", container_id),
+        }
 
-        // Get container information
-        let container_info = match self.docker_client.inspect_container(&container_id).await {
-            Ok(info) => info,
-            Err(e) => {
-                warn!("Failed to inspect container {}: {}", container_id, e);
-                return Ok(()); // Don't fail the entire event processing
-            }
+        // Get container information, as the inventory knows it after the event
+        let inspected = match (inspected, &self.inventory) {
+            (Some(info), _) => Some(info),
+            (None, Some(inventory)) if action.is_some() => inventory.get(&container_id),
+            (None, _) => None,
+        };
+        let container_info = match inspected {
+            Some(info) => info,
+            None => match self.docker_client.inspect_container(&container_id).await {
+                Ok(info) => info,
+                Err(e) => {
+                    warn!("Failed to inspect container {}: {}", container_id, e);
+                    return Ok(()); // Don't fail the entire event processing
+                }
+            },
         };
 
-        // Create container start event
-        let start_event = ContainerStartEvent {
-            container: container_info,
+        // Publish to subscribers; each handles it in its own time
+        let event = match action {
+            None => ContainerEvent::Start(ContainerStartEvent {
+                container: container_info,
+            }),
+            Some(action) => ContainerEvent::Lifecycle(ContainerLifecycleEvent {
+                container: container_info,
+                action,
+            }),
         };
+        self.bus.publish(event);
 
-        // Notify all handlers
-        let handlers = self.handlers.read().await;
-        for handler in handlers.iter() {
-            if let Err(e) = handler.handle_container_start(start_event.clone()).await {
-                error!("Handler failed to process container start event: {}", e);
-                // Continue processing other handlers
+        Ok(())
+    }
+
+    /// Check before inspecting anything whether an event may concern a warp or a
+    /// target. Container events carry the container's name and labels in their actor
+    /// attributes; network events only name the container, so it must be known to the
+    /// inventory, or it's new and its start event will follow.
+    fn is_relevant(&self, event: &EventMessage) -> bool {
+        let Some(classifier) = &self.classifier else {
+            return true;
+        };
+        let Some(actor) = &event.actor else {
+            return true;
+        };
+        let attributes = actor.attributes.clone().unwrap_or_default();
+        let inventory = self.inventory.as_deref();
+        let known = |id: Option<&String>| {
+            id.and_then(|id| inventory?.get(id))
+                .is_some_and(|container| classifier.is_relevant(&container, inventory))
+        };
+
+        if event.typ == Some(EventMessageTypeEnum::NETWORK) {
+            return inventory.is_none() || known(attributes.get("container"));
+        }
+
+        // Podman may not report them; then there's nothing to tell by
+        let Some(name) = attributes.get("name") else {
+            return true;
+        };
+        let container = ContainerInfo {
+            id: actor.id.clone().unwrap_or_default(),
+            name: name.trim_start_matches('/').to_string(),
+            networks: vec![],
+            state: ContainerState::Running,
+            pid: None,
+            network_mode: None,
+            sandbox_key: None,
+            started_at: None,
+            // Besides the labels, the attributes only hold the name, image and details
+            // of the action, e.g. the exit code
+            labels: attributes,
+        };
+        // A renamed container is known to the inventory under its old name
+        classifier.is_relevant(&container, inventory) || known(actor.id.as_ref())
+    }
+
+    /// Event filters for each stream: container starts, stops and lifecycle events,
+    /// plus what keeps an inventory current when there is one
+    fn event_filters(&self) -> Vec<HashMap<String, Vec<String>>> {
+        let mut container_events = vec![
+            "start", "stop", "die", "pause", "unpause", "restart", "kill",
+        ];
+        let network_events = ["connect", "disconnect"];
+        if self.inventory.is_some() {
+            container_events.extend(["destroy", "rename", "health_status"]);
+        }
+
+        let filters = |types: &[&str], events: &[&str], label: Option<&String>| {
+            let mut filters = HashMap::new();
+            filters.insert(
+                "type".to_string(),
+                types.iter().map(|t| t.to_string()).collect(),
+            );
+            filters.insert(
+                "event".to_string(),
+                events.iter().map(|e| e.to_string()).collect(),
+            );
+            if let Some(label) = label {
+                filters.insert("label".to_string(), vec![label.clone()]);
             }
+            filters
+        };
+
+        if self.label_filter.is_empty() {
+            let mut types = vec!["container"];
+            if self.inventory.is_some() {
+                types.push("network");
+                container_events.extend(network_events);
+            }
+            return vec![filters(&types, &container_events, None)];
         }
 
-        Ok(())
+        let mut streams: Vec<_> = self
+            .label_filter
+            .iter()
+            .map(|label| filters(&["container"], &container_events, Some(label)))
+            .collect();
+        if self.inventory.is_some() {
+            // Label filters would match the network's labels rather than the container's
+            streams.push(filters(&["network"], &network_events, None));
+        }
+        streams
     }
 
     /// Start monitoring with retry logic
@@ -141,7 +448,7 @@ impl DockerEventMonitor {
         let mut retry_count = 0;
 
         loop {
-            match self.start_monitoring_internal().await {
+            match self.start_monitoring_internal(retry_count > 0).await {
                 Ok(()) => {
                     info!("Docker event monitoring completed successfully");
                     return Ok(());
@@ -167,54 +474,33 @@ impl DockerEventMonitor {
         }
     }
 
-    /// Internal monitoring implementation
-    async fn start_monitoring_internal(&self) -> Result<(), EventError> {
+    /// Internal monitoring implementation. After a reconnect, the inventory is resynced
+    /// to catch up on the events missed meanwhile.
+    async fn start_monitoring_internal(&self, reconnected: bool) -> Result<(), EventError> {
         info!("Starting Docker event monitoring");
 
-        // Create a new Docker connection for event streaming using the same configuration
-        let docker = match &self.docker_connection {
-            DockerConnection::Socket(socket_path) => {
-                Docker::connect_with_socket(socket_path, 120, bollard::API_DEFAULT_VERSION)
-                    .map_err(|e| {
-                        EventError::StartFailed(format!(
-                            "Failed to connect to Docker socket {}: {}",
-                            socket_path, e
-                        ))
-                    })?
+        let streams = self
+            .event_filters()
+            .into_iter()
+            .map(|filters| self.source.events(filters))
+            .collect::<Result<Vec<_>, _>>()?;
+        if reconnected {
+            if let Some(inventory) = &self.inventory {
+                inventory.request_resync();
             }
-            DockerConnection::Http(url) => {
-                Docker::connect_with_http(url, 120, bollard::API_DEFAULT_VERSION).map_err(|e| {
-                    EventError::StartFailed(format!(
-                        "Failed to connect to Docker HTTP {}: {}",
-                        url, e
-                    ))
-                })?
-            }
-            DockerConnection::Default => Docker::connect_with_socket_defaults().map_err(|e| {
-                EventError::StartFailed(format!("Failed to connect to Docker: {}", e))
-            })?,
-        };
-
-        // Set up event filters for container events
-        let mut filters = HashMap::new();
-        filters.insert("type".to_string(), vec!["container".to_string()]);
-        filters.insert(
-            "event".to_string(),
-            vec!["start".to_string(), "stop".to_string()],
-        );
-
-        let options = EventsOptions {
-            since: None,
-            until: None,
-            filters: Some(filters),
-        };
-
-        let mut stream = docker.events(Some(options));
+        }
+        let deduplicate = streams.len() > 1;
+        let mut recent = RecentEvents::default();
+        let mut stream = stream::select_all(streams);
 
         while let Some(event_result) = stream.next().await {
             match event_result {
                 Ok(event) => {
                     debug!("Received Docker event: {:?}", event);
+                    if deduplicate && recent.repeated(&event) {
+                        debug!("Skipping copy of an event received on another stream");
+                        continue;
+                    }
                     if let Err(e) = self.process_event(event).await {
                         error!("Failed to process Docker event: {}", e);
                         // Continue processing other events
@@ -222,7 +508,7 @@ impl DockerEventMonitor {
                 }
                 Err(e) => {
                     error!("Docker event stream error: {}", e);
-                    return Err(EventError::StreamError(e.to_string()));
+                    return Err(e);
                 }
             }
         }
@@ -232,7 +518,35 @@ impl DockerEventMonitor {
     }
 }
 
-impl EventMonitor for DockerEventMonitor {
+/// Normalize runtime-specific differences in event payloads to Docker's format.
+///
+/// Podman reports container deaths as `died` and, in older versions, leaves the
+/// actor ID empty while naming the container in the actor's attributes.
+fn normalize_event(mut event: EventMessage, runtime: ContainerRuntime) -> EventMessage {
+    if runtime != ContainerRuntime::Podman {
+        return event;
+    }
+
+    if event.action.as_deref() == Some("died") {
+        event.action = Some("die".to_string());
+    }
+
+    if let Some(actor) = event.actor.as_mut() {
+        if actor.id.as_deref().unwrap_or("").is_empty() {
+            // Containers can be inspected by name as well as by ID
+            actor.id = actor
+                .attributes
+                .as_ref()
+                .and_then(|attributes| attributes.get("name"))
+                .filter(|name| !name.is_empty())
+                .cloned();
+        }
+    }
+
+    event
+}
+
+impl<C: DockerClient, S: EventSource> EventMonitor for DockerEventMonitor<C, S> {
     async fn start_monitoring(&self) -> Result<(), EventError> {
         self.start_monitoring_with_retry().await
     }
@@ -245,14 +559,7 @@ impl EventMonitor for DockerEventMonitor {
     }
 
     fn subscribe_to_events(&self, handler: Box<dyn EventHandler>) -> Result<(), EventError> {
-        let handler_arc = Arc::from(handler);
-        tokio::spawn({
-            let handlers = Arc::clone(&self.handlers);
-            async move {
-                let mut handlers_guard = handlers.write().await;
-                handlers_guard.push(handler_arc);
-            }
-        });
+        self.bus.subscribe_handler(handler)?;
         Ok(())
     }
 }
@@ -260,31 +567,21 @@ impl EventMonitor for DockerEventMonitor {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::docker::classifier::DefaultContainerClassifier;
+    use crate::docker::NetworkInfo;
     use crate::error::HandlerError;
+    use ipnetwork::IpNetwork;
+    use std::collections::VecDeque;
+    use std::net::IpAddr;
+    use std::str::FromStr;
     use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::Mutex;
+    use tokio::sync::mpsc;
+    use tokio::time::timeout;
 
-    // Mock event handler for testing
+    // Mock event handler forwarding the events it handles
     struct MockEventHandler {
-        call_count: Arc<AtomicUsize>,
-        received_events: Arc<Mutex<Vec<ContainerStartEvent>>>,
-    }
-
-    impl MockEventHandler {
-        fn new() -> Self {
-            Self {
-                call_count: Arc::new(AtomicUsize::new(0)),
-                received_events: Arc::new(Mutex::new(Vec::new())),
-            }
-        }
-
-        fn get_call_count(&self) -> usize {
-            self.call_count.load(Ordering::SeqCst)
-        }
-
-        fn get_received_events(&self) -> Vec<ContainerStartEvent> {
-            self.received_events.lock().unwrap().clone()
-        }
+        events: mpsc::UnboundedSender<ContainerEvent>,
     }
 
     impl EventHandler for MockEventHandler {
@@ -295,8 +592,19 @@ mod tests {
             Box<dyn std::future::Future<Output = Result<(), HandlerError>> + Send + '_>,
         > {
             Box::pin(async move {
-                self.call_count.fetch_add(1, Ordering::SeqCst);
-                self.received_events.lock().unwrap().push(event);
+                self.events.send(ContainerEvent::Start(event)).unwrap();
+                Ok(())
+            })
+        }
+
+        fn handle_container_lifecycle(
+            &self,
+            event: ContainerLifecycleEvent,
+        ) -> std::pin::Pin<
+            Box<dyn std::future::Future<Output = Result<(), HandlerError>> + Send + '_>,
+        > {
+            Box::pin(async move {
+                self.events.send(ContainerEvent::Lifecycle(event)).unwrap();
                 Ok(())
             })
         }
@@ -309,7 +617,7 @@ mod tests {
         // We don't assert success here since Docker may not be available in test environment
         match result {
             Ok(monitor) => {
-                assert_eq!(monitor.handlers.read().await.len(), 0);
+                assert_eq!(monitor.bus().subscribers(), 0);
             }
             Err(_) => {
                 // Docker not available, which is fine for unit tests
@@ -317,126 +625,507 @@ mod tests {
         }
     }
 
-    #[tokio::test]
-    async fn test_retry_configuration() {
-        // Test retry configuration without requiring Docker connection
-        if let Ok(monitor) = DockerEventMonitor::new() {
-            let monitor = monitor.with_retry_config(Duration::from_millis(100), 5);
+    /// Events a scripted connection streams, or the error opening it
+    type Connection = Result<Vec<Result<EventMessage, EventError>>, EventError>;
 
-            assert_eq!(monitor.retry_delay, Duration::from_millis(100));
-            assert_eq!(monitor.max_retries, 5);
+    /// Event source replaying scripted connections: each one either fails to
+    /// open or streams its events and ends
+    #[derive(Default)]
+    struct MockEventSource {
+        connections: Mutex<VecDeque<Connection>>,
+        opened: Mutex<Vec<HashMap<String, Vec<String>>>>,
+    }
+
+    impl MockEventSource {
+        fn new(connections: Vec<Connection>) -> Self {
+            Self {
+                connections: Mutex::new(connections.into()),
+                opened: Mutex::new(Vec::new()),
+            }
+        }
+
+        fn opened(&self) -> usize {
+            self.opened.lock().unwrap().len()
         }
     }
 
-    #[test]
-    fn test_process_event_with_missing_data() {
-        // Test that process_event handles missing event data gracefully
-        if let Ok(monitor) = DockerEventMonitor::new() {
-            let rt = tokio::runtime::Runtime::new().unwrap();
+    impl EventSource for MockEventSource {
+        fn events(&self, filters: HashMap<String, Vec<String>>) -> Result<EventStream, EventError> {
+            self.opened.lock().unwrap().push(filters);
+            let events = self
+                .connections
+                .lock()
+                .unwrap()
+                .pop_front()
+                .unwrap_or_else(|| {
+                    Err(EventError::StartFailed("Connection refused".to_string()))
+                })?;
+            Ok(futures_util::stream::iter(events).boxed())
+        }
+    }
 
-            // Test event with missing type
-            let event = EventMessage {
-                typ: None,
-                action: Some("start".to_string()),
-                actor: None,
-                time: None,
-                time_nano: None,
-                scope: None,
-            };
+    // Mock Docker client knowing a fixed set of containers
+    #[derive(Default)]
+    struct MockDockerClient {
+        containers: Vec<ContainerInfo>,
+        inspects: AtomicUsize,
+    }
 
-            rt.block_on(async {
-                let result = monitor.process_event(event).await;
-                assert!(result.is_ok());
-            });
+    impl DockerClient for MockDockerClient {
+        async fn list_containers(&self, _all: bool) -> Result<Vec<ContainerInfo>, DockerError> {
+            Ok(self.containers.clone())
         }
+
+        async fn inspect_container(&self, id: &str) -> Result<ContainerInfo, DockerError> {
+            self.inspects.fetch_add(1, Ordering::SeqCst);
+            self.containers
+                .iter()
+                .find(|c| c.id == id)
+                .cloned()
+                .ok_or_else(|| DockerError::ContainerNotFound {
+                    container_id: id.to_string(),
+                })
+        }
+
+        async fn get_container_networks(&self, id: &str) -> Result<Vec<NetworkInfo>, DockerError> {
+            Ok(self.inspect_container(id).await?.networks)
+        }
+    }
+
+    fn test_container(id: &str) -> ContainerInfo {
+        ContainerInfo {
+            id: id.to_string(),
+            name: format!("{}-name", id),
+            labels: HashMap::new(),
+            networks: vec![NetworkInfo {
+                name: "bridge".to_string(),
+                ip_address: IpAddr::from_str("172.17.0.2").unwrap(),
+                gateway: Some(IpAddr::from_str("172.17.0.1").unwrap()),
+                subnet: IpNetwork::new(IpAddr::from_str("172.17.0.0").unwrap(), 16).unwrap(),
+            }],
+            state: ContainerState::Running,
+            pid: Some(30),
+            network_mode: None,
+            sandbox_key: None,
+            started_at: None,
+        }
+    }
+
+    fn container_event(action: &str, id: &str) -> EventMessage {
+        EventMessage {
+            typ: Some(EventMessageTypeEnum::CONTAINER),
+            action: Some(action.to_string()),
+            actor: Some(bollard::models::EventActor {
+                id: Some(id.to_string()),
+                attributes: None,
+            }),
+            time: None,
+            time_nano: None,
+            scope: None,
+        }
+    }
+
+    fn mock_monitor(
+        connections: Vec<Connection>,
+    ) -> DockerEventMonitor<MockDockerClient, MockEventSource> {
+        let client = MockDockerClient {
+            containers: vec![test_container("web")],
+            ..Default::default()
+        };
+        DockerEventMonitor::with_source(client, MockEventSource::new(connections))
+            .with_retry_config(Duration::from_millis(1), 2)
+    }
+
+    /// Events published on the monitor's bus so far
+    fn published(events: &mut mpsc::Receiver<ContainerEvent>) -> Vec<ContainerEvent> {
+        let mut published = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            published.push(event);
+        }
+        published
+    }
+
+    #[tokio::test]
+    async fn test_retry_configuration() {
+        let monitor = mock_monitor(vec![]).with_retry_config(Duration::from_millis(100), 5);
+
+        assert_eq!(monitor.retry_delay, Duration::from_millis(100));
+        assert_eq!(monitor.max_retries, 5);
+    }
+
+    #[tokio::test]
+    async fn test_process_event_with_missing_data() {
+        let monitor = mock_monitor(vec![]);
+        let mut events = monitor.bus().subscribe();
+
+        // Test event with missing type and actor
+        let event = EventMessage {
+            typ: None,
+            action: Some("start".to_string()),
+            actor: None,
+            time: None,
+            time_nano: None,
+            scope: None,
+        };
+        assert!(monitor.process_event(event).await.is_ok());
+
+        // Containers gone before they could be inspected are skipped
+        let event = container_event("start", "gone");
+        assert!(monitor.process_event(event).await.is_ok());
+
+        assert!(published(&mut events).is_empty());
     }
 
     #[tokio::test]
     async fn test_event_handler_subscription() {
-        if let Ok(monitor) = DockerEventMonitor::new() {
-            let handler = Box::new(MockEventHandler::new());
+        let monitor = mock_monitor(vec![Ok(vec![Ok(container_event("start", "web"))])]);
+        let (sender, mut handled) = mpsc::unbounded_channel();
+        monitor
+            .subscribe_to_events(Box::new(MockEventHandler { events: sender }))
+            .unwrap();
+
+        // Registered right away, so monitoring can start without missing events
+        assert_eq!(monitor.bus().subscribers(), 1);
+        monitor.start_monitoring().await.unwrap();
+
+        let event = timeout(Duration::from_secs(1), handled.recv())
+            .await
+            .expect("handler did not receive the event")
+            .unwrap();
+        assert!(matches!(event, ContainerEvent::Start(_)));
+        assert_eq!(event.container().id, "web");
+    }
+
+    #[tokio::test]
+    async fn test_event_processing_and_handler_invocation() {
+        let image_pull = EventMessage {
+            typ: Some(EventMessageTypeEnum::IMAGE),
+            ..container_event("pull", "nginx")
+        };
+        let monitor = mock_monitor(vec![Ok(vec![
+            Ok(container_event("start", "web")),
+            Ok(container_event("stop", "web")),
+            Ok(image_pull),
+            Ok(container_event("start", "gone")),
+        ])]);
+        let mut events = monitor.bus().subscribe();
+
+        // The stream ending completes monitoring
+        monitor.start_monitoring().await.unwrap();
+
+        // Only the events of an existing container are published
+        let events = published(&mut events);
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[0], ContainerEvent::Start(_)));
+        assert_eq!(events[0].container().id, "web");
+        assert_eq!(events[0].container().name, "web-name");
+        assert!(matches!(
+            &events[1],
+            ContainerEvent::Lifecycle(event) if event.action == LifecycleAction::Stop
+        ));
+
+        let opened = monitor.source.opened.lock().unwrap();
+        assert_eq!(opened[0]["type"], vec!["container"]);
+        assert_eq!(
+            opened[0]["event"],
+            vec!["start", "stop", "die", "pause", "unpause", "restart", "kill"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_event_dispatch() {
+        let mut kill = container_event("kill", "web");
+        kill.actor.as_mut().unwrap().attributes =
+            Some(HashMap::from([("signal".to_string(), "15".to_string())]));
+        let mut die = container_event("die", "web");
+        die.actor.as_mut().unwrap().attributes =
+            Some(HashMap::from([("exitCode".to_string(), "143".to_string())]));
+        let inventory = Arc::new(ContainerInventory::new("network.warp.target"));
+        let monitor = mock_monitor(vec![Ok(vec![
+            Ok(container_event("start", "web")),
+            Ok(container_event("pause", "web")),
+            Ok(container_event("unpause", "web")),
+            Ok(container_event("restart", "web")),
+            Ok(kill),
+            Ok(container_event("stop", "web")),
+            Ok(die),
+        ])])
+        .with_inventory(Arc::clone(&inventory));
+
+        let mut events = monitor.bus().subscribe();
 
-            // Subscribe handler
-            monitor.subscribe_to_events(handler).unwrap();
+        monitor.start_monitoring().await.unwrap();
 
-            // Give some time for the async subscription to complete
-            sleep(Duration::from_millis(10)).await;
+        let lifecycle_events: Vec<_> = published(&mut events)
+            .into_iter()
+            .filter_map(|event| match event {
+                ContainerEvent::Lifecycle(event) => Some(event),
+                ContainerEvent::Start(_) => None,
+            })
+            .collect();
+        let actions: Vec<_> = lifecycle_events.iter().map(|e| e.action.clone()).collect();
+        assert_eq!(
+            actions,
+            vec![
+                LifecycleAction::Pause,
+                LifecycleAction::Unpause,
+                LifecycleAction::Restart,
+                LifecycleAction::Kill {
+                    signal: Some("15".to_string())
+                },
+                LifecycleAction::Stop,
+                LifecycleAction::Die {
+                    exit_code: Some("143".to_string())
+                },
+            ]
+        );
+        // Subscribers see the container as the event left it
+        assert_eq!(lifecycle_events[0].container.state, ContainerState::Paused);
+        assert_eq!(lifecycle_events[1].container.state, ContainerState::Running);
+        assert_eq!(lifecycle_events[5].container.state, ContainerState::Stopped);
+        // Only the start and the restart are inspected
+        assert_eq!(monitor.docker_client.inspects.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_event_monitoring_retries() {
+        let connections = || {
+            vec![
+                Err(EventError::StartFailed("Connection refused".to_string())),
+                Ok(vec![Err(EventError::StreamError(
+                    "Connection reset".to_string(),
+                ))]),
+                Ok(vec![Ok(container_event("start", "web"))]),
+            ]
+        };
+
+        // Failed connections and broken streams are retried
+        let monitor = mock_monitor(connections());
+        let mut events = monitor.bus().subscribe();
+        monitor.start_monitoring().await.unwrap();
+        assert_eq!(monitor.source.opened(), 3);
+        assert_eq!(published(&mut events).len(), 1);
+
+        // Until the retries run out
+        let monitor = mock_monitor(connections()).with_retry_config(Duration::from_millis(1), 1);
+        assert!(matches!(
+            monitor.start_monitoring().await,
+            Err(EventError::StreamError(_))
+        ));
+        assert_eq!(monitor.source.opened(), 2);
+    }
 
-            // Check that handler was added
-            assert_eq!(monitor.handlers.read().await.len(), 1);
+    #[tokio::test]
+    async fn test_reconnect_resyncs_inventory() {
+        let inventory = Arc::new(ContainerInventory::new("network.warp.target"));
+        let monitor = mock_monitor(vec![
+            Ok(vec![Err(EventError::StreamError(
+                "Connection reset".to_string(),
+            ))]),
+            Ok(vec![]),
+        ])
+        .with_inventory(Arc::clone(&inventory));
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let resync = inventory.spawn_resync(
+            Arc::new(MockDockerClient {
+                containers: vec![test_container("web")],
+                ..Default::default()
+            }),
+            Duration::from_secs(3600),
+            move |changed| tx.send(changed).unwrap(),
+        );
+
+        // web started while the stream was down; the resync after reconnecting reports it
+        monitor.start_monitoring().await.unwrap();
+        let changed = timeout(Duration::from_secs(1), rx.recv()).await;
+        resync.abort();
+
+        assert_eq!(changed.unwrap().unwrap(), vec!["web"]);
+    }
+
+    #[tokio::test]
+    async fn test_event_monitoring_with_inventory() {
+        let inventory = Arc::new(ContainerInventory::new("network.warp.target"));
+        let monitor = mock_monitor(vec![Ok(vec![
+            Ok(container_event("start", "web")),
+            Ok(container_event("die", "web")),
+        ])])
+        .with_inventory(Arc::clone(&inventory));
+        let mut events = monitor.bus().subscribe();
+
+        monitor.start_monitoring().await.unwrap();
+
+        // The container the inventory inspected is published as is, and the death
+        // as the inventory saw it, without inspecting again
+        let events = published(&mut events);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].container().state, ContainerState::Stopped);
+        assert_eq!(monitor.docker_client.inspects.load(Ordering::SeqCst), 1);
+        assert_eq!(inventory.get("web").unwrap().state, ContainerState::Stopped);
+
+        let opened = monitor.source.opened.lock().unwrap();
+        assert_eq!(opened[0]["type"], vec!["container", "network"]);
+        for event in ["die", "destroy", "rename", "health_status", "connect"] {
+            assert!(opened[0]["event"].iter().any(|e| e == event));
         }
     }
 
+    fn labelled_event(action: &str, id: &str, attributes: &[(&str, &str)]) -> EventMessage {
+        let mut event = container_event(action, id);
+        event.actor.as_mut().unwrap().attributes = Some(
+            attributes
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        );
+        event
+    }
+
     #[tokio::test]
-    async fn test_event_processing_and_handler_invocation() {
-        // Create a test monitor that we can use to test event processing
-        if let Ok(monitor) = DockerEventMonitor::new() {
-            let handler = Box::new(MockEventHandler::new());
-
-            // Get references to the handler's internal state before moving it
-            let handler_call_count = Arc::clone(&handler.call_count);
-            let handler_events = Arc::clone(&handler.received_events);
-
-            // Subscribe handler
-            monitor.subscribe_to_events(handler).unwrap();
-
-            // Give some time for the async subscription to complete
-            sleep(Duration::from_millis(10)).await;
-
-            // Verify handler was added
-            assert_eq!(monitor.handlers.read().await.len(), 1);
-
-            // Create mock container data that the Docker client would return
-            use crate::docker::{ContainerInfo, ContainerState, NetworkInfo};
-            use ipnetwork::IpNetwork;
-            use std::collections::HashMap;
-            use std::net::IpAddr;
-            use std::str::FromStr;
-
-            let container_info = ContainerInfo {
-                id: "test-container-123".to_string(),
-                name: "test-container".to_string(),
-                labels: HashMap::new(),
-                networks: vec![NetworkInfo {
-                    name: "bridge".to_string(),
-                    ip_address: IpAddr::from_str("172.17.0.2").unwrap(),
-                    gateway: Some(IpAddr::from_str("172.17.0.1").unwrap()),
-                    subnet: IpNetwork::new(IpAddr::from_str("172.17.0.0").unwrap(), 16).unwrap(),
-                }],
-                state: ContainerState::Running,
-                pid: Some(30),
-            };
-
-            // Manually create a ContainerStartEvent and notify handlers
-            // This simulates what would happen in process_event after successful container inspection
-            let start_event = ContainerStartEvent {
-                container: container_info,
-            };
+    async fn test_label_filtered_streams() {
+        let inventory = Arc::new(ContainerInventory::new("network.warp.target"));
+        let at = |mut event: EventMessage, time_nano| {
+            event.time_nano = Some(time_nano);
+            event
+        };
+        let monitor = mock_monitor(vec![
+            Ok(vec![Ok(at(container_event("start", "web"), 1))]),
+            Ok(vec![
+                Ok(at(container_event("start", "web"), 1)),
+                Ok(at(container_event("stop", "web"), 2)),
+            ]),
+            Ok(vec![]),
+        ])
+        .with_inventory(inventory)
+        .with_label_filter(vec![
+            "network.warp.target".to_string(),
+            "network.warp.role".to_string(),
+        ]);
+        let mut events = monitor.bus().subscribe();
 
-            // Manually notify handlers (simulating successful event processing)
-            let handlers = monitor.handlers.read().await;
-            for handler in handlers.iter() {
-                if let Err(e) = handler.handle_container_start(start_event.clone()).await {
-                    panic!("Handler failed: {}", e);
-                }
-            }
+        monitor.start_monitoring().await.unwrap();
+        // The start matches both labels, and is published once
+        assert_eq!(published(&mut events).len(), 2);
+        assert_eq!(monitor.docker_client.inspects.load(Ordering::SeqCst), 1);
 
-            // Verify the handler was called
-            assert_eq!(handler_call_count.load(Ordering::SeqCst), 1);
+        // A stream per label, as Docker only passes events matching all label filters
+        let opened = monitor.source.opened.lock().unwrap();
+        assert_eq!(opened.len(), 3);
+        assert_eq!(opened[0]["type"], vec!["container"]);
+        assert_eq!(opened[0]["label"], vec!["network.warp.target"]);
+        assert_eq!(opened[1]["label"], vec!["network.warp.role"]);
+        assert!(opened[1]["event"].iter().any(|e| e == "die"));
+        assert_eq!(opened[2]["type"], vec!["network"]);
+        assert_eq!(opened[2]["event"], vec!["connect", "disconnect"]);
+        assert!(!opened[2].contains_key("label"));
+    }
+
+    #[tokio::test]
+    async fn test_irrelevant_events_are_not_inspected() {
+        let mut warp = test_container("warp");
+        warp.name = "warp-1".to_string();
+        warp.labels
+            .insert("network.warp.serve".to_string(), "app=frontend".to_string());
+        let inventory = Arc::new(ContainerInventory::new("network.warp.target"));
+        inventory.upsert(warp);
+        inventory.upsert(test_container("cache"));
+
+        let client = MockDockerClient {
+            containers: vec![test_container("web"), test_container("front")],
+            ..Default::default()
+        };
+        let network_connect = EventMessage {
+            typ: Some(EventMessageTypeEnum::NETWORK),
+            ..labelled_event("connect", "bridge", &[("container", "db")])
+        };
+        let source = MockEventSource::new(vec![Ok(vec![
+            Ok(labelled_event(
+                "start",
+                "db",
+                &[("name", "db"), ("image", "postgres")],
+            )),
+            Ok(network_connect),
+            Ok(labelled_event("die", "cache", &[("name", "cache-name")])),
+            Ok(labelled_event(
+                "start",
+                "web",
+                &[("name", "web-name"), ("network.warp.target", "warp-1")],
+            )),
+            Ok(labelled_event(
+                "start",
+                "front",
+                &[("name", "front-name"), ("app", "frontend")],
+            )),
+        ])]);
+        let classifier = DefaultContainerClassifier::with_simple_pattern(
+            "warp-*".to_string(),
+            "network.warp.target".to_string(),
+            "network.warp.network".to_string(),
+        );
+        let monitor = DockerEventMonitor::with_source(client, source)
+            .with_inventory(Arc::clone(&inventory))
+            .with_classifier(Arc::new(classifier));
+        let mut events = monitor.bus().subscribe();
 
-            // Verify the handler received the correct event
-            let received_events = handler_events.lock().unwrap();
-            assert_eq!(received_events.len(), 1);
-            assert_eq!(received_events[0].container.id, "test-container-123");
-            assert_eq!(received_events[0].container.name, "test-container");
+        monitor.start_monitoring().await.unwrap();
+
+        // The target and the container the warp's selector serves are inspected once
+        let ids: Vec<_> = published(&mut events)
+            .iter()
+            .map(|event| event.container().id.clone())
+            .collect();
+        assert_eq!(ids, vec!["web", "front"]);
+        assert_eq!(monitor.docker_client.inspects.load(Ordering::SeqCst), 2);
+        assert!(inventory.get("db").is_none());
+        // The inventory still follows the events of irrelevant containers it knows
+        assert_eq!(
+            inventory.get("cache").unwrap().state,
+            ContainerState::Stopped
+        );
+    }
+
+    fn podman_event(action: &str, id: Option<&str>, name: &str) -> EventMessage {
+        EventMessage {
+            typ: Some(EventMessageTypeEnum::CONTAINER),
+            action: Some(action.to_string()),
+            actor: Some(bollard::models::EventActor {
+                id: id.map(|id| id.to_string()),
+                attributes: Some(HashMap::from([("name".to_string(), name.to_string())])),
+            }),
+            time: None,
+            time_nano: None,
+            scope: Some(bollard::models::EventMessageScopeEnum::LOCAL),
         }
     }
 
+    #[test]
+    fn test_normalize_podman_event() {
+        let event = normalize_event(
+            podman_event("died", Some("abc"), "app"),
+            ContainerRuntime::Podman,
+        );
+        assert_eq!(event.action.as_deref(), Some("die"));
+        assert_eq!(event.actor.unwrap().id.as_deref(), Some("abc"));
+
+        // Older Podman versions only name the container
+        let event = normalize_event(
+            podman_event("start", Some(""), "app"),
+            ContainerRuntime::Podman,
+        );
+        assert_eq!(event.action.as_deref(), Some("start"));
+        assert_eq!(event.actor.unwrap().id.as_deref(), Some("app"));
+
+        // Docker events are left untouched
+        let event = normalize_event(podman_event("died", None, "app"), ContainerRuntime::Docker);
+        assert_eq!(event.action.as_deref(), Some("died"));
+        assert_eq!(event.actor.unwrap().id, None);
+    }
+
     #[test]
     fn test_docker_connection_configuration() {
         // Test default connection
         if let Ok(monitor) = DockerEventMonitor::new() {
-            match monitor.docker_connection {
+            match monitor.source.connection {
                 DockerConnection::Default => {}
                 _ => panic!("Expected Default connection type"),
             }
@@ -444,8 +1133,8 @@ mod tests {
 
         // Test socket connection
         if let Ok(monitor) = DockerEventMonitor::with_socket("/var/run/docker.sock") {
-            match monitor.docker_connection {
-                DockerConnection::Socket(path) => {
+            match monitor.source.connection {
+                DockerConnection::Endpoint(DockerEndpoint::Socket(path)) => {
                     assert_eq!(path, "/var/run/docker.sock");
                 }
                 _ => panic!("Expected Socket connection type"),
@@ -454,12 +1143,71 @@ mod tests {
 
         // Test HTTP connection
         if let Ok(monitor) = DockerEventMonitor::with_http("http://localhost:2376") {
-            match monitor.docker_connection {
-                DockerConnection::Http(url) => {
+            match monitor.source.connection {
+                DockerConnection::Endpoint(DockerEndpoint::Http(url)) => {
                     assert_eq!(url, "http://localhost:2376");
                 }
                 _ => panic!("Expected Http connection type"),
             }
         }
+
+        // Test connection to a resolved endpoint with API settings
+        let api = DockerApiConfig {
+            version: Some(ApiVersion {
+                major: 1,
+                minor: 41,
+            }),
+            timeout: 30,
+        };
+        if let Ok(monitor) = DockerEventMonitor::connect(
+            &DockerEndpoint::Http("tcp://localhost:2375".to_string()),
+            &api,
+        ) {
+            match monitor.source.connection {
+                DockerConnection::Endpoint(DockerEndpoint::Http(url)) => {
+                    assert_eq!(url, "tcp://localhost:2375");
+                }
+                _ => panic!("Expected Http connection type"),
+            }
+            assert_eq!(monitor.source.api, api);
+            assert_eq!(monitor.docker_client.api_version(), api.version.unwrap());
+        }
+
+        // Test TLS connection
+        let dir = tempfile::tempdir().unwrap();
+        let tls = DockerTlsConfig {
+            ca: dir.path().join("ca.pem"),
+            cert: dir.path().join("cert.pem"),
+            key: dir.path().join("key.pem"),
+        };
+        assert!(matches!(
+            DockerEventMonitor::with_ssl("tcp://localhost:2376", &tls),
+            Err(DockerError::ConnectionFailed(_))
+        ));
+
+        // The event source keeps the TLS endpoint and only reads the
+        // certificates when it opens a stream
+        let source = BollardEventSource {
+            connection: DockerConnection::Endpoint(DockerEndpoint::Ssl(
+                "tcp://localhost:2376".to_string(),
+                tls.clone(),
+            )),
+            api,
+        };
+        match &source.connection {
+            DockerConnection::Endpoint(DockerEndpoint::Ssl(address, config)) => {
+                assert_eq!(address, "tcp://localhost:2376");
+                assert_eq!(config, &tls);
+            }
+            _ => panic!("Expected Ssl connection type"),
+        }
+        assert_eq!(source.api, api);
+        match source.events(HashMap::new()) {
+            Err(EventError::StartFailed(message)) => {
+                assert!(message.contains("tcp://localhost:2376 (TLS"));
+                assert!(message.contains(&tls.ca.display().to_string()));
+            }
+            _ => panic!("Expected the missing certificates to fail the stream"),
+        }
     }
 }
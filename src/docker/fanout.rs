@@ -0,0 +1,212 @@
+//! Bounded-concurrency fan-out over many containers
+//!
+//! A warp starting, or startup reconciliation, may concern hundreds of targets.
+//! Handling them one by one is slow, while handling them all at once floods the
+//! Docker API and netlink, so they run with a concurrency limit and each target
+//! gets its own timeout. The outcome is summarized for logging.
+
+use futures_util::stream::{self, StreamExt};
+use std::fmt;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Outcome of a fan-out
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FanOutSummary {
+    /// Targets handled successfully
+    pub succeeded: Vec<String>,
+    /// Targets that failed, with their error
+    pub failed: Vec<(String, String)>,
+    /// Targets that didn't complete within the per-target timeout
+    pub timed_out: Vec<String>,
+    pub elapsed: Duration,
+}
+
+impl FanOutSummary {
+    /// Total number of targets
+    pub fn total(&self) -> usize {
+        self.succeeded.len() + self.failed.len() + self.timed_out.len()
+    }
+
+    /// Check whether every target succeeded
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty() && self.timed_out.is_empty()
+    }
+
+    /// Log the summary, listing the targets that didn't succeed
+    pub fn log(&self, context: &str) {
+        if self.is_success() {
+            info!("{}: {}", context, self);
+            return;
+        }
+
+        warn!("{}: {}", context, self);
+        for (target, error) in &self.failed {
+            warn!("{}: {} failed: {}", context, target, error);
+        }
+        for target in &self.timed_out {
+            warn!("{}: {} timed out", context, target);
+        }
+    }
+}
+
+impl fmt::Display for FanOutSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} targets succeeded, {} failed, {} timed out in {:.2?}",
+            self.succeeded.len(),
+            self.total(),
+            self.failed.len(),
+            self.timed_out.len(),
+            self.elapsed
+        )
+    }
+}
+
+/// Result of one target
+enum Outcome {
+    Succeeded,
+    Failed(String),
+    TimedOut,
+}
+
+/// Runs an operation over many targets with bounded concurrency and per-target timeouts
+#[derive(Debug, Clone, Copy)]
+pub struct FanOut {
+    concurrency: usize,
+    target_timeout: Duration,
+}
+
+impl FanOut {
+    /// Create a fan-out running at most `concurrency` targets at a time
+    pub fn new(concurrency: usize, target_timeout: Duration) -> Self {
+        Self {
+            concurrency: concurrency.max(1),
+            target_timeout,
+        }
+    }
+
+    /// Run an operation for each named target and summarize the outcome.
+    ///
+    /// An operation that times out is dropped where it stands, so it must leave
+    /// state its next run can pick up from.
+    pub async fn run<T, F, Fut, E>(
+        &self,
+        targets: impl IntoIterator<Item = (String, T)>,
+        operation: F,
+    ) -> FanOutSummary
+    where
+        F: Fn(T) -> Fut,
+        Fut: Future<Output = Result<(), E>>,
+        E: fmt::Display,
+    {
+        let started = Instant::now();
+        let timeout = self.target_timeout;
+        let operation = &operation;
+
+        let outcomes: Vec<(String, Outcome)> = stream::iter(targets)
+            .map(|(name, target)| async move {
+                let outcome = match tokio::time::timeout(timeout, operation(target)).await {
+                    Ok(Ok(())) => Outcome::Succeeded,
+                    Ok(Err(e)) => Outcome::Failed(e.to_string()),
+                    Err(_) => Outcome::TimedOut,
+                };
+                (name, outcome)
+            })
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await;
+
+        let mut summary = FanOutSummary::default();
+        for (name, outcome) in outcomes {
+            match outcome {
+                Outcome::Succeeded => summary.succeeded.push(name),
+                Outcome::Failed(error) => summary.failed.push((name, error)),
+                Outcome::TimedOut => summary.timed_out.push(name),
+            }
+        }
+        summary.succeeded.sort();
+        summary.failed.sort();
+        summary.timed_out.sort();
+        summary.elapsed = started.elapsed();
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::time::sleep;
+
+    fn targets(count: usize) -> Vec<(String, usize)> {
+        (0..count)
+            .map(|i| (format!("target-{:02}", i), i))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_bounds_concurrency() {
+        let running = AtomicUsize::new(0);
+        let max_running = AtomicUsize::new(0);
+
+        let summary = FanOut::new(3, Duration::from_secs(5))
+            .run(targets(12), |_| async {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                max_running.fetch_max(now, Ordering::SeqCst);
+                sleep(Duration::from_millis(10)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                Ok::<(), String>(())
+            })
+            .await;
+
+        assert!(summary.is_success());
+        assert_eq!(summary.succeeded.len(), 12);
+        assert_eq!(max_running.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_summarizes_failures_and_timeouts() {
+        let summary = FanOut::new(4, Duration::from_millis(50))
+            .run(targets(6), |i| async move {
+                match i % 3 {
+                    0 => Ok(()),
+                    1 => Err(format!("error {}", i)),
+                    _ => {
+                        sleep(Duration::from_secs(5)).await;
+                        Ok(())
+                    }
+                }
+            })
+            .await;
+
+        assert!(!summary.is_success());
+        assert_eq!(summary.total(), 6);
+        assert_eq!(summary.succeeded, vec!["target-00", "target-03"]);
+        assert_eq!(
+            summary.failed,
+            vec![
+                ("target-01".to_string(), "error 1".to_string()),
+                ("target-04".to_string(), "error 4".to_string())
+            ]
+        );
+        assert_eq!(summary.timed_out, vec!["target-02", "target-05"]);
+        assert!(summary
+            .to_string()
+            .starts_with("2 of 6 targets succeeded, 2 failed, 2 timed out in"));
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_without_targets() {
+        let summary = FanOut::new(0, Duration::from_secs(1))
+            .run(Vec::<(String, ())>::new(), |_| async {
+                Ok::<(), String>(())
+            })
+            .await;
+
+        assert!(summary.is_success());
+        assert_eq!(summary.total(), 0);
+    }
+}
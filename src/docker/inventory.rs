@@ -0,0 +1,969 @@
+//! Event-driven in-memory inventory of containers
+//!
+//! Finding the targets of a warp that just started would otherwise take a full
+//! container list plus an inspect per container. The inventory is seeded from a
+//! single list call, kept current from the event stream and indexed by ID,
+//! name, target label value, compose project, swarm service and whether the
+//! container carries a warp selector, so lookups don't touch the Docker API.
+//! A periodic full resync repairs the state after missed events, e.g. while
+//! the event stream was reconnecting, and reports the containers it changed so
+//! their routes can be reconciled.
+//!
+//! Containers seeded from the list lack inspect-only fields such as the PID
+//! until a start event inspects them. Events applied while a resync waits for
+//! the list win over it, as the list may predate them.
+
+use crate::config::DEFAULT_WARP_SELECTOR_LABEL;
+use crate::docker::classifier::COMPOSE_PROJECT_LABEL;
+use crate::docker::{ContainerInfo, ContainerState, DockerClient, SWARM_SERVICE_NAME_LABEL};
+use crate::error::DockerError;
+use bollard::models::EventMessage;
+use bollard::secret::EventMessageTypeEnum;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, warn};
+
+/// Container with the state only events report
+#[derive(Debug, Clone)]
+struct Entry {
+    container: ContainerInfo,
+    /// Last health status, e.g. `healthy`, from `health_status` events
+    health: Option<String>,
+    /// Sequence number of the last change from an event
+    updated: u64,
+}
+
+/// Labels the inventory indexes containers by
+#[derive(Debug)]
+struct IndexLabels {
+    /// Label naming a target's warp, indexed by value
+    target: String,
+    /// Label holding a warp's selector, indexed by presence
+    selector: String,
+}
+
+/// Containers by ID and the indexes over them
+#[derive(Debug, Default)]
+struct Indexes {
+    containers: HashMap<String, Entry>,
+    by_name: HashMap<String, String>,
+    by_target: HashMap<String, HashSet<String>>,
+    by_project: HashMap<String, HashSet<String>>,
+    by_service: HashMap<String, HashSet<String>>,
+    by_selector: HashSet<String>,
+    /// Sequence number of the last change from an event
+    sequence: u64,
+    /// Containers destroyed since the last resync, with the sequence number
+    destroyed: HashMap<String, u64>,
+}
+
+impl Indexes {
+    fn insert(&mut self, labels: &IndexLabels, entry: Entry) {
+        self.remove(labels, &entry.container.id);
+
+        let container = &entry.container;
+        let id = &container.id;
+        self.by_name.insert(container.name.clone(), id.clone());
+        add_to(
+            &mut self.by_target,
+            container.labels.get(&labels.target),
+            id,
+        );
+        add_to(
+            &mut self.by_project,
+            container.labels.get(COMPOSE_PROJECT_LABEL),
+            id,
+        );
+        add_to(
+            &mut self.by_service,
+            container.labels.get(SWARM_SERVICE_NAME_LABEL),
+            id,
+        );
+        if container.labels.contains_key(&labels.selector) {
+            self.by_selector.insert(id.clone());
+        }
+
+        self.containers.insert(id.clone(), entry);
+    }
+
+    fn remove(&mut self, labels: &IndexLabels, id: &str) -> Option<Entry> {
+        let entry = self.containers.remove(id)?;
+
+        let container = &entry.container;
+        if self
+            .by_name
+            .get(&container.name)
+            .is_some_and(|named| named == id)
+        {
+            self.by_name.remove(&container.name);
+        }
+        remove_from(
+            &mut self.by_target,
+            container.labels.get(&labels.target),
+            id,
+        );
+        remove_from(
+            &mut self.by_project,
+            container.labels.get(COMPOSE_PROJECT_LABEL),
+            id,
+        );
+        remove_from(
+            &mut self.by_service,
+            container.labels.get(SWARM_SERVICE_NAME_LABEL),
+            id,
+        );
+        self.by_selector.remove(id);
+
+        Some(entry)
+    }
+
+    /// Resolve a container ID, or a name as Podman sometimes reports instead
+    fn resolve(&self, id_or_name: &str) -> Option<String> {
+        if self.containers.contains_key(id_or_name) {
+            return Some(id_or_name.to_string());
+        }
+        self.by_name.get(id_or_name).cloned()
+    }
+
+    fn collect<'a>(&self, ids: impl IntoIterator<Item = &'a String>) -> Vec<ContainerInfo> {
+        let mut containers: Vec<ContainerInfo> = ids
+            .into_iter()
+            .filter_map(|id| self.containers.get(id))
+            .map(|entry| entry.container.clone())
+            .collect();
+        containers.sort_by(|a, b| a.name.cmp(&b.name));
+        containers
+    }
+}
+
+fn add_to(index: &mut HashMap<String, HashSet<String>>, key: Option<&String>, id: &str) {
+    if let Some(key) = key {
+        index.entry(key.clone()).or_default().insert(id.to_string());
+    }
+}
+
+fn remove_from(index: &mut HashMap<String, HashSet<String>>, key: Option<&String>, id: &str) {
+    if let Some(key) = key {
+        if let Some(ids) = index.get_mut(key) {
+            ids.remove(id);
+            if ids.is_empty() {
+                index.remove(key);
+            }
+        }
+    }
+}
+
+/// Check whether a listed container differs from the known one in what the list reports
+fn listed_differs(known: &ContainerInfo, listed: &ContainerInfo) -> bool {
+    known.name != listed.name
+        || known.state != listed.state
+        || known.labels != listed.labels
+        || known.network_mode != listed.network_mode
+        || known.networks.len() != listed.networks.len()
+        || !listed
+            .networks
+            .iter()
+            .all(|network| known.networks.contains(network))
+}
+
+/// In-memory container inventory, kept current from Docker events
+pub struct ContainerInventory {
+    labels: IndexLabels,
+    indexes: RwLock<Indexes>,
+    resync_requested: Notify,
+}
+
+impl ContainerInventory {
+    /// Create an empty inventory indexing containers by the value of a target label
+    pub fn new(target_label: impl Into<String>) -> Self {
+        Self {
+            labels: IndexLabels {
+                target: target_label.into(),
+                selector: DEFAULT_WARP_SELECTOR_LABEL.to_string(),
+            },
+            indexes: RwLock::new(Indexes::default()),
+            resync_requested: Notify::new(),
+        }
+    }
+
+    /// Set the label holding warp selectors, to index the containers carrying one.
+    /// Set before adding containers.
+    pub fn with_selector_label(mut self, label: impl Into<String>) -> Self {
+        self.labels.selector = label.into();
+        self
+    }
+
+    /// Number of containers
+    pub fn len(&self) -> usize {
+        self.read().containers.len()
+    }
+
+    /// Check whether the inventory has no containers
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get a container by ID
+    pub fn get(&self, id: &str) -> Option<ContainerInfo> {
+        let indexes = self.read();
+        indexes
+            .containers
+            .get(id)
+            .map(|entry| entry.container.clone())
+    }
+
+    /// Get a container by name
+    pub fn get_by_name(&self, name: &str) -> Option<ContainerInfo> {
+        let indexes = self.read();
+        let id = indexes.by_name.get(name)?;
+        indexes
+            .containers
+            .get(id)
+            .map(|entry| entry.container.clone())
+    }
+
+    /// Get the last reported health status of a container
+    pub fn health(&self, id: &str) -> Option<String> {
+        let indexes = self.read();
+        indexes.containers.get(id)?.health.clone()
+    }
+
+    /// Get the containers whose target label has a value, ordered by name
+    pub fn targets_of(&self, value: &str) -> Vec<ContainerInfo> {
+        let indexes = self.read();
+        indexes.collect(indexes.by_target.get(value).into_iter().flatten())
+    }
+
+    /// Get the containers of a compose project, ordered by name
+    pub fn in_project(&self, project: &str) -> Vec<ContainerInfo> {
+        let indexes = self.read();
+        indexes.collect(indexes.by_project.get(project).into_iter().flatten())
+    }
+
+    /// Get the task containers of a swarm service, ordered by name
+    pub fn in_service(&self, service: &str) -> Vec<ContainerInfo> {
+        let indexes = self.read();
+        indexes.collect(indexes.by_service.get(service).into_iter().flatten())
+    }
+
+    /// Get the containers carrying the selector label, ordered by name
+    pub fn selecting(&self) -> Vec<ContainerInfo> {
+        let indexes = self.read();
+        indexes.collect(&indexes.by_selector)
+    }
+
+    /// Get all containers
+    pub fn containers(&self) -> Vec<ContainerInfo> {
+        self.read()
+            .containers
+            .values()
+            .map(|entry| entry.container.clone())
+            .collect()
+    }
+
+    /// Add or replace a container, keeping its health status
+    pub fn upsert(&self, container: ContainerInfo) {
+        let mut indexes = self.write();
+        let health = indexes
+            .containers
+            .get(&container.id)
+            .and_then(|entry| entry.health.clone());
+        indexes.sequence += 1;
+        let updated = indexes.sequence;
+        indexes.insert(
+            &self.labels,
+            Entry {
+                container,
+                health,
+                updated,
+            },
+        );
+    }
+
+    /// Remove a container
+    pub fn remove(&self, id: &str) -> Option<ContainerInfo> {
+        let mut indexes = self.write();
+        indexes.sequence += 1;
+        let sequence = indexes.sequence;
+        indexes.destroyed.insert(id.to_string(), sequence);
+        indexes
+            .remove(&self.labels, id)
+            .map(|entry| entry.container)
+    }
+
+    /// Replace the inventory with a full container list. Returns the IDs of the containers
+    /// the list added, changed or dropped, sorted, i.e. those whose events were missed.
+    ///
+    /// Containers changed or destroyed by events while the list was requested keep
+    /// the state from those events.
+    pub async fn resync<C: DockerClient>(&self, client: &C) -> Result<Vec<String>, DockerError> {
+        let started = self.read().sequence;
+        let containers = client.list_containers(true).await?;
+
+        let mut indexes = self.write();
+        let mut previous = std::mem::take(&mut *indexes);
+        indexes.sequence = previous.sequence;
+        let mut changed = Vec::new();
+        for mut container in containers {
+            if previous
+                .destroyed
+                .get(&container.id)
+                .is_some_and(|&destroyed| destroyed > started)
+            {
+                continue;
+            }
+
+            let mut health = None;
+            if let Some(entry) = previous.containers.remove(&container.id) {
+                if entry.updated > started {
+                    indexes.insert(&self.labels, entry);
+                    continue;
+                }
+                health = entry.health;
+                // The list doesn't report what inspect did; it still holds unless restarted
+                let known = entry.container;
+                if listed_differs(&known, &container) {
+                    changed.push(container.id.clone());
+                }
+                if container.state.is_active() && known.state.is_active() {
+                    container.pid = container.pid.or(known.pid);
+                    container.started_at = container.started_at.or(known.started_at);
+                    container.sandbox_key = container.sandbox_key.or(known.sandbox_key);
+                }
+            } else {
+                changed.push(container.id.clone());
+            }
+            indexes.insert(
+                &self.labels,
+                Entry {
+                    container,
+                    health,
+                    updated: started,
+                },
+            );
+        }
+        // Containers started while the list was requested may be missing from it
+        for (id, entry) in previous.containers {
+            if entry.updated > started {
+                indexes.insert(&self.labels, entry);
+            } else {
+                changed.push(id);
+            }
+        }
+
+        changed.sort();
+        Ok(changed)
+    }
+
+    /// Update the inventory from a Docker event.
+    ///
+    /// Start, restart and network events re-inspect the container; the inspected container is
+    /// returned so callers handling the event don't need to inspect it again.
+    pub async fn apply_event<C: DockerClient>(
+        &self,
+        event: &EventMessage,
+        client: &C,
+    ) -> Option<ContainerInfo> {
+        let id = self.record_event(event)?;
+        self.refresh(&id, client).await
+    }
+
+    /// Update the inventory from what a Docker event tells by itself, without
+    /// inspecting anything. Returns the container to re-inspect for the rest, e.g. the
+    /// PID and networks after a start. Until then, a started container is marked running
+    /// without a PID, and one the inventory doesn't know waits for the next resync.
+    pub fn record_event(&self, event: &EventMessage) -> Option<String> {
+        let action = event.action.as_deref()?;
+        let actor = event.actor.as_ref()?;
+        let attribute = |name: &str| {
+            actor
+                .attributes
+                .as_ref()
+                .and_then(|attributes| attributes.get(name))
+                .filter(|value| !value.is_empty())
+        };
+
+        if event.typ == Some(EventMessageTypeEnum::NETWORK) {
+            return match action {
+                "connect" | "disconnect" => attribute("container").cloned(),
+                _ => None,
+            };
+        }
+        if event.typ.is_some() && event.typ != Some(EventMessageTypeEnum::CONTAINER) {
+            return None;
+        }
+
+        let id = actor.id.as_deref().filter(|id| !id.is_empty())?;
+        match action {
+            "start" | "restart" => {
+                self.update(id, |entry| {
+                    entry.container.state = ContainerState::Running;
+                    entry.container.pid = None;
+                });
+                return Some(id.to_string());
+            }
+            "pause" => self.update(id, |entry| entry.container.state = ContainerState::Paused),
+            "unpause" => self.update(id, |entry| entry.container.state = ContainerState::Running),
+            "stop" | "die" => self.update(id, |entry| {
+                entry.container.state = ContainerState::Stopped;
+                entry.container.pid = None;
+            }),
+            "destroy" => {
+                let id = self.read().resolve(id);
+                if let Some(id) = id {
+                    self.remove(&id);
+                }
+            }
+            "rename" => {
+                if let Some(name) = attribute("name") {
+                    let name = name.trim_start_matches('/').to_string();
+                    self.update(id, |entry| entry.container.name = name);
+                }
+            }
+            action => {
+                // Reported as `health_status: healthy`
+                if let Some(status) = action.strip_prefix("health_status:") {
+                    let status = status.trim().to_string();
+                    self.update(id, |entry| entry.health = Some(status));
+                }
+            }
+        }
+        None
+    }
+
+    /// Ask the resync task to resync now, e.g. after the event stream reconnected
+    pub fn request_resync(&self) {
+        self.resync_requested.notify_one();
+    }
+
+    /// Resync the inventory periodically and when requested, passing the IDs of the
+    /// containers each resync changed to `on_changes`. The inventory should be seeded before.
+    pub fn spawn_resync<C, F>(
+        self: &Arc<Self>,
+        client: Arc<C>,
+        interval: Duration,
+        on_changes: F,
+    ) -> JoinHandle<()>
+    where
+        C: DockerClient + 'static,
+        F: Fn(Vec<String>) + Send + 'static,
+    {
+        let inventory = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            ticker.tick().await;
+
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = inventory.resync_requested.notified() => ticker.reset(),
+                }
+                match inventory.resync(client.as_ref()).await {
+                    Ok(changed) => {
+                        debug!(
+                            "Resynced container inventory: {} containers, {} changed",
+                            inventory.len(),
+                            changed.len()
+                        );
+                        if !changed.is_empty() {
+                            on_changes(changed);
+                        }
+                    }
+                    Err(e) => warn!("Failed to resync container inventory: {}", e),
+                }
+            }
+        })
+    }
+
+    /// Re-inspect a container and store the result
+    async fn refresh<C: DockerClient>(&self, id: &str, client: &C) -> Option<ContainerInfo> {
+        match client.inspect_container(id).await {
+            Ok(container) => {
+                self.upsert(container.clone());
+                Some(container)
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to inspect container {} for the inventory: {}",
+                    id, e
+                );
+                None
+            }
+        }
+    }
+
+    /// Modify a container and reindex it
+    fn update(&self, id_or_name: &str, modify: impl FnOnce(&mut Entry)) {
+        let mut indexes = self.write();
+        let Some(id) = indexes.resolve(id_or_name) else {
+            debug!(
+                "Ignoring event for container {} not in the inventory",
+                id_or_name
+            );
+            return;
+        };
+        if let Some(mut entry) = indexes.remove(&self.labels, &id) {
+            modify(&mut entry);
+            indexes.sequence += 1;
+            entry.updated = indexes.sequence;
+            indexes.insert(&self.labels, entry);
+        }
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Indexes> {
+        self.indexes.read().expect("inventory lock poisoned")
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Indexes> {
+        self.indexes.write().expect("inventory lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker::NetworkInfo;
+    use bollard::models::EventActor;
+    use std::sync::Mutex;
+
+    const TARGET_LABEL: &str = "network.warp.target";
+
+    /// Docker client serving a mutable container list and counting calls
+    #[derive(Default)]
+    struct MockDockerClient {
+        containers: Mutex<Vec<ContainerInfo>>,
+        lists: Mutex<usize>,
+        inspects: Mutex<usize>,
+    }
+
+    impl MockDockerClient {
+        fn set(&self, containers: Vec<ContainerInfo>) {
+            *self.containers.lock().unwrap() = containers;
+        }
+    }
+
+    impl DockerClient for MockDockerClient {
+        async fn list_containers(&self, _all: bool) -> Result<Vec<ContainerInfo>, DockerError> {
+            *self.lists.lock().unwrap() += 1;
+            // The list doesn't report PIDs
+            Ok(self
+                .containers
+                .lock()
+                .unwrap()
+                .iter()
+                .cloned()
+                .map(|container| ContainerInfo {
+                    pid: None,
+                    ..container
+                })
+                .collect())
+        }
+
+        async fn inspect_container(&self, id: &str) -> Result<ContainerInfo, DockerError> {
+            *self.inspects.lock().unwrap() += 1;
+            self.containers
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|c| c.id == id)
+                .cloned()
+                .ok_or_else(|| DockerError::ContainerNotFound {
+                    container_id: id.to_string(),
+                })
+        }
+
+        async fn get_container_networks(&self, id: &str) -> Result<Vec<NetworkInfo>, DockerError> {
+            Ok(self.inspect_container(id).await?.networks)
+        }
+    }
+
+    fn container(id: &str, labels: &[(&str, &str)]) -> ContainerInfo {
+        ContainerInfo {
+            id: id.to_string(),
+            name: format!("{}-name", id),
+            labels: labels
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            networks: vec![],
+            state: ContainerState::Running,
+            pid: Some(100),
+            network_mode: None,
+            sandbox_key: None,
+            started_at: None,
+        }
+    }
+
+    fn event(
+        typ: EventMessageTypeEnum,
+        action: &str,
+        id: &str,
+        attributes: &[(&str, &str)],
+    ) -> EventMessage {
+        EventMessage {
+            typ: Some(typ),
+            action: Some(action.to_string()),
+            actor: Some(EventActor {
+                id: Some(id.to_string()),
+                attributes: Some(
+                    attributes
+                        .iter()
+                        .map(|(k, v)| (k.to_string(), v.to_string()))
+                        .collect(),
+                ),
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn ids(containers: Vec<ContainerInfo>) -> Vec<String> {
+        containers.into_iter().map(|c| c.id).collect()
+    }
+
+    #[tokio::test]
+    async fn test_seed_and_indexes() {
+        let client = MockDockerClient::default();
+        client.set(vec![
+            container("warp", &[(COMPOSE_PROJECT_LABEL, "shop")]),
+            container(
+                "web",
+                &[(TARGET_LABEL, "warp"), (COMPOSE_PROJECT_LABEL, "shop")],
+            ),
+            container("api", &[(TARGET_LABEL, "warp")]),
+            container("task", &[(SWARM_SERVICE_NAME_LABEL, "edge")]),
+        ]);
+        let inventory = ContainerInventory::new(TARGET_LABEL);
+
+        assert_eq!(
+            inventory.resync(&client).await.unwrap(),
+            vec!["api", "task", "warp", "web"]
+        );
+        assert_eq!(*client.inspects.lock().unwrap(), 0);
+
+        assert_eq!(inventory.get("web").unwrap().name, "web-name");
+        assert_eq!(inventory.get_by_name("api-name").unwrap().id, "api");
+        assert_eq!(ids(inventory.targets_of("warp")), vec!["api", "web"]);
+        assert_eq!(ids(inventory.in_project("shop")), vec!["warp", "web"]);
+        assert_eq!(ids(inventory.in_service("edge")), vec!["task"]);
+        assert!(inventory.targets_of("other").is_empty());
+
+        // Unchanged containers aren't reported
+        assert!(inventory.resync(&client).await.unwrap().is_empty());
+
+        // Containers gone from the list are dropped on resync
+        client.set(vec![container("warp", &[])]);
+        assert_eq!(
+            inventory.resync(&client).await.unwrap(),
+            vec!["api", "task", "warp", "web"]
+        );
+        assert!(inventory.targets_of("warp").is_empty());
+        assert!(inventory.in_project("shop").is_empty());
+        assert_eq!(inventory.get_by_name("api-name"), None);
+    }
+
+    #[tokio::test]
+    async fn test_apply_container_events() {
+        let client = MockDockerClient::default();
+        let inventory = ContainerInventory::new(TARGET_LABEL);
+
+        // Start inspects the container and hands it back
+        client.set(vec![container("web", &[(TARGET_LABEL, "warp")])]);
+        let started = inventory
+            .apply_event(
+                &event(EventMessageTypeEnum::CONTAINER, "start", "web", &[]),
+                &client,
+            )
+            .await
+            .unwrap();
+        assert_eq!(started.pid, Some(100));
+        assert_eq!(ids(inventory.targets_of("warp")), vec!["web"]);
+
+        // Rename reindexes by the new name
+        inventory
+            .apply_event(
+                &event(
+                    EventMessageTypeEnum::CONTAINER,
+                    "rename",
+                    "web",
+                    &[("name", "frontend"), ("oldName", "/web-name")],
+                ),
+                &client,
+            )
+            .await;
+        assert_eq!(inventory.get_by_name("web-name"), None);
+        assert_eq!(inventory.get_by_name("frontend").unwrap().id, "web");
+
+        inventory
+            .apply_event(
+                &event(
+                    EventMessageTypeEnum::CONTAINER,
+                    "health_status: unhealthy",
+                    "web",
+                    &[],
+                ),
+                &client,
+            )
+            .await;
+        assert_eq!(inventory.health("web"), Some("unhealthy".to_string()));
+
+        inventory
+            .apply_event(
+                &event(EventMessageTypeEnum::CONTAINER, "pause", "web", &[]),
+                &client,
+            )
+            .await;
+        let paused = inventory.get("web").unwrap();
+        assert_eq!(paused.state, ContainerState::Paused);
+        assert!(paused.pid.is_some());
+        inventory
+            .apply_event(
+                &event(EventMessageTypeEnum::CONTAINER, "unpause", "web", &[]),
+                &client,
+            )
+            .await;
+        assert_eq!(inventory.get("web").unwrap().state, ContainerState::Running);
+
+        inventory
+            .apply_event(
+                &event(EventMessageTypeEnum::CONTAINER, "die", "web", &[]),
+                &client,
+            )
+            .await;
+        let stopped = inventory.get("web").unwrap();
+        assert_eq!(stopped.state, ContainerState::Stopped);
+        assert_eq!(stopped.pid, None);
+
+        inventory
+            .apply_event(
+                &event(EventMessageTypeEnum::CONTAINER, "destroy", "web", &[]),
+                &client,
+            )
+            .await;
+        assert!(inventory.is_empty());
+        assert!(inventory.targets_of("warp").is_empty());
+        assert_eq!(*client.lists.lock().unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_record_events_without_inspecting() {
+        let client = MockDockerClient::default();
+        let inventory = ContainerInventory::new(TARGET_LABEL).with_selector_label("warp.serve");
+        client.set(vec![
+            container("warp", &[("warp.serve", "app=web")]),
+            container("web", &[("app", "web")]),
+        ]);
+        inventory.resync(&client).await.unwrap();
+        assert_eq!(ids(inventory.selecting()), vec!["warp"]);
+
+        let die = event(EventMessageTypeEnum::CONTAINER, "die", "web", &[]);
+        assert_eq!(inventory.record_event(&die), None);
+        assert_eq!(inventory.get("web").unwrap().state, ContainerState::Stopped);
+
+        // A start is recorded, leaving the inspect to the caller
+        let start = event(EventMessageTypeEnum::CONTAINER, "start", "web", &[]);
+        assert_eq!(inventory.record_event(&start), Some("web".to_string()));
+        let started = inventory.get("web").unwrap();
+        assert_eq!(started.state, ContainerState::Running);
+        assert_eq!(started.pid, None);
+        let connect = event(
+            EventMessageTypeEnum::NETWORK,
+            "connect",
+            "network-id",
+            &[("container", "web")],
+        );
+        assert_eq!(inventory.record_event(&connect), Some("web".to_string()));
+
+        // Unknown containers wait for the next resync
+        let start = event(EventMessageTypeEnum::CONTAINER, "start", "db", &[]);
+        assert_eq!(inventory.record_event(&start), Some("db".to_string()));
+        assert!(inventory.get("db").is_none());
+
+        let destroy = event(EventMessageTypeEnum::CONTAINER, "destroy", "warp", &[]);
+        inventory.record_event(&destroy);
+        assert!(inventory.selecting().is_empty());
+        assert_eq!(*client.inspects.lock().unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_apply_network_events() {
+        let client = MockDockerClient::default();
+        let inventory = ContainerInventory::new(TARGET_LABEL);
+        client.set(vec![container("web", &[])]);
+        inventory.resync(&client).await.unwrap();
+
+        let mut connected = container("web", &[]);
+        connected.networks.push(NetworkInfo {
+            name: "frontend".to_string(),
+            ip_address: "172.20.0.3".parse().unwrap(),
+            gateway: None,
+            subnet: "172.20.0.0/16".parse().unwrap(),
+        });
+        client.set(vec![connected]);
+
+        let refreshed = inventory
+            .apply_event(
+                &event(
+                    EventMessageTypeEnum::NETWORK,
+                    "connect",
+                    "network-id",
+                    &[("container", "web"), ("name", "frontend")],
+                ),
+                &client,
+            )
+            .await;
+        assert!(refreshed.is_some());
+        assert_eq!(inventory.get("web").unwrap().networks.len(), 1);
+
+        // Events of other types are ignored
+        let ignored = inventory
+            .apply_event(
+                &event(EventMessageTypeEnum::IMAGE, "pull", "nginx", &[]),
+                &client,
+            )
+            .await;
+        assert!(ignored.is_none());
+        assert_eq!(*client.inspects.lock().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_resync_keeps_inspected_fields() {
+        let client = MockDockerClient::default();
+        let inventory = ContainerInventory::new(TARGET_LABEL);
+        client.set(vec![container("web", &[])]);
+
+        inventory
+            .apply_event(
+                &event(EventMessageTypeEnum::CONTAINER, "start", "web", &[]),
+                &client,
+            )
+            .await;
+        inventory
+            .apply_event(
+                &event(
+                    EventMessageTypeEnum::CONTAINER,
+                    "health_status: healthy",
+                    "web",
+                    &[],
+                ),
+                &client,
+            )
+            .await;
+
+        inventory.resync(&client).await.unwrap();
+        assert_eq!(inventory.get("web").unwrap().pid, Some(100));
+        assert_eq!(inventory.health("web"), Some("healthy".to_string()));
+    }
+
+    /// Docker client holding back its container list until released
+    #[derive(Default)]
+    struct GatedDockerClient {
+        inner: MockDockerClient,
+        listed: Notify,
+        release: Notify,
+    }
+
+    impl DockerClient for GatedDockerClient {
+        async fn list_containers(&self, all: bool) -> Result<Vec<ContainerInfo>, DockerError> {
+            let containers = self.inner.list_containers(all).await?;
+            self.listed.notify_one();
+            self.release.notified().await;
+            Ok(containers)
+        }
+
+        async fn inspect_container(&self, id: &str) -> Result<ContainerInfo, DockerError> {
+            self.inner.inspect_container(id).await
+        }
+
+        async fn get_container_networks(&self, id: &str) -> Result<Vec<NetworkInfo>, DockerError> {
+            self.inner.get_container_networks(id).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_events_during_resync_win_over_the_list() {
+        let client = GatedDockerClient::default();
+        let inventory = ContainerInventory::new(TARGET_LABEL);
+        client.inner.set(vec![
+            container("web", &[(TARGET_LABEL, "warp")]),
+            container("api", &[]),
+        ]);
+        client.release.notify_one();
+        inventory.resync(&client).await.unwrap();
+
+        let events = async {
+            // The list is taken; web dies, api is destroyed and new starts before it lands
+            client.listed.notified().await;
+            client.inner.set(vec![container("new", &[])]);
+            for (action, id) in [("die", "web"), ("destroy", "api"), ("start", "new")] {
+                inventory
+                    .apply_event(
+                        &event(EventMessageTypeEnum::CONTAINER, action, id, &[]),
+                        &client,
+                    )
+                    .await;
+            }
+            client.release.notify_one();
+        };
+        let (changed, _) = tokio::join!(inventory.resync(&client), events);
+
+        // The events already reported their containers
+        assert!(changed.unwrap().is_empty());
+        assert_eq!(inventory.get("web").unwrap().state, ContainerState::Stopped);
+        assert_eq!(inventory.get("api"), None);
+        assert_eq!(inventory.get("new").unwrap().pid, Some(100));
+
+        // The next resync takes the list as it is
+        client.release.notify_one();
+        assert_eq!(inventory.resync(&client).await.unwrap(), vec!["web"]);
+        assert_eq!(inventory.get("web"), None);
+    }
+
+    #[tokio::test]
+    async fn test_periodic_resync() {
+        let client = Arc::new(MockDockerClient::default());
+        let inventory = Arc::new(ContainerInventory::new(TARGET_LABEL));
+        inventory.resync(client.as_ref()).await.unwrap();
+
+        // A container started while events were missed shows up on the next resync
+        client.set(vec![container("web", &[])]);
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let resync = inventory.spawn_resync(
+            Arc::clone(&client),
+            Duration::from_millis(20),
+            move |changed| tx.send(changed).unwrap(),
+        );
+        let changed = tokio::time::timeout(Duration::from_secs(1), rx.recv()).await;
+        resync.abort();
+
+        assert_eq!(changed.unwrap().unwrap(), vec!["web"]);
+        assert!(inventory.get("web").is_some());
+        assert!(*client.lists.lock().unwrap() >= 2);
+    }
+
+    #[tokio::test]
+    async fn test_requested_resync() {
+        let client = Arc::new(MockDockerClient::default());
+        let inventory = Arc::new(ContainerInventory::new(TARGET_LABEL));
+        inventory.resync(client.as_ref()).await.unwrap();
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let resync = inventory.spawn_resync(
+            Arc::clone(&client),
+            Duration::from_secs(3600),
+            move |changed| tx.send(changed).unwrap(),
+        );
+
+        // E.g. the event stream reconnected after missing web's start
+        client.set(vec![container("web", &[])]);
+        inventory.request_resync();
+        let changed = tokio::time::timeout(Duration::from_secs(1), rx.recv()).await;
+        resync.abort();
+
+        assert_eq!(changed.unwrap().unwrap(), vec!["web"]);
+    }
+}
@@ -2,17 +2,69 @@
 //!
 //! Handles Docker API connections, event monitoring, and container classification
 
-use crate::error::{DockerError, EventError, HandlerError};
-use bollard::models::{ContainerInspectResponse, ContainerSummary};
+use crate::docker::connection::DockerEndpoint;
+use crate::error::{ConfigError, DockerError, EventError, HandlerError};
+use bollard::models::{ContainerInspectResponse, ContainerSummary, EndpointSettings};
 use bollard::query_parameters::{InspectContainerOptions, ListContainersOptions};
-use bollard::Docker;
+use bollard::{ClientVersion, Docker};
 use ipnetwork::IpNetwork;
 use std::collections::HashMap;
+use std::fmt;
 use std::net::IpAddr;
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::Arc;
 
+pub mod bus;
 pub mod classifier;
+pub mod connection;
 pub mod events;
+pub mod fanout;
+pub mod inventory;
+pub mod reconciler;
+pub mod scheduler;
+pub mod selector;
+
+/// Label Docker sets on swarm task containers to their service name
+pub const SWARM_SERVICE_NAME_LABEL: &str = "com.docker.swarm.service.name";
+/// Label Docker sets on swarm task containers to their task ID
+pub const SWARM_TASK_ID_LABEL: &str = "com.docker.swarm.task.id";
+/// Label Docker sets on swarm task containers to the node running them
+pub const SWARM_NODE_ID_LABEL: &str = "com.docker.swarm.node.id";
+
+/// Container runtime serving the Docker-compatible API
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ContainerRuntime {
+    #[default]
+    Docker,
+    /// Podman's Docker-compatible socket. Rootful Podman keeps network namespaces
+    /// under `/run/netns`, reported as the sandbox key.
+    Podman,
+}
+
+impl FromStr for ContainerRuntime {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "docker" => Ok(ContainerRuntime::Docker),
+            "podman" => Ok(ContainerRuntime::Podman),
+            _ => Err(ConfigError::InvalidFormat(format!(
+                "Invalid container runtime: '{}'. Must be one of: docker, podman",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ContainerRuntime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContainerRuntime::Docker => f.write_str("docker"),
+            ContainerRuntime::Podman => f.write_str("podman"),
+        }
+    }
+}
 
 /// Container information structure
 #[derive(Debug, Clone, PartialEq)]
@@ -23,6 +75,32 @@ pub struct ContainerInfo {
     pub networks: Vec<NetworkInfo>,
     pub state: ContainerState,
     pub pid: Option<i64>,
+    /// Docker network mode, e.g. `bridge`, `host` or `container:<id>`
+    pub network_mode: Option<String>,
+    /// Path of the container's network namespace as reported by the runtime, if usable
+    pub sandbox_key: Option<String>,
+    /// Time the container was last started, as reported by inspect
+    pub started_at: Option<String>,
+}
+
+impl ContainerInfo {
+    /// Get the swarm task this container runs, if it is a swarm task container
+    pub fn swarm_task(&self) -> Option<SwarmTask> {
+        let service_name = self.labels.get(SWARM_SERVICE_NAME_LABEL)?;
+        Some(SwarmTask {
+            service_name: service_name.clone(),
+            task_id: self.labels.get(SWARM_TASK_ID_LABEL).cloned(),
+            node_id: self.labels.get(SWARM_NODE_ID_LABEL).cloned(),
+        })
+    }
+}
+
+/// Swarm task information of a task container, from its labels
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwarmTask {
+    pub service_name: String,
+    pub task_id: Option<String>,
+    pub node_id: Option<String>,
 }
 
 /// Network information for containers
@@ -39,18 +117,91 @@ pub struct NetworkInfo {
 pub enum ContainerState {
     Starting,
     Running,
+    /// Frozen by `docker pause`; its processes and namespaces remain
+    Paused,
     Stopped,
 }
 
+impl ContainerState {
+    /// Whether the container has processes, and so namespaces: running or paused
+    pub fn is_active(&self) -> bool {
+        matches!(self, ContainerState::Running | ContainerState::Paused)
+    }
+}
+
 /// Container start event
 #[derive(Debug, Clone)]
 pub struct ContainerStartEvent {
     pub container: ContainerInfo,
 }
 
+/// Lifecycle change of a container other than starting
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LifecycleAction {
+    /// Stopped by `docker stop`; a `die` follows
+    Stop,
+    /// Its main process exited, with the exit code as reported by the daemon (e.g. `137`)
+    Die {
+        exit_code: Option<String>,
+    },
+    Pause,
+    Unpause,
+    /// Restarted by `docker restart`; the container has a new network namespace
+    Restart,
+    /// Sent a signal, as reported by the daemon (e.g. `15`). A `die` follows if it was fatal.
+    Kill {
+        signal: Option<String>,
+    },
+}
+
+impl fmt::Display for LifecycleAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LifecycleAction::Stop => f.write_str("stop"),
+            LifecycleAction::Die {
+                exit_code: Some(exit_code),
+            } => write!(f, "die (exit code {})", exit_code),
+            LifecycleAction::Die { exit_code: None } => f.write_str("die"),
+            LifecycleAction::Pause => f.write_str("pause"),
+            LifecycleAction::Unpause => f.write_str("unpause"),
+            LifecycleAction::Restart => f.write_str("restart"),
+            LifecycleAction::Kill {
+                signal: Some(signal),
+            } => write!(f, "kill ({})", signal),
+            LifecycleAction::Kill { signal: None } => f.write_str("kill"),
+        }
+    }
+}
+
+/// Container stop, die, pause, unpause, restart or kill event
+#[derive(Debug, Clone)]
+pub struct ContainerLifecycleEvent {
+    pub container: ContainerInfo,
+    pub action: LifecycleAction,
+}
+
+/// Event delivered to subscribers of the event bus
+#[derive(Debug, Clone)]
+pub enum ContainerEvent {
+    Start(ContainerStartEvent),
+    Lifecycle(ContainerLifecycleEvent),
+}
+
+impl ContainerEvent {
+    /// The container the event is about, as inspected after the event
+    pub fn container(&self) -> &ContainerInfo {
+        match self {
+            ContainerEvent::Start(event) => &event.container,
+            ContainerEvent::Lifecycle(event) => &event.container,
+        }
+    }
+}
+
 /// Docker client wrapper trait for testability
 pub trait DockerClient: Send + Sync {
-    /// List all containers
+    /// List all containers, with their networks.
+    ///
+    /// The PID, start time and sandbox key are only known to `inspect_container`.
     fn list_containers(
         &self,
         all: bool,
@@ -67,11 +218,159 @@ pub trait DockerClient: Send + Sync {
         &self,
         id: &str,
     ) -> impl std::future::Future<Output = Result<Vec<NetworkInfo>, DockerError>> + Send;
+
+    /// Get the container runtime behind the API
+    fn runtime(&self) -> ContainerRuntime {
+        ContainerRuntime::Docker
+    }
+
+    /// Get the swarm node ID of the daemon, if it is part of a swarm
+    fn local_node_id(
+        &self,
+    ) -> impl std::future::Future<Output = Result<Option<String>, DockerError>> + Send {
+        async { Ok(None) }
+    }
+}
+
+/// A client shared by the inventory, the namespace manager and others
+impl<D: DockerClient> DockerClient for Arc<D> {
+    async fn list_containers(&self, all: bool) -> Result<Vec<ContainerInfo>, DockerError> {
+        self.as_ref().list_containers(all).await
+    }
+
+    async fn inspect_container(&self, id: &str) -> Result<ContainerInfo, DockerError> {
+        self.as_ref().inspect_container(id).await
+    }
+
+    async fn get_container_networks(&self, id: &str) -> Result<Vec<NetworkInfo>, DockerError> {
+        self.as_ref().get_container_networks(id).await
+    }
+
+    fn runtime(&self) -> ContainerRuntime {
+        self.as_ref().runtime()
+    }
+
+    async fn local_node_id(&self) -> Result<Option<String>, DockerError> {
+        self.as_ref().local_node_id().await
+    }
+}
+
+/// Oldest Docker API version providing the event filters and health fields relied on
+pub const MINIMUM_API_VERSION: ApiVersion = ApiVersion {
+    major: 1,
+    minor: 24,
+};
+
+/// Seconds a Docker API request may take by default
+pub const DEFAULT_API_TIMEOUT: u64 = 120;
+
+/// Docker API version, e.g. `1.41`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ApiVersion {
+    pub major: usize,
+    pub minor: usize,
+}
+
+impl FromStr for ApiVersion {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            ConfigError::InvalidFormat(format!(
+                "Invalid Docker API version: '{}'. Expected <major>.<minor>, e.g. 1.41",
+                s
+            ))
+        };
+
+        let version = s.trim();
+        let version = version.strip_prefix('v').unwrap_or(version);
+        let (major, minor) = version.split_once('.').ok_or_else(invalid)?;
+        Ok(Self {
+            major: major.parse().map_err(|_| invalid())?,
+            minor: minor.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl From<ApiVersion> for ClientVersion {
+    fn from(version: ApiVersion) -> Self {
+        ClientVersion {
+            major_version: version.major,
+            minor_version: version.minor,
+        }
+    }
+}
+
+impl From<ClientVersion> for ApiVersion {
+    fn from(version: ClientVersion) -> Self {
+        ApiVersion {
+            major: version.major_version,
+            minor: version.minor_version,
+        }
+    }
+}
+
+/// API version and request timeout of a Docker connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DockerApiConfig {
+    /// Version to use; negotiated with the daemon if unset
+    pub version: Option<ApiVersion>,
+    /// Request timeout in seconds
+    pub timeout: u64,
+}
+
+impl Default for DockerApiConfig {
+    fn default() -> Self {
+        Self {
+            version: None,
+            timeout: DEFAULT_API_TIMEOUT,
+        }
+    }
+}
+
+/// Client certificate and CA for connecting to a TLS-protected daemon
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DockerTlsConfig {
+    /// CA certificate the daemon's certificate is verified against
+    pub ca: PathBuf,
+    /// Client certificate presented to the daemon
+    pub cert: PathBuf,
+    /// Private key of the client certificate
+    pub key: PathBuf,
+}
+
+/// Open a bollard connection to an endpoint. Without a configured version, the
+/// newest one bollard supports is used until it is negotiated.
+pub(crate) fn connect_docker(
+    endpoint: &DockerEndpoint,
+    api: &DockerApiConfig,
+) -> Result<Docker, bollard::errors::Error> {
+    let version = api
+        .version
+        .map(ClientVersion::from)
+        .unwrap_or(*bollard::API_DEFAULT_VERSION);
+
+    match endpoint {
+        DockerEndpoint::Socket(path) => Docker::connect_with_socket(path, api.timeout, &version),
+        DockerEndpoint::Http(address) => Docker::connect_with_http(address, api.timeout, &version),
+        DockerEndpoint::Ssl(address, tls) => {
+            Docker::connect_with_ssl(address, &tls.key, &tls.cert, &tls.ca, api.timeout, &version)
+        }
+    }
 }
 
 /// Bollard-based Docker client implementation
 pub struct BollardDockerClient {
     docker: Docker,
+    runtime: ContainerRuntime,
+    /// API version set in the configuration, as opposed to negotiated
+    configured_version: Option<ApiVersion>,
 }
 
 impl BollardDockerClient {
@@ -80,23 +379,104 @@ impl BollardDockerClient {
         let docker = Docker::connect_with_socket_defaults()
             .map_err(|e| DockerError::ConnectionFailed(e.to_string()))?;
 
-        Ok(Self { docker })
+        Ok(Self {
+            docker,
+            runtime: ContainerRuntime::default(),
+            configured_version: None,
+        })
     }
 
     /// Create a new Docker client with custom socket path
     pub fn with_socket(socket_path: &str) -> Result<Self, DockerError> {
-        let docker = Docker::connect_with_socket(socket_path, 120, bollard::API_DEFAULT_VERSION)
-            .map_err(|e| DockerError::ConnectionFailed(e.to_string()))?;
-
-        Ok(Self { docker })
+        Self::connect(
+            &DockerEndpoint::Socket(socket_path.to_string()),
+            &DockerApiConfig::default(),
+        )
     }
 
     /// Create a new Docker client with HTTP connection
     pub fn with_http(url: &str) -> Result<Self, DockerError> {
-        let docker = Docker::connect_with_http(url, 120, bollard::API_DEFAULT_VERSION)
+        Self::connect(
+            &DockerEndpoint::Http(url.to_string()),
+            &DockerApiConfig::default(),
+        )
+    }
+
+    /// Create a new Docker client with a TLS connection authenticated by client certificate
+    pub fn with_ssl(address: &str, tls: &DockerTlsConfig) -> Result<Self, DockerError> {
+        Self::connect(
+            &DockerEndpoint::Ssl(address.to_string(), tls.clone()),
+            &DockerApiConfig::default(),
+        )
+    }
+
+    /// Create a new Docker client for a resolved endpoint
+    pub fn connect(endpoint: &DockerEndpoint, api: &DockerApiConfig) -> Result<Self, DockerError> {
+        let docker = connect_docker(endpoint, api)
             .map_err(|e| DockerError::ConnectionFailed(e.to_string()))?;
 
-        Ok(Self { docker })
+        Ok(Self {
+            docker,
+            runtime: ContainerRuntime::default(),
+            configured_version: api.version,
+        })
+    }
+
+    /// Check the daemon's API version and settle on the version to use.
+    ///
+    /// A configured version is kept if the daemon supports it, otherwise the
+    /// newest version both sides support is used.
+    pub async fn negotiate_api_version(&mut self) -> Result<ApiVersion, DockerError> {
+        let daemon_version = self
+            .docker
+            .version()
+            .await
+            .map_err(|e| DockerError::ApiError(e.to_string()))?
+            .api_version
+            .ok_or_else(|| {
+                DockerError::ApiError("Docker daemon did not report its API version".to_string())
+            })?;
+        let daemon: ApiVersion = daemon_version.parse().map_err(|_| {
+            DockerError::ApiError(format!(
+                "Docker daemon reported an invalid API version: {}",
+                daemon_version
+            ))
+        })?;
+
+        if daemon < MINIMUM_API_VERSION {
+            return Err(DockerError::ApiVersionTooOld {
+                version: daemon.to_string(),
+                minimum: MINIMUM_API_VERSION.to_string(),
+            });
+        }
+
+        match self.configured_version {
+            Some(configured) if configured > daemon => Err(DockerError::ApiVersionTooNew {
+                configured: configured.to_string(),
+                daemon: daemon.to_string(),
+            }),
+            Some(configured) => Ok(configured),
+            None => {
+                self.docker = self
+                    .docker
+                    .clone()
+                    .negotiate_version()
+                    .await
+                    .map_err(|e| DockerError::ApiError(e.to_string()))?;
+                Ok(self.api_version())
+            }
+        }
+    }
+
+    /// Get the API version requests are made with
+    pub fn api_version(&self) -> ApiVersion {
+        self.docker.client_version().into()
+    }
+
+    /// Set the container runtime behind the API, to normalize its responses
+    pub fn with_runtime(mut self, runtime: ContainerRuntime) -> Self {
+        self.runtime = runtime;
+        self
     }
 
     /// Convert bollard container summary to our ContainerInfo
@@ -112,6 +492,7 @@ impl BollardDockerClient {
             .unwrap_or_default();
 
         let labels = summary.labels.unwrap_or_default();
+        let network_mode = summary.host_config.and_then(|c| c.network_mode);
         let state_enum = summary.state;
 
         let state = if let Some(state_enum) = state_enum {
@@ -121,17 +502,22 @@ impl BollardDockerClient {
                 ContainerSummaryStateEnum::CREATED | ContainerSummaryStateEnum::RESTARTING => {
                     ContainerState::Starting
                 }
-                ContainerSummaryStateEnum::PAUSED
-                | ContainerSummaryStateEnum::EXITED
-                | ContainerSummaryStateEnum::DEAD => ContainerState::Stopped,
+                ContainerSummaryStateEnum::PAUSED => ContainerState::Paused,
+                ContainerSummaryStateEnum::EXITED | ContainerSummaryStateEnum::DEAD => {
+                    ContainerState::Stopped
+                }
                 _ => ContainerState::Stopped,
             }
         } else {
             ContainerState::Stopped
         };
 
-        // Networks will be populated separately via inspect_container
-        let networks = Vec::new();
+        // The summary reports the same endpoints as inspect, sparing a call per container
+        let networks = summary
+            .network_settings
+            .and_then(|settings| settings.networks)
+            .map(|endpoints| self.convert_networks(endpoints))
+            .unwrap_or_default();
 
         Ok(ContainerInfo {
             id,
@@ -140,6 +526,9 @@ impl BollardDockerClient {
             networks,
             state,
             pid: None,
+            network_mode,
+            sandbox_key: None,
+            started_at: None,
         })
     }
 
@@ -155,9 +544,12 @@ impl BollardDockerClient {
             .unwrap_or_default();
 
         let labels = inspect.config.and_then(|c| c.labels).unwrap_or_default();
+        let network_mode = inspect.host_config.and_then(|c| c.network_mode);
 
         let state_info = inspect.state.unwrap_or_default();
-        let state = if state_info.running.unwrap_or(false) {
+        let state = if state_info.paused.unwrap_or(false) {
+            ContainerState::Paused
+        } else if state_info.running.unwrap_or(false) {
             ContainerState::Running
         } else if state_info.restarting.unwrap_or(false) {
             ContainerState::Starting
@@ -165,55 +557,19 @@ impl BollardDockerClient {
             ContainerState::Stopped
         };
         let pid = state_info.pid;
+        let started_at = state_info.started_at.filter(|time| !time.is_empty());
 
         // Extract network information
         let mut networks = Vec::new();
+        let mut sandbox_key = None;
         if let Some(network_settings) = inspect.network_settings {
-            if let Some(networks_map) = network_settings.networks {
-                for (network_name, network_config) in networks_map {
-                    if let Some(ip_str) = network_config.ip_address {
-                        if !ip_str.is_empty() {
-                            if let Ok(ip_address) = IpAddr::from_str(&ip_str) {
-                                let gateway = network_config
-                                    .gateway
-                                    .and_then(|g| if g.is_empty() { None } else { Some(g) })
-                                    .and_then(|g| IpAddr::from_str(&g).ok());
-
-                                // Try to parse subnet from IP prefix length or use a default
-                                let subnet = if let Some(prefix_len) = network_config.ip_prefix_len
-                                {
-                                    match ip_address {
-                                        IpAddr::V4(_) => {
-                                            IpNetwork::new(ip_address, prefix_len as u8)
-                                                .unwrap_or_else(|_| {
-                                                    IpNetwork::new(ip_address, 24).unwrap()
-                                                })
-                                        }
-                                        IpAddr::V6(_) => {
-                                            IpNetwork::new(ip_address, prefix_len as u8)
-                                                .unwrap_or_else(|_| {
-                                                    IpNetwork::new(ip_address, 64).unwrap()
-                                                })
-                                        }
-                                    }
-                                } else {
-                                    // Default subnet based on IP version
-                                    match ip_address {
-                                        IpAddr::V4(_) => IpNetwork::new(ip_address, 24).unwrap(),
-                                        IpAddr::V6(_) => IpNetwork::new(ip_address, 64).unwrap(),
-                                    }
-                                };
-
-                                networks.push(NetworkInfo {
-                                    name: network_name,
-                                    ip_address,
-                                    gateway,
-                                    subnet,
-                                });
-                            }
-                        }
-                    }
-                }
+            // Docker's sandbox keys live in the daemon's mount namespace, Podman's in /run/netns
+            if self.runtime == ContainerRuntime::Podman {
+                sandbox_key = network_settings.sandbox_key.filter(|key| !key.is_empty());
+            }
+
+            if let Some(endpoints) = network_settings.networks {
+                networks = self.convert_networks(endpoints);
             }
         }
 
@@ -224,11 +580,96 @@ impl BollardDockerClient {
             networks,
             state,
             pid,
+            network_mode,
+            sandbox_key,
+            started_at,
         })
     }
+
+    /// Convert the network endpoints of a container, as reported by both list and inspect
+    fn convert_networks(&self, endpoints: HashMap<String, EndpointSettings>) -> Vec<NetworkInfo> {
+        let mut networks = Vec::new();
+        for (network_name, mut network_config) in endpoints {
+            // Podman leaves the IPv4 fields empty on IPv6-only networks
+            if self.runtime == ContainerRuntime::Podman
+                && network_config
+                    .ip_address
+                    .as_deref()
+                    .unwrap_or("")
+                    .is_empty()
+            {
+                if let Some(ipv6) = network_config
+                    .global_ipv6_address
+                    .take()
+                    .filter(|ip| !ip.is_empty())
+                {
+                    network_config.ip_address = Some(ipv6);
+                    network_config.ip_prefix_len = network_config.global_ipv6_prefix_len;
+                    network_config.gateway = network_config.ipv6_gateway.take();
+                }
+            }
+
+            // Swarm tasks on overlay networks may only report their address in
+            // the IPAM attachment, possibly in CIDR form
+            let mut ip_prefix_len = network_config.ip_prefix_len;
+            let ip_address = network_config
+                .ip_address
+                .filter(|ip| !ip.is_empty())
+                .or_else(|| {
+                    let attachment = network_config.ipam_config.as_ref()?;
+                    let (ip, prefix) =
+                        split_attachment_address(attachment.ipv4_address.as_deref()?);
+                    if prefix.is_some() && ip_prefix_len.unwrap_or(0) == 0 {
+                        ip_prefix_len = prefix;
+                    }
+                    Some(ip.to_string())
+                });
+            if let Some(ip_str) = ip_address {
+                if !ip_str.is_empty() {
+                    if let Ok(ip_address) = IpAddr::from_str(&ip_str) {
+                        let gateway = network_config
+                            .gateway
+                            .and_then(|g| if g.is_empty() { None } else { Some(g) })
+                            .and_then(|g| IpAddr::from_str(&g).ok());
+
+                        // Try to parse subnet from IP prefix length or use a default
+                        let subnet = if let Some(prefix_len) =
+                            ip_prefix_len.filter(|prefix| *prefix > 0)
+                        {
+                            match ip_address {
+                                IpAddr::V4(_) => IpNetwork::new(ip_address, prefix_len as u8)
+                                    .unwrap_or_else(|_| IpNetwork::new(ip_address, 24).unwrap()),
+                                IpAddr::V6(_) => IpNetwork::new(ip_address, prefix_len as u8)
+                                    .unwrap_or_else(|_| IpNetwork::new(ip_address, 64).unwrap()),
+                            }
+                        } else {
+                            // Default subnet based on IP version
+                            match ip_address {
+                                IpAddr::V4(_) => IpNetwork::new(ip_address, 24).unwrap(),
+                                IpAddr::V6(_) => IpNetwork::new(ip_address, 64).unwrap(),
+                            }
+                        };
+
+                        networks.push(NetworkInfo {
+                            name: network_name,
+                            ip_address,
+                            gateway,
+                            subnet,
+                        });
+                    }
+                }
+            }
+        }
+
+        networks
+    }
 }
 
 impl DockerClient for BollardDockerClient {
+    fn runtime(&self) -> ContainerRuntime {
+        self.runtime
+    }
+
     async fn list_containers(&self, all: bool) -> Result<Vec<ContainerInfo>, DockerError> {
         let options = ListContainersOptions {
             all,
@@ -264,12 +705,34 @@ impl DockerClient for BollardDockerClient {
         let container_info = self.inspect_container(id).await?;
         Ok(container_info.networks)
     }
+
+    async fn local_node_id(&self) -> Result<Option<String>, DockerError> {
+        let info = self
+            .docker
+            .info()
+            .await
+            .map_err(|e| DockerError::ApiError(e.to_string()))?;
+
+        Ok(info
+            .swarm
+            .and_then(|swarm| swarm.node_id)
+            .filter(|node_id| !node_id.is_empty()))
+    }
+}
+
+/// Split a network attachment address such as `10.0.1.5/24` into address and prefix length
+fn split_attachment_address(address: &str) -> (&str, Option<i64>) {
+    match address.split_once('/') {
+        Some((ip, prefix)) => (ip, prefix.parse().ok()),
+        None => (address, None),
+    }
 }
 
 /// Event monitor trait
 pub trait EventMonitor {
     fn start_monitoring(&self) -> impl std::future::Future<Output = Result<(), EventError>> + Send;
     fn stop_monitoring(&self) -> impl std::future::Future<Output = Result<(), EventError>> + Send;
+    /// Subscribe a handler, which receives every event published once this returns
     fn subscribe_to_events(&self, handler: Box<dyn EventHandler>) -> Result<(), EventError>;
 }
 
@@ -279,6 +742,15 @@ pub trait EventHandler: Send + Sync {
         &self,
         event: ContainerStartEvent,
     ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<(), HandlerError>> + Send + '_>>;
+
+    /// Handle a stop, die, pause, unpause, restart or kill. Ignored unless implemented.
+    fn handle_container_lifecycle(
+        &self,
+        _event: ContainerLifecycleEvent,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<(), HandlerError>> + Send + '_>>
+    {
+        Box::pin(async { Ok(()) })
+    }
 }
 
 #[cfg(test)]
@@ -292,6 +764,14 @@ mod tests {
         assert_ne!(ContainerState::Running, ContainerState::Stopped);
     }
 
+    #[test]
+    fn test_container_state_is_active() {
+        assert!(ContainerState::Running.is_active());
+        assert!(ContainerState::Paused.is_active());
+        assert!(!ContainerState::Stopped.is_active());
+        assert!(!ContainerState::Starting.is_active());
+    }
+
     #[test]
     fn test_container_info_creation() {
         let mut labels = HashMap::new();
@@ -304,6 +784,9 @@ mod tests {
             networks: vec![],
             state: ContainerState::Running,
             pid: Some(30),
+            network_mode: None,
+            sandbox_key: None,
+            started_at: None,
         };
 
         assert_eq!(container.id, "test-id");
@@ -343,6 +826,9 @@ mod tests {
             networks: vec![],
             state: ContainerState::Starting,
             pid: Some(30),
+            network_mode: None,
+            sandbox_key: None,
+            started_at: None,
         };
 
         let event = ContainerStartEvent {
@@ -402,6 +888,9 @@ mod tests {
             networks: vec![],
             state: ContainerState::Running,
             pid: Some(30),
+            network_mode: None,
+            sandbox_key: None,
+            started_at: None,
         };
 
         mock_client.add_container(container.clone());
@@ -427,4 +916,297 @@ mod tests {
             _ => panic!("Expected ContainerNotFound error"),
         }
     }
+
+    /// Swarm-shaped inspect payload of a task container on an overlay network
+    const SWARM_TASK_INSPECT: &str = r#"{
+        "Id": "task-container",
+        "Name": "/warp.1.x1y2z3",
+        "State": {"Status": "running", "Running": true, "Pid": 4242},
+        "Config": {"Labels": {
+            "com.docker.swarm.service.name": "warp",
+            "com.docker.swarm.task.id": "x1y2z3",
+            "com.docker.swarm.node.id": "node-a"
+        }},
+        "HostConfig": {"NetworkMode": "default"},
+        "NetworkSettings": {"Networks": {"warp-overlay": {
+            "IPAMConfig": {"IPv4Address": "10.0.1.5/24"},
+            "IPAddress": "",
+            "IPPrefixLen": 0,
+            "Gateway": ""
+        }}}
+    }"#;
+
+    const SWARM_INFO: &str = r#"{"Swarm": {"NodeID": "node-a", "LocalNodeState": "active"}}"#;
+
+    /// Serve canned Docker API responses by path suffix on a local port
+    async fn start_fake_docker_api(routes: Vec<(&'static str, &'static str)>) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let routes = routes.clone();
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut buf = [0u8; 1024];
+                    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                        match stream.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => request.extend_from_slice(&buf[..n]),
+                        }
+                    }
+
+                    let request = String::from_utf8_lossy(&request);
+                    let path = request
+                        .split_whitespace()
+                        .nth(1)
+                        .unwrap_or_default()
+                        .split('?')
+                        .next()
+                        .unwrap_or_default();
+                    let (status, body) = routes
+                        .iter()
+                        .find(|(suffix, _)| path.ends_with(suffix))
+                        .map(|(_, body)| ("200 OK", *body))
+                        .unwrap_or(("404 Not Found", r#"{"message": "not found"}"#));
+
+                    let response = format!(
+                        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        status,
+                        body.len(),
+                        body
+                    );
+                    let _ = stream.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+
+        format!("http://{}", address)
+    }
+
+    #[tokio::test]
+    async fn test_inspect_swarm_task_on_overlay_network() {
+        let url = start_fake_docker_api(vec![
+            ("/containers/task-container/json", SWARM_TASK_INSPECT),
+            ("/info", SWARM_INFO),
+        ])
+        .await;
+        let client = BollardDockerClient::with_http(&url).unwrap();
+
+        let container = client.inspect_container("task-container").await.unwrap();
+        assert_eq!(container.name, "warp.1.x1y2z3");
+        assert_eq!(container.pid, Some(4242));
+
+        // The overlay address comes from the IPAM attachment
+        assert_eq!(container.networks.len(), 1);
+        let network = &container.networks[0];
+        assert_eq!(network.name, "warp-overlay");
+        assert_eq!(network.ip_address, "10.0.1.5".parse::<IpAddr>().unwrap());
+        assert_eq!(network.subnet.to_string(), "10.0.1.5/24");
+        assert_eq!(network.gateway, None);
+
+        let task = container.swarm_task().unwrap();
+        assert_eq!(task.service_name, "warp");
+        assert_eq!(task.task_id, Some("x1y2z3".to_string()));
+        assert_eq!(task.node_id, Some("node-a".to_string()));
+
+        assert_eq!(
+            client.local_node_id().await.unwrap(),
+            Some("node-a".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_containers_with_networks() {
+        let url = start_fake_docker_api(vec![(
+            "/containers/json",
+            r#"[{
+                "Id": "app-container",
+                "Names": ["/app"],
+                "State": "running",
+                "Labels": {"network.warp.target": "warp"},
+                "HostConfig": {"NetworkMode": "frontend"},
+                "NetworkSettings": {"Networks": {
+                    "frontend": {"IPAddress": "172.20.0.3", "IPPrefixLen": 16, "Gateway": "172.20.0.1"},
+                    "none": {"IPAddress": ""}
+                }}
+            }]"#,
+        )])
+        .await;
+        let client = BollardDockerClient::with_http(&url).unwrap();
+
+        let containers = client.list_containers(false).await.unwrap();
+        assert_eq!(containers.len(), 1);
+        let container = &containers[0];
+        assert_eq!(container.name, "app");
+        assert_eq!(container.state, ContainerState::Running);
+        assert_eq!(container.network_mode, Some("frontend".to_string()));
+        assert_eq!(container.pid, None);
+
+        assert_eq!(container.networks.len(), 1);
+        let network = &container.networks[0];
+        assert_eq!(network.name, "frontend");
+        assert_eq!(network.ip_address, "172.20.0.3".parse::<IpAddr>().unwrap());
+        assert_eq!(network.subnet.to_string(), "172.20.0.3/16");
+        assert_eq!(network.gateway, Some("172.20.0.1".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_local_node_id_outside_swarm() {
+        let url = start_fake_docker_api(vec![(
+            "/info",
+            r#"{"Swarm": {"NodeID": "", "LocalNodeState": "inactive"}}"#,
+        )])
+        .await;
+        let client = BollardDockerClient::with_http(&url).unwrap();
+
+        assert_eq!(client.local_node_id().await.unwrap(), None);
+    }
+
+    /// Podman-shaped inspect payload of a rootful container on an IPv6-only network
+    const PODMAN_INSPECT: &str = r#"{
+        "Id": "podman-container",
+        "Name": "app",
+        "State": {"Status": "running", "Running": true, "Pid": 5151},
+        "Config": {"Labels": {}},
+        "HostConfig": {"NetworkMode": "bridge"},
+        "NetworkSettings": {
+            "SandboxKey": "/run/netns/netns-6f2b8c1e",
+            "Networks": {"podman-v6": {
+                "IPAddress": "",
+                "IPPrefixLen": 0,
+                "Gateway": "",
+                "GlobalIPv6Address": "fd00:10::5",
+                "GlobalIPv6PrefixLen": 64,
+                "IPv6Gateway": "fd00:10::1"
+            }}
+        }
+    }"#;
+
+    #[tokio::test]
+    async fn test_inspect_podman_container() {
+        let url =
+            start_fake_docker_api(vec![("/containers/podman-container/json", PODMAN_INSPECT)])
+                .await;
+        let client = BollardDockerClient::with_http(&url)
+            .unwrap()
+            .with_runtime(ContainerRuntime::Podman);
+
+        let container = client.inspect_container("podman-container").await.unwrap();
+        assert_eq!(container.name, "app");
+        assert_eq!(
+            container.sandbox_key,
+            Some("/run/netns/netns-6f2b8c1e".to_string())
+        );
+        assert_eq!(container.networks.len(), 1);
+        let network = &container.networks[0];
+        assert_eq!(network.ip_address, "fd00:10::5".parse::<IpAddr>().unwrap());
+        assert_eq!(network.subnet.prefix(), 64);
+        assert_eq!(network.gateway, Some("fd00:10::1".parse().unwrap()));
+
+        // In Docker mode the sandbox key and Podman's IPv6 fields are ignored
+        let client = BollardDockerClient::with_http(&url).unwrap();
+        let container = client.inspect_container("podman-container").await.unwrap();
+        assert_eq!(container.sandbox_key, None);
+        assert!(container.networks.is_empty());
+    }
+
+    #[test]
+    fn test_container_runtime_parsing() {
+        assert_eq!(
+            "docker".parse::<ContainerRuntime>().unwrap(),
+            ContainerRuntime::Docker
+        );
+        assert_eq!(
+            " Podman ".parse::<ContainerRuntime>().unwrap(),
+            ContainerRuntime::Podman
+        );
+        assert_eq!(ContainerRuntime::Podman.to_string(), "podman");
+        assert!("containerd".parse::<ContainerRuntime>().is_err());
+    }
+
+    #[test]
+    fn test_api_version_parsing() {
+        assert_eq!(
+            "1.41".parse::<ApiVersion>().unwrap(),
+            ApiVersion {
+                major: 1,
+                minor: 41
+            }
+        );
+        assert_eq!("v1.24".parse::<ApiVersion>().unwrap(), MINIMUM_API_VERSION);
+        assert!("1.9".parse::<ApiVersion>().unwrap() < MINIMUM_API_VERSION);
+        assert_eq!(MINIMUM_API_VERSION.to_string(), "1.24");
+        assert!("1".parse::<ApiVersion>().is_err());
+        assert!("latest".parse::<ApiVersion>().is_err());
+    }
+
+    fn api_config(version: Option<&str>) -> DockerApiConfig {
+        DockerApiConfig {
+            version: version.map(|v| v.parse().unwrap()),
+            timeout: 5,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_negotiate_api_version() {
+        let url = start_fake_docker_api(vec![(
+            "/version",
+            r#"{"Version": "24.0.7", "ApiVersion": "1.43", "MinAPIVersion": "1.12"}"#,
+        )])
+        .await;
+        let endpoint = DockerEndpoint::Http(url);
+
+        // Without a configured version, the daemon's is used
+        let mut client = BollardDockerClient::connect(&endpoint, &api_config(None)).unwrap();
+        assert_eq!(client.api_version(), (*bollard::API_DEFAULT_VERSION).into());
+        let version = client.negotiate_api_version().await.unwrap();
+        assert_eq!(version, "1.43".parse().unwrap());
+        assert_eq!(client.api_version(), version);
+
+        // A configured version the daemon supports is kept
+        let mut client =
+            BollardDockerClient::connect(&endpoint, &api_config(Some("1.41"))).unwrap();
+        assert_eq!(
+            client.negotiate_api_version().await.unwrap(),
+            "1.41".parse().unwrap()
+        );
+
+        let mut client =
+            BollardDockerClient::connect(&endpoint, &api_config(Some("1.45"))).unwrap();
+        assert!(matches!(
+            client.negotiate_api_version().await,
+            Err(DockerError::ApiVersionTooNew { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_negotiate_api_version_with_old_daemon() {
+        let url = start_fake_docker_api(vec![(
+            "/version",
+            r#"{"Version": "1.11.2", "ApiVersion": "1.23"}"#,
+        )])
+        .await;
+        let mut client =
+            BollardDockerClient::connect(&DockerEndpoint::Http(url), &api_config(None)).unwrap();
+
+        match client.negotiate_api_version().await {
+            Err(DockerError::ApiVersionTooOld { version, minimum }) => {
+                assert_eq!(version, "1.23");
+                assert_eq!(minimum, "1.24");
+            }
+            other => panic!("Expected ApiVersionTooOld, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_split_attachment_address() {
+        assert_eq!(
+            split_attachment_address("10.0.1.5/24"),
+            ("10.0.1.5", Some(24))
+        );
+        assert_eq!(split_attachment_address("10.0.1.5"), ("10.0.1.5", None));
+    }
 }
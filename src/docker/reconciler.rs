@@ -0,0 +1,847 @@
+//! Reconciliation of target routing against the container inventory
+//!
+//! The reconciler works out from the inventory which warp, if any, a target
+//! should be routed through and leaves programming the routes to a
+//! [`TargetRouter`]. When a warp starts, and on startup reconciliation, the
+//! targets concerned are handled through a [`FanOut`], so hundreds of them
+//! neither run one by one nor all at once. A warp destroyed before its work ran
+//! is gone from the inventory, so its targets are found by the routes the
+//! router keeps through it.
+//!
+//! Each target is routed under a lock of its own, so a warp's fan-out and the
+//! scheduler's reconcile of the target's own key never program it at the same
+//! time; whoever goes second works from the inventory's state at that point.
+//!
+//! Targets of a paused warp are handled by the [`PausedWarpPolicy`]: under
+//! failover, a running warp serving them is preferred, and under the kill switch
+//! they are blocked until the warp is unpaused.
+
+use crate::config::{DEFAULT_FANOUT_CONCURRENCY, DEFAULT_FANOUT_TARGET_TIMEOUT};
+use crate::docker::classifier::{
+    ContainerClassifier, ContainerType, TargetContainerInfo, WarpContainerInfo,
+};
+use crate::docker::fanout::{FanOut, FanOutSummary};
+use crate::docker::inventory::ContainerInventory;
+use crate::docker::scheduler::{Reconciler, WorkKey};
+use crate::docker::{ContainerInfo, ContainerState};
+use crate::error::HandlerError;
+use crate::routing::PausedWarpPolicy;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+use tracing::debug;
+
+/// Programs the routes of target containers
+pub trait TargetRouter: Send + Sync {
+    /// Route a target through a warp, replacing any routes through another warp
+    fn route(
+        &self,
+        target: TargetContainerInfo,
+        warp: WarpContainerInfo,
+    ) -> Pin<Box<dyn Future<Output = Result<(), HandlerError>> + Send + '_>>;
+
+    /// Remove the warp routes of a target; a no-op for targets without any
+    fn unroute(
+        &self,
+        target_id: String,
+    ) -> Pin<Box<dyn Future<Output = Result<(), HandlerError>> + Send + '_>>;
+
+    /// Replace the warp routes of a target with routes dropping its traffic, so
+    /// it doesn't leak through the default route
+    fn block(
+        &self,
+        target_id: String,
+    ) -> Pin<Box<dyn Future<Output = Result<(), HandlerError>> + Send + '_>>;
+
+    /// Get the IDs of the targets currently routed through a warp, sorted
+    fn routed_through(&self, warp_id: &str) -> Vec<String>;
+
+    /// Let go of what is held for a container that stopped or is gone, after its
+    /// routes, or those of its targets for a warp, were reconciled
+    fn release(
+        &self,
+        container_id: String,
+    ) -> Pin<Box<dyn Future<Output = Result<(), HandlerError>> + Send + '_>>;
+}
+
+/// Locks serializing the routing of each target, by container ID
+#[derive(Default)]
+struct TargetLocks {
+    locks: Mutex<HashMap<String, Weak<AsyncMutex<()>>>>,
+}
+
+impl TargetLocks {
+    /// Wait for the lock of a target; it's released when the guard is dropped
+    async fn lock(&self, target_id: &str) -> OwnedMutexGuard<()> {
+        let lock = {
+            let mut locks = self.locks.lock().expect("target locks poisoned");
+            // Locks nobody holds or waits for are gone
+            locks.retain(|_, lock| lock.strong_count() > 0);
+            match locks.get(target_id).and_then(Weak::upgrade) {
+                Some(lock) => lock,
+                None => {
+                    let lock = Arc::new(AsyncMutex::new(()));
+                    locks.insert(target_id.to_string(), Arc::downgrade(&lock));
+                    lock
+                }
+            }
+        };
+        lock.lock_owned().await
+    }
+}
+
+/// Reconciler routing targets through the warps the inventory resolves them to
+pub struct RoutingReconciler {
+    inventory: Arc<ContainerInventory>,
+    classifier: Arc<dyn ContainerClassifier + Send + Sync>,
+    router: Arc<dyn TargetRouter>,
+    fan_out: FanOut,
+    paused_warp_policy: PausedWarpPolicy,
+    target_locks: TargetLocks,
+}
+
+impl RoutingReconciler {
+    /// Create a reconciler with the default fan-out limits
+    pub fn new(
+        inventory: Arc<ContainerInventory>,
+        classifier: Arc<dyn ContainerClassifier + Send + Sync>,
+        router: Arc<dyn TargetRouter>,
+    ) -> Self {
+        Self {
+            inventory,
+            classifier,
+            router,
+            fan_out: FanOut::new(
+                DEFAULT_FANOUT_CONCURRENCY,
+                Duration::from_secs(DEFAULT_FANOUT_TARGET_TIMEOUT),
+            ),
+            paused_warp_policy: PausedWarpPolicy::default(),
+            target_locks: TargetLocks::default(),
+        }
+    }
+
+    /// Set the fan-out used for the targets of a warp and for startup reconciliation
+    pub fn with_fan_out(mut self, fan_out: FanOut) -> Self {
+        self.fan_out = fan_out;
+        self
+    }
+
+    /// Set what happens to the targets of paused warps
+    pub fn with_paused_warp_policy(mut self, policy: PausedWarpPolicy) -> Self {
+        self.paused_warp_policy = policy;
+        self
+    }
+
+    /// Reconcile every running target of the inventory, e.g. on startup
+    pub async fn reconcile_all(&self) -> FanOutSummary {
+        let selector_warps = self
+            .classifier
+            .find_selector_warps_in_inventory(&self.inventory);
+        let targets: Vec<(String, String)> = self
+            .inventory
+            .containers()
+            .into_iter()
+            .filter(|container| container.state.is_active())
+            .filter_map(|container| self.as_target(&container, &selector_warps))
+            .map(|target| (target.container.name, target.container.id))
+            .collect();
+
+        let summary = self
+            .fan_out
+            .run(targets, |id| self.reconcile_target_by_id(id))
+            .await;
+        summary.log("Startup reconciliation");
+        summary
+    }
+
+    /// Reconcile the running targets of a warp, e.g. after it started or stopped
+    async fn reconcile_targets_of(&self, warp: &WarpContainerInfo) {
+        let targets: Vec<(String, String)> = self
+            .classifier
+            .find_targets_in_inventory(warp, &self.inventory)
+            .into_iter()
+            .map(|target| (target.container.name, target.container.id))
+            .collect();
+
+        let summary = self
+            .fan_out
+            .run(targets, |id| self.reconcile_target_by_id(id))
+            .await;
+        summary.log(&format!("Targets of warp {}", warp.container.name));
+    }
+
+    /// Reconcile the targets routed through a warp that is gone from the
+    /// inventory, e.g. destroyed before its work ran, so that the targets can't
+    /// be found by their labels against it
+    async fn reconcile_targets_routed_through(&self, warp_id: &str) {
+        let targets: Vec<(String, String)> = self
+            .router
+            .routed_through(warp_id)
+            .into_iter()
+            .map(|id| {
+                let name = self
+                    .inventory
+                    .get(&id)
+                    .map_or_else(|| id.clone(), |container| container.name);
+                (name, id)
+            })
+            .collect();
+        if targets.is_empty() {
+            return;
+        }
+
+        let summary = self
+            .fan_out
+            .run(targets, |id| self.reconcile_target_by_id(id))
+            .await;
+        summary.log(&format!("Targets of removed warp {}", warp_id));
+    }
+
+    /// Reconcile a container as a target under its lock, as the inventory knows
+    /// it once the lock is held
+    async fn reconcile_target_by_id(&self, id: String) -> Result<(), HandlerError> {
+        let _lock = self.target_locks.lock(&id).await;
+        let Some(container) = self.inventory.get(&id) else {
+            // Destroyed containers keep no routes
+            self.router.unroute(id.clone()).await?;
+            return self.router.release(id).await;
+        };
+
+        // Selected targets may fail over to another warp's selector
+        let selector_warps = self
+            .classifier
+            .find_selector_warps_in_inventory(&self.inventory);
+        match self.as_target(&container, &selector_warps) {
+            Some(target) => self.reconcile_target(target).await,
+            None => self.router.unroute(id).await,
+        }
+    }
+
+    /// Treat a container as a target: by its target label, or as served by the
+    /// selector of one of the given warps
+    fn as_target(
+        &self,
+        container: &ContainerInfo,
+        selector_warps: &[WarpContainerInfo],
+    ) -> Option<TargetContainerInfo> {
+        match self.classifier.classify_container(container) {
+            ContainerType::TargetContainer(target) => Some(target),
+            ContainerType::WarpContainer(_) => None,
+            ContainerType::Ignored => self
+                .prefer_running(selector_warps, |warps| {
+                    self.classifier
+                        .select_warp_by_selector(container, warps)
+                        .cloned()
+                })
+                .map(|warp| TargetContainerInfo {
+                    container: container.clone(),
+                    warp_target: warp.container.name,
+                    route_metric: self.classifier.extract_route_metric(container),
+                }),
+        }
+    }
+
+    /// Pick a warp among candidates. Under failover, paused warps are left aside
+    /// as long as a running one can be picked.
+    fn prefer_running(
+        &self,
+        warps: &[WarpContainerInfo],
+        pick: impl Fn(&[WarpContainerInfo]) -> Option<WarpContainerInfo>,
+    ) -> Option<WarpContainerInfo> {
+        if self.paused_warp_policy == PausedWarpPolicy::Failover {
+            let running: Vec<WarpContainerInfo> = warps
+                .iter()
+                .filter(|warp| warp.container.state == ContainerState::Running)
+                .cloned()
+                .collect();
+            if let Some(warp) = pick(&running) {
+                return Some(warp);
+            }
+        }
+        pick(warps)
+    }
+
+    /// Route a target through its warp, or remove its routes if it or its warp
+    /// isn't running
+    async fn reconcile_target(&self, target: TargetContainerInfo) -> Result<(), HandlerError> {
+        let id = target.container.id.clone();
+        if !target.container.state.is_active() {
+            self.router.unroute(id.clone()).await?;
+            return self.router.release(id).await;
+        }
+
+        let warps = self
+            .classifier
+            .find_warps_in_inventory(&target, &self.inventory);
+        let warp = self.prefer_running(&warps, |warps| {
+            self.classifier
+                .find_warp_for_target(&target, warps)
+                .cloned()
+        });
+        match warp {
+            Some(warp)
+                if warp.container.state == ContainerState::Paused
+                    && self.paused_warp_policy == PausedWarpPolicy::KillSwitch =>
+            {
+                debug!(
+                    "Blocking target {} while warp {} is paused",
+                    target.container.name, warp.container.name
+                );
+                self.router.block(id).await
+            }
+            Some(warp) if warp.container.state.is_active() => self.router.route(target, warp).await,
+            _ => {
+                debug!(
+                    "No running warp {} for target {}",
+                    target.warp_target, target.container.name
+                );
+                self.router.unroute(id).await
+            }
+        }
+    }
+}
+
+impl Reconciler for RoutingReconciler {
+    fn reconcile(
+        &self,
+        key: WorkKey,
+    ) -> Pin<Box<dyn Future<Output = Result<(), HandlerError>> + Send + '_>> {
+        Box::pin(async move {
+            let (WorkKey::Container(id) | WorkKey::Warp(id)) = &key;
+            match self.inventory.get(id) {
+                Some(container) => {
+                    if let ContainerType::WarpContainer(warp) =
+                        self.classifier.classify_container(&container)
+                    {
+                        // A warp starting or stopping concerns all of its targets
+                        self.reconcile_targets_of(&warp).await;
+                        if !warp.container.state.is_active() {
+                            return self.router.release(warp.container.id).await;
+                        }
+                        return Ok(());
+                    }
+                }
+                None => self.reconcile_targets_routed_through(id).await,
+            }
+
+            match key {
+                WorkKey::Container(id) => self.reconcile_target_by_id(id).await,
+                WorkKey::Warp(id) if self.inventory.get(&id).is_none() => {
+                    self.router.release(id).await
+                }
+                WorkKey::Warp(_) => Ok(()),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker::classifier::{
+        DefaultContainerClassifier, COMPOSE_PROJECT_LABEL, COMPOSE_SERVICE_LABEL,
+    };
+    use crate::docker::{
+        ContainerState, NetworkInfo, SWARM_NODE_ID_LABEL, SWARM_SERVICE_NAME_LABEL,
+    };
+    use std::collections::{HashMap, HashSet};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use tokio::time::sleep;
+
+    /// Router recording the routes it was asked to program
+    #[derive(Default)]
+    struct RecordingRouter {
+        calls: Mutex<Vec<String>>,
+        released: Mutex<Vec<String>>,
+        /// Warp each routed target is routed through
+        routed: Mutex<HashMap<String, String>>,
+        failing: HashSet<String>,
+        running: AtomicUsize,
+        max_running: AtomicUsize,
+    }
+
+    impl RecordingRouter {
+        fn calls(&self) -> Vec<String> {
+            let mut calls = self.calls.lock().unwrap().clone();
+            calls.sort();
+            calls
+        }
+    }
+
+    impl TargetRouter for RecordingRouter {
+        fn route(
+            &self,
+            target: TargetContainerInfo,
+            warp: WarpContainerInfo,
+        ) -> Pin<Box<dyn Future<Output = Result<(), HandlerError>> + Send + '_>> {
+            Box::pin(async move {
+                let now = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max_running.fetch_max(now, Ordering::SeqCst);
+                sleep(Duration::from_millis(10)).await;
+                self.running.fetch_sub(1, Ordering::SeqCst);
+
+                if self.failing.contains(&target.container.name) {
+                    return Err(HandlerError::ExecutionFailed("boom".to_string()));
+                }
+                self.calls.lock().unwrap().push(format!(
+                    "route {} via {}",
+                    target.container.name, warp.container.name
+                ));
+                self.routed
+                    .lock()
+                    .unwrap()
+                    .insert(target.container.id, warp.container.id);
+                Ok(())
+            })
+        }
+
+        fn unroute(
+            &self,
+            target_id: String,
+        ) -> Pin<Box<dyn Future<Output = Result<(), HandlerError>> + Send + '_>> {
+            Box::pin(async move {
+                self.routed.lock().unwrap().remove(&target_id);
+                self.calls
+                    .lock()
+                    .unwrap()
+                    .push(format!("unroute {}", target_id));
+                Ok(())
+            })
+        }
+
+        fn block(
+            &self,
+            target_id: String,
+        ) -> Pin<Box<dyn Future<Output = Result<(), HandlerError>> + Send + '_>> {
+            Box::pin(async move {
+                self.calls
+                    .lock()
+                    .unwrap()
+                    .push(format!("block {}", target_id));
+                Ok(())
+            })
+        }
+
+        fn routed_through(&self, warp_id: &str) -> Vec<String> {
+            let mut targets: Vec<String> = self
+                .routed
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(_, warp)| *warp == warp_id)
+                .map(|(target, _)| target.clone())
+                .collect();
+            targets.sort();
+            targets
+        }
+
+        fn release(
+            &self,
+            container_id: String,
+        ) -> Pin<Box<dyn Future<Output = Result<(), HandlerError>> + Send + '_>> {
+            Box::pin(async move {
+                self.released.lock().unwrap().push(container_id);
+                Ok(())
+            })
+        }
+    }
+
+    fn container(name: &str, labels: &[(&str, &str)], state: ContainerState) -> ContainerInfo {
+        ContainerInfo {
+            id: name.to_string(),
+            name: name.to_string(),
+            labels: labels
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect::<HashMap<_, _>>(),
+            networks: vec![NetworkInfo {
+                name: "bridge".to_string(),
+                ip_address: "172.17.0.2".parse().unwrap(),
+                gateway: None,
+                subnet: "172.17.0.0/16".parse().unwrap(),
+            }],
+            state,
+            pid: Some(30),
+            network_mode: None,
+            sandbox_key: None,
+            started_at: None,
+        }
+    }
+
+    fn target(name: &str, warp: &str, state: ContainerState) -> ContainerInfo {
+        container(name, &[("network.warp.target", warp)], state)
+    }
+
+    fn reconciler(
+        containers: Vec<ContainerInfo>,
+        router: Arc<RecordingRouter>,
+    ) -> RoutingReconciler {
+        let inventory = Arc::new(ContainerInventory::new("network.warp.target"));
+        for container in containers {
+            inventory.upsert(container);
+        }
+        let classifier = DefaultContainerClassifier::with_simple_pattern(
+            "warp-*".to_string(),
+            "network.warp.target".to_string(),
+            "network.warp.network".to_string(),
+        )
+        .with_warp_selector_label("network.warp.serve".to_string());
+        RoutingReconciler::new(inventory, Arc::new(classifier), router)
+    }
+
+    #[tokio::test]
+    async fn test_warp_start_fans_out_over_targets() {
+        let mut containers = vec![
+            container("warp-1", &[], ContainerState::Running),
+            target("stopped", "warp-1", ContainerState::Stopped),
+            target("other", "warp-2", ContainerState::Running),
+        ];
+        containers.extend(
+            (0..8).map(|i| target(&format!("app-{}", i), "warp-1", ContainerState::Running)),
+        );
+        let router = Arc::new(RecordingRouter::default());
+        let reconciler = reconciler(containers, router.clone())
+            .with_fan_out(FanOut::new(3, Duration::from_secs(5)));
+
+        reconciler
+            .reconcile(WorkKey::Container("warp-1".to_string()))
+            .await
+            .unwrap();
+
+        let expected: Vec<String> = (0..8)
+            .map(|i| format!("route app-{} via warp-1", i))
+            .collect();
+        assert_eq!(router.calls(), expected);
+        assert_eq!(router.max_running.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_and_target_key_do_not_overlap() {
+        let router = Arc::new(RecordingRouter::default());
+        let reconciler = reconciler(
+            vec![
+                container("warp-1", &[], ContainerState::Running),
+                target("app", "warp-1", ContainerState::Running),
+            ],
+            router.clone(),
+        );
+
+        // The warp's start and the target's own start are reconciled at the same time
+        let (warp, app) = tokio::join!(
+            reconciler.reconcile(WorkKey::Container("warp-1".to_string())),
+            reconciler.reconcile(WorkKey::Container("app".to_string())),
+        );
+        warp.unwrap();
+        app.unwrap();
+
+        assert_eq!(
+            router.calls(),
+            vec!["route app via warp-1", "route app via warp-1"]
+        );
+        assert_eq!(router.max_running.load(Ordering::SeqCst), 1);
+        assert!(reconciler
+            .target_locks
+            .locks
+            .lock()
+            .unwrap()
+            .values()
+            .all(|lock| lock.strong_count() == 0));
+    }
+
+    #[tokio::test]
+    async fn test_target_without_running_warp_is_unrouted() {
+        let router = Arc::new(RecordingRouter::default());
+        let reconciler = reconciler(
+            vec![
+                container("warp-1", &[], ContainerState::Stopped),
+                target("app", "warp-1", ContainerState::Running),
+                target("orphan", "warp-9", ContainerState::Running),
+            ],
+            router.clone(),
+        );
+
+        for id in ["app", "orphan", "gone"] {
+            reconciler
+                .reconcile(WorkKey::Container(id.to_string()))
+                .await
+                .unwrap();
+        }
+        // A stopped warp takes the routes of its targets with it
+        reconciler
+            .reconcile(WorkKey::Warp("warp-1".to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            router.calls(),
+            vec![
+                "unroute app",
+                "unroute app",
+                "unroute gone",
+                "unroute orphan"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stopped_and_destroyed_containers_are_released() {
+        let router = Arc::new(RecordingRouter::default());
+        let reconciler = reconciler(
+            vec![
+                container("warp-1", &[], ContainerState::Stopped),
+                target("app", "warp-1", ContainerState::Running),
+                target("old", "warp-1", ContainerState::Stopped),
+            ],
+            router.clone(),
+        );
+
+        for id in ["warp-1", "app", "old", "gone"] {
+            reconciler
+                .reconcile(WorkKey::Container(id.to_string()))
+                .await
+                .unwrap();
+        }
+
+        // The running target is only unrouted
+        assert_eq!(
+            *router.released.lock().unwrap(),
+            vec!["warp-1", "old", "gone"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_targets_of_destroyed_warp_are_unrouted() {
+        for key in [WorkKey::Container, WorkKey::Warp] {
+            let router = Arc::new(RecordingRouter::default());
+            let reconciler = reconciler(
+                vec![
+                    container("warp-1", &[], ContainerState::Running),
+                    target("app", "warp-1", ContainerState::Running),
+                    target("api", "warp-1", ContainerState::Running),
+                ],
+                router.clone(),
+            );
+            reconciler.reconcile_all().await;
+
+            // Removed with `docker rm -f` before its work runs
+            reconciler.inventory.remove("warp-1");
+            reconciler
+                .reconcile(key("warp-1".to_string()))
+                .await
+                .unwrap();
+
+            assert_eq!(router.routed_through("warp-1"), Vec::<String>::new());
+            assert_eq!(
+                router.calls()[..4],
+                [
+                    "route api via warp-1",
+                    "route app via warp-1",
+                    "unroute api",
+                    "unroute app"
+                ]
+            );
+            assert!(router
+                .released
+                .lock()
+                .unwrap()
+                .contains(&"warp-1".to_string()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_selected_containers_are_routed() {
+        let router = Arc::new(RecordingRouter::default());
+        let reconciler = reconciler(
+            vec![
+                container(
+                    "warp-1",
+                    &[("network.warp.serve", "app=frontend")],
+                    ContainerState::Running,
+                ),
+                container("front", &[("app", "frontend")], ContainerState::Running),
+                container("db", &[], ContainerState::Running),
+            ],
+            router.clone(),
+        );
+
+        for id in ["front", "db"] {
+            reconciler
+                .reconcile(WorkKey::Container(id.to_string()))
+                .await
+                .unwrap();
+        }
+        reconciler
+            .reconcile(WorkKey::Container("warp-1".to_string()))
+            .await
+            .unwrap();
+        let summary = reconciler.reconcile_all().await;
+
+        assert_eq!(summary.succeeded, vec!["front"]);
+        assert_eq!(
+            router.calls(),
+            vec![
+                "route front via warp-1",
+                "route front via warp-1",
+                "route front via warp-1",
+                "unroute db"
+            ]
+        );
+    }
+
+    fn paused_warps() -> Vec<ContainerInfo> {
+        let replica = [
+            (COMPOSE_PROJECT_LABEL, "shop"),
+            (COMPOSE_SERVICE_LABEL, "warp"),
+        ];
+        let selector = [("network.warp.serve", "app=frontend")];
+        vec![
+            container("warp-1", &replica, ContainerState::Paused),
+            container("warp-2", &replica, ContainerState::Running),
+            container(
+                "app",
+                &[
+                    (COMPOSE_PROJECT_LABEL, "shop"),
+                    ("network.warp.target", "warp"),
+                ],
+                ContainerState::Running,
+            ),
+            container("warp-solo", &[], ContainerState::Paused),
+            target("solo", "warp-solo", ContainerState::Running),
+            container("warp-a", &selector, ContainerState::Paused),
+            container("warp-b", &selector, ContainerState::Running),
+            container("front", &[("app", "frontend")], ContainerState::Running),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_paused_warp_policies() {
+        // Ignored, the paused warps keep their targets. A scaled warp's running
+        // replica is picked under any policy.
+        let router = Arc::new(RecordingRouter::default());
+        reconciler(paused_warps(), router.clone())
+            .reconcile_all()
+            .await;
+        assert_eq!(
+            router.calls(),
+            vec![
+                "route app via warp-2",
+                "route front via warp-a",
+                "route solo via warp-solo"
+            ]
+        );
+
+        // Failover moves targets to running warps serving them, if any
+        let router = Arc::new(RecordingRouter::default());
+        let reconciler_with = |router: Arc<RecordingRouter>, policy| {
+            reconciler(paused_warps(), router).with_paused_warp_policy(policy)
+        };
+        let failover = reconciler_with(router.clone(), PausedWarpPolicy::Failover);
+        failover.reconcile_all().await;
+        assert_eq!(
+            router.calls(),
+            vec![
+                "route app via warp-2",
+                "route front via warp-b",
+                "route solo via warp-solo"
+            ]
+        );
+
+        // Pausing a warp moves the targets it selects
+        let router = Arc::new(RecordingRouter::default());
+        reconciler_with(router.clone(), PausedWarpPolicy::Failover)
+            .reconcile(WorkKey::Warp("warp-a".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(router.calls(), vec!["route front via warp-b"]);
+
+        // The kill switch blocks the targets of paused warps
+        let router = Arc::new(RecordingRouter::default());
+        reconciler_with(router.clone(), PausedWarpPolicy::KillSwitch)
+            .reconcile_all()
+            .await;
+        assert_eq!(
+            router.calls(),
+            vec!["block front", "block solo", "route app via warp-2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tasks_of_other_nodes_are_ignored() {
+        let task = |name: &str, node: &str| {
+            let mut task = target(name, "warp-1", ContainerState::Running);
+            task.labels
+                .insert(SWARM_SERVICE_NAME_LABEL.to_string(), name.to_string());
+            task.labels
+                .insert(SWARM_NODE_ID_LABEL.to_string(), node.to_string());
+            task
+        };
+        let inventory = Arc::new(ContainerInventory::new("network.warp.target"));
+        for container in [
+            container("warp-1", &[], ContainerState::Running),
+            task("local", "node-a"),
+            task("remote", "node-b"),
+        ] {
+            inventory.upsert(container);
+        }
+        let classifier = DefaultContainerClassifier::with_simple_pattern(
+            "warp-*".to_string(),
+            "network.warp.target".to_string(),
+            "network.warp.network".to_string(),
+        )
+        .with_local_node_id(Some("node-a".to_string()));
+        let router = Arc::new(RecordingRouter::default());
+        let reconciler = RoutingReconciler::new(inventory, Arc::new(classifier), router.clone());
+
+        // The remote task's namespace lives on node-b, out of reach of this daemon;
+        // it is only ever unrouted, like any container that isn't a target
+        reconciler
+            .reconcile(WorkKey::Container("warp-1".to_string()))
+            .await
+            .unwrap();
+        reconciler
+            .reconcile(WorkKey::Container("remote".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(
+            router.calls(),
+            vec!["route local via warp-1", "unroute remote"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_all_summarizes_targets() {
+        let router = Arc::new(RecordingRouter {
+            failing: HashSet::from(["broken".to_string()]),
+            ..Default::default()
+        });
+        let reconciler = reconciler(
+            vec![
+                container("warp-1", &[], ContainerState::Running),
+                container("plain", &[], ContainerState::Running),
+                target("app", "warp-1", ContainerState::Running),
+                target("broken", "warp-1", ContainerState::Running),
+                target("orphan", "warp-9", ContainerState::Running),
+                target("stopped", "warp-1", ContainerState::Stopped),
+            ],
+            router.clone(),
+        );
+
+        let summary = reconciler.reconcile_all().await;
+        assert_eq!(summary.succeeded, vec!["app", "orphan"]);
+        assert_eq!(
+            summary.failed,
+            vec![(
+                "broken".to_string(),
+                "Handler execution failed: boom".to_string()
+            )]
+        );
+        assert_eq!(
+            router.calls(),
+            vec!["route app via warp-1", "unroute orphan"]
+        );
+    }
+}
@@ -0,0 +1,424 @@
+//! Serialized, coalescing work scheduling for container events
+//!
+//! Events don't carry work themselves: each one schedules a reconcile of the
+//! container (or warp) it concerns, which brings routing in line with the
+//! container's current state. Reconciles of one key run strictly one at a
+//! time, and events arriving while a reconcile is queued are absorbed by it, so
+//! a `start`/`stop`/`start` burst costs a single reconcile. Different keys run
+//! in parallel up to a bound.
+//!
+//! A restart gives a container a new network namespace without the routes we
+//! installed, so it reconciles the container, which for a warp covers its targets,
+//! as does a stop or a die taking the namespace away. Pausing
+//! or unpausing a warp reconciles its targets only if a [`PausedWarpPolicy`] other
+//! than ignoring it is configured; the reconciler applies the policy.
+
+use crate::docker::{ContainerLifecycleEvent, ContainerStartEvent, EventHandler, LifecycleAction};
+use crate::error::HandlerError;
+use crate::routing::PausedWarpPolicy;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{Notify, Semaphore};
+use tracing::{debug, error};
+
+/// Number of keys reconciled in parallel by default
+pub const DEFAULT_MAX_PARALLEL_RECONCILES: usize = 8;
+
+/// What a unit of work is serialized on
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum WorkKey {
+    /// A single container, by ID
+    Container(String),
+    /// The targets routed through a warp container, by the warp's ID
+    Warp(String),
+}
+
+impl fmt::Display for WorkKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorkKey::Container(id) => write!(f, "container {}", id),
+            WorkKey::Warp(id) => write!(f, "warp {}", id),
+        }
+    }
+}
+
+/// Brings the routing state of a key in line with the current container state
+pub trait Reconciler: Send + Sync {
+    fn reconcile(
+        &self,
+        key: WorkKey,
+    ) -> Pin<Box<dyn Future<Output = Result<(), HandlerError>> + Send + '_>>;
+}
+
+/// Scheduling state of a key with queued or running work
+#[derive(Debug, Default)]
+struct KeyState {
+    /// Whether the reconcile has started; until then new events are absorbed by it
+    started: bool,
+    /// Whether another reconcile is needed after the running one
+    rerun: bool,
+}
+
+/// Scheduler running reconciles serialized per key, with bounded parallelism across keys
+#[derive(Clone)]
+pub struct WorkScheduler {
+    reconciler: Arc<dyn Reconciler>,
+    keys: Arc<Mutex<HashMap<WorkKey, KeyState>>>,
+    permits: Arc<Semaphore>,
+    idle: Arc<Notify>,
+    paused_warp_policy: PausedWarpPolicy,
+}
+
+impl WorkScheduler {
+    /// Create a scheduler reconciling at most `max_parallel` keys at a time
+    pub fn new(reconciler: Arc<dyn Reconciler>, max_parallel: usize) -> Self {
+        Self {
+            reconciler,
+            keys: Arc::new(Mutex::new(HashMap::new())),
+            permits: Arc::new(Semaphore::new(max_parallel.max(1))),
+            idle: Arc::new(Notify::new()),
+            paused_warp_policy: PausedWarpPolicy::default(),
+        }
+    }
+
+    /// Reconcile the targets of warps being paused or unpaused under this policy
+    pub fn with_paused_warp_policy(mut self, policy: PausedWarpPolicy) -> Self {
+        self.paused_warp_policy = policy;
+        self
+    }
+
+    /// Schedule a reconcile of a key.
+    ///
+    /// If one is already queued for the key, it covers this event too. If one is
+    /// running, another runs after it.
+    pub fn submit(&self, key: WorkKey) {
+        let mut keys = self.keys.lock().expect("scheduler lock poisoned");
+        if let Some(state) = keys.get_mut(&key) {
+            if state.started {
+                state.rerun = true;
+            }
+            debug!("Coalesced event for {} into pending reconcile", key);
+            return;
+        }
+
+        keys.insert(key.clone(), KeyState::default());
+        drop(keys);
+
+        let scheduler = self.clone();
+        tokio::spawn(async move { scheduler.run(key).await });
+    }
+
+    /// Number of keys with queued or running work
+    pub fn pending(&self) -> usize {
+        self.keys.lock().expect("scheduler lock poisoned").len()
+    }
+
+    /// Wait until no work is queued or running
+    pub async fn wait_idle(&self) {
+        loop {
+            let notified = self.idle.notified();
+            if self.pending() == 0 {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Run reconciles of a key until no more are needed
+    async fn run(&self, key: WorkKey) {
+        loop {
+            let _permit = self
+                .permits
+                .acquire()
+                .await
+                .expect("scheduler semaphore is never closed");
+            self.keys
+                .lock()
+                .expect("scheduler lock poisoned")
+                .entry(key.clone())
+                .or_default()
+                .started = true;
+
+            if let Err(e) = self.reconciler.reconcile(key.clone()).await {
+                error!("Failed to reconcile {}: {}", key, e);
+            }
+
+            let mut keys = self.keys.lock().expect("scheduler lock poisoned");
+            match keys.get_mut(&key) {
+                Some(state) if state.rerun => {
+                    // Events arrived during the reconcile; queue exactly one more
+                    *state = KeyState::default();
+                }
+                _ => {
+                    keys.remove(&key);
+                    if keys.is_empty() {
+                        self.idle.notify_waiters();
+                    }
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl EventHandler for WorkScheduler {
+    fn handle_container_start(
+        &self,
+        event: ContainerStartEvent,
+    ) -> Pin<Box<dyn Future<Output = Result<(), HandlerError>> + Send + '_>> {
+        self.submit(WorkKey::Container(event.container.id));
+        Box::pin(async { Ok(()) })
+    }
+
+    fn handle_container_lifecycle(
+        &self,
+        event: ContainerLifecycleEvent,
+    ) -> Pin<Box<dyn Future<Output = Result<(), HandlerError>> + Send + '_>> {
+        let id = event.container.id;
+        match event.action {
+            // Whether it's a target or a warp, its routes went with its old namespace
+            LifecycleAction::Restart | LifecycleAction::Stop | LifecycleAction::Die { .. } => {
+                self.submit(WorkKey::Container(id))
+            }
+            LifecycleAction::Pause | LifecycleAction::Unpause
+                if self.paused_warp_policy != PausedWarpPolicy::Ignore =>
+            {
+                self.submit(WorkKey::Warp(id));
+            }
+            // A fatal kill is followed by a `die`, which the next start recovers from
+            _ => {}
+        }
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker::{ContainerInfo, ContainerState};
+    use std::time::Duration;
+    use tokio::time::sleep;
+
+    /// Reconciler recording calls and the parallelism it sees
+    #[derive(Default)]
+    struct RecordingReconciler {
+        calls: Mutex<Vec<WorkKey>>,
+        running: Mutex<HashMap<WorkKey, usize>>,
+        max_per_key: Mutex<usize>,
+        max_total: Mutex<usize>,
+        delay: Duration,
+    }
+
+    impl RecordingReconciler {
+        fn with_delay(delay: Duration) -> Arc<Self> {
+            Arc::new(Self {
+                delay,
+                ..Default::default()
+            })
+        }
+
+        fn calls_for(&self, key: &WorkKey) -> usize {
+            self.calls
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|k| *k == key)
+                .count()
+        }
+    }
+
+    impl Reconciler for RecordingReconciler {
+        fn reconcile(
+            &self,
+            key: WorkKey,
+        ) -> Pin<Box<dyn Future<Output = Result<(), HandlerError>> + Send + '_>> {
+            Box::pin(async move {
+                {
+                    let mut running = self.running.lock().unwrap();
+                    let count = running.entry(key.clone()).or_default();
+                    *count += 1;
+                    let mut max_per_key = self.max_per_key.lock().unwrap();
+                    *max_per_key = (*max_per_key).max(*count);
+                    let total: usize = running.values().sum();
+                    let mut max_total = self.max_total.lock().unwrap();
+                    *max_total = (*max_total).max(total);
+                }
+                self.calls.lock().unwrap().push(key.clone());
+
+                sleep(self.delay).await;
+
+                *self.running.lock().unwrap().get_mut(&key).unwrap() -= 1;
+                Ok(())
+            })
+        }
+    }
+
+    fn container(id: &str) -> WorkKey {
+        WorkKey::Container(id.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_events_during_reconcile_run_once_more() {
+        let reconciler = RecordingReconciler::with_delay(Duration::from_millis(50));
+        let scheduler = WorkScheduler::new(reconciler.clone(), 4);
+
+        scheduler.submit(container("app"));
+        sleep(Duration::from_millis(10)).await;
+
+        // start, stop, start while the first reconcile runs
+        for _ in 0..3 {
+            scheduler.submit(container("app"));
+        }
+        scheduler.wait_idle().await;
+
+        assert_eq!(reconciler.calls_for(&container("app")), 2);
+        assert_eq!(*reconciler.max_per_key.lock().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_queued_events_coalesce() {
+        let reconciler = RecordingReconciler::with_delay(Duration::from_millis(50));
+        let scheduler = WorkScheduler::new(reconciler.clone(), 1);
+
+        scheduler.submit(container("busy"));
+        sleep(Duration::from_millis(10)).await;
+
+        // Queued behind the only permit, these collapse into one reconcile
+        for _ in 0..3 {
+            scheduler.submit(container("app"));
+        }
+        scheduler.submit(WorkKey::Warp("warp".to_string()));
+        assert_eq!(scheduler.pending(), 3);
+        scheduler.wait_idle().await;
+
+        assert_eq!(reconciler.calls_for(&container("app")), 1);
+        assert_eq!(reconciler.calls_for(&WorkKey::Warp("warp".to_string())), 1);
+        assert_eq!(scheduler.pending(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_bounded_parallelism_across_keys() {
+        let reconciler = RecordingReconciler::with_delay(Duration::from_millis(20));
+        let scheduler = WorkScheduler::new(reconciler.clone(), 2);
+
+        for i in 0..6 {
+            scheduler.submit(container(&format!("app-{}", i)));
+        }
+        scheduler.wait_idle().await;
+
+        assert_eq!(reconciler.calls.lock().unwrap().len(), 6);
+        assert_eq!(*reconciler.max_total.lock().unwrap(), 2);
+    }
+
+    fn test_container(id: &str) -> ContainerInfo {
+        ContainerInfo {
+            id: id.to_string(),
+            name: id.to_string(),
+            labels: HashMap::new(),
+            networks: vec![],
+            state: ContainerState::Running,
+            pid: Some(30),
+            network_mode: None,
+            sandbox_key: None,
+            started_at: None,
+        }
+    }
+
+    fn lifecycle_event(id: &str, action: LifecycleAction) -> ContainerLifecycleEvent {
+        ContainerLifecycleEvent {
+            container: test_container(id),
+            action,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_start_events_schedule_container_reconcile() {
+        let reconciler = RecordingReconciler::with_delay(Duration::ZERO);
+        let scheduler = WorkScheduler::new(reconciler.clone(), 1);
+
+        let event = ContainerStartEvent {
+            container: test_container("app"),
+        };
+        scheduler.handle_container_start(event).await.unwrap();
+        scheduler.wait_idle().await;
+
+        assert_eq!(reconciler.calls_for(&container("app")), 1);
+    }
+
+    #[tokio::test]
+    async fn test_restart_reconciles_container() {
+        let reconciler = RecordingReconciler::with_delay(Duration::ZERO);
+        let scheduler = WorkScheduler::new(reconciler.clone(), 2);
+
+        scheduler
+            .handle_container_lifecycle(lifecycle_event("app", LifecycleAction::Restart))
+            .await
+            .unwrap();
+        scheduler
+            .handle_container_lifecycle(lifecycle_event(
+                "app",
+                LifecycleAction::Kill {
+                    signal: Some("9".to_string()),
+                },
+            ))
+            .await
+            .unwrap();
+        scheduler.wait_idle().await;
+
+        assert_eq!(reconciler.calls_for(&container("app")), 1);
+        assert_eq!(reconciler.calls_for(&WorkKey::Warp("app".to_string())), 0);
+    }
+
+    #[tokio::test]
+    async fn test_stop_and_die_reconcile_container() {
+        let reconciler = RecordingReconciler::with_delay(Duration::from_millis(20));
+        let scheduler = WorkScheduler::new(reconciler.clone(), 2);
+
+        for action in [
+            LifecycleAction::Stop,
+            LifecycleAction::Die {
+                exit_code: Some("0".to_string()),
+            },
+        ] {
+            scheduler
+                .handle_container_lifecycle(lifecycle_event("warp", action))
+                .await
+                .unwrap();
+        }
+        scheduler.wait_idle().await;
+
+        // The die is absorbed by the reconcile the stop queued
+        assert_eq!(reconciler.calls_for(&container("warp")), 1);
+    }
+
+    #[tokio::test]
+    async fn test_paused_warp_policy() {
+        let warp = WorkKey::Warp("warp".to_string());
+
+        // Ignored by default
+        let reconciler = RecordingReconciler::with_delay(Duration::ZERO);
+        let scheduler = WorkScheduler::new(reconciler.clone(), 1);
+        scheduler
+            .handle_container_lifecycle(lifecycle_event("warp", LifecycleAction::Pause))
+            .await
+            .unwrap();
+        scheduler.wait_idle().await;
+        assert_eq!(reconciler.calls_for(&warp), 0);
+
+        let reconciler = RecordingReconciler::with_delay(Duration::ZERO);
+        let scheduler = WorkScheduler::new(reconciler.clone(), 1)
+            .with_paused_warp_policy(PausedWarpPolicy::KillSwitch);
+        for action in [LifecycleAction::Pause, LifecycleAction::Unpause] {
+            scheduler
+                .handle_container_lifecycle(lifecycle_event("warp", action))
+                .await
+                .unwrap();
+            scheduler.wait_idle().await;
+        }
+        assert_eq!(reconciler.calls_for(&warp), 2);
+        assert_eq!(reconciler.calls_for(&container("warp")), 0);
+    }
+}
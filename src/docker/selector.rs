@@ -0,0 +1,137 @@
+//! Label selectors used by warp containers to pick the containers they serve
+
+use crate::error::ConfigError;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// A single selector requirement: the label must exist, and have the value if one is given
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabelRequirement {
+    pub key: String,
+    pub value: Option<String>,
+}
+
+/// Label selector in `key=value,key` form; all requirements must match
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabelSelector {
+    pub requirements: Vec<LabelRequirement>,
+}
+
+impl LabelSelector {
+    /// Check whether a set of labels satisfies all requirements
+    pub fn matches(&self, labels: &HashMap<String, String>) -> bool {
+        self.requirements.iter().all(|requirement| {
+            match (labels.get(&requirement.key), &requirement.value) {
+                (Some(actual), Some(expected)) => actual == expected,
+                (Some(_), None) => true,
+                (None, _) => false,
+            }
+        })
+    }
+
+    /// Number of requirements, used to prefer the most specific selector
+    pub fn specificity(&self) -> usize {
+        self.requirements.len()
+    }
+}
+
+impl FromStr for LabelSelector {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut requirements = Vec::new();
+
+        for part in s.split(',') {
+            let part = part.trim();
+            if part.is_empty() {
+                continue;
+            }
+
+            let (key, value) = match part.split_once('=') {
+                Some((key, value)) => (key.trim(), Some(value.trim().to_string())),
+                None => (part, None),
+            };
+            if key.is_empty() {
+                return Err(ConfigError::InvalidFormat(format!(
+                    "Invalid label selector '{}': empty label key",
+                    s
+                )));
+            }
+
+            requirements.push(LabelRequirement {
+                key: key.to_string(),
+                value,
+            });
+        }
+
+        // An empty selector would match every container
+        if requirements.is_empty() {
+            return Err(ConfigError::InvalidFormat(
+                "Label selector cannot be empty".to_string(),
+            ));
+        }
+
+        Ok(Self { requirements })
+    }
+}
+
+impl fmt::Display for LabelSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self
+            .requirements
+            .iter()
+            .map(|requirement| match &requirement.value {
+                Some(value) => format!("{}={}", requirement.key, value),
+                None => requirement.key.clone(),
+            })
+            .collect();
+        write!(f, "{}", parts.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_label_selector() {
+        let selector: LabelSelector = "app=frontend, env=prod,canary".parse().unwrap();
+
+        assert_eq!(selector.specificity(), 3);
+        assert_eq!(selector.requirements[0].key, "app");
+        assert_eq!(selector.requirements[0].value, Some("frontend".to_string()));
+        assert_eq!(selector.requirements[2].key, "canary");
+        assert_eq!(selector.requirements[2].value, None);
+        assert_eq!(selector.to_string(), "app=frontend,env=prod,canary");
+
+        assert!("".parse::<LabelSelector>().is_err());
+        assert!(" , ".parse::<LabelSelector>().is_err());
+        assert!("=frontend".parse::<LabelSelector>().is_err());
+    }
+
+    #[test]
+    fn test_label_selector_matches() {
+        let selector: LabelSelector = "app=frontend,env=prod".parse().unwrap();
+
+        assert!(selector.matches(&labels(&[("app", "frontend"), ("env", "prod")])));
+        assert!(selector.matches(&labels(&[
+            ("app", "frontend"),
+            ("env", "prod"),
+            ("team", "web")
+        ])));
+        assert!(!selector.matches(&labels(&[("app", "frontend"), ("env", "dev")])));
+        assert!(!selector.matches(&labels(&[("app", "frontend")])));
+
+        let selector: LabelSelector = "canary".parse().unwrap();
+        assert!(selector.matches(&labels(&[("canary", "")])));
+        assert!(!selector.matches(&labels(&[])));
+    }
+}
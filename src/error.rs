@@ -35,6 +35,14 @@ pub enum DockerError {
 
     #[error("Docker API error: {0}")]
     ApiError(String),
+
+    #[error(
+        "Docker daemon API version {version} is older than the minimum supported version {minimum}"
+    )]
+    ApiVersionTooOld { version: String, minimum: String },
+
+    #[error("Configured Docker API version {configured} is newer than the daemon's {daemon}")]
+    ApiVersionTooNew { configured: String, daemon: String },
 }
 
 /// Network operation errors
@@ -52,6 +60,31 @@ pub enum NetworkError {
     #[error("Insufficient privileges for network operation")]
     InsufficientPrivileges,
 
+    #[error("Refusing to modify the network namespace of container {container_id}: {reason}")]
+    HostNamespace {
+        container_id: String,
+        reason: String,
+    },
+
+    #[error("Container {container_id} shares its network namespace with warp container {warp_id} and cannot be routed through it")]
+    SharesWarpNamespace {
+        container_id: String,
+        warp_id: String,
+    },
+
+    #[error("Network namespace of container {container_id} is already routed through warp container {existing_warp_id}, refusing {requested_warp_id}")]
+    NamespaceConflict {
+        container_id: String,
+        existing_warp_id: String,
+        requested_warp_id: String,
+    },
+
+    #[error("Container {container_id} restarted while its network namespace was being opened")]
+    NamespaceChanged { container_id: String },
+
+    #[error("Network operation timed out: {0}")]
+    Timeout(String),
+
     #[error("Network operation failed: {0}")]
     OperationFailed(String),
 }
@@ -70,6 +103,9 @@ pub enum RouteError {
 
     #[error("Invalid route configuration: {0}")]
     InvalidRoute(String),
+
+    #[error("Failed to resolve hostname: {0}")]
+    Resolution(String),
 }
 
 /// Configuration errors
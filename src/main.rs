@@ -1,39 +1,230 @@
 use clap::Parser;
-use tracing::info;
-use tracing_subscriber;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
 
-mod config;
-mod docker;
-mod network;
-mod routing;
-mod error;
+use docker_network_warp::config::cli::{print_default_config, CliArgs};
+use docker_network_warp::config::{AppConfig, DefaultConfigurationManager};
+use docker_network_warp::docker::classifier::{ContainerClassifier, DefaultContainerClassifier};
+use docker_network_warp::docker::connection::{
+    resolve_connection, DockerEnvironment, ResolvedConnection,
+};
+use docker_network_warp::docker::events::DockerEventMonitor;
+use docker_network_warp::docker::inventory::ContainerInventory;
+use docker_network_warp::docker::reconciler::RoutingReconciler;
+use docker_network_warp::docker::scheduler::{
+    WorkKey, WorkScheduler, DEFAULT_MAX_PARALLEL_RECONCILES,
+};
+use docker_network_warp::docker::{BollardDockerClient, DockerClient, EventMonitor};
+use docker_network_warp::error::{AppError, ConfigError, EventError};
+use docker_network_warp::network::forwarding::ForwardingManager;
+use docker_network_warp::network::namespace::NamespaceManager;
+use docker_network_warp::network::rootless::RootlessContext;
+use docker_network_warp::routing::manager::RtNetlinkRouteManager;
+use docker_network_warp::routing::resolver::{HostnameTracker, SystemResolver};
+use docker_network_warp::routing::router::NetlinkTargetRouter;
 
-use crate::config::cli::CliArgs;
-use crate::error::AppError;
-
-#[tokio::main]
-async fn main() -> Result<(), AppError> {
+fn main() -> Result<(), AppError> {
     let cli = CliArgs::parse();
-    
+
+    if cli.print_default_config {
+        print_default_config();
+        return Ok(());
+    }
+
     // Initialize logging
     let log_level = cli.log_level.as_deref().unwrap_or("info");
-    tracing_subscriber::fmt()
-        .with_env_filter(log_level)
-        .init();
-    
+    tracing_subscriber::fmt().with_env_filter(log_level).init();
+
+    let config_manager = DefaultConfigurationManager::new(&cli)?;
+    let config = config_manager.get_config();
+    let environment = DockerEnvironment::from_env();
+    let connection = resolve_connection(config, &environment)?;
+
+    if cli.validate_config {
+        println!("Configuration is valid");
+        println!("Docker connection: {}", connection);
+        return Ok(());
+    }
+
+    // Joining rootlesskit's user namespace requires a single-threaded process,
+    // so it happens before the runtime starts its threads
+    let rootless = if config.docker_rootless {
+        let runtime_dir =
+            environment
+                .runtime_dir
+                .as_deref()
+                .ok_or_else(|| ConfigError::MissingRequired {
+                    field: "XDG_RUNTIME_DIR for rootless Docker".to_string(),
+                })?;
+        let rootless = RootlessContext::discover(runtime_dir)?;
+        rootless.join()?;
+        Some(Arc::new(rootless))
+    } else {
+        None
+    };
+
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(run(config, connection, rootless))
+}
+
+async fn run(
+    config: &AppConfig,
+    connection: ResolvedConnection,
+    rootless: Option<Arc<RootlessContext>>,
+) -> Result<(), AppError> {
     info!("Starting Docker Network Warp");
-    
-    // TODO: Initialize configuration manager
-    // TODO: Initialize Docker event monitor
-    // TODO: Initialize network manager
-    // TODO: Initialize route manager
-    // TODO: Start main event processing loop
-    
+    info!("Docker connection: {}", connection);
+
+    let mut api = config.docker_api()?;
+    let runtime = config.container_runtime()?;
+
+    // A daemon too old for the events and fields relied on, or older than the
+    // configured version, fails startup
+    let mut monitor =
+        DockerEventMonitor::connect(&connection.endpoint, &api)?.with_runtime(runtime);
+    let version = monitor.negotiate_api_version().await?;
+    info!("Docker API version: {}", version);
+    api.version = Some(version);
+
+    let docker_client =
+        Arc::new(BollardDockerClient::connect(&connection.endpoint, &api)?.with_runtime(runtime));
+    let mut namespaces = NamespaceManager::new(Arc::clone(&docker_client))
+        .with_allow_host_namespace(config.dangerously_allow_host_network);
+    if let Some(rootless) = rootless {
+        info!(
+            "Rootless Docker: in the namespaces of rootlesskit process {}",
+            rootless.child_pid()
+        );
+        namespaces = namespaces.with_rootless(rootless);
+    }
+    let namespaces = Arc::new(namespaces);
+    let route_precedence = config.route_precedence()?;
+    let route_manager = Arc::new(
+        RtNetlinkRouteManager::new(Arc::clone(&namespaces)).with_route_precedence(route_precedence),
+    );
+
+    let mut router = NetlinkTargetRouter::new(
+        Arc::clone(&namespaces),
+        route_manager,
+        config.routing_rules.clone(),
+    )
+    .with_metric_policy(config.route_metrics(), route_precedence);
+    if config.warp_forwarding {
+        router = router.with_forwarding(ForwardingManager::new(
+            Arc::clone(&namespaces),
+            config.warp_egress_interface.clone(),
+        ));
+    }
+    let router = Arc::new(router);
+
+    // Hostname rules are routed to their addresses once resolved, and kept current
+    let mut hostnames = HostnameTracker::new(
+        SystemResolver,
+        Duration::from_secs(config.hostname_refresh_interval),
+        config.hostname_history_size,
+    );
+    let now = Instant::now();
+    for hostname in config
+        .routing_rules
+        .iter()
+        .filter_map(|rule| rule.hostname())
+    {
+        hostnames.track(hostname, now);
+    }
+    if hostnames.next_refresh().is_some() {
+        let router = Arc::clone(&router);
+        tokio::spawn(async move { hostnames.run_refresh(router.as_ref()).await });
+    }
+
+    // Swarm tasks scheduled on other nodes have no namespace here
+    let local_node_id = match docker_client.local_node_id().await {
+        Ok(node_id) => node_id,
+        Err(e) => {
+            warn!(
+                "Failed to query the swarm node ID, assuming all tasks are local: {}",
+                e
+            );
+            None
+        }
+    };
+    if let Some(node_id) = &local_node_id {
+        info!("Swarm node ID: {}", node_id);
+    }
+
+    let classifier: Arc<dyn ContainerClassifier + Send + Sync> = Arc::new(
+        DefaultContainerClassifier::new(
+            config.warp_container_pattern.clone(),
+            config.target_container_label.clone(),
+            config.network_preference_label.clone(),
+        )
+        .map_err(|e| {
+            ConfigError::ValidationError(format!("Invalid warp container pattern: {}", e))
+        })?
+        .with_route_attribute_label_prefix(config.route_attribute_label_prefix.clone())
+        .with_route_metric_label(config.route_metric_label.clone())
+        .with_warp_selector_label(config.warp_selector_label.clone())
+        .with_warp_role_label(config.warp_role_label.clone())
+        .with_local_node_id(local_node_id),
+    );
+    let inventory = Arc::new(
+        ContainerInventory::new(config.target_container_label.clone())
+            .with_selector_label(config.warp_selector_label.clone()),
+    );
+
+    let paused_warp_policy = config.paused_warp_policy()?;
+    let reconciler = Arc::new(
+        RoutingReconciler::new(Arc::clone(&inventory), Arc::clone(&classifier), router)
+            .with_fan_out(config.fan_out())
+            .with_paused_warp_policy(paused_warp_policy),
+    );
+    let scheduler = WorkScheduler::new(reconciler.clone(), DEFAULT_MAX_PARALLEL_RECONCILES)
+        .with_paused_warp_policy(paused_warp_policy);
+
+    let monitor = Arc::new(
+        monitor
+            .with_inventory(Arc::clone(&inventory))
+            .with_label_filter(config.event_label_filter(classifier.as_ref()))
+            .with_classifier(classifier),
+    );
+    monitor.subscribe_to_events(Box::new(scheduler.clone()))?;
+
+    // Events arriving while the inventory is seeded are applied on top of it, so
+    // monitoring starts first
+    let monitoring = tokio::spawn({
+        let monitor = Arc::clone(&monitor);
+        async move { monitor.start_monitoring().await }
+    });
+
+    inventory.resync(docker_client.as_ref()).await?;
+    info!(
+        "Container inventory seeded with {} containers",
+        inventory.len()
+    );
+    reconciler.reconcile_all().await;
+    // Containers whose events were missed, e.g. while the event stream reconnected,
+    // are reconciled once a resync finds them changed
+    inventory.spawn_resync(
+        Arc::clone(&docker_client),
+        Duration::from_secs(config.inventory_resync_interval),
+        move |changed| {
+            for id in changed {
+                scheduler.submit(WorkKey::Container(id));
+            }
+        },
+    );
+
     info!("Docker Network Warp started successfully");
-    
-    // Keep the application running
-    tokio::signal::ctrl_c().await?;
-    
+
+    tokio::select! {
+        result = monitoring => {
+            result.map_err(|e| EventError::StreamError(e.to_string()))??;
+        }
+        result = tokio::signal::ctrl_c() => result?,
+    }
+
     info!("Shutting down Docker Network Warp");
     Ok(())
-}
\ No newline at end of file
+}
@@ -44,3 +44,9 @@ impl NetworkDiscovery {
         }
     }
 }
+
+impl Default for NetworkDiscovery {
+    fn default() -> Self {
+        Self::new()
+    }
+}
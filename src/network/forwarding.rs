@@ -0,0 +1,452 @@
+//! Packet forwarding and masquerading inside warp containers
+//!
+//! Routing target traffic to a warp container only works if the warp forwards
+//! it and NATs it out of its tunnel. When enabled, the first target routed
+//! through a warp turns on IP forwarding in the warp's namespace and installs
+//! an nftables masquerade rule for the targets' subnets. Both are undone when
+//! the last target detaches.
+
+use crate::docker::DockerClient;
+use crate::error::NetworkError;
+use crate::network::{namespace::NamespaceManager, NetworkNamespace};
+use ipnetwork::IpNetwork;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// nftables table holding the masquerade rules, owned by this application
+pub const NFT_TABLE_NAME: &str = "docker_network_warp";
+
+/// Forwarding sysctls enabled in warp namespaces
+const FORWARDING_SYSCTLS: [&str; 2] = [
+    "/proc/sys/net/ipv4/ip_forward",
+    "/proc/sys/net/ipv6/conf/all/forwarding",
+];
+
+/// Forwarding state of a warp container
+#[derive(Debug)]
+struct WarpForwarding {
+    namespace: NetworkNamespace,
+    /// Subnets of the targets routed through the warp, by target container ID
+    targets: HashMap<String, Vec<IpNetwork>>,
+    /// Sysctl values found before forwarding was enabled, restored on teardown
+    saved_sysctls: Vec<(String, String)>,
+}
+
+impl WarpForwarding {
+    fn subnets(&self) -> BTreeSet<IpNetwork> {
+        self.targets.values().flatten().copied().collect()
+    }
+}
+
+/// Manages IP forwarding and masquerading in warp container namespaces
+pub struct ForwardingManager<D: DockerClient> {
+    namespace_manager: Arc<NamespaceManager<D>>,
+    egress_interface: String,
+    warps: HashMap<String, WarpForwarding>,
+}
+
+impl<D: DockerClient> ForwardingManager<D> {
+    /// Create a new forwarding manager masquerading out of the given interface
+    pub fn new(namespace_manager: Arc<NamespaceManager<D>>, egress_interface: String) -> Self {
+        Self {
+            namespace_manager,
+            egress_interface,
+            warps: HashMap::new(),
+        }
+    }
+
+    /// Check whether forwarding is currently set up for a warp container
+    pub fn is_active(&self, warp_container_id: &str) -> bool {
+        self.warps.contains_key(warp_container_id)
+    }
+
+    /// Get the target container IDs routed through a warp container
+    pub fn targets(&self, warp_container_id: &str) -> Vec<String> {
+        let mut targets: Vec<String> = self
+            .warps
+            .get(warp_container_id)
+            .map(|warp| warp.targets.keys().cloned().collect())
+            .unwrap_or_default();
+        targets.sort();
+        targets
+    }
+
+    /// Get the inode of the namespace forwarding was set up in for a warp container
+    pub fn namespace_inode(&self, warp_container_id: &str) -> Option<u64> {
+        self.warps
+            .get(warp_container_id)
+            .and_then(|warp| warp.namespace.inode)
+    }
+
+    /// Set up forwarding for a target routed through a warp container.
+    ///
+    /// The first target enables IP forwarding in the warp's namespace; every
+    /// target updates the masquerade rule with its subnets.
+    pub async fn attach_target(
+        &mut self,
+        warp_namespace: &NetworkNamespace,
+        target_container_id: &str,
+        subnets: &[IpNetwork],
+    ) -> Result<(), NetworkError> {
+        let warp_container_id = warp_namespace.container_id.clone();
+        let subnets: Vec<IpNetwork> = subnets.iter().map(normalize_subnet).collect();
+
+        if !self.warps.contains_key(&warp_container_id) {
+            let saved_sysctls = self
+                .namespace_manager
+                .execute_in_namespace(warp_namespace, enable_forwarding)
+                .await?;
+            self.warps.insert(
+                warp_container_id.clone(),
+                WarpForwarding {
+                    namespace: warp_namespace.clone(),
+                    targets: HashMap::new(),
+                    saved_sysctls,
+                },
+            );
+        }
+
+        let warp = self
+            .warps
+            .get_mut(&warp_container_id)
+            .expect("warp forwarding is set up above");
+        let previous = warp
+            .targets
+            .insert(target_container_id.to_string(), subnets);
+
+        let ruleset = masquerade_ruleset(&self.egress_interface, &warp.subnets());
+        let result = self
+            .namespace_manager
+            .execute_in_namespace(&warp.namespace, move || apply_ruleset(&ruleset))
+            .await;
+
+        if result.is_err() {
+            // Keep the bookkeeping in line with the rules actually installed
+            match previous {
+                Some(previous) => {
+                    warp.targets
+                        .insert(target_container_id.to_string(), previous);
+                }
+                None => {
+                    warp.targets.remove(target_container_id);
+                }
+            }
+
+            // Don't leave forwarding enabled for a warp without targets
+            if warp.targets.is_empty() {
+                let saved_sysctls = std::mem::take(&mut warp.saved_sysctls);
+                self.warps.remove(&warp_container_id);
+                if let Err(e) = self
+                    .namespace_manager
+                    .execute_in_namespace(warp_namespace, move || restore_sysctls(&saved_sysctls))
+                    .await
+                {
+                    warn!(
+                        "Failed to restore forwarding sysctls of warp {}: {}",
+                        warp_container_id, e
+                    );
+                }
+            }
+        }
+        result
+    }
+
+    /// Remove a target from a warp container.
+    ///
+    /// When the last target detaches, the masquerade rule is removed and the
+    /// forwarding sysctls are restored to their previous values.
+    pub async fn detach_target(
+        &mut self,
+        warp_container_id: &str,
+        target_container_id: &str,
+    ) -> Result<(), NetworkError> {
+        let Some(warp) = self.warps.get_mut(warp_container_id) else {
+            return Ok(());
+        };
+        if warp.targets.remove(target_container_id).is_none() {
+            return Ok(());
+        }
+
+        if !warp.targets.is_empty() {
+            let ruleset = masquerade_ruleset(&self.egress_interface, &warp.subnets());
+            return self
+                .namespace_manager
+                .execute_in_namespace(&warp.namespace, move || apply_ruleset(&ruleset))
+                .await;
+        }
+
+        let WarpForwarding {
+            namespace,
+            saved_sysctls,
+            ..
+        } = self
+            .warps
+            .remove(warp_container_id)
+            .expect("warp forwarding exists above");
+        self.namespace_manager
+            .execute_in_namespace(&namespace, move || {
+                apply_ruleset(&teardown_ruleset())?;
+                restore_sysctls(&saved_sysctls)
+            })
+            .await
+    }
+
+    /// Forget a warp container whose namespace is gone, e.g. after it stopped
+    pub fn release_warp(&mut self, warp_container_id: &str) {
+        self.warps.remove(warp_container_id);
+    }
+}
+
+/// Reduce a container's interface address to the subnet it belongs to
+fn normalize_subnet(subnet: &IpNetwork) -> IpNetwork {
+    IpNetwork::new(subnet.network(), subnet.prefix()).unwrap_or(*subnet)
+}
+
+/// Build the nftables script replacing our table with masquerade rules for the subnets
+pub fn masquerade_ruleset(egress_interface: &str, subnets: &BTreeSet<IpNetwork>) -> String {
+    let mut rules = String::new();
+    for subnet in subnets {
+        let family = match subnet {
+            IpNetwork::V4(_) => "ip",
+            IpNetwork::V6(_) => "ip6",
+        };
+        rules.push_str(&format!(
+            "\t\t{} saddr {} oifname \"{}\" masquerade\n",
+            family, subnet, egress_interface
+        ));
+    }
+
+    // Declaring the table first makes the delete succeed when it doesn't exist yet
+    format!(
+        "table inet {table}\n\
+         delete table inet {table}\n\
+         table inet {table} {{\n\
+         \tchain postrouting {{\n\
+         \t\ttype nat hook postrouting priority srcnat; policy accept;\n\
+         {rules}\
+         \t}}\n\
+         }}\n",
+        table = NFT_TABLE_NAME,
+        rules = rules
+    )
+}
+
+/// Build the nftables script removing our table
+fn teardown_ruleset() -> String {
+    format!(
+        "table inet {table}\ndelete table inet {table}\n",
+        table = NFT_TABLE_NAME
+    )
+}
+
+/// Apply an nftables script atomically in the current namespace
+fn apply_ruleset(ruleset: &str) -> Result<(), NetworkError> {
+    let mut child = Command::new("nft")
+        .args(["-f", "-"])
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| NetworkError::OperationFailed(format!("Failed to run nft: {}", e)))?;
+
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(ruleset.as_bytes())
+        .map_err(|e| NetworkError::OperationFailed(format!("Failed to write to nft: {}", e)))?;
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| NetworkError::OperationFailed(format!("Failed to run nft: {}", e)))?;
+    if !output.status.success() {
+        return Err(NetworkError::OperationFailed(format!(
+            "nft failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Enable forwarding in the current namespace, returning the previous values
+fn enable_forwarding() -> Result<Vec<(String, String)>, NetworkError> {
+    enable_sysctls(&FORWARDING_SYSCTLS)
+}
+
+/// Set sysctls to 1, returning the previous values of those changed.
+///
+/// All values are read before anything is written. Sysctls that don't exist,
+/// such as the IPv6 ones when IPv6 is disabled, are skipped. If a write fails,
+/// the sysctls changed already are restored.
+fn enable_sysctls(paths: &[&str]) -> Result<Vec<(String, String)>, NetworkError> {
+    let mut previous = Vec::new();
+    for path in paths {
+        match read_optional_sysctl(path)? {
+            Some(value) if value != "1" => previous.push((path.to_string(), value)),
+            Some(_) => {}
+            None => debug!("Skipping missing sysctl {}", path),
+        }
+    }
+
+    let mut saved = Vec::with_capacity(previous.len());
+    for (path, value) in previous {
+        if let Err(e) = write_sysctl(&path, "1") {
+            if let Err(restore) = restore_sysctls(&saved) {
+                warn!(
+                    "Failed to restore sysctls after a failed write: {}",
+                    restore
+                );
+            }
+            return Err(e);
+        }
+        saved.push((path, value));
+    }
+    Ok(saved)
+}
+
+/// Restore sysctls saved by `enable_forwarding`
+fn restore_sysctls(saved: &[(String, String)]) -> Result<(), NetworkError> {
+    for (path, value) in saved {
+        write_sysctl(path, value)?;
+    }
+    Ok(())
+}
+
+/// Read a sysctl, or None if the kernel doesn't provide it
+fn read_optional_sysctl(path: &str) -> Result<Option<String>, NetworkError> {
+    match fs::read_to_string(path) {
+        Ok(value) => Ok(Some(value.trim().to_string())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(sysctl_error(path, e)),
+    }
+}
+
+fn write_sysctl(path: &str, value: &str) -> Result<(), NetworkError> {
+    fs::write(path, value).map_err(|e| sysctl_error(path, e))
+}
+
+fn sysctl_error(path: &str, e: std::io::Error) -> NetworkError {
+    if e.kind() == std::io::ErrorKind::PermissionDenied {
+        NetworkError::InsufficientPrivileges
+    } else {
+        NetworkError::OperationFailed(format!("Failed to access {}: {}", path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docker::{ContainerInfo, NetworkInfo};
+    use crate::error::DockerError;
+
+    struct MockDockerClient;
+
+    impl DockerClient for MockDockerClient {
+        async fn list_containers(&self, _all: bool) -> Result<Vec<ContainerInfo>, DockerError> {
+            Ok(vec![])
+        }
+
+        async fn inspect_container(&self, id: &str) -> Result<ContainerInfo, DockerError> {
+            Err(DockerError::ContainerNotFound {
+                container_id: id.to_string(),
+            })
+        }
+
+        async fn get_container_networks(&self, _id: &str) -> Result<Vec<NetworkInfo>, DockerError> {
+            Ok(vec![])
+        }
+    }
+
+    #[test]
+    fn test_masquerade_ruleset() {
+        let subnets: BTreeSet<IpNetwork> = [
+            "172.18.0.0/16".parse().unwrap(),
+            "fd00:dead:beef::/64".parse().unwrap(),
+        ]
+        .into_iter()
+        .collect();
+
+        let ruleset = masquerade_ruleset("CloudflareWARP", &subnets);
+
+        assert!(ruleset.starts_with("table inet docker_network_warp\ndelete table inet"));
+        assert!(ruleset.contains("type nat hook postrouting priority srcnat; policy accept;"));
+        assert!(ruleset.contains("ip saddr 172.18.0.0/16 oifname \"CloudflareWARP\" masquerade"));
+        assert!(
+            ruleset.contains("ip6 saddr fd00:dead:beef::/64 oifname \"CloudflareWARP\" masquerade")
+        );
+    }
+
+    #[test]
+    fn test_normalize_subnet() {
+        // Docker reports the container's address with the network's prefix length
+        let subnet: IpNetwork = "172.18.0.5/16".parse().unwrap();
+        assert_eq!(normalize_subnet(&subnet).to_string(), "172.18.0.0/16");
+    }
+
+    #[test]
+    fn test_enable_sysctls() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = |name: &str| dir.path().join(name).to_string_lossy().to_string();
+        fs::write(path("ip_forward"), "0\n").unwrap();
+        fs::write(path("forwarding"), "1\n").unwrap();
+
+        // A missing sysctl, e.g. with IPv6 disabled, is skipped
+        let saved =
+            enable_sysctls(&[&path("ip_forward"), &path("forwarding"), &path("missing")]).unwrap();
+        assert_eq!(saved, vec![(path("ip_forward"), "0".to_string())]);
+        assert_eq!(fs::read_to_string(path("ip_forward")).unwrap(), "1");
+
+        restore_sysctls(&saved).unwrap();
+        assert_eq!(fs::read_to_string(path("ip_forward")).unwrap(), "0");
+    }
+
+    #[test]
+    fn test_enable_sysctls_reads_before_writing() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let ip_forward = dir.path().join("ip_forward");
+        fs::write(&ip_forward, "0\n").unwrap();
+        // A directory can't be read as a sysctl
+        let unreadable = dir.path().join("conf");
+        fs::create_dir(&unreadable).unwrap();
+
+        let result =
+            enable_sysctls(&[&ip_forward.to_string_lossy(), &unreadable.to_string_lossy()]);
+        assert!(matches!(result, Err(NetworkError::OperationFailed(_))));
+        assert_eq!(fs::read_to_string(&ip_forward).unwrap(), "0\n");
+    }
+
+    #[tokio::test]
+    async fn test_attach_target_invalid_namespace() {
+        let mut manager = ForwardingManager::new(
+            Arc::new(NamespaceManager::new(MockDockerClient)),
+            "wg0".to_string(),
+        );
+        let namespace = NetworkNamespace {
+            path: "/nonexistent/namespace/path".to_string(),
+            container_id: "warp-1".to_string(),
+            inode: None,
+            handle: None,
+        };
+
+        let result = manager
+            .attach_target(&namespace, "target-1", &["172.18.0.0/16".parse().unwrap()])
+            .await;
+
+        assert!(matches!(result, Err(NetworkError::NamespaceAccess(_))));
+        assert!(!manager.is_active("warp-1"));
+        assert!(manager.targets("warp-1").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_detach_unknown_target() {
+        let mut manager = ForwardingManager::new(
+            Arc::new(NamespaceManager::new(MockDockerClient)),
+            "wg0".to_string(),
+        );
+        assert!(manager.detach_target("warp-1", "target-1").await.is_ok());
+    }
+}
@@ -3,10 +3,16 @@
 //! Handles network namespace operations and container network discovery
 
 use crate::error::NetworkError;
+use std::fs::File;
 use std::net::IpAddr;
+use std::sync::Arc;
 
 pub mod discovery;
+pub mod forwarding;
 pub mod namespace;
+pub mod registry;
+pub mod rootless;
+pub mod worker;
 
 pub use namespace::ContainerNetworkAnalysis;
 
@@ -15,6 +21,11 @@ pub use namespace::ContainerNetworkAnalysis;
 pub struct NetworkNamespace {
     pub path: String,
     pub container_id: String,
+    /// Inode of the namespace, identical for containers sharing the namespace
+    pub inode: Option<u64>,
+    /// Open handle pinning the namespace, used instead of `path` when present so that
+    /// a PID reused after the container exited can't redirect operations elsewhere
+    pub handle: Option<Arc<File>>,
 }
 
 /// Network manager trait
@@ -1,44 +1,145 @@
 //! Network namespace operations
 
-use crate::docker::{DockerClient, NetworkInfo};
+use crate::docker::{ContainerInfo, DockerClient, NetworkInfo};
 use crate::error::NetworkError;
+use crate::network::rootless::RootlessContext;
+use crate::network::worker::{NamespaceWorkerPool, DEFAULT_NAMESPACE_OPERATION_TIMEOUT};
 use crate::network::{NetworkManager, NetworkNamespace};
-use netns_rs::NetNs;
+use std::collections::HashMap;
+use std::fs::File;
 use std::net::IpAddr;
+use std::os::unix::fs::MetadataExt;
 use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::debug;
+
+/// Network namespace of this process
+const OWN_NAMESPACE_PATH: &str = "/proc/self/ns/net";
+/// Network namespaces of the host: the daemon's own and PID 1's
+const HOST_NAMESPACE_PATHS: [&str; 2] = [OWN_NAMESPACE_PATH, "/proc/1/ns/net"];
 
 /// Network namespace manager implementation
 pub struct NamespaceManager<D: DockerClient> {
     docker_client: D,
+    allow_host_namespace: bool,
+    /// Namespaces opened for running containers, kept until they stop or restart
+    namespaces: Mutex<HashMap<String, CachedNamespace>>,
+    /// Threads running namespace operations; the shared pool if not set
+    workers: Option<Arc<NamespaceWorkerPool>>,
+    operation_timeout: Duration,
+    /// Namespaces of a rootless daemon, whose PIDs need translating
+    rootless: Option<Arc<RootlessContext>>,
 }
 
 impl<D: DockerClient> NamespaceManager<D> {
     /// Create a new namespace manager with a Docker client
     #[allow(dead_code)]
     pub fn new(docker_client: D) -> Self {
-        Self { docker_client }
+        Self {
+            docker_client,
+            allow_host_namespace: false,
+            namespaces: Mutex::new(HashMap::new()),
+            workers: None,
+            operation_timeout: DEFAULT_NAMESPACE_OPERATION_TIMEOUT,
+            rootless: None,
+        }
+    }
+
+    /// Run namespace operations on a dedicated worker pool instead of the shared one
+    pub fn with_worker_pool(mut self, workers: Arc<NamespaceWorkerPool>) -> Self {
+        self.workers = Some(workers);
+        self
+    }
+
+    /// Set the timeout of operations run in namespaces
+    pub fn with_operation_timeout(mut self, timeout: Duration) -> Self {
+        self.operation_timeout = timeout;
+        self
+    }
+
+    /// Find container namespaces of a rootless daemon, translating its PIDs.
+    ///
+    /// The process should have joined rootlesskit's namespaces already.
+    pub fn with_rootless(mut self, rootless: Arc<RootlessContext>) -> Self {
+        self.rootless = Some(rootless);
+        self
+    }
+
+    /// Allow returning the host's network namespace for containers.
+    ///
+    /// Dangerous: routes installed there, such as a default route via a warp,
+    /// apply to the whole host.
+    pub fn with_allow_host_namespace(mut self, allow: bool) -> Self {
+        self.allow_host_namespace = allow;
+        self
+    }
+
+    /// Close the namespace handle of a container, e.g. after a stop event.
+    ///
+    /// Returns true if a handle was open. The next lookup opens the namespace again.
+    pub fn release_container(&self, container_id: &str) -> bool {
+        self.namespaces
+            .lock()
+            .expect("namespace handles lock poisoned")
+            .remove(container_id)
+            .is_some()
     }
 
     /// Get container PID from Docker inspect
     #[allow(dead_code)]
     async fn get_container_pid(&self, container_id: &str) -> Result<u32, NetworkError> {
-        let container = self
-            .docker_client
+        let container = self.inspect_container(container_id).await?;
+        Self::container_pid(&container)
+    }
+
+    async fn inspect_container(&self, container_id: &str) -> Result<ContainerInfo, NetworkError> {
+        self.docker_client
             .inspect_container(container_id)
             .await
             .map_err(|e| {
                 NetworkError::OperationFailed(format!("Failed to inspect container: {}", e))
-            })?;
+            })
+    }
 
-        // Check if container is running
-        match container.state {
-            crate::docker::ContainerState::Running => {}
-            _ => {
-                return Err(NetworkError::OperationFailed(format!(
-                    "Container {} is not running",
-                    container_id
-                )))
+    /// Get the network namespace path of an inspected container.
+    ///
+    /// The sandbox key reported by the runtime (e.g. `/run/netns/...` for rootful
+    /// Podman) is preferred when it is reachable, otherwise the namespace is found
+    /// through the container's PID. Sandbox keys of a rootless daemon are only
+    /// bind mounts within rootlesskit's mount namespace, so PIDs are used instead.
+    fn namespace_path(&self, container: &ContainerInfo) -> Result<String, NetworkError> {
+        let sandbox_key = match self.rootless {
+            Some(_) => None,
+            None => container.sandbox_key.as_deref(),
+        };
+        if let Some(key) = sandbox_key {
+            if container.state.is_active() && Path::new(key).exists() {
+                return Ok(key.to_string());
             }
+            debug!(
+                "Sandbox key {} of container {} is not usable, falling back to its PID",
+                key, container.id
+            );
+        }
+
+        let mut pid = Self::container_pid(container)?;
+        if let Some(ref rootless) = self.rootless {
+            pid = rootless.translate_pid(pid)?;
+        }
+        Ok(format!("/proc/{}/ns/net", pid))
+    }
+
+    /// Get the PID of an inspected container
+    fn container_pid(container: &ContainerInfo) -> Result<u32, NetworkError> {
+        let container_id = &container.id;
+
+        // Check if container is running; paused containers keep their namespaces
+        if !container.state.is_active() {
+            return Err(NetworkError::OperationFailed(format!(
+                "Container {} is not running",
+                container_id
+            )));
         }
 
         // Get PID from container info
@@ -60,50 +161,55 @@ impl<D: DockerClient> NamespaceManager<D> {
         Ok(pid as u32)
     }
 
-    /// Execute a function within a network namespace
-    #[allow(dead_code)]
+    /// Execute a function within a network namespace.
+    ///
+    /// The function runs on a namespace worker thread, never on the calling
+    /// runtime's threads, and fails with a timeout error if it doesn't complete
+    /// within the operation timeout.
     pub async fn execute_in_namespace<F, R>(
         &self,
         namespace: &NetworkNamespace,
         func: F,
     ) -> Result<R, NetworkError>
     where
-        F: FnOnce() -> Result<R, NetworkError> + Send,
-        R: Send,
+        F: FnOnce() -> Result<R, NetworkError> + Send + 'static,
+        R: Send + 'static,
     {
-        // Check if namespace path exists
-        if !Path::new(&namespace.path).exists() {
-            return Err(NetworkError::NamespaceAccess(format!(
-                "Namespace path does not exist: {}",
-                namespace.path
-            )));
-        }
-
-        // Try to get the namespace
-        let ns = NetNs::get(&namespace.path).map_err(|e| {
-            // Check for permission errors specifically
-            if e.to_string().contains("Permission denied")
-                || e.to_string().contains("Operation not permitted")
-            {
-                NetworkError::InsufficientPrivileges
-            } else {
-                NetworkError::NamespaceAccess(e.to_string())
-            }
-        })?;
+        let handle = match namespace.handle {
+            Some(ref handle) => Arc::clone(handle),
+            None => Arc::new(open_namespace(&namespace.path)?),
+        };
+
+        let workers = match self.workers {
+            Some(ref workers) => Arc::clone(workers),
+            None => NamespaceWorkerPool::shared()?,
+        };
+        workers
+            .execute(handle, self.operation_timeout, func)
+            .await
+            .map_err(|e| match e {
+                NetworkError::NamespaceAccess(msg) => {
+                    NetworkError::NamespaceAccess(format!("{} ({})", msg, namespace.path))
+                }
+                e => e,
+            })
+    }
 
-        // Enter the namespace
-        let _guard = ns.enter().map_err(|e| {
-            if e.to_string().contains("Permission denied")
-                || e.to_string().contains("Operation not permitted")
-            {
-                NetworkError::InsufficientPrivileges
-            } else {
-                NetworkError::NamespaceAccess(e.to_string())
-            }
-        })?;
+    /// Paths of the namespaces containers must not share.
+    ///
+    /// A rootless daemon's host network is rootlesskit's namespace, which this
+    /// process joined at startup; PID 1's namespace belongs to another user
+    /// namespace and can't be read from there.
+    fn host_namespace_paths(&self) -> &'static [&'static str] {
+        match self.rootless {
+            Some(_) => &[OWN_NAMESPACE_PATH],
+            None => &HOST_NAMESPACE_PATHS,
+        }
+    }
 
-        // Execute the function within the namespace
-        func()
+    /// Get the timeout of operations run in namespaces
+    pub fn operation_timeout(&self) -> Duration {
+        self.operation_timeout
     }
 }
 
@@ -112,21 +218,71 @@ impl<D: DockerClient> NetworkManager for NamespaceManager<D> {
         &self,
         container_id: &str,
     ) -> Result<NetworkNamespace, NetworkError> {
-        let pid = self.get_container_pid(container_id).await?;
-        let ns_path = format!("/proc/{}/ns/net", pid);
-
-        // Verify the namespace path exists
-        if !Path::new(&ns_path).exists() {
-            return Err(NetworkError::NamespaceAccess(format!(
-                "Network namespace not found at {}",
-                ns_path
-            )));
+        // Reuse the handle opened when the container was first seen, unless it
+        // restarted since and got a new namespace
+        let container = self.inspect_container(container_id).await?;
+        if let Some(cached) = self
+            .namespaces
+            .lock()
+            .expect("namespace handles lock poisoned")
+            .get(container_id)
+            .filter(|cached| cached.serves(&container))
+        {
+            return Ok(cached.namespace.clone());
+        }
+
+        if !self.allow_host_namespace && container.network_mode.as_deref() == Some("host") {
+            return Err(NetworkError::HostNamespace {
+                container_id: container_id.to_string(),
+                reason: "container runs with network mode 'host'".to_string(),
+            });
         }
 
-        Ok(NetworkNamespace {
+        let ns_path = self.namespace_path(&container)?;
+        let handle = open_namespace(&ns_path)?;
+
+        // The PID may have been reused if the container exited before the namespace
+        // was opened, in which case it was restarted or is no longer running
+        let current = self.inspect_container(container_id).await?;
+        verify_unchanged(&container, &current)?;
+
+        let inode = handle
+            .metadata()
+            .map(|metadata| metadata.ino())
+            .map_err(|e| {
+                NetworkError::NamespaceAccess(format!("Failed to stat {}: {}", ns_path, e))
+            })?;
+        if !self.allow_host_namespace {
+            let host_inodes = namespace_inodes(self.host_namespace_paths()).map_err(|e| {
+                NetworkError::HostNamespace {
+                    container_id: container_id.to_string(),
+                    reason: format!(
+                        "its network namespace can't be told apart from the host's: {}",
+                        e
+                    ),
+                }
+            })?;
+            check_host_namespace(container_id, inode, &host_inodes)?;
+        }
+
+        let namespace = NetworkNamespace {
             path: ns_path,
             container_id: container_id.to_string(),
-        })
+            inode: Some(inode),
+            handle: Some(Arc::new(handle)),
+        };
+        self.namespaces
+            .lock()
+            .expect("namespace handles lock poisoned")
+            .insert(
+                container_id.to_string(),
+                CachedNamespace {
+                    namespace: namespace.clone(),
+                    pid: current.pid,
+                    started_at: current.started_at,
+                },
+            );
+        Ok(namespace)
     }
 
     async fn get_container_networks(
@@ -171,9 +327,9 @@ impl<D: DockerClient> NetworkManager for NamespaceManager<D> {
             return Ok(networks[0].ip_address);
         }
 
-        return Err(NetworkError::MultipleNetworksExist {
+        Err(NetworkError::MultipleNetworksExist {
             container_id: container_id.to_string(),
-        });
+        })
     }
 }
 
@@ -294,6 +450,91 @@ impl<D: DockerClient> NamespaceManager<D> {
     }
 }
 
+/// Get the inode identifying the network namespace at a path
+pub fn namespace_inode(path: &str) -> Result<u64, NetworkError> {
+    std::fs::metadata(path)
+        .map(|metadata| metadata.ino())
+        .map_err(|e| {
+            if e.kind() == std::io::ErrorKind::PermissionDenied {
+                NetworkError::InsufficientPrivileges
+            } else {
+                NetworkError::NamespaceAccess(format!("Failed to stat {}: {}", path, e))
+            }
+        })
+}
+
+/// Open a network namespace file, keeping the namespace alive while the handle is held
+pub fn open_namespace(path: &str) -> Result<File, NetworkError> {
+    File::open(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::PermissionDenied => NetworkError::InsufficientPrivileges,
+        std::io::ErrorKind::NotFound => {
+            NetworkError::NamespaceAccess(format!("Network namespace not found at {}", path))
+        }
+        _ => NetworkError::NamespaceAccess(format!("Failed to open {}: {}", path, e)),
+    })
+}
+
+/// Namespace handle of a container, with the process it was opened through
+struct CachedNamespace {
+    namespace: NetworkNamespace,
+    pid: Option<i64>,
+    started_at: Option<String>,
+}
+
+impl CachedNamespace {
+    /// Check whether the handle still belongs to the container as inspected now
+    fn serves(&self, container: &ContainerInfo) -> bool {
+        container.state.is_active()
+            && container.pid == self.pid
+            && container.started_at == self.started_at
+    }
+}
+
+/// Check that a container wasn't restarted or stopped between two inspections
+fn verify_unchanged(before: &ContainerInfo, after: &ContainerInfo) -> Result<(), NetworkError> {
+    if !after.state.is_active()
+        || after.pid != before.pid
+        || after.started_at != before.started_at
+        || after.sandbox_key != before.sandbox_key
+    {
+        return Err(NetworkError::NamespaceChanged {
+            container_id: before.id.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Get the inodes of the host's network namespace: the daemon's own and PID 1's.
+///
+/// Fails if either can't be read, as a container's namespace then can't be ruled
+/// out as the host's.
+pub fn host_namespace_inodes() -> Result<Vec<u64>, NetworkError> {
+    namespace_inodes(&HOST_NAMESPACE_PATHS)
+}
+
+/// Get the inodes of namespaces, failing if any can't be read
+fn namespace_inodes(paths: &[&str]) -> Result<Vec<u64>, NetworkError> {
+    paths.iter().map(|path| namespace_inode(path)).collect()
+}
+
+/// Reject a container namespace that is one of the host's namespaces
+pub fn check_host_namespace(
+    container_id: &str,
+    inode: u64,
+    host_inodes: &[u64],
+) -> Result<(), NetworkError> {
+    if host_inodes.contains(&inode) {
+        return Err(NetworkError::HostNamespace {
+            container_id: container_id.to_string(),
+            reason: format!(
+                "its network namespace (inode {}) is the host's network namespace",
+                inode
+            ),
+        });
+    }
+    Ok(())
+}
+
 /// Container network analysis result
 #[derive(Debug, Clone)]
 pub struct ContainerNetworkAnalysis {
@@ -391,6 +632,9 @@ mod tests {
             }],
             state,
             pid: Some(30),
+            network_mode: None,
+            sandbox_key: None,
+            started_at: None,
         }
     }
 
@@ -863,6 +1107,274 @@ mod tests {
         assert_eq!(pid, 30);
     }
 
+    #[test]
+    fn test_namespace_inode() {
+        // Both paths refer to the namespace of the test process
+        let inode = namespace_inode("/proc/self/ns/net").unwrap();
+        assert_eq!(namespace_inode("/proc/thread-self/ns/net").unwrap(), inode);
+
+        let result = namespace_inode("/nonexistent/namespace/path");
+        assert!(matches!(result, Err(NetworkError::NamespaceAccess(_))));
+    }
+
+    #[test]
+    fn test_check_host_namespace() {
+        assert!(check_host_namespace("app", 4026532000, &[4026531840]).is_ok());
+
+        let result = check_host_namespace("app", 4026531840, &[4026532001, 4026531840]);
+        assert!(matches!(result, Err(NetworkError::HostNamespace { .. })));
+
+        // The test process runs in a namespace that counts as the host's
+        let own = namespace_inode("/proc/self/ns/net").unwrap();
+        assert_eq!(namespace_inodes(&["/proc/self/ns/net"]).unwrap(), vec![own]);
+
+        // A namespace that can't be read fails the lookup rather than being left out
+        let result = namespace_inodes(&["/proc/self/ns/net", "/nonexistent/namespace/path"]);
+        assert!(matches!(result, Err(NetworkError::NamespaceAccess(_))));
+    }
+
+    #[tokio::test]
+    async fn test_get_container_namespace_host_network() {
+        let mut mock_client = MockDockerClient::new();
+        let mut container =
+            create_test_container("test-123", "test-container", ContainerState::Running);
+        container.network_mode = Some("host".to_string());
+        mock_client.add_container(container);
+
+        let manager = NamespaceManager::new(mock_client);
+        let result = manager.get_container_namespace("test-123").await;
+
+        assert!(matches!(
+            result,
+            Err(NetworkError::HostNamespace { ref container_id, .. }) if container_id == "test-123"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_container_namespace_host_inode() {
+        // A container whose PID lives in the host namespace, here our own process
+        let mut mock_client = MockDockerClient::new();
+        let mut container =
+            create_test_container("test-123", "test-container", ContainerState::Running);
+        container.pid = Some(std::process::id() as i64);
+        mock_client.add_container(container);
+
+        let manager = NamespaceManager::new(mock_client);
+        let result = manager.get_container_namespace("test-123").await;
+        assert!(matches!(result, Err(NetworkError::HostNamespace { .. })));
+
+        // The dangerous override lets it through
+        let mut mock_client = MockDockerClient::new();
+        let mut container =
+            create_test_container("test-123", "test-container", ContainerState::Running);
+        container.pid = Some(std::process::id() as i64);
+        mock_client.add_container(container);
+
+        let manager = NamespaceManager::new(mock_client).with_allow_host_namespace(true);
+        let namespace = manager.get_container_namespace("test-123").await.unwrap();
+        assert_eq!(
+            namespace.inode,
+            Some(namespace_inode("/proc/self/ns/net").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_namespace_path_prefers_sandbox_key() {
+        let sandbox = tempfile::NamedTempFile::new().unwrap();
+        let sandbox_key = sandbox.path().to_string_lossy().to_string();
+
+        let manager = NamespaceManager::new(MockDockerClient::new());
+        let mut container =
+            create_test_container("test-123", "test-container", ContainerState::Running);
+        container.sandbox_key = Some(sandbox_key.clone());
+        assert_eq!(manager.namespace_path(&container).unwrap(), sandbox_key);
+
+        // Unreachable sandbox keys fall back to the PID
+        container.sandbox_key = Some("/nonexistent/netns/netns-1234".to_string());
+        assert_eq!(
+            manager.namespace_path(&container).unwrap(),
+            "/proc/30/ns/net"
+        );
+
+        // Stopped containers are refused either way
+        container.sandbox_key = Some(sandbox_key);
+        container.state = ContainerState::Stopped;
+        assert!(manager.namespace_path(&container).is_err());
+    }
+
+    #[test]
+    fn test_namespace_path_rootless() {
+        // rootlesskit's child 4000 runs the daemon in a PID namespace, where the
+        // container is PID 30
+        let proc_root = tempfile::TempDir::new().unwrap();
+        for (pid, status) in [
+            (4000, "PPid:\t3999\nNSpid:\t4000\t1\n"),
+            (4100, "PPid:\t4000\nNSpid:\t4100\t30\n"),
+        ] {
+            let dir = proc_root.path().join(pid.to_string());
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("status"), status).unwrap();
+        }
+        let rootless = RootlessContext::new(4000).with_proc_root(proc_root.path());
+        let manager =
+            NamespaceManager::new(MockDockerClient::new()).with_rootless(Arc::new(rootless));
+
+        // Sandbox keys only exist in rootlesskit's mount namespace
+        let sandbox = tempfile::NamedTempFile::new().unwrap();
+        let mut container =
+            create_test_container("test-123", "test-container", ContainerState::Running);
+        container.sandbox_key = Some(sandbox.path().to_string_lossy().to_string());
+        assert_eq!(
+            manager.namespace_path(&container).unwrap(),
+            "/proc/4100/ns/net"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_container_namespace_reuses_handle() {
+        // Our own process stands in for the container
+        let mut mock_client = MockDockerClient::new();
+        let mut container =
+            create_test_container("test-123", "test-container", ContainerState::Running);
+        container.pid = Some(std::process::id() as i64);
+        mock_client.add_container(container);
+
+        let manager = NamespaceManager::new(mock_client).with_allow_host_namespace(true);
+        let first = manager.get_container_namespace("test-123").await.unwrap();
+        let second = manager.get_container_namespace("test-123").await.unwrap();
+
+        let handle = first.handle.as_ref().unwrap();
+        assert!(Arc::ptr_eq(handle, second.handle.as_ref().unwrap()));
+        assert_eq!(handle.metadata().unwrap().ino(), first.inode.unwrap());
+
+        // A stop event closes the handle, the next lookup opens a new one
+        assert!(manager.release_container("test-123"));
+        assert!(!manager.release_container("test-123"));
+        let third = manager.get_container_namespace("test-123").await.unwrap();
+        assert!(!Arc::ptr_eq(handle, third.handle.as_ref().unwrap()));
+    }
+
+    // Mock Docker client whose single container can be restarted during a test
+    struct RestartableDockerClient {
+        container: std::sync::Mutex<ContainerInfo>,
+    }
+
+    impl DockerClient for RestartableDockerClient {
+        async fn list_containers(&self, _all: bool) -> Result<Vec<ContainerInfo>, DockerError> {
+            Ok(vec![self.container.lock().unwrap().clone()])
+        }
+
+        async fn inspect_container(&self, _id: &str) -> Result<ContainerInfo, DockerError> {
+            Ok(self.container.lock().unwrap().clone())
+        }
+
+        async fn get_container_networks(&self, _id: &str) -> Result<Vec<NetworkInfo>, DockerError> {
+            Ok(self.container.lock().unwrap().networks.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_container_namespace_rootless_host_inode() {
+        // Without a PID namespace of rootlesskit's, PIDs need no translation; our
+        // own process stands in for a host-network container of the rootless daemon
+        let proc_root = tempfile::TempDir::new().unwrap();
+        let dir = proc_root.path().join("4000");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("status"), "PPid:\t3999\nNSpid:\t4000\n").unwrap();
+        let rootless = Arc::new(RootlessContext::new(4000).with_proc_root(proc_root.path()));
+
+        let mut mock_client = MockDockerClient::new();
+        let mut container =
+            create_test_container("test-123", "test-container", ContainerState::Running);
+        container.pid = Some(std::process::id() as i64);
+        mock_client.add_container(container);
+
+        // PID 1 is unreadable for a rootless user, so only our namespace is compared
+        let manager = NamespaceManager::new(mock_client).with_rootless(rootless);
+        assert_eq!(manager.host_namespace_paths(), &[OWN_NAMESPACE_PATH]);
+        let result = manager.get_container_namespace("test-123").await;
+        assert!(matches!(
+            result,
+            Err(NetworkError::HostNamespace { ref reason, .. }) if reason.contains("is the host's")
+        ));
+
+        let manager = NamespaceManager::new(MockDockerClient::new());
+        assert_eq!(manager.host_namespace_paths(), &HOST_NAMESPACE_PATHS);
+    }
+
+    #[tokio::test]
+    async fn test_get_container_namespace_after_restart() {
+        // Our own process stands in for the container
+        let mut container =
+            create_test_container("test-123", "test-container", ContainerState::Running);
+        container.pid = Some(std::process::id() as i64);
+        container.started_at = Some("2025-01-01T00:00:00.000000000Z".to_string());
+        let client = RestartableDockerClient {
+            container: std::sync::Mutex::new(container),
+        };
+
+        let manager = NamespaceManager::new(client).with_allow_host_namespace(true);
+        let first = manager.get_container_namespace("test-123").await.unwrap();
+
+        // Without a lifecycle event in between, the restart is noticed by the lookup
+        manager.docker_client.container.lock().unwrap().started_at =
+            Some("2025-01-01T00:05:00.000000000Z".to_string());
+        let second = manager.get_container_namespace("test-123").await.unwrap();
+        assert!(!Arc::ptr_eq(
+            first.handle.as_ref().unwrap(),
+            second.handle.as_ref().unwrap()
+        ));
+
+        let third = manager.get_container_namespace("test-123").await.unwrap();
+        assert!(Arc::ptr_eq(
+            second.handle.as_ref().unwrap(),
+            third.handle.as_ref().unwrap()
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_execute_in_invalid_namespace() {
+        // A regular file stands in for a namespace; entering it fails before
+        // privileges are checked
+        let fake = tempfile::NamedTempFile::new().unwrap();
+        let namespace = NetworkNamespace {
+            path: fake.path().to_string_lossy().to_string(),
+            container_id: "test-123".to_string(),
+            inode: None,
+            handle: None,
+        };
+
+        let manager = NamespaceManager::new(MockDockerClient::new());
+        let result = manager.execute_in_namespace(&namespace, || Ok(())).await;
+        assert!(matches!(
+            result,
+            Err(NetworkError::NamespaceAccess(ref msg)) if msg.contains(&namespace.path)
+        ));
+    }
+
+    #[test]
+    fn test_verify_unchanged() {
+        let mut before =
+            create_test_container("test-123", "test-container", ContainerState::Running);
+        before.started_at = Some("2025-01-01T00:00:00.000000000Z".to_string());
+        assert!(verify_unchanged(&before, &before.clone()).is_ok());
+
+        let mut restarted = before.clone();
+        restarted.started_at = Some("2025-01-01T00:05:00.000000000Z".to_string());
+        assert!(matches!(
+            verify_unchanged(&before, &restarted),
+            Err(NetworkError::NamespaceChanged { ref container_id }) if container_id == "test-123"
+        ));
+
+        let mut stopped = before.clone();
+        stopped.state = ContainerState::Stopped;
+        assert!(verify_unchanged(&before, &stopped).is_err());
+
+        let mut moved = before.clone();
+        moved.pid = Some(31);
+        assert!(verify_unchanged(&before, &moved).is_err());
+    }
+
     #[tokio::test]
     async fn test_get_container_pid_no_pid() {
         let mut mock_client = MockDockerClient::new();
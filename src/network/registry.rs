@@ -0,0 +1,293 @@
+//! Tracking of network namespaces shared between containers
+//!
+//! Containers started with `--network container:<name>` share the namespace of
+//! another container. Namespaces are identified by inode so that a shared
+//! namespace is programmed once, conflicting associations are refused, and a
+//! target sharing its warp's namespace is never routed to itself.
+
+use crate::error::NetworkError;
+use crate::network::NetworkNamespace;
+use std::collections::{BTreeSet, HashMap};
+
+/// Identity of a network namespace: its inode.
+///
+/// Paths can't tell whether two containers share a namespace, e.g.
+/// `/proc/<pid>/ns/net` of two processes, so namespaces without a known inode
+/// are refused rather than keyed by path.
+fn namespace_key(namespace: &NetworkNamespace) -> Result<u64, NetworkError> {
+    namespace.inode.ok_or_else(|| {
+        NetworkError::NamespaceAccess(format!(
+            "Inode of the network namespace of container {} is unknown ({})",
+            namespace.container_id, namespace.path
+        ))
+    })
+}
+
+/// Containers sharing a namespace and the warp it is routed through
+#[derive(Debug, Default)]
+struct SharedNamespace {
+    containers: BTreeSet<String>,
+    /// Target containers in this namespace associated with `warp_id`
+    targets: BTreeSet<String>,
+    warp_id: Option<String>,
+}
+
+/// Result of associating a target's namespace with a warp
+#[derive(Debug, Clone, PartialEq)]
+pub enum Association {
+    /// The namespace isn't routed yet and must be programmed
+    Program,
+    /// The namespace is already routed through the same warp by another container
+    AlreadyProgrammed { programmed_by: String },
+}
+
+/// Registry of the network namespaces of known containers
+#[derive(Debug, Default)]
+pub struct NamespaceRegistry {
+    namespaces: HashMap<u64, SharedNamespace>,
+}
+
+impl NamespaceRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the namespace of a container
+    pub fn register(&mut self, namespace: &NetworkNamespace) -> Result<(), NetworkError> {
+        self.namespaces
+            .entry(namespace_key(namespace)?)
+            .or_default()
+            .containers
+            .insert(namespace.container_id.clone());
+        Ok(())
+    }
+
+    /// Forget a container, e.g. after it stopped
+    pub fn unregister(&mut self, namespace: &NetworkNamespace) {
+        let Ok(key) = namespace_key(namespace) else {
+            return;
+        };
+        if let Some(shared) = self.namespaces.get_mut(&key) {
+            shared.containers.remove(&namespace.container_id);
+            if shared.containers.is_empty() && shared.targets.is_empty() {
+                self.namespaces.remove(&key);
+            }
+        }
+    }
+
+    /// Get the IDs of all registered containers sharing a namespace, sorted
+    pub fn containers_sharing(&self, namespace: &NetworkNamespace) -> Vec<String> {
+        namespace_key(namespace)
+            .ok()
+            .and_then(|key| self.namespaces.get(&key))
+            .map(|shared| shared.containers.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Associate a target's namespace with a warp.
+    ///
+    /// Targets sharing the warp's own namespace are refused, as are targets whose
+    /// namespace is already routed through a different warp. A namespace already
+    /// routed through the same warp is merged and must not be programmed again.
+    pub fn associate(
+        &mut self,
+        target: &NetworkNamespace,
+        warp: &NetworkNamespace,
+    ) -> Result<Association, NetworkError> {
+        let key = namespace_key(target)?;
+        if key == namespace_key(warp)? {
+            return Err(NetworkError::SharesWarpNamespace {
+                container_id: target.container_id.clone(),
+                warp_id: warp.container_id.clone(),
+            });
+        }
+
+        let shared = self.namespaces.entry(key).or_default();
+        if let Some(ref existing) = shared.warp_id {
+            if *existing != warp.container_id {
+                return Err(NetworkError::NamespaceConflict {
+                    container_id: target.container_id.clone(),
+                    existing_warp_id: existing.clone(),
+                    requested_warp_id: warp.container_id.clone(),
+                });
+            }
+        }
+        shared.containers.insert(target.container_id.clone());
+
+        match shared.warp_id {
+            Some(_) => {
+                let programmed_by = shared
+                    .targets
+                    .iter()
+                    .find(|id| **id != target.container_id)
+                    .cloned();
+                shared.targets.insert(target.container_id.clone());
+                Ok(match programmed_by {
+                    Some(programmed_by) => Association::AlreadyProgrammed { programmed_by },
+                    None => Association::Program,
+                })
+            }
+            None => {
+                shared.warp_id = Some(warp.container_id.clone());
+                shared.targets.insert(target.container_id.clone());
+                Ok(Association::Program)
+            }
+        }
+    }
+
+    /// Release a target's association.
+    ///
+    /// Returns true when it was the last target associated in its namespace, in
+    /// which case the namespace's routes should be removed.
+    pub fn release(&mut self, target: &NetworkNamespace) -> bool {
+        let Some(shared) = namespace_key(target)
+            .ok()
+            .and_then(|key| self.namespaces.get_mut(&key))
+        else {
+            return false;
+        };
+        if !shared.targets.remove(&target.container_id) {
+            return false;
+        }
+
+        if shared.targets.is_empty() {
+            shared.warp_id = None;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn namespace(container_id: &str, inode: u64) -> NetworkNamespace {
+        NetworkNamespace {
+            path: format!("/proc/{}/ns/net", inode),
+            container_id: container_id.to_string(),
+            inode: Some(inode),
+            handle: None,
+        }
+    }
+
+    #[test]
+    fn test_containers_sharing_namespace() {
+        let mut registry = NamespaceRegistry::new();
+        registry.register(&namespace("app", 100)).unwrap();
+        registry.register(&namespace("sidecar", 100)).unwrap();
+        registry.register(&namespace("other", 200)).unwrap();
+
+        assert_eq!(
+            registry.containers_sharing(&namespace("app", 100)),
+            vec!["app".to_string(), "sidecar".to_string()]
+        );
+
+        registry.unregister(&namespace("sidecar", 100));
+        assert_eq!(
+            registry.containers_sharing(&namespace("app", 100)),
+            vec!["app".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_associate_shared_namespace_is_programmed_once() {
+        let mut registry = NamespaceRegistry::new();
+        let warp = namespace("warp", 1);
+
+        assert_eq!(
+            registry.associate(&namespace("app", 100), &warp).unwrap(),
+            Association::Program
+        );
+        assert_eq!(
+            registry
+                .associate(&namespace("sidecar", 100), &warp)
+                .unwrap(),
+            Association::AlreadyProgrammed {
+                programmed_by: "app".to_string()
+            }
+        );
+
+        // Routes are only removed when the last target in the namespace leaves
+        assert!(!registry.release(&namespace("app", 100)));
+        assert!(registry.release(&namespace("sidecar", 100)));
+        assert!(!registry.release(&namespace("sidecar", 100)));
+
+        // The namespace can then be routed through another warp
+        assert_eq!(
+            registry
+                .associate(&namespace("app", 100), &namespace("warp-2", 2))
+                .unwrap(),
+            Association::Program
+        );
+    }
+
+    #[test]
+    fn test_associate_conflicting_warps() {
+        let mut registry = NamespaceRegistry::new();
+        registry
+            .associate(&namespace("app", 100), &namespace("warp-1", 1))
+            .unwrap();
+
+        let result = registry.associate(&namespace("sidecar", 100), &namespace("warp-2", 2));
+        assert!(matches!(
+            result,
+            Err(NetworkError::NamespaceConflict {
+                ref existing_warp_id,
+                ref requested_warp_id,
+                ..
+            }) if existing_warp_id == "warp-1" && requested_warp_id == "warp-2"
+        ));
+
+        // The refused target leaves the registry as it was
+        assert_eq!(
+            registry.containers_sharing(&namespace("app", 100)),
+            vec!["app".to_string()]
+        );
+        assert!(!registry.release(&namespace("sidecar", 100)));
+        assert_eq!(
+            registry
+                .associate(&namespace("sidecar", 100), &namespace("warp-1", 1))
+                .unwrap(),
+            Association::AlreadyProgrammed {
+                programmed_by: "app".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_associate_target_sharing_warp_namespace() {
+        let mut registry = NamespaceRegistry::new();
+
+        let result = registry.associate(&namespace("app", 1), &namespace("warp", 1));
+        assert!(matches!(
+            result,
+            Err(NetworkError::SharesWarpNamespace { .. })
+        ));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("shares its network namespace with warp container warp"));
+    }
+
+    #[test]
+    fn test_namespaces_without_inode_are_refused() {
+        let mut registry = NamespaceRegistry::new();
+        let mut app = namespace("app", 100);
+        app.inode = None;
+        let warp = namespace("warp", 1);
+
+        assert!(matches!(
+            registry.associate(&app, &warp),
+            Err(NetworkError::NamespaceAccess(_))
+        ));
+        assert!(matches!(
+            registry.associate(&namespace("sidecar", 100), &app),
+            Err(NetworkError::NamespaceAccess(_))
+        ));
+        assert!(registry.register(&app).is_err());
+        assert!(!registry.release(&app));
+    }
+}
@@ -0,0 +1,308 @@
+//! Rootless Docker support
+//!
+//! A rootless daemon runs inside the user, mount and network namespaces set up
+//! by rootlesskit, and possibly a PID namespace of its own. Container network
+//! namespaces belong to rootlesskit's user namespace, so entering them requires
+//! being in that user namespace first. And the PIDs the daemon reports are only
+//! valid within its PID namespace.
+//!
+//! Rootless mode therefore joins rootlesskit's user and network namespaces at
+//! startup, making them home for the namespace workers, and translates container
+//! PIDs to the PIDs `/proc` knows them by. Restarting the rootless daemon starts
+//! a new rootlesskit, which requires restarting this application as well.
+
+use crate::error::NetworkError;
+use crate::network::namespace::{namespace_inode, open_namespace};
+use nix::errno::Errno;
+use nix::sched::{setns, CloneFlags};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+/// rootlesskit state directory of `dockerd-rootless.sh`, relative to the runtime directory
+const ROOTLESSKIT_STATE_DIR: &str = "dockerd-rootless";
+/// File in the state directory holding the PID of rootlesskit's child process
+const CHILD_PID_FILE: &str = "child_pid";
+/// Ancestors walked up at most when checking that a process belongs to rootlesskit
+const MAX_PROCESS_DEPTH: usize = 64;
+
+/// Namespaces of a rootless Docker daemon, found through rootlesskit's child process
+#[derive(Debug, Clone)]
+pub struct RootlessContext {
+    /// Host PID of rootlesskit's child, the init process of the daemon's namespaces
+    child_pid: u32,
+    proc_root: PathBuf,
+}
+
+impl RootlessContext {
+    /// Create a context for rootlesskit's child process, by host PID
+    pub fn new(child_pid: u32) -> Self {
+        Self {
+            child_pid,
+            proc_root: PathBuf::from("/proc"),
+        }
+    }
+
+    /// Find rootlesskit's child process from the state `dockerd-rootless.sh` keeps in
+    /// the user's runtime directory, e.g. `/run/user/1000`
+    pub fn discover(runtime_dir: &Path) -> Result<Self, NetworkError> {
+        let path = runtime_dir.join(ROOTLESSKIT_STATE_DIR).join(CHILD_PID_FILE);
+        let content = fs::read_to_string(&path).map_err(|e| {
+            NetworkError::NamespaceAccess(format!(
+                "Failed to read rootlesskit's child PID from {}: {}. Is rootless Docker running?",
+                path.display(),
+                e
+            ))
+        })?;
+        let child_pid = content.trim().parse().map_err(|_| {
+            NetworkError::NamespaceAccess(format!(
+                "Invalid rootlesskit child PID '{}' in {}",
+                content.trim(),
+                path.display()
+            ))
+        })?;
+
+        debug!("Found rootlesskit child process {}", child_pid);
+        Ok(Self::new(child_pid))
+    }
+
+    /// Read processes from another procfs mount than `/proc`
+    pub fn with_proc_root(mut self, proc_root: impl Into<PathBuf>) -> Self {
+        self.proc_root = proc_root.into();
+        self
+    }
+
+    /// Host PID of rootlesskit's child process
+    pub fn child_pid(&self) -> u32 {
+        self.child_pid
+    }
+
+    /// Path of one of rootlesskit's namespaces, e.g. `net` or `user`
+    pub fn namespace_path(&self, kind: &str) -> String {
+        format!(
+            "{}/{}/ns/{}",
+            self.proc_root.display(),
+            self.child_pid,
+            kind
+        )
+    }
+
+    /// Move this process into rootlesskit's user and network namespaces.
+    ///
+    /// The kernel only lets single-threaded processes join a user namespace, so
+    /// this must run before the async runtime and the namespace workers start.
+    /// Namespaces the process is in already, e.g. when started through
+    /// `nsenter`, are skipped.
+    pub fn join(&self) -> Result<(), NetworkError> {
+        for (kind, flag) in [
+            ("user", CloneFlags::CLONE_NEWUSER),
+            ("net", CloneFlags::CLONE_NEWNET),
+        ] {
+            let path = self.namespace_path(kind);
+            let own = format!("{}/self/ns/{}", self.proc_root.display(), kind);
+            if namespace_inode(&path)? == namespace_inode(&own)? {
+                debug!("Already in rootlesskit's {} namespace", kind);
+                continue;
+            }
+
+            let namespace = open_namespace(&path)?;
+            setns(&namespace, flag).map_err(|e| match e {
+                Errno::EPERM => NetworkError::InsufficientPrivileges,
+                Errno::EINVAL => NetworkError::NamespaceAccess(format!(
+                    "Failed to join rootlesskit's {} namespace ({}); it must be joined before any threads start",
+                    kind, path
+                )),
+                e => NetworkError::NamespaceAccess(format!(
+                    "Failed to join rootlesskit's {} namespace ({}): {}",
+                    kind, path, e
+                )),
+            })?;
+        }
+
+        info!(
+            "Joined the user and network namespaces of rootlesskit process {}",
+            self.child_pid
+        );
+        Ok(())
+    }
+
+    /// Translate a container PID reported by the daemon to its host PID.
+    ///
+    /// Without a PID namespace of rootlesskit's, the PIDs are the same.
+    pub fn translate_pid(&self, pid: u32) -> Result<u32, NetworkError> {
+        let child = self.read_status(self.child_pid)?;
+        // rootlesskit's child is PID 1 of the namespace the daemon reports PIDs in
+        let level = child.ns_pids.len() - 1;
+        if level == 0 {
+            return Ok(pid);
+        }
+
+        let entries = fs::read_dir(&self.proc_root).map_err(|e| {
+            NetworkError::NamespaceAccess(format!(
+                "Failed to list processes in {}: {}",
+                self.proc_root.display(),
+                e
+            ))
+        })?;
+        for entry in entries.flatten() {
+            let Some(host_pid) = entry.file_name().to_str().and_then(|n| n.parse().ok()) else {
+                continue;
+            };
+            // Processes may exit while they are looked at
+            let Ok(status) = self.read_status(host_pid) else {
+                continue;
+            };
+            if status.ns_pids.get(level) == Some(&pid) && self.is_descendant(status) {
+                debug!("Translated rootless PID {} to host PID {}", pid, host_pid);
+                return Ok(host_pid);
+            }
+        }
+
+        Err(NetworkError::NamespaceAccess(format!(
+            "No process with PID {} in the PID namespace of rootlesskit process {}",
+            pid, self.child_pid
+        )))
+    }
+
+    /// Check whether a process descends from rootlesskit's child, rather than
+    /// belonging to another PID namespace at the same level
+    fn is_descendant(&self, mut status: ProcessStatus) -> bool {
+        for _ in 0..MAX_PROCESS_DEPTH {
+            if status.ppid == self.child_pid {
+                return true;
+            }
+            if status.ppid == 0 {
+                return false;
+            }
+            match self.read_status(status.ppid) {
+                Ok(parent) => status = parent,
+                Err(_) => return false,
+            }
+        }
+        false
+    }
+
+    fn read_status(&self, pid: u32) -> Result<ProcessStatus, NetworkError> {
+        let path = self.proc_root.join(pid.to_string()).join("status");
+        let content = fs::read_to_string(&path).map_err(|e| {
+            NetworkError::NamespaceAccess(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+        Ok(ProcessStatus::parse(pid, &content))
+    }
+}
+
+/// Fields of `/proc/<pid>/status` used for PID translation
+#[derive(Debug, Clone, PartialEq)]
+struct ProcessStatus {
+    ppid: u32,
+    /// PIDs of the process from the outermost PID namespace inwards
+    ns_pids: Vec<u32>,
+}
+
+impl ProcessStatus {
+    fn parse(pid: u32, content: &str) -> Self {
+        let mut status = Self {
+            ppid: 0,
+            ns_pids: Vec::new(),
+        };
+        for line in content.lines() {
+            if let Some(ppid) = line.strip_prefix("PPid:") {
+                status.ppid = ppid.trim().parse().unwrap_or(0);
+            } else if let Some(ns_pids) = line.strip_prefix("NSpid:") {
+                status.ns_pids = ns_pids
+                    .split_whitespace()
+                    .filter_map(|p| p.parse().ok())
+                    .collect();
+            }
+        }
+        // Kernels before 4.1 don't report NSpid
+        if status.ns_pids.is_empty() {
+            status.ns_pids.push(pid);
+        }
+        status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_status(proc_root: &Path, pid: u32, ppid: u32, ns_pids: &[u32]) {
+        let dir = proc_root.join(pid.to_string());
+        fs::create_dir_all(&dir).unwrap();
+        let ns_pids: Vec<String> = ns_pids.iter().map(|p| p.to_string()).collect();
+        fs::write(
+            dir.join("status"),
+            format!(
+                "Name:\tsleep\nPid:\t{}\nPPid:\t{}\nNSpid:\t{}\n",
+                pid,
+                ppid,
+                ns_pids.join("\t")
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_discover_child_pid() {
+        let runtime_dir = TempDir::new().unwrap();
+        assert!(matches!(
+            RootlessContext::discover(runtime_dir.path()),
+            Err(NetworkError::NamespaceAccess(_))
+        ));
+
+        let state_dir = runtime_dir.path().join(ROOTLESSKIT_STATE_DIR);
+        fs::create_dir_all(&state_dir).unwrap();
+        fs::write(state_dir.join(CHILD_PID_FILE), "4321\n").unwrap();
+
+        let context = RootlessContext::discover(runtime_dir.path()).unwrap();
+        assert_eq!(context.child_pid(), 4321);
+        assert_eq!(context.namespace_path("net"), "/proc/4321/ns/net");
+    }
+
+    #[test]
+    fn test_translate_pid_in_pid_namespace() {
+        let proc_root = TempDir::new().unwrap();
+        let proc = proc_root.path();
+        // rootlesskit's child is PID 1 of its namespace, the shim is its child
+        write_status(proc, 4000, 3999, &[4000, 1]);
+        write_status(proc, 4100, 4000, &[4100, 30]);
+        write_status(proc, 4200, 4100, &[4200, 42, 1]);
+        // PID 42 of another PID namespace at the same level
+        write_status(proc, 5000, 1, &[5000, 42]);
+
+        let context = RootlessContext::new(4000).with_proc_root(proc);
+        assert_eq!(context.translate_pid(42).unwrap(), 4200);
+        assert_eq!(context.translate_pid(30).unwrap(), 4100);
+        assert!(context.translate_pid(99).is_err());
+    }
+
+    #[test]
+    fn test_translate_pid_without_pid_namespace() {
+        let proc_root = TempDir::new().unwrap();
+        write_status(proc_root.path(), 4000, 3999, &[4000]);
+
+        let context = RootlessContext::new(4000).with_proc_root(proc_root.path());
+        assert_eq!(context.translate_pid(4200).unwrap(), 4200);
+    }
+
+    #[test]
+    fn test_parse_status_without_ns_pids() {
+        let status = ProcessStatus::parse(77, "Name:\tsh\nPPid:\t1\n");
+        assert_eq!(
+            status,
+            ProcessStatus {
+                ppid: 1,
+                ns_pids: vec![77]
+            }
+        );
+    }
+
+    #[test]
+    fn test_join_own_namespaces() {
+        // Our own process stands in for rootlesskit's child, so there's nothing to join
+        let context = RootlessContext::new(std::process::id());
+        context.join().unwrap();
+    }
+}
@@ -0,0 +1,288 @@
+//! Dedicated OS threads for operations inside network namespaces
+//!
+//! `setns` switches the namespace of the calling thread only. Calling it on a
+//! tokio worker leaks the namespace into every task later scheduled on that
+//! thread, and blocking on the runtime from there can deadlock it. Namespace
+//! operations are therefore sent over a channel to a pool of plain threads that
+//! enter the namespace, run the operation and return to their home namespace
+//! before picking up the next one. Results come back asynchronously, bounded by
+//! a per-operation timeout.
+
+use crate::error::NetworkError;
+use crate::network::namespace::open_namespace;
+use nix::errno::Errno;
+use nix::sched::{setns, CloneFlags};
+use std::fs::File;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tracing::{debug, error};
+
+/// Number of threads in the shared worker pool
+pub const DEFAULT_NAMESPACE_WORKERS: usize = 4;
+
+/// Time an operation may take, including waiting for a free worker
+pub const DEFAULT_NAMESPACE_OPERATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Shared pool, started on first use
+static SHARED_POOL: Mutex<Option<Arc<NamespaceWorkerPool>>> = Mutex::new(None);
+
+/// Operation run by a worker. Returns false if the worker couldn't return to its
+/// home namespace and must not run further operations.
+type Job = Box<dyn FnOnce(&File) -> bool + Send>;
+
+/// State shared by the workers of a pool
+struct WorkerShared {
+    receiver: Mutex<mpsc::Receiver<Job>>,
+    /// Namespace the workers return to after each operation
+    home: File,
+}
+
+/// Pool of threads running operations inside network namespaces
+pub struct NamespaceWorkerPool {
+    sender: mpsc::Sender<Job>,
+    workers: usize,
+}
+
+impl NamespaceWorkerPool {
+    /// Start a pool with the given number of workers, at home in the caller's namespace
+    pub fn new(workers: usize) -> Result<Self, NetworkError> {
+        let workers = workers.max(1);
+        let (sender, receiver) = mpsc::channel();
+        let shared = Arc::new(WorkerShared {
+            receiver: Mutex::new(receiver),
+            home: open_namespace("/proc/thread-self/ns/net")?,
+        });
+
+        for index in 0..workers {
+            spawn_worker(Arc::clone(&shared), index)?;
+        }
+
+        Ok(Self { sender, workers })
+    }
+
+    /// Get the process-wide pool, starting it on first use
+    pub fn shared() -> Result<Arc<Self>, NetworkError> {
+        let mut pool = SHARED_POOL.lock().expect("worker pool lock poisoned");
+        if let Some(ref pool) = *pool {
+            return Ok(Arc::clone(pool));
+        }
+
+        let started = Arc::new(Self::new(DEFAULT_NAMESPACE_WORKERS)?);
+        *pool = Some(Arc::clone(&started));
+        Ok(started)
+    }
+
+    /// Number of worker threads
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+
+    /// Run a function inside a network namespace on a worker thread.
+    ///
+    /// On timeout the caller gets an error right away. An operation that hasn't
+    /// started yet is skipped, one already running completes on its worker and
+    /// its result is discarded.
+    pub async fn execute<F, R>(
+        &self,
+        namespace: Arc<File>,
+        timeout: Duration,
+        func: F,
+    ) -> Result<R, NetworkError>
+    where
+        F: FnOnce() -> Result<R, NetworkError> + Send + 'static,
+        R: Send + 'static,
+    {
+        let (result_sender, result_receiver) = oneshot::channel();
+
+        let job: Job = Box::new(move |home: &File| {
+            if result_sender.is_closed() {
+                debug!("Skipping namespace operation abandoned by its caller");
+                return true;
+            }
+
+            let result = enter_namespace(&namespace).and_then(|()| {
+                catch_unwind(AssertUnwindSafe(func)).unwrap_or_else(|_| {
+                    Err(NetworkError::OperationFailed(
+                        "Namespace operation panicked".to_string(),
+                    ))
+                })
+            });
+            let _ = result_sender.send(result);
+
+            match enter_namespace(home) {
+                Ok(()) => true,
+                Err(e) => {
+                    error!("Namespace worker failed to return to its namespace: {}", e);
+                    false
+                }
+            }
+        });
+
+        self.sender.send(job).map_err(|_| {
+            NetworkError::OperationFailed("Namespace worker pool has shut down".to_string())
+        })?;
+
+        match tokio::time::timeout(timeout, result_receiver).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(NetworkError::OperationFailed(
+                "Namespace worker exited before completing the operation".to_string(),
+            )),
+            Err(_) => Err(NetworkError::Timeout(format!(
+                "Namespace operation did not complete within {:?}",
+                timeout
+            ))),
+        }
+    }
+}
+
+/// Start a worker thread. It enters the home namespace first, as threads inherit
+/// the namespace of the thread spawning them.
+fn spawn_worker(shared: Arc<WorkerShared>, index: usize) -> Result<(), NetworkError> {
+    thread::Builder::new()
+        .name(format!("netns-worker-{}", index))
+        .spawn(move || {
+            if let Err(e) = enter_namespace(&shared.home) {
+                error!("Namespace worker {} failed to start: {}", index, e);
+                return;
+            }
+
+            loop {
+                // Hold the lock only while waiting, so other workers can run jobs
+                let job = match shared.receiver.lock() {
+                    Ok(receiver) => receiver.recv(),
+                    Err(_) => return,
+                };
+                let Ok(job) = job else {
+                    // The pool was dropped
+                    return;
+                };
+
+                if !job(&shared.home) {
+                    // This thread is stuck in a foreign namespace; hand over to a fresh one
+                    if let Err(e) = spawn_worker(Arc::clone(&shared), index) {
+                        error!("Failed to replace namespace worker {}: {}", index, e);
+                    }
+                    return;
+                }
+            }
+        })
+        .map(|_| ())
+        .map_err(|e| {
+            NetworkError::OperationFailed(format!("Failed to start namespace worker: {}", e))
+        })
+}
+
+/// Move the calling thread into a network namespace
+fn enter_namespace(namespace: &File) -> Result<(), NetworkError> {
+    setns(namespace, CloneFlags::CLONE_NEWNET).map_err(|e| match e {
+        Errno::EPERM | Errno::EACCES => NetworkError::InsufficientPrivileges,
+        e => NetworkError::NamespaceAccess(format!("Failed to enter namespace: {}", e)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::MetadataExt;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    fn thread_namespace_inode() -> Result<u64, NetworkError> {
+        crate::network::namespace::namespace_inode("/proc/thread-self/ns/net")
+    }
+
+    fn own_namespace() -> Arc<File> {
+        Arc::new(open_namespace("/proc/self/ns/net").unwrap())
+    }
+
+    #[tokio::test]
+    #[ignore = "entering a network namespace requires CAP_SYS_ADMIN"]
+    async fn test_execute_runs_on_worker_thread() {
+        let pool = NamespaceWorkerPool::new(2).unwrap();
+        assert_eq!(pool.workers(), 2);
+
+        let result = pool
+            .execute(own_namespace(), Duration::from_secs(5), || {
+                Ok(thread::current().name().map(|name| name.to_string()))
+            })
+            .await;
+
+        assert!(result.unwrap().unwrap().starts_with("netns-worker-"));
+
+        // The calling thread never changes namespace
+        let home = own_namespace().metadata().unwrap().ino();
+        assert_eq!(thread_namespace_inode().unwrap(), home);
+    }
+
+    #[tokio::test]
+    #[ignore = "entering a network namespace requires CAP_SYS_ADMIN"]
+    async fn test_execute_propagates_errors() {
+        let pool = NamespaceWorkerPool::new(1).unwrap();
+
+        let result: Result<(), NetworkError> = pool
+            .execute(own_namespace(), Duration::from_secs(5), || {
+                Err(NetworkError::OperationFailed("boom".to_string()))
+            })
+            .await;
+        assert!(matches!(result, Err(NetworkError::OperationFailed(_))));
+
+        let result: Result<(), NetworkError> = pool
+            .execute(own_namespace(), Duration::from_secs(5), || panic!("boom"))
+            .await;
+        assert!(result.is_err());
+
+        // The worker survives a panicking operation
+        let result = pool
+            .execute(own_namespace(), Duration::from_secs(5), || Ok(42))
+            .await;
+        assert!(matches!(result, Ok(42)));
+    }
+
+    #[tokio::test]
+    #[ignore = "entering a network namespace requires CAP_SYS_ADMIN"]
+    async fn test_execute_timeout() {
+        let pool = NamespaceWorkerPool::new(1).unwrap();
+
+        let result = pool
+            .execute(own_namespace(), Duration::from_millis(50), || {
+                thread::sleep(Duration::from_millis(500));
+                Ok(())
+            })
+            .await;
+        assert!(matches!(result, Err(NetworkError::Timeout(_))));
+    }
+
+    #[tokio::test]
+    async fn test_execute_in_invalid_namespace() {
+        let pool = NamespaceWorkerPool::new(1).unwrap();
+        // A regular file stands in for a namespace; setns refuses it before
+        // checking privileges
+        let fake = tempfile::tempfile().unwrap();
+        let ran = Arc::new(AtomicBool::new(false));
+
+        let flag = Arc::clone(&ran);
+        let result = pool
+            .execute(Arc::new(fake), Duration::from_secs(5), move || {
+                flag.store(true, Ordering::SeqCst);
+                Ok(())
+            })
+            .await;
+
+        assert!(matches!(result, Err(NetworkError::NamespaceAccess(_))));
+        assert!(!ran.load(Ordering::SeqCst));
+
+        // The calling thread never changes namespace
+        let home = own_namespace().metadata().unwrap().ino();
+        assert_eq!(thread_namespace_inode().unwrap(), home);
+    }
+
+    #[test]
+    fn test_shared_pool_is_reused() {
+        let first = NamespaceWorkerPool::shared().unwrap();
+        let second = NamespaceWorkerPool::shared().unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(first.workers(), DEFAULT_NAMESPACE_WORKERS);
+    }
+}
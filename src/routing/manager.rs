@@ -3,33 +3,76 @@
 use crate::docker::DockerClient;
 use crate::error::{NetworkError, RouteError};
 use crate::network::{namespace::NamespaceManager, NetworkNamespace};
-use crate::routing::{IpNetwork, RouteEntry, RouteManager};
-use rtnetlink::{new_connection, Handle};
-use std::net::IpAddr;
+use crate::routing::{
+    IpNetwork, RouteAttributes, RouteEntry, RouteManager, RoutePrecedence, RouteProtocol,
+    RouteScope,
+};
+use futures_util::stream::TryStreamExt;
+use rtnetlink::packet_route::link::LinkAttribute;
+use rtnetlink::packet_route::route::{
+    RouteAddress, RouteAttribute, RouteFlags, RouteMessage, RouteMetric,
+    RouteProtocol as NetlinkRouteProtocol, RouteScope as NetlinkRouteScope, RouteType,
+};
+use rtnetlink::{new_connection, Handle, RouteMessageBuilder};
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+
+/// Netlink connection whose socket was created inside a network namespace
+struct NetlinkConnection {
+    handle: Handle,
+    task: JoinHandle<()>,
+    /// Containers sharing the namespace that used the connection
+    containers: HashSet<String>,
+}
+
+impl NetlinkConnection {
+    /// Check whether the connection is still running.
+    ///
+    /// The socket keeps its namespace alive, so the namespace's inode can't be
+    /// reused by another namespace, e.g. after a restart, while it runs.
+    fn serves(&self) -> bool {
+        !self.task.is_finished()
+    }
+}
+
+impl Drop for NetlinkConnection {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
 
 /// Route manager implementation using rtnetlink
 pub struct RtNetlinkRouteManager<D: DockerClient> {
-    namespace_manager: NamespaceManager<D>,
+    namespace_manager: Arc<NamespaceManager<D>>,
+    precedence: RoutePrecedence,
+    /// Netlink connections by namespace inode, shared by containers in the same namespace
+    connections: Mutex<HashMap<u64, NetlinkConnection>>,
 }
 
 impl<D: DockerClient> RtNetlinkRouteManager<D> {
-    /// Create a new route manager
-    pub fn new(docker_client: D) -> Self {
+    /// Create a new route manager finding namespaces through a shared namespace manager
+    pub fn new(namespace_manager: Arc<NamespaceManager<D>>) -> Self {
         Self {
-            namespace_manager: NamespaceManager::new(docker_client),
+            namespace_manager,
+            precedence: RoutePrecedence::default(),
+            connections: Mutex::new(HashMap::new()),
         }
     }
 
-    /// Create a new rtnetlink connection within the current namespace
-    async fn create_connection() -> Result<Handle, RouteError> {
-        let (connection, handle, _) = new_connection().map_err(|e| {
-            RouteError::AddRoute(format!("Failed to create netlink connection: {}", e))
-        })?;
-
-        // Spawn the connection handler
-        tokio::spawn(connection);
+    /// Set how added routes interact with existing routes such as Docker's.
+    ///
+    /// With [`RoutePrecedence::Override`], an existing route to the same
+    /// destination with the same metric is replaced instead of rejected.
+    pub fn with_route_precedence(mut self, precedence: RoutePrecedence) -> Self {
+        self.precedence = precedence;
+        self
+    }
 
-        Ok(handle)
+    /// Check whether added routes replace existing ones, as configured by the precedence
+    fn replaces_existing_routes(&self) -> bool {
+        self.precedence == RoutePrecedence::Override
     }
 
     /// Convert our IpNetwork to components
@@ -39,14 +82,233 @@ impl<D: DockerClient> RtNetlinkRouteManager<D> {
             IpNetwork::V6 { addr, prefix } => (IpAddr::V6(*addr), *prefix),
         }
     }
+
+    /// Convert our route scope to the netlink representation
+    fn convert_scope(scope: RouteScope) -> NetlinkRouteScope {
+        match scope {
+            RouteScope::Universe => NetlinkRouteScope::Universe,
+            RouteScope::Site => NetlinkRouteScope::Site,
+            RouteScope::Link => NetlinkRouteScope::Link,
+            RouteScope::Host => NetlinkRouteScope::Host,
+        }
+    }
+
+    /// Convert our route protocol to the netlink representation
+    fn convert_protocol(protocol: RouteProtocol) -> NetlinkRouteProtocol {
+        match protocol {
+            RouteProtocol::Boot => NetlinkRouteProtocol::Boot,
+            RouteProtocol::Static => NetlinkRouteProtocol::Static,
+            RouteProtocol::Kernel => NetlinkRouteProtocol::Kernel,
+            RouteProtocol::Dhcp => NetlinkRouteProtocol::Dhcp,
+            RouteProtocol::Other(value) => NetlinkRouteProtocol::from(value),
+        }
+    }
+
+    /// Build the netlink message describing a route entry
+    fn build_route_message(
+        route: &RouteEntry,
+        interface_index: Option<u32>,
+    ) -> Result<RouteMessage, NetworkError> {
+        let (dest_addr, prefix) = Self::convert_network(&route.destination);
+        let invalid = |e: String| {
+            NetworkError::OperationFailed(format!(
+                "Invalid route {}/{} via {}: {}",
+                dest_addr, prefix, route.gateway, e
+            ))
+        };
+
+        let mut builder = RouteMessageBuilder::<IpAddr>::new()
+            .destination_prefix(dest_addr, prefix)
+            .map_err(|e| invalid(e.to_string()))?
+            .gateway(route.gateway)
+            .map_err(|e| invalid(e.to_string()))?;
+
+        if let Some(index) = interface_index {
+            builder = builder.output_interface(index);
+        }
+
+        if let Some(metric) = route.metric {
+            builder = builder.priority(metric);
+        }
+
+        let attributes = &route.attributes;
+        if let Some(scope) = attributes.scope {
+            builder = builder.scope(Self::convert_scope(scope));
+        }
+
+        if let Some(protocol) = attributes.protocol {
+            builder = builder.protocol(Self::convert_protocol(protocol));
+        }
+
+        if attributes.onlink == Some(true) {
+            builder = builder.onlink();
+        }
+
+        if let Some(prefsrc) = attributes.prefsrc {
+            builder = builder
+                .pref_source(prefsrc)
+                .map_err(|e| invalid(e.to_string()))?;
+        }
+
+        let mut metrics = Vec::new();
+        if let Some(mtu) = attributes.mtu {
+            metrics.push(RouteMetric::Mtu(mtu));
+        }
+        if let Some(advmss) = attributes.advmss {
+            metrics.push(RouteMetric::Advmss(advmss));
+        }
+        if !metrics.is_empty() {
+            builder
+                .get_mut()
+                .attributes
+                .push(RouteAttribute::Metrics(metrics));
+        }
+
+        Ok(builder.build())
+    }
+
+    /// Build the netlink message of a blackhole route to a route entry's
+    /// destination with its metric; the gateway and attributes don't apply
+    fn build_blackhole_message(route: &RouteEntry) -> Result<RouteMessage, NetworkError> {
+        let (dest_addr, prefix) = Self::convert_network(&route.destination);
+        let mut builder = RouteMessageBuilder::<IpAddr>::new()
+            .destination_prefix(dest_addr, prefix)
+            .map_err(|e| {
+                NetworkError::OperationFailed(format!(
+                    "Invalid blackhole route {}/{}: {}",
+                    dest_addr, prefix, e
+                ))
+            })?
+            .kind(RouteType::BlackHole);
+
+        if let Some(metric) = route.metric {
+            builder = builder.priority(metric);
+        }
+
+        Ok(builder.build())
+    }
+
+    /// Convert a netlink route message back into a route entry.
+    ///
+    /// Routes without a gateway (e.g. directly connected subnets) are skipped.
+    fn convert_route_message(
+        message: &RouteMessage,
+        interface_names: &HashMap<u32, String>,
+    ) -> Option<RouteEntry> {
+        let header = &message.header;
+        let prefix = header.destination_prefix_length;
+
+        let mut destination = None;
+        let mut gateway = None;
+        let mut interface = None;
+        let mut metric = None;
+        let mut attributes = RouteAttributes {
+            scope: match header.scope {
+                NetlinkRouteScope::Universe => Some(RouteScope::Universe),
+                NetlinkRouteScope::Site => Some(RouteScope::Site),
+                NetlinkRouteScope::Link => Some(RouteScope::Link),
+                NetlinkRouteScope::Host => Some(RouteScope::Host),
+                _ => None,
+            },
+            onlink: Some(header.flags.contains(RouteFlags::Onlink)),
+            protocol: Some(match header.protocol {
+                NetlinkRouteProtocol::Boot => RouteProtocol::Boot,
+                NetlinkRouteProtocol::Static => RouteProtocol::Static,
+                NetlinkRouteProtocol::Kernel => RouteProtocol::Kernel,
+                NetlinkRouteProtocol::Dhcp => RouteProtocol::Dhcp,
+                other => RouteProtocol::Other(u8::from(other)),
+            }),
+            ..Default::default()
+        };
+
+        for attribute in &message.attributes {
+            match attribute {
+                RouteAttribute::Destination(RouteAddress::Inet(addr)) => {
+                    destination = Some(IpNetwork::new_v4(*addr, prefix));
+                }
+                RouteAttribute::Destination(RouteAddress::Inet6(addr)) => {
+                    destination = Some(IpNetwork::new_v6(*addr, prefix));
+                }
+                RouteAttribute::Gateway(RouteAddress::Inet(addr)) => {
+                    gateway = Some(IpAddr::V4(*addr));
+                }
+                RouteAttribute::Gateway(RouteAddress::Inet6(addr)) => {
+                    gateway = Some(IpAddr::V6(*addr));
+                }
+                RouteAttribute::PrefSource(RouteAddress::Inet(addr)) => {
+                    attributes.prefsrc = Some(IpAddr::V4(*addr));
+                }
+                RouteAttribute::PrefSource(RouteAddress::Inet6(addr)) => {
+                    attributes.prefsrc = Some(IpAddr::V6(*addr));
+                }
+                RouteAttribute::Oif(index) => {
+                    interface = interface_names.get(index).cloned();
+                }
+                RouteAttribute::Priority(priority) => metric = Some(*priority),
+                RouteAttribute::Metrics(metrics) => {
+                    for route_metric in metrics {
+                        match route_metric {
+                            RouteMetric::Mtu(mtu) => attributes.mtu = Some(*mtu),
+                            RouteMetric::Advmss(advmss) => attributes.advmss = Some(*advmss),
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let gateway = gateway?;
+
+        // The default route carries no destination attribute
+        let destination = destination.unwrap_or(match gateway {
+            IpAddr::V4(_) => IpNetwork::new_v4(Ipv4Addr::UNSPECIFIED, prefix),
+            IpAddr::V6(_) => IpNetwork::new_v6(Ipv6Addr::UNSPECIFIED, prefix),
+        });
+
+        Some(RouteEntry {
+            destination,
+            gateway,
+            interface,
+            metric,
+            attributes,
+        })
+    }
+
+    /// Look up the index of a network interface by name
+    async fn resolve_interface_index(handle: &Handle, name: &str) -> Result<u32, NetworkError> {
+        let mut links = handle.link().get().match_name(name.to_string()).execute();
+
+        match links.try_next().await {
+            Ok(Some(link)) => Ok(link.header.index),
+            Ok(None) => Err(NetworkError::OperationFailed(format!(
+                "Interface {} not found",
+                name
+            ))),
+            Err(e) => Err(NetworkError::OperationFailed(format!(
+                "Failed to look up interface {}: {}",
+                name, e
+            ))),
+        }
+    }
+
+    /// Build the netlink message for a route, resolving its output interface
+    async fn prepare_route_message(
+        handle: &Handle,
+        route: &RouteEntry,
+    ) -> Result<RouteMessage, NetworkError> {
+        let interface_index = match route.interface {
+            Some(ref name) => Some(Self::resolve_interface_index(handle, name).await?),
+            None => None,
+        };
+
+        Self::build_route_message(route, interface_index)
+    }
 }
 
 impl<D: DockerClient> RtNetlinkRouteManager<D> {
-    /// Execute a route operation within a network namespace
-    /// This utility method handles the common pattern of:
-    /// 1. Executing within the namespace
-    /// 2. Creating a netlink connection
-    /// 3. Converting network errors to route errors
+    /// Execute a route operation within a network namespace, over the
+    /// namespace's netlink connection
     async fn execute_route_operation<T, F, Fut>(
         &self,
         namespace: &NetworkNamespace,
@@ -58,19 +320,101 @@ impl<D: DockerClient> RtNetlinkRouteManager<D> {
         Fut: std::future::Future<Output = Result<T, NetworkError>> + Send,
         T: Send + 'static,
     {
-        self.namespace_manager
-            .execute_in_namespace(namespace, move || {
-                let rt = tokio::runtime::Handle::current();
-                rt.block_on(async {
-                    let handle = Self::create_connection().await.map_err(|e| {
-                        NetworkError::OperationFailed(format!("Failed to create connection: {}", e))
-                    })?;
+        // Connections to namespaces without a handle only live for this operation
+        let (handle, _connection) = self
+            .connection_handle(namespace)
+            .await
+            .map_err(error_converter)?;
 
-                    operation(handle).await
-                })
+        let timeout = self.namespace_manager.operation_timeout();
+        match tokio::time::timeout(timeout, operation(handle)).await {
+            Ok(result) => result.map_err(error_converter),
+            Err(_) => Err(error_converter(NetworkError::Timeout(format!(
+                "Route operation did not complete within {:?}",
+                timeout
+            )))),
+        }
+    }
+
+    /// Get a netlink handle for a namespace, reusing the connection of its namespace
+    /// when the namespace is held open. Otherwise a new connection is returned with
+    /// it, to be dropped after use.
+    async fn connection_handle(
+        &self,
+        namespace: &NetworkNamespace,
+    ) -> Result<(Handle, Option<NetlinkConnection>), NetworkError> {
+        let (Some(inode), Some(_)) = (namespace.inode, &namespace.handle) else {
+            let connection = self.open_connection(namespace).await?;
+            return Ok((connection.handle.clone(), Some(connection)));
+        };
+
+        if let Some(handle) = self.shared_connection(inode, namespace) {
+            return Ok((handle, None));
+        }
+
+        let opened = self.open_connection(namespace).await?;
+        let mut connections = self
+            .connections
+            .lock()
+            .expect("netlink connections lock poisoned");
+        // Another operation may have opened one meanwhile; keep a single connection
+        let connection = match connections.get_mut(&inode) {
+            Some(existing) if existing.serves() => existing,
+            _ => {
+                connections.insert(inode, opened);
+                connections
+                    .get_mut(&inode)
+                    .expect("connection just inserted")
+            }
+        };
+        connection.containers.insert(namespace.container_id.clone());
+        Ok((connection.handle.clone(), None))
+    }
+
+    /// Get the handle of a running connection to a namespace, recording the
+    /// container as one of its users
+    fn shared_connection(&self, inode: u64, namespace: &NetworkNamespace) -> Option<Handle> {
+        let mut connections = self
+            .connections
+            .lock()
+            .expect("netlink connections lock poisoned");
+        let connection = connections
+            .get_mut(&inode)
+            .filter(|connection| connection.serves())?;
+        connection.containers.insert(namespace.container_id.clone());
+        Some(connection.handle.clone())
+    }
+
+    /// Open a netlink connection inside a namespace.
+    ///
+    /// Only the socket is created inside the namespace, on a namespace worker
+    /// thread; it stays bound to that namespace, so the connection runs on the
+    /// runtime like any other task.
+    async fn open_connection(
+        &self,
+        namespace: &NetworkNamespace,
+    ) -> Result<NetlinkConnection, NetworkError> {
+        // Registering the socket with the reactor needs the runtime's context
+        let runtime = tokio::runtime::Handle::current();
+        let (connection, handle) = self
+            .namespace_manager
+            .execute_in_namespace(namespace, move || {
+                let _context = runtime.enter();
+                let (connection, handle, _) = new_connection().map_err(|e| {
+                    NetworkError::OperationFailed(format!(
+                        "Failed to create netlink connection: {}",
+                        e
+                    ))
+                })?;
+                Ok((connection, handle))
             })
-            .await
-            .map_err(error_converter)
+            .await?;
+
+        Ok(NetlinkConnection {
+            handle,
+            task: tokio::spawn(connection),
+            containers: HashSet::new(),
+        })
     }
 
     /// Convert NetworkError to RouteError for add operations
@@ -123,24 +467,22 @@ impl<D: DockerClient + Send + Sync> RouteManager for RtNetlinkRouteManager<D> {
         route: &RouteEntry,
     ) -> Result<(), RouteError> {
         let route_clone = route.clone();
+        let replace = self.replaces_existing_routes();
 
         self.execute_route_operation(
             namespace,
-            move |_handle| async move {
-                let (dest_addr, prefix) = Self::convert_network(&route_clone.destination);
-
-                // For now, we'll implement a basic route addition
-                // The exact rtnetlink API usage needs to be researched further
-                // This is a placeholder that establishes the structure
+            move |handle| async move {
+                let message = Self::prepare_route_message(&handle, &route_clone).await?;
 
-                // The actual implementation would use rtnetlink to add routes
-                // For now, we'll return an error indicating this needs implementation
-                // This establishes the structure for future implementation
-
-                Err(NetworkError::OperationFailed(format!(
-                    "Route addition not yet fully implemented for {} via {} (prefix: {})",
-                    dest_addr, route_clone.gateway, prefix
-                )))
+                let request = handle.route().add(message);
+                let request = if replace { request.replace() } else { request };
+                request.execute().await.map_err(|e| {
+                    let (dest_addr, prefix) = Self::convert_network(&route_clone.destination);
+                    NetworkError::OperationFailed(format!(
+                        "Failed to add route {}/{} via {}: {}",
+                        dest_addr, prefix, route_clone.gateway, e
+                    ))
+                })
             },
             Self::map_add_route_error,
         )
@@ -156,15 +498,16 @@ impl<D: DockerClient + Send + Sync> RouteManager for RtNetlinkRouteManager<D> {
 
         self.execute_route_operation(
             namespace,
-            move |_handle| async move {
-                let (dest_addr, prefix) = Self::convert_network(&route_clone.destination);
-
-                // The actual implementation would use rtnetlink to delete routes
+            move |handle| async move {
+                let message = Self::prepare_route_message(&handle, &route_clone).await?;
 
-                Err(NetworkError::OperationFailed(format!(
-                    "Route removal not yet fully implemented for {} via {} (prefix: {})",
-                    dest_addr, route_clone.gateway, prefix
-                )))
+                handle.route().del(message).execute().await.map_err(|e| {
+                    let (dest_addr, prefix) = Self::convert_network(&route_clone.destination);
+                    NetworkError::OperationFailed(format!(
+                        "Failed to remove route {}/{} via {}: {}",
+                        dest_addr, prefix, route_clone.gateway, e
+                    ))
+                })
             },
             Self::map_remove_route_error,
         )
@@ -177,24 +520,138 @@ impl<D: DockerClient + Send + Sync> RouteManager for RtNetlinkRouteManager<D> {
     ) -> Result<Vec<RouteEntry>, RouteError> {
         self.execute_route_operation(
             namespace,
-            move |_handle| async move {
-                // For now, return empty list - route listing is complex and not critical for MVP
-                // This can be implemented later when needed for debugging/monitoring
-                Ok(Vec::new())
+            move |handle| async move {
+                let list_failed = |e: rtnetlink::Error| {
+                    NetworkError::OperationFailed(format!("Failed to list routes: {}", e))
+                };
+
+                // Map interface indexes to names so listed routes carry their interface
+                let interface_names: HashMap<u32, String> = handle
+                    .link()
+                    .get()
+                    .execute()
+                    .map_ok(|link| {
+                        let name = link
+                            .attributes
+                            .iter()
+                            .find_map(|attribute| match attribute {
+                                LinkAttribute::IfName(name) => Some(name.clone()),
+                                _ => None,
+                            });
+                        (link.header.index, name)
+                    })
+                    .try_filter_map(|(index, name)| async move { Ok(name.map(|n| (index, n))) })
+                    .try_collect()
+                    .await
+                    .map_err(list_failed)?;
+
+                let mut routes = Vec::new();
+                for request in [
+                    RouteMessageBuilder::<Ipv4Addr>::new().build(),
+                    RouteMessageBuilder::<Ipv6Addr>::new().build(),
+                ] {
+                    let messages: Vec<RouteMessage> = handle
+                        .route()
+                        .get(request)
+                        .execute()
+                        .try_collect()
+                        .await
+                        .map_err(list_failed)?;
+
+                    routes.extend(
+                        messages
+                            .iter()
+                            .filter_map(|m| Self::convert_route_message(m, &interface_names)),
+                    );
+                }
+
+                Ok(routes)
             },
             Self::map_list_route_error,
         )
         .await
     }
+
+    async fn add_blackhole_route(
+        &self,
+        namespace: &NetworkNamespace,
+        route: &RouteEntry,
+    ) -> Result<(), RouteError> {
+        let route_clone = route.clone();
+
+        self.execute_route_operation(
+            namespace,
+            move |handle| async move {
+                let message = Self::build_blackhole_message(&route_clone)?;
+
+                handle
+                    .route()
+                    .add(message)
+                    .replace()
+                    .execute()
+                    .await
+                    .map_err(|e| {
+                        let (dest_addr, prefix) = Self::convert_network(&route_clone.destination);
+                        NetworkError::OperationFailed(format!(
+                            "Failed to add blackhole route {}/{}: {}",
+                            dest_addr, prefix, e
+                        ))
+                    })
+            },
+            Self::map_add_route_error,
+        )
+        .await
+    }
+
+    async fn remove_blackhole_route(
+        &self,
+        namespace: &NetworkNamespace,
+        route: &RouteEntry,
+    ) -> Result<(), RouteError> {
+        let route_clone = route.clone();
+
+        self.execute_route_operation(
+            namespace,
+            move |handle| async move {
+                let message = Self::build_blackhole_message(&route_clone)?;
+
+                handle.route().del(message).execute().await.map_err(|e| {
+                    let (dest_addr, prefix) = Self::convert_network(&route_clone.destination);
+                    NetworkError::OperationFailed(format!(
+                        "Failed to remove blackhole route {}/{}: {}",
+                        dest_addr, prefix, e
+                    ))
+                })
+            },
+            Self::map_remove_route_error,
+        )
+        .await
+    }
+
+    /// Close the namespace handle of a container, and the netlink connection of its
+    /// namespace once no other container shares it
+    fn release_container(&self, container_id: &str) {
+        self.connections
+            .lock()
+            .expect("netlink connections lock poisoned")
+            .retain(|_, connection| {
+                connection.containers.remove(container_id);
+                !connection.containers.is_empty()
+            });
+        self.namespace_manager.release_container(container_id);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::AppConfig;
     use crate::docker::{ContainerInfo, ContainerState, NetworkInfo};
     use crate::error::DockerError;
     use std::collections::HashMap;
+    use std::fs::File;
     use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+    use std::os::unix::fs::MetadataExt;
 
     // Mock Docker client for testing
     struct MockDockerClient;
@@ -212,6 +669,9 @@ mod tests {
                 networks: vec![],
                 state: ContainerState::Running,
                 pid: Some(1234),
+                network_mode: None,
+                sandbox_key: None,
+                started_at: None,
             })
         }
 
@@ -223,7 +683,7 @@ mod tests {
     #[tokio::test]
     async fn test_route_manager_creation() {
         let docker_client = MockDockerClient;
-        let _manager = RtNetlinkRouteManager::new(docker_client);
+        let _manager = RtNetlinkRouteManager::new(Arc::new(NamespaceManager::new(docker_client)));
         // Just test that we can create the manager without panicking
     }
 
@@ -262,6 +722,7 @@ mod tests {
             gateway: IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)),
             interface: Some("eth0".to_string()),
             metric: Some(100),
+            attributes: RouteAttributes::default(),
         };
 
         assert_eq!(route.destination.prefix(), 8);
@@ -270,14 +731,149 @@ mod tests {
         assert_eq!(route.metric, Some(100));
     }
 
+    #[test]
+    fn test_build_route_message_with_attributes() {
+        let route = RouteEntry {
+            destination: IpNetwork::V4 {
+                addr: Ipv4Addr::new(0, 0, 0, 0),
+                prefix: 0,
+            },
+            gateway: IpAddr::V4(Ipv4Addr::new(172, 17, 0, 2)),
+            interface: None,
+            metric: Some(200),
+            attributes: RouteAttributes {
+                mtu: Some(1280),
+                advmss: Some(1240),
+                scope: Some(RouteScope::Universe),
+                onlink: Some(true),
+                prefsrc: Some(IpAddr::V4(Ipv4Addr::new(172, 17, 0, 3))),
+                protocol: Some(RouteProtocol::Boot),
+            },
+        };
+
+        let message =
+            RtNetlinkRouteManager::<MockDockerClient>::build_route_message(&route, Some(7))
+                .unwrap();
+
+        assert_eq!(message.header.destination_prefix_length, 0);
+        assert_eq!(message.header.scope, NetlinkRouteScope::Universe);
+        assert_eq!(message.header.protocol, NetlinkRouteProtocol::Boot);
+        assert!(message.header.flags.contains(RouteFlags::Onlink));
+        assert!(message.attributes.contains(&RouteAttribute::Oif(7)));
+        assert!(message.attributes.contains(&RouteAttribute::Priority(200)));
+        assert!(message.attributes.contains(&RouteAttribute::Metrics(vec![
+            RouteMetric::Mtu(1280),
+            RouteMetric::Advmss(1240),
+        ])));
+        assert!(message
+            .attributes
+            .contains(&RouteAttribute::PrefSource(RouteAddress::Inet(
+                Ipv4Addr::new(172, 17, 0, 3)
+            ))));
+
+        // Round trip back into a route entry
+        let mut names = HashMap::new();
+        names.insert(7, "eth0".to_string());
+        let converted =
+            RtNetlinkRouteManager::<MockDockerClient>::convert_route_message(&message, &names)
+                .unwrap();
+        assert_eq!(converted.interface, Some("eth0".to_string()));
+        assert_eq!(
+            converted,
+            RouteEntry {
+                interface: Some("eth0".to_string()),
+                ..route
+            }
+        );
+    }
+
+    #[test]
+    fn test_build_route_message_defaults() {
+        let route = RouteEntry {
+            destination: IpNetwork::V4 {
+                addr: Ipv4Addr::new(10, 0, 0, 0),
+                prefix: 8,
+            },
+            gateway: IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)),
+            interface: None,
+            metric: None,
+            attributes: RouteAttributes::default(),
+        };
+
+        let message =
+            RtNetlinkRouteManager::<MockDockerClient>::build_route_message(&route, None).unwrap();
+
+        assert_eq!(message.header.protocol, NetlinkRouteProtocol::Static);
+        assert!(!message.header.flags.contains(RouteFlags::Onlink));
+        assert!(!message
+            .attributes
+            .iter()
+            .any(|a| matches!(a, RouteAttribute::Metrics(_) | RouteAttribute::Oif(_))));
+    }
+
+    #[test]
+    fn test_build_route_message_version_mismatch() {
+        let route = RouteEntry {
+            destination: IpNetwork::V4 {
+                addr: Ipv4Addr::new(10, 0, 0, 0),
+                prefix: 8,
+            },
+            gateway: IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)),
+            interface: None,
+            metric: None,
+            attributes: RouteAttributes {
+                prefsrc: Some(IpAddr::V6(Ipv6Addr::LOCALHOST)),
+                ..Default::default()
+            },
+        };
+
+        let result = RtNetlinkRouteManager::<MockDockerClient>::build_route_message(&route, None);
+        assert!(matches!(result, Err(NetworkError::OperationFailed(_))));
+    }
+
+    #[test]
+    fn test_build_blackhole_message() {
+        let route = RouteEntry {
+            destination: IpNetwork::V4 {
+                addr: Ipv4Addr::new(10, 0, 0, 0),
+                prefix: 8,
+            },
+            gateway: IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)),
+            interface: Some("eth0".to_string()),
+            metric: Some(150),
+            attributes: RouteAttributes {
+                mtu: Some(1400),
+                ..Default::default()
+            },
+        };
+
+        let message =
+            RtNetlinkRouteManager::<MockDockerClient>::build_blackhole_message(&route).unwrap();
+
+        assert_eq!(message.header.kind, RouteType::BlackHole);
+        assert_eq!(message.header.destination_prefix_length, 8);
+        assert!(message.attributes.contains(&RouteAttribute::Priority(150)));
+        assert!(message
+            .attributes
+            .contains(&RouteAttribute::Destination(RouteAddress::Inet(
+                Ipv4Addr::new(10, 0, 0, 0)
+            ))));
+        assert!(!message.attributes.iter().any(|a| matches!(
+            a,
+            RouteAttribute::Gateway(_) | RouteAttribute::Metrics(_) | RouteAttribute::Oif(_)
+        )));
+    }
+
     #[tokio::test]
     async fn test_add_route_returns_error() {
         let docker_client = MockDockerClient;
-        let manager = RtNetlinkRouteManager::new(docker_client);
+        let manager = RtNetlinkRouteManager::new(Arc::new(NamespaceManager::new(docker_client)));
 
         let namespace = NetworkNamespace {
             path: "/proc/1234/ns/net".to_string(),
             container_id: "test".to_string(),
+            inode: None,
+            handle: None,
         };
 
         let route = RouteEntry {
@@ -288,6 +884,7 @@ mod tests {
             gateway: IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)),
             interface: None,
             metric: None,
+            attributes: RouteAttributes::default(),
         };
 
         // This should return an error (either namespace access or not implemented)
@@ -302,4 +899,148 @@ mod tests {
             _ => panic!("Expected AddRoute error"),
         }
     }
+
+    #[test]
+    fn test_configured_precedence_replaces_existing_routes() {
+        let manager =
+            || RtNetlinkRouteManager::new(Arc::new(NamespaceManager::new(MockDockerClient)));
+        assert!(!manager().replaces_existing_routes());
+
+        let config = AppConfig {
+            route_precedence: "override".to_string(),
+            ..Default::default()
+        };
+        let manager = manager().with_route_precedence(config.route_precedence().unwrap());
+        assert!(manager.replaces_existing_routes());
+    }
+
+    fn own_namespace() -> NetworkNamespace {
+        NetworkNamespace {
+            path: "/proc/self/ns/net".to_string(),
+            container_id: "test".to_string(),
+            inode: Some(std::fs::metadata("/proc/self/ns/net").unwrap().ino()),
+            handle: Some(Arc::new(File::open("/proc/self/ns/net").unwrap())),
+        }
+    }
+
+    fn own_connection() -> NetlinkConnection {
+        let (connection, handle, _) = new_connection().unwrap();
+        NetlinkConnection {
+            handle,
+            task: tokio::spawn(connection),
+            containers: HashSet::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_netlink_connection_serves_while_running() {
+        let connection = own_connection();
+        assert!(connection.serves());
+
+        connection.task.abort();
+        while !connection.task.is_finished() {
+            tokio::task::yield_now().await;
+        }
+        assert!(!connection.serves());
+    }
+
+    #[tokio::test]
+    async fn test_containers_sharing_namespace_share_connection() {
+        let manager = RtNetlinkRouteManager::new(Arc::new(NamespaceManager::new(MockDockerClient)));
+        let first = own_namespace();
+        let inode = first.inode.unwrap();
+        manager
+            .connections
+            .lock()
+            .unwrap()
+            .insert(inode, own_connection());
+
+        let second = NetworkNamespace {
+            container_id: "sidecar".to_string(),
+            ..own_namespace()
+        };
+        for namespace in [&first, &second] {
+            let (_, temporary) = manager.connection_handle(namespace).await.unwrap();
+            assert!(temporary.is_none());
+        }
+        assert_eq!(
+            manager.connections.lock().unwrap()[&inode].containers.len(),
+            2
+        );
+
+        // The connection stays open while a container still uses it
+        manager.release_container("test");
+        assert_eq!(manager.connections.lock().unwrap().len(), 1);
+        manager.release_container("sidecar");
+        assert!(manager.connections.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "entering a network namespace requires CAP_SYS_ADMIN"]
+    async fn test_connection_reused_until_release() {
+        let manager = RtNetlinkRouteManager::new(Arc::new(NamespaceManager::new(MockDockerClient)));
+        let namespace = own_namespace();
+
+        manager.list_routes(&namespace).await.unwrap();
+        let inode = namespace.inode.unwrap();
+        let task_id = |manager: &RtNetlinkRouteManager<MockDockerClient>| {
+            manager.connections.lock().unwrap()[&inode].task.id()
+        };
+        let first = task_id(&manager);
+
+        manager.list_routes(&namespace).await.unwrap();
+        // Removing a route that doesn't exist fails, over the same connection
+        let route = RouteEntry {
+            destination: IpNetwork::V4 {
+                addr: Ipv4Addr::new(198, 51, 100, 0),
+                prefix: 24,
+            },
+            gateway: IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+            interface: None,
+            metric: None,
+            attributes: RouteAttributes::default(),
+        };
+        assert!(manager.remove_route(&namespace, &route).await.is_err());
+        assert_eq!(manager.connections.lock().unwrap().len(), 1);
+        assert_eq!(task_id(&manager), first);
+
+        // The namespace reopened, e.g. by another container sharing it, keeps it
+        let reopened = own_namespace();
+        manager.list_routes(&reopened).await.unwrap();
+        assert_eq!(task_id(&manager), first);
+
+        manager.release_container("test");
+        assert!(manager.connections.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "entering a network namespace requires CAP_SYS_ADMIN"]
+    async fn test_connection_not_cached_without_namespace_handle() {
+        let manager = RtNetlinkRouteManager::new(Arc::new(NamespaceManager::new(MockDockerClient)));
+        let mut namespace = own_namespace();
+        namespace.handle = None;
+
+        manager.list_routes(&namespace).await.unwrap();
+        assert!(manager.connections.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_connection_not_cached_after_failure() {
+        let manager = RtNetlinkRouteManager::new(Arc::new(NamespaceManager::new(MockDockerClient)));
+        // A regular file stands in for a namespace, which can't be entered
+        let fake = tempfile::NamedTempFile::new().unwrap();
+        let namespace = NetworkNamespace {
+            path: fake.path().to_string_lossy().to_string(),
+            container_id: "test".to_string(),
+            inode: Some(1),
+            handle: Some(Arc::new(File::open(fake.path()).unwrap())),
+        };
+
+        assert!(manager.list_routes(&namespace).await.is_err());
+        assert!(manager.connections.lock().unwrap().is_empty());
+
+        // Releasing a container without a connection is a no-op
+        manager.release_container("test");
+        assert!(manager.connections.lock().unwrap().is_empty());
+    }
 }
@@ -2,11 +2,18 @@
 //!
 //! Handles routing table operations within container network namespaces
 
+use crate::config::{
+    DEFAULT_ROUTE_METRIC_DEFAULT, DEFAULT_ROUTE_METRIC_HOST, DEFAULT_ROUTE_METRIC_NETWORK,
+};
 use crate::error::RouteError;
 use crate::network::NetworkNamespace;
+use std::fmt;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
 
 pub mod manager;
+pub mod resolver;
+pub mod router;
 pub mod rules;
 
 /// Route entry structure
@@ -16,6 +23,327 @@ pub struct RouteEntry {
     pub gateway: IpAddr,
     pub interface: Option<String>,
     pub metric: Option<u32>,
+    pub attributes: RouteAttributes,
+}
+
+/// Smallest MTU the kernel accepts on an IPv4 route
+pub const MIN_ROUTE_MTU: u32 = 68;
+/// Smallest MTU the kernel accepts on an IPv6 route
+pub const MIN_ROUTE_MTU_V6: u32 = 1280;
+/// Largest MTU representable on a route
+pub const MAX_ROUTE_MTU: u32 = 65535;
+
+/// Metric Docker assigns to the routes it installs in a container
+pub const DOCKER_ROUTE_METRIC: u32 = 0;
+
+/// Decides which route wins when one of ours has the same destination as an
+/// existing kernel route (e.g. Docker's default route, which has metric 0)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RoutePrecedence {
+    /// Plain kernel semantics: the lowest metric wins and existing routes are never
+    /// replaced. Our routes must not use metric 0, which would clash with Docker's.
+    #[default]
+    Metric,
+    /// Our routes win: they replace existing routes with the same destination and
+    /// metric. Default routes must use metric 0 to replace Docker's default route.
+    Override,
+}
+
+impl FromStr for RoutePrecedence {
+    type Err = RouteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "metric" => Ok(RoutePrecedence::Metric),
+            "override" => Ok(RoutePrecedence::Override),
+            _ => Err(RouteError::InvalidRoute(format!(
+                "Invalid route precedence: '{}'. Must be one of: metric, override",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for RoutePrecedence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoutePrecedence::Metric => f.write_str("metric"),
+            RoutePrecedence::Override => f.write_str("override"),
+        }
+    }
+}
+
+/// What happens to the targets of a warp container while it's paused.
+///
+/// A paused warp keeps its network namespace and addresses, so routes through it
+/// stay in place but traffic stalls until it's unpaused.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PausedWarpPolicy {
+    /// Leave the routes through the paused warp in place
+    #[default]
+    Ignore,
+    /// Route the targets through another running warp, if one serves them
+    Failover,
+    /// Replace the targets' routes with ones dropping their traffic, rather than
+    /// letting it leak through the default route
+    KillSwitch,
+}
+
+impl FromStr for PausedWarpPolicy {
+    type Err = RouteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "ignore" => Ok(PausedWarpPolicy::Ignore),
+            "failover" => Ok(PausedWarpPolicy::Failover),
+            "kill-switch" => Ok(PausedWarpPolicy::KillSwitch),
+            _ => Err(RouteError::InvalidRoute(format!(
+                "Invalid paused warp policy: '{}'. Must be one of: ignore, failover, kill-switch",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for PausedWarpPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PausedWarpPolicy::Ignore => f.write_str("ignore"),
+            PausedWarpPolicy::Failover => f.write_str("failover"),
+            PausedWarpPolicy::KillSwitch => f.write_str("kill-switch"),
+        }
+    }
+}
+
+/// Metrics used for calculated routes, by kind of destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RouteMetrics {
+    /// Metric for network routes (e.g. 10.0.0.0/8)
+    pub network: u32,
+    /// Metric for default routes (0.0.0.0/0, ::/0)
+    pub default_route: u32,
+    /// Metric for host routes (/32, /128)
+    pub host: u32,
+}
+
+impl Default for RouteMetrics {
+    fn default() -> Self {
+        Self {
+            network: DEFAULT_ROUTE_METRIC_NETWORK,
+            default_route: DEFAULT_ROUTE_METRIC_DEFAULT,
+            host: DEFAULT_ROUTE_METRIC_HOST,
+        }
+    }
+}
+
+impl RouteMetrics {
+    /// Get the metric for a destination network
+    pub fn for_destination(&self, destination: &IpNetwork) -> u32 {
+        match destination {
+            IpNetwork::V4 { prefix: 0, .. } | IpNetwork::V6 { prefix: 0, .. } => self.default_route,
+            IpNetwork::V4 { prefix: 32, .. } | IpNetwork::V6 { prefix: 128, .. } => self.host,
+            _ => self.network,
+        }
+    }
+}
+
+/// Check a route metric against the route precedence policy
+pub fn validate_metric(
+    precedence: RoutePrecedence,
+    metric: u32,
+    is_default_route: bool,
+) -> Result<(), RouteError> {
+    match precedence {
+        RoutePrecedence::Metric if metric == DOCKER_ROUTE_METRIC => {
+            Err(RouteError::InvalidRoute(format!(
+                "Route metric {} is used by Docker's own routes; use route precedence 'override' to replace them",
+                metric
+            )))
+        }
+        RoutePrecedence::Override if is_default_route && metric != DOCKER_ROUTE_METRIC => {
+            Err(RouteError::InvalidRoute(format!(
+                "Default route metric must be {} with route precedence 'override', otherwise Docker's default route still wins (got {})",
+                DOCKER_ROUTE_METRIC, metric
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Optional kernel route attributes (`ip route ... mtu/advmss/scope/onlink/src/proto`)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RouteAttributes {
+    pub mtu: Option<u32>,
+    pub advmss: Option<u32>,
+    pub scope: Option<RouteScope>,
+    pub onlink: Option<bool>,
+    pub prefsrc: Option<IpAddr>,
+    pub protocol: Option<RouteProtocol>,
+}
+
+impl RouteAttributes {
+    /// Fill attributes that are unset here from `fallback`
+    pub fn or(self, fallback: &RouteAttributes) -> Self {
+        Self {
+            mtu: self.mtu.or(fallback.mtu),
+            advmss: self.advmss.or(fallback.advmss),
+            scope: self.scope.or(fallback.scope),
+            onlink: self.onlink.or(fallback.onlink),
+            prefsrc: self.prefsrc.or(fallback.prefsrc),
+            protocol: self.protocol.or(fallback.protocol),
+        }
+    }
+
+    /// Check whether no attribute is set
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Validate attribute values independent of the route they are applied to
+    pub fn validate(&self) -> Result<(), RouteError> {
+        if let Some(mtu) = self.mtu {
+            if !(MIN_ROUTE_MTU..=MAX_ROUTE_MTU).contains(&mtu) {
+                return Err(RouteError::InvalidRoute(format!(
+                    "Route MTU {} is out of range {}-{}",
+                    mtu, MIN_ROUTE_MTU, MAX_ROUTE_MTU
+                )));
+            }
+        }
+
+        if let Some(advmss) = self.advmss {
+            if advmss == 0 || advmss >= self.mtu.unwrap_or(MAX_ROUTE_MTU) {
+                return Err(RouteError::InvalidRoute(format!(
+                    "Route advmss {} must be non-zero and smaller than the MTU",
+                    advmss
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Set a single attribute from its `ip route` keyword and string value
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), RouteError> {
+        let value = value.trim();
+        match key.trim().to_lowercase().as_str() {
+            "mtu" => self.mtu = Some(parse_u32_attribute(key, value)?),
+            "advmss" => self.advmss = Some(parse_u32_attribute(key, value)?),
+            "scope" => self.scope = Some(value.parse()?),
+            "onlink" => {
+                self.onlink = Some(match value.to_lowercase().as_str() {
+                    "" | "true" | "yes" | "1" => true,
+                    "false" | "no" | "0" => false,
+                    _ => {
+                        return Err(RouteError::InvalidRoute(format!(
+                            "Invalid onlink value: '{}'. Must be true or false",
+                            value
+                        )))
+                    }
+                })
+            }
+            "prefsrc" | "src" => {
+                self.prefsrc = Some(IpAddr::from_str(value).map_err(|_| {
+                    RouteError::InvalidRoute(format!(
+                        "Invalid preferred source address: '{}'",
+                        value
+                    ))
+                })?)
+            }
+            "protocol" | "proto" => self.protocol = Some(value.parse()?),
+            _ => {
+                return Err(RouteError::InvalidRoute(format!(
+                    "Unknown route attribute: '{}'",
+                    key
+                )))
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_u32_attribute(key: &str, value: &str) -> Result<u32, RouteError> {
+    value
+        .parse::<u32>()
+        .map_err(|_| RouteError::InvalidRoute(format!("Invalid {} value: '{}'", key.trim(), value)))
+}
+
+/// Route scope, as accepted by `ip route ... scope`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteScope {
+    Universe,
+    Site,
+    Link,
+    Host,
+}
+
+impl FromStr for RouteScope {
+    type Err = RouteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "universe" | "global" => Ok(RouteScope::Universe),
+            "site" => Ok(RouteScope::Site),
+            "link" => Ok(RouteScope::Link),
+            "host" => Ok(RouteScope::Host),
+            _ => Err(RouteError::InvalidRoute(format!(
+                "Invalid route scope: '{}'. Must be one of: universe, site, link, host",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for RouteScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RouteScope::Universe => "universe",
+            RouteScope::Site => "site",
+            RouteScope::Link => "link",
+            RouteScope::Host => "host",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Route protocol (origin), as accepted by `ip route ... proto`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteProtocol {
+    Boot,
+    Static,
+    Kernel,
+    Dhcp,
+    Other(u8),
+}
+
+impl FromStr for RouteProtocol {
+    type Err = RouteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "boot" => Ok(RouteProtocol::Boot),
+            "static" => Ok(RouteProtocol::Static),
+            "kernel" => Ok(RouteProtocol::Kernel),
+            "dhcp" => Ok(RouteProtocol::Dhcp),
+            other => other.parse::<u8>().map(RouteProtocol::Other).map_err(|_| {
+                RouteError::InvalidRoute(format!(
+                    "Invalid route protocol: '{}'. Must be one of: boot, static, kernel, dhcp or a number",
+                    s
+                ))
+            }),
+        }
+    }
+}
+
+impl fmt::Display for RouteProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RouteProtocol::Boot => f.write_str("boot"),
+            RouteProtocol::Static => f.write_str("static"),
+            RouteProtocol::Kernel => f.write_str("kernel"),
+            RouteProtocol::Dhcp => f.write_str("dhcp"),
+            RouteProtocol::Other(value) => write!(f, "{}", value),
+        }
+    }
 }
 
 /// IP network representation
@@ -69,4 +397,23 @@ pub trait RouteManager {
         &self,
         namespace: &NetworkNamespace,
     ) -> impl std::future::Future<Output = Result<Vec<RouteEntry>, RouteError>> + Send;
+
+    /// Install a blackhole route to a route entry's destination, with its metric,
+    /// dropping the traffic to it. An existing route with the same destination and
+    /// metric is replaced.
+    fn add_blackhole_route(
+        &self,
+        namespace: &NetworkNamespace,
+        route: &RouteEntry,
+    ) -> impl std::future::Future<Output = Result<(), RouteError>> + Send;
+
+    /// Remove a blackhole route installed by [`Self::add_blackhole_route`]
+    fn remove_blackhole_route(
+        &self,
+        namespace: &NetworkNamespace,
+        route: &RouteEntry,
+    ) -> impl std::future::Future<Output = Result<(), RouteError>> + Send;
+
+    /// Close what is held open for a container, e.g. after it stopped
+    fn release_container(&self, container_id: &str);
 }
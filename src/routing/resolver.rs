@@ -0,0 +1,517 @@
+//! Hostname resolution for hostname-based routing rules
+//!
+//! Rules whose destination is a hostname are turned into host routes for the
+//! addresses the hostname resolves to. Answers are refreshed when their TTL
+//! expires if the resolver reports one, and at a fixed interval otherwise, as
+//! with the system resolver. A bounded history of recent answers keeps addresses
+//! routed while they keep showing up, so round-robin DNS doesn't cause route churn.
+
+use crate::error::RouteError;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::future::Future;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// Shortest refresh interval, so that a zero TTL doesn't cause a busy loop
+pub const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Check whether a routing rule destination is a hostname rather than a CIDR
+pub fn is_hostname(destination: &str) -> bool {
+    let name = destination.strip_suffix('.').unwrap_or(destination);
+
+    if name.is_empty() || name.len() > 253 || name.parse::<IpAddr>().is_ok() {
+        return false;
+    }
+
+    let valid_labels = name.split('.').all(|label| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    });
+
+    // A numeric top-level label means a mistyped address (e.g. "192.168.1"), not a name
+    let numeric_tld = name
+        .rsplit('.')
+        .next()
+        .is_some_and(|label| label.chars().all(|c| c.is_ascii_digit()));
+
+    valid_labels && !numeric_tld
+}
+
+/// Answer of a hostname lookup
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedHost {
+    pub addresses: Vec<IpAddr>,
+    /// Time to live of the answer, if the resolver knows it
+    pub ttl: Option<Duration>,
+}
+
+/// Hostname resolver trait
+pub trait HostnameResolver {
+    /// Resolve a hostname to its addresses
+    fn resolve(
+        &self,
+        hostname: &str,
+    ) -> impl Future<Output = Result<ResolvedHost, RouteError>> + Send;
+}
+
+/// Resolver using the system's name service (`getaddrinfo`).
+///
+/// The system resolver doesn't report TTLs, so answers are refreshed at the
+/// tracker's default interval, the configured `hostname_refresh_interval`.
+#[derive(Debug, Clone, Default)]
+pub struct SystemResolver;
+
+impl HostnameResolver for SystemResolver {
+    async fn resolve(&self, hostname: &str) -> Result<ResolvedHost, RouteError> {
+        let addresses = tokio::net::lookup_host((hostname, 0))
+            .await
+            .map_err(|e| RouteError::Resolution(format!("{}: {}", hostname, e)))?
+            .map(|addr| addr.ip())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+
+        Ok(ResolvedHost {
+            addresses,
+            ttl: None,
+        })
+    }
+}
+
+/// Addresses to add or remove host routes for after a refresh
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AddressChanges {
+    pub added: Vec<IpAddr>,
+    pub removed: Vec<IpAddr>,
+}
+
+impl AddressChanges {
+    /// Check whether the refresh changed nothing
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Applies the address changes of a refreshed hostname to its host routes
+pub trait HostRouteUpdater {
+    fn update_host_routes(
+        &self,
+        hostname: &str,
+        changes: &AddressChanges,
+    ) -> impl Future<Output = Result<(), RouteError>> + Send;
+}
+
+/// Resolution state of a tracked hostname
+#[derive(Debug)]
+struct HostState {
+    /// Most recent answers, newest last
+    history: VecDeque<BTreeSet<IpAddr>>,
+    /// Addresses that currently have host routes
+    active: BTreeSet<IpAddr>,
+    refresh_at: Instant,
+}
+
+/// Tracks the addresses of hostname routing rules.
+///
+/// An address is routed as soon as it appears in an answer and stays routed
+/// until it is missing from all of the last `history_size` answers.
+pub struct HostnameTracker<R: HostnameResolver> {
+    resolver: R,
+    default_ttl: Duration,
+    history_size: usize,
+    hosts: HashMap<String, HostState>,
+}
+
+impl<R: HostnameResolver> HostnameTracker<R> {
+    /// Create a new tracker
+    pub fn new(resolver: R, default_ttl: Duration, history_size: usize) -> Self {
+        Self {
+            resolver,
+            default_ttl: default_ttl.max(MIN_REFRESH_INTERVAL),
+            history_size: history_size.max(1),
+            hosts: HashMap::new(),
+        }
+    }
+
+    /// Start tracking a hostname; it is resolved on the next refresh
+    pub fn track(&mut self, hostname: &str, now: Instant) {
+        self.hosts
+            .entry(hostname.to_string())
+            .or_insert_with(|| HostState {
+                history: VecDeque::new(),
+                active: BTreeSet::new(),
+                refresh_at: now,
+            });
+    }
+
+    /// Stop tracking a hostname, returning the addresses whose routes should be removed
+    pub fn forget(&mut self, hostname: &str) -> Vec<IpAddr> {
+        self.hosts
+            .remove(hostname)
+            .map(|state| state.active.into_iter().collect())
+            .unwrap_or_default()
+    }
+
+    /// Get the addresses currently routed for a hostname
+    pub fn addresses(&self, hostname: &str) -> Vec<IpAddr> {
+        self.hosts
+            .get(hostname)
+            .map(|state| state.active.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Get the earliest time a tracked hostname needs to be refreshed
+    pub fn next_refresh(&self) -> Option<Instant> {
+        self.hosts.values().map(|state| state.refresh_at).min()
+    }
+
+    /// Resolve a hostname again and compute the resulting address changes.
+    ///
+    /// On resolution failure the current addresses are kept and the hostname
+    /// is retried after the default TTL.
+    pub async fn refresh(
+        &mut self,
+        hostname: &str,
+        now: Instant,
+    ) -> Result<AddressChanges, RouteError> {
+        self.track(hostname, now);
+        let result = self.resolver.resolve(hostname).await;
+
+        let state = self
+            .hosts
+            .get_mut(hostname)
+            .expect("hostname is tracked above");
+
+        let answer = match result {
+            Ok(answer) => answer,
+            Err(e) => {
+                state.refresh_at = now + self.default_ttl;
+                return Err(e);
+            }
+        };
+
+        let ttl = answer
+            .ttl
+            .unwrap_or(self.default_ttl)
+            .max(MIN_REFRESH_INTERVAL);
+        state.refresh_at = now + ttl;
+
+        state
+            .history
+            .push_back(answer.addresses.into_iter().collect());
+        while state.history.len() > self.history_size {
+            state.history.pop_front();
+        }
+
+        let current: BTreeSet<IpAddr> = state.history.iter().flatten().copied().collect();
+        let changes = AddressChanges {
+            added: current.difference(&state.active).copied().collect(),
+            removed: state.active.difference(&current).copied().collect(),
+        };
+        state.active = current;
+
+        Ok(changes)
+    }
+
+    /// Refresh all hostnames whose TTL has expired
+    pub async fn refresh_expired(
+        &mut self,
+        now: Instant,
+    ) -> Vec<(String, Result<AddressChanges, RouteError>)> {
+        let expired: Vec<String> = self
+            .hosts
+            .iter()
+            .filter(|(_, state)| state.refresh_at <= now)
+            .map(|(hostname, _)| hostname.clone())
+            .collect();
+
+        let mut results = Vec::with_capacity(expired.len());
+        for hostname in expired {
+            let result = self.refresh(&hostname, now).await;
+            results.push((hostname, result));
+        }
+        results
+    }
+
+    /// Refresh hostnames as their TTLs expire, handing changed addresses to the updater.
+    ///
+    /// Runs until no hostname is tracked. Resolution and update failures are
+    /// logged; the hostname is looked up again at its next refresh.
+    pub async fn run_refresh<U: HostRouteUpdater>(&mut self, updater: &U) {
+        while let Some(refresh_at) = self.next_refresh() {
+            tokio::time::sleep_until(refresh_at.into()).await;
+
+            let now = tokio::time::Instant::now().into_std();
+            for (hostname, result) in self.refresh_expired(now).await {
+                let changes = match result {
+                    Ok(changes) if changes.is_empty() => continue,
+                    Ok(changes) => changes,
+                    Err(e) => {
+                        warn!("Keeping current addresses of {}: {}", hostname, e);
+                        continue;
+                    }
+                };
+
+                debug!(
+                    "Addresses of {} changed: {} added, {} removed",
+                    hostname,
+                    changes.added.len(),
+                    changes.removed.len()
+                );
+                if let Err(e) = updater.update_host_routes(&hostname, &changes).await {
+                    warn!("Failed to update host routes of {}: {}", hostname, e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use std::sync::{Arc, Mutex};
+
+    /// Resolver returning scripted answers, one per lookup
+    #[derive(Clone, Default)]
+    struct MockResolver {
+        answers: Arc<Mutex<VecDeque<Result<ResolvedHost, String>>>>,
+        lookups: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl MockResolver {
+        fn push_answer(&self, addresses: &[IpAddr], ttl: Option<u64>) {
+            self.answers.lock().unwrap().push_back(Ok(ResolvedHost {
+                addresses: addresses.to_vec(),
+                ttl: ttl.map(Duration::from_secs),
+            }));
+        }
+
+        fn push_error(&self, message: &str) {
+            self.answers
+                .lock()
+                .unwrap()
+                .push_back(Err(message.to_string()));
+        }
+    }
+
+    impl HostnameResolver for MockResolver {
+        async fn resolve(&self, hostname: &str) -> Result<ResolvedHost, RouteError> {
+            self.lookups.lock().unwrap().push(hostname.to_string());
+            self.answers
+                .lock()
+                .unwrap()
+                .pop_front()
+                .unwrap_or_else(|| Err("no answer".to_string()))
+                .map_err(RouteError::Resolution)
+        }
+    }
+
+    /// Updater recording the changes it is handed
+    #[derive(Default)]
+    struct RecordingUpdater {
+        updates: Mutex<Vec<(String, AddressChanges)>>,
+    }
+
+    impl HostRouteUpdater for RecordingUpdater {
+        async fn update_host_routes(
+            &self,
+            hostname: &str,
+            changes: &AddressChanges,
+        ) -> Result<(), RouteError> {
+            self.updates
+                .lock()
+                .unwrap()
+                .push((hostname.to_string(), changes.clone()));
+            Ok(())
+        }
+    }
+
+    fn ip(last: u8) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::new(203, 0, 113, last))
+    }
+
+    #[test]
+    fn test_is_hostname() {
+        assert!(is_hostname("api.example.com"));
+        assert!(is_hostname("api.example.com."));
+        assert!(is_hostname("localhost"));
+        assert!(is_hostname("my-service.internal"));
+
+        assert!(!is_hostname(""));
+        assert!(!is_hostname("10.0.0.0/8"));
+        assert!(!is_hostname("10.0.0.1"));
+        assert!(!is_hostname("::1"));
+        assert!(!is_hostname("-bad.example.com"));
+        assert!(!is_hostname("bad..example.com"));
+        assert!(!is_hostname("under_score.example.com"));
+        assert!(!is_hostname(&"a".repeat(64)));
+    }
+
+    #[test]
+    fn test_is_hostname_rejects_numeric_names() {
+        assert!(!is_hostname("10"));
+        assert!(!is_hostname("192.168.1"));
+        assert!(!is_hostname("10.0.0.1."));
+        assert!(!is_hostname("host.123"));
+
+        // Digits are fine anywhere but in the top-level label
+        assert!(is_hostname("1password.com"));
+        assert!(is_hostname("10.example.com"));
+        assert!(is_hostname("host.x1"));
+    }
+
+    #[tokio::test]
+    async fn test_refresh_adds_and_removes_addresses() {
+        let resolver = MockResolver::default();
+        let mut tracker = HostnameTracker::new(resolver.clone(), Duration::from_secs(60), 1);
+        let now = Instant::now();
+
+        resolver.push_answer(&[ip(1), ip(2)], Some(30));
+        let changes = tracker.refresh("api.example.com", now).await.unwrap();
+        assert_eq!(changes.added, vec![ip(1), ip(2)]);
+        assert!(changes.removed.is_empty());
+        assert_eq!(tracker.next_refresh(), Some(now + Duration::from_secs(30)));
+
+        resolver.push_answer(&[ip(2), ip(3)], None);
+        let changes = tracker.refresh("api.example.com", now).await.unwrap();
+        assert_eq!(changes.added, vec![ip(3)]);
+        assert_eq!(changes.removed, vec![ip(1)]);
+        assert_eq!(tracker.addresses("api.example.com"), vec![ip(2), ip(3)]);
+        // Without a TTL in the answer the default is used
+        assert_eq!(tracker.next_refresh(), Some(now + Duration::from_secs(60)));
+
+        assert_eq!(tracker.forget("api.example.com"), vec![ip(2), ip(3)]);
+        assert!(tracker.addresses("api.example.com").is_empty());
+        assert_eq!(tracker.next_refresh(), None);
+    }
+
+    #[tokio::test]
+    async fn test_history_dampens_flapping_answers() {
+        let resolver = MockResolver::default();
+        let mut tracker = HostnameTracker::new(resolver.clone(), Duration::from_secs(60), 3);
+        let now = Instant::now();
+
+        resolver.push_answer(&[ip(1)], None);
+        resolver.push_answer(&[ip(2)], None);
+        resolver.push_answer(&[ip(1)], None);
+        resolver.push_answer(&[ip(2)], None);
+
+        let changes = tracker.refresh("cdn.example.com", now).await.unwrap();
+        assert_eq!(changes.added, vec![ip(1)]);
+        let changes = tracker.refresh("cdn.example.com", now).await.unwrap();
+        assert_eq!(changes.added, vec![ip(2)]);
+        assert!(changes.removed.is_empty());
+
+        // Alternating answers don't cause any further changes
+        for _ in 0..2 {
+            let changes = tracker.refresh("cdn.example.com", now).await.unwrap();
+            assert!(changes.is_empty());
+        }
+
+        // An address is removed once it's missing from the whole history,
+        // which now holds [2], [1], [2]
+        resolver.push_answer(&[ip(2)], None);
+        let changes = tracker.refresh("cdn.example.com", now).await.unwrap();
+        assert!(changes.is_empty());
+        resolver.push_answer(&[ip(2)], None);
+        let changes = tracker.refresh("cdn.example.com", now).await.unwrap();
+        assert_eq!(changes.removed, vec![ip(1)]);
+        assert_eq!(tracker.addresses("cdn.example.com"), vec![ip(2)]);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_expired() {
+        let resolver = MockResolver::default();
+        let mut tracker = HostnameTracker::new(resolver.clone(), Duration::from_secs(60), 1);
+        let now = Instant::now();
+
+        tracker.track("a.example.com", now);
+        resolver.push_answer(&[ip(1)], Some(10));
+        let results = tracker.refresh_expired(now).await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, "a.example.com");
+
+        // Not expired yet
+        let results = tracker.refresh_expired(now + Duration::from_secs(5)).await;
+        assert!(results.is_empty());
+
+        // Resolution failures keep the current addresses
+        resolver.push_error("SERVFAIL");
+        let later = now + Duration::from_secs(10);
+        let results = tracker.refresh_expired(later).await;
+        assert!(results[0].1.is_err());
+        assert_eq!(tracker.addresses("a.example.com"), vec![ip(1)]);
+        assert_eq!(
+            tracker.next_refresh(),
+            Some(later + Duration::from_secs(60))
+        );
+
+        assert_eq!(resolver.lookups.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_zero_ttl_is_clamped() {
+        let resolver = MockResolver::default();
+        let mut tracker = HostnameTracker::new(resolver.clone(), Duration::from_secs(60), 1);
+        let now = Instant::now();
+
+        let v6 = IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1));
+        resolver.push_answer(&[v6], Some(0));
+        tracker.refresh("v6.example.com", now).await.unwrap();
+        assert_eq!(tracker.next_refresh(), Some(now + MIN_REFRESH_INTERVAL));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_run_refresh_applies_changes_on_ttl_expiry() {
+        let resolver = MockResolver::default();
+        let mut tracker = HostnameTracker::new(resolver.clone(), Duration::from_secs(60), 1);
+        let updater = RecordingUpdater::default();
+        let start = tokio::time::Instant::now();
+        tracker.track("api.example.com", start.into_std());
+
+        resolver.push_answer(&[ip(1)], Some(30));
+        resolver.push_answer(&[ip(1)], Some(30));
+        resolver.push_error("SERVFAIL");
+        resolver.push_answer(&[ip(2)], Some(30));
+
+        // Stop before the next lookup at 150s
+        let run = tracker.run_refresh(&updater);
+        let _ = tokio::time::timeout(Duration::from_secs(140), run).await;
+
+        // Unchanged answers and failed lookups aren't handed to the updater
+        let updates = updater.updates.lock().unwrap().clone();
+        assert_eq!(
+            updates,
+            vec![
+                (
+                    "api.example.com".to_string(),
+                    AddressChanges {
+                        added: vec![ip(1)],
+                        removed: vec![],
+                    }
+                ),
+                (
+                    "api.example.com".to_string(),
+                    AddressChanges {
+                        added: vec![ip(2)],
+                        removed: vec![ip(1)],
+                    }
+                ),
+            ]
+        );
+        // Lookups at 0s and 30s, the failure at 60s retried after the default TTL at 120s
+        assert_eq!(resolver.lookups.lock().unwrap().len(), 4);
+        assert_eq!(tracker.addresses("api.example.com"), vec![ip(2)]);
+    }
+
+    #[tokio::test]
+    async fn test_run_refresh_returns_without_hostnames() {
+        let mut tracker = HostnameTracker::new(MockResolver::default(), Duration::from_secs(60), 1);
+        tracker.run_refresh(&RecordingUpdater::default()).await;
+    }
+}
@@ -0,0 +1,1617 @@
+//! Programming of target routes over rtnetlink
+//!
+//! [`NetlinkTargetRouter`] is the [`TargetRouter`] driven by the reconciler. It
+//! routes the configured destinations of a target through the warp's address on
+//! a network they share, in the target's namespace. Targets sharing a namespace
+//! are programmed once, as recorded in a [`NamespaceRegistry`], and the routes
+//! are removed with the last of them. With warp forwarding enabled, the warp is
+//! set up to forward and masquerade the traffic of the targets routed through it.
+//!
+//! Blocked targets get blackholes with Docker's route metric, replacing its
+//! routes to the same destinations, which are put back once unblocked.
+//!
+//! Hostname rules are routed as host routes to the addresses their hostnames
+//! resolve to. As a [`HostRouteUpdater`], the router adds and removes these
+//! routes in the namespaces it programmed whenever the addresses change.
+
+use crate::config::RoutingRule;
+use crate::docker::classifier::{TargetContainerInfo, WarpContainerInfo};
+use crate::docker::reconciler::TargetRouter;
+use crate::docker::{DockerClient, NetworkInfo};
+use crate::error::{HandlerError, NetworkError, RouteError};
+use crate::network::forwarding::ForwardingManager;
+use crate::network::namespace::NamespaceManager;
+use crate::network::registry::{Association, NamespaceRegistry};
+use crate::network::{NetworkManager, NetworkNamespace};
+use crate::routing::manager::RtNetlinkRouteManager;
+use crate::routing::resolver::{AddressChanges, HostRouteUpdater};
+use crate::routing::rules::RoutingRuleCalculator;
+use crate::routing::{
+    RouteAttributes, RouteEntry, RouteManager, RouteMetrics, RoutePrecedence, DOCKER_ROUTE_METRIC,
+};
+use ipnetwork::IpNetwork as ExternalIpNetwork;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Display;
+use std::future::Future;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Mutex as AsyncMutex;
+use tracing::{debug, info, warn};
+
+/// Metric of kill-switch blackholes. It is Docker's own, so that blackholes
+/// replace Docker's routes to the same destinations instead of losing to them.
+const BLACKHOLE_ROUTE_METRIC: u32 = DOCKER_ROUTE_METRIC;
+
+/// What the routes of a target are calculated from, besides the rules
+#[derive(Debug, Clone)]
+struct RouteBasis {
+    /// Warp address the routes go through, or unspecified addresses of both
+    /// families for a target blocked before being routed
+    gateways: Vec<IpAddr>,
+    target_metric: Option<u32>,
+    warp_attributes: RouteAttributes,
+}
+
+impl RouteBasis {
+    fn through(gateway: IpAddr, target: &TargetContainerInfo, warp: &WarpContainerInfo) -> Self {
+        Self {
+            gateways: vec![gateway],
+            target_metric: target.route_metric,
+            warp_attributes: warp.route_attributes.clone(),
+        }
+    }
+
+    /// Basis of the routes to block for a target that was never routed. Only
+    /// their destinations and metrics matter, so the gateways are placeholders.
+    fn blocked() -> Self {
+        Self {
+            gateways: vec![
+                IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+                IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            ],
+            target_metric: None,
+            warp_attributes: RouteAttributes::default(),
+        }
+    }
+}
+
+/// A target whose namespace carries warp routes or blackholes
+#[derive(Debug)]
+struct RoutedTarget {
+    namespace: NetworkNamespace,
+    /// Warp the target is routed through; none if it was blocked before being routed
+    warp_id: Option<String>,
+    /// Inode of the warp's namespace when the target was routed; a restarted warp
+    /// keeping its address has a new one
+    warp_inode: Option<u64>,
+    /// Routes the target asked for, which another target may have programmed
+    routes: Vec<RouteEntry>,
+    basis: RouteBasis,
+    /// Whether routing the target completed, rather than failing or being interrupted
+    complete: bool,
+}
+
+/// Routes installed in a target namespace, shared by the targets in it
+#[derive(Debug)]
+struct ProgrammedRoutes {
+    namespace: NetworkNamespace,
+    routes: Vec<RouteEntry>,
+    /// Whether blackholes stand in for the routes
+    blocked: bool,
+    /// Existing routes the routes or blackholes replaced, such as Docker's default
+    /// route, restored when they are removed
+    displaced: Vec<RouteEntry>,
+    /// Basis of the routes, from the target that programmed them
+    basis: RouteBasis,
+}
+
+/// Routing state, by target container ID and by namespace inode
+#[derive(Debug, Default)]
+struct RouterState {
+    registry: NamespaceRegistry,
+    targets: HashMap<String, RoutedTarget>,
+    programmed: HashMap<u64, ProgrammedRoutes>,
+    /// Current addresses of the hostnames of hostname rules
+    hostname_addresses: HashMap<String, BTreeSet<IpAddr>>,
+}
+
+/// Target router installing routes in container namespaces over rtnetlink
+pub struct NetlinkTargetRouter<D: DockerClient, R = RtNetlinkRouteManager<D>> {
+    namespaces: Arc<NamespaceManager<D>>,
+    route_manager: Arc<R>,
+    calculator: RoutingRuleCalculator,
+    rules: Vec<RoutingRule>,
+    forwarding: Option<AsyncMutex<ForwardingManager<D>>>,
+    state: Mutex<RouterState>,
+}
+
+impl<D, R> NetlinkTargetRouter<D, R>
+where
+    D: DockerClient + Send + Sync + 'static,
+    R: RouteManager + Send + Sync + 'static,
+{
+    /// Create a router routing the destinations of the given rules
+    pub fn new(
+        namespaces: Arc<NamespaceManager<D>>,
+        route_manager: Arc<R>,
+        rules: Vec<RoutingRule>,
+    ) -> Self {
+        Self {
+            namespaces,
+            route_manager,
+            calculator: RoutingRuleCalculator::new(),
+            rules,
+            forwarding: None,
+            state: Mutex::new(RouterState::default()),
+        }
+    }
+
+    /// Set the global route metrics and the precedence policy they are validated
+    /// against, which should match the route manager's
+    pub fn with_metric_policy(
+        mut self,
+        metrics: RouteMetrics,
+        precedence: RoutePrecedence,
+    ) -> Self {
+        self.calculator = self.calculator.with_metric_policy(metrics, precedence);
+        self
+    }
+
+    /// Set up forwarding and masquerading in the warps targets are routed through
+    pub fn with_forwarding(mut self, forwarding: ForwardingManager<D>) -> Self {
+        self.forwarding = Some(AsyncMutex::new(forwarding));
+        self
+    }
+
+    /// Find the warp's address on a network it shares with the target, and the
+    /// target's subnet on that network
+    fn shared_network(
+        &self,
+        target: &TargetContainerInfo,
+        warp: &WarpContainerInfo,
+    ) -> Result<(IpAddr, ExternalIpNetwork), NetworkError> {
+        let shared: Vec<NetworkInfo> = warp
+            .container
+            .networks
+            .iter()
+            .filter(|network| {
+                target
+                    .container
+                    .networks
+                    .iter()
+                    .any(|own| own.name == network.name)
+            })
+            .cloned()
+            .collect();
+        if shared.is_empty() {
+            return Err(NetworkError::OperationFailed(format!(
+                "Target {} shares no network with warp {}",
+                target.container.name, warp.container.name
+            )));
+        }
+
+        let network = self
+            .namespaces
+            .select_network_by_preference(&shared, warp.target_network.as_deref())?;
+        let subnet = target
+            .container
+            .networks
+            .iter()
+            .find(|own| own.name == network.name)
+            .map(|own| own.subnet)
+            .expect("shared networks are networks of the target");
+        Ok((network.ip_address, subnet))
+    }
+
+    /// Calculate the routes of a target from a basis.
+    ///
+    /// Rules for the other IP family than a gateway's are skipped. Hostname rules
+    /// get host routes to the addresses their hostnames currently resolve to.
+    fn calculate_routes(
+        &self,
+        basis: &RouteBasis,
+        hostname_addresses: &HashMap<String, BTreeSet<IpAddr>>,
+    ) -> Result<Vec<RouteEntry>, RouteError> {
+        let mut routes = Vec::new();
+        for rule in &self.rules {
+            if let Some(hostname) = rule.hostname() {
+                let Some(addresses) = hostname_addresses.get(hostname) else {
+                    continue;
+                };
+                let addresses: Vec<IpAddr> = addresses.iter().copied().collect();
+                for &gateway in &basis.gateways {
+                    routes.extend(self.calculator.calculate_hostname_routes(
+                        rule,
+                        &addresses,
+                        gateway,
+                        None,
+                        basis.target_metric,
+                        &basis.warp_attributes,
+                    )?);
+                }
+                continue;
+            }
+
+            let ipv4 = rule_is_ipv4(rule)?;
+            for &gateway in basis.gateways.iter().filter(|g| g.is_ipv4() == ipv4) {
+                routes.extend(self.calculator.calculate_rule_routes(
+                    rule,
+                    gateway,
+                    None,
+                    basis.target_metric,
+                    &basis.warp_attributes,
+                )?);
+            }
+        }
+        Ok(routes)
+    }
+
+    /// Calculate the routes of a target from a basis with the current hostname addresses
+    fn desired_routes(&self, basis: &RouteBasis) -> Result<Vec<RouteEntry>, RouteError> {
+        let state = self.state.lock().expect("router state poisoned");
+        self.calculate_routes(basis, &state.hostname_addresses)
+    }
+
+    /// Check whether a target is routed through a warp with the given routes
+    fn is_routed(
+        &self,
+        target_id: &str,
+        namespace: &NetworkNamespace,
+        warp_namespace: &NetworkNamespace,
+        routes: &[RouteEntry],
+    ) -> bool {
+        let state = self.state.lock().expect("router state poisoned");
+        let Some(current) = state.targets.get(target_id) else {
+            return false;
+        };
+        current.complete
+            && current.namespace.inode == namespace.inode
+            && current.warp_id.as_deref() == Some(warp_namespace.container_id.as_str())
+            && current.warp_inode == warp_namespace.inode
+            && current.routes == routes
+            && namespace
+                .inode
+                .and_then(|inode| state.programmed.get(&inode))
+                .is_some_and(|programmed| !programmed.blocked)
+    }
+
+    async fn route_target(
+        &self,
+        target: TargetContainerInfo,
+        warp: WarpContainerInfo,
+    ) -> Result<(), HandlerError> {
+        let target_id = target.container.id.clone();
+        let target_namespace = self.namespace(&target_id).await?;
+        let warp_namespace = self.namespace(&warp.container.id).await?;
+        let (gateway, subnet) = self.shared_network(&target, &warp).map_err(|e| {
+            failed(
+                &format!("No gateway for target {}", target.container.name),
+                e,
+            )
+        })?;
+        let basis = RouteBasis::through(gateway, &target, &warp);
+        let routes = self.desired_routes(&basis).map_err(|e| {
+            failed(
+                &format!("Invalid routes for target {}", target.container.name),
+                e,
+            )
+        })?;
+
+        let result = self
+            .route_through(
+                &target_id,
+                &target_namespace,
+                &warp_namespace,
+                subnet,
+                routes,
+                basis,
+            )
+            .await;
+        match result {
+            Ok(true) => {
+                info!(
+                    "Routed target {} through warp {} via {}",
+                    target.container.name, warp.container.name, gateway
+                );
+                Ok(())
+            }
+            Ok(false) => {
+                debug!(
+                    "Target {} is already routed through warp {}",
+                    target.container.name, warp.container.name
+                );
+                Ok(())
+            }
+            Err(e) => Err(failed(
+                &format!(
+                    "Failed to route target {} through warp {}",
+                    target.container.name, warp.container.name
+                ),
+                e,
+            )),
+        }
+    }
+
+    /// Route a target through a warp once their namespaces and the routes are known.
+    /// Returns whether anything changed, i.e. the target wasn't routed so already.
+    ///
+    /// Routing may be interrupted, e.g. by a timeout. Routes are recorded before they
+    /// are added and the target only counts as routed once done, so the next attempt
+    /// undoes what an interrupted one left behind.
+    async fn route_through(
+        &self,
+        target_id: &str,
+        target_namespace: &NetworkNamespace,
+        warp_namespace: &NetworkNamespace,
+        subnet: ExternalIpNetwork,
+        routes: Vec<RouteEntry>,
+        basis: RouteBasis,
+    ) -> Result<bool, String> {
+        if self.is_routed(target_id, target_namespace, warp_namespace, &routes) {
+            return Ok(false);
+        }
+
+        // Routes through another warp, a previous namespace or blackholes go first
+        self.release_restarted_warp(warp_namespace).await;
+        self.unroute_target(target_id).await;
+
+        let association = {
+            let mut state = self.state.lock().expect("router state poisoned");
+            let association = state
+                .registry
+                .associate(target_namespace, warp_namespace)
+                .map_err(|e| e.to_string())?;
+            state.targets.insert(
+                target_id.to_string(),
+                RoutedTarget {
+                    namespace: target_namespace.clone(),
+                    warp_id: Some(warp_namespace.container_id.clone()),
+                    warp_inode: warp_namespace.inode,
+                    routes: routes.clone(),
+                    basis,
+                    complete: false,
+                },
+            );
+            association
+        };
+        if let Association::AlreadyProgrammed { ref programmed_by } = association {
+            if self.differs_from_programmed(target_namespace, &routes) {
+                warn!(
+                    "Target {} shares its network namespace with {}, whose routes apply instead of \
+                     the ones its own labels ask for, e.g. another metric or route attributes",
+                    target_id, programmed_by
+                );
+            }
+        }
+
+        let result = self
+            .program(
+                target_id,
+                target_namespace,
+                warp_namespace,
+                subnet,
+                routes,
+                association,
+            )
+            .await;
+        if let Err(e) = result {
+            self.unroute_target(target_id).await;
+            return Err(e);
+        }
+
+        let mut state = self.state.lock().expect("router state poisoned");
+        if let Some(target) = state.targets.get_mut(target_id) {
+            target.complete = true;
+        }
+        Ok(true)
+    }
+
+    /// Check whether routes differ from the ones programmed in a namespace
+    fn differs_from_programmed(&self, namespace: &NetworkNamespace, routes: &[RouteEntry]) -> bool {
+        let state = self.state.lock().expect("router state poisoned");
+        namespace
+            .inode
+            .and_then(|inode| state.programmed.get(&inode))
+            .is_some_and(|programmed| programmed.routes != routes)
+    }
+
+    /// Set up forwarding for a target and install its routes, unless another
+    /// target sharing its namespace did already
+    async fn program(
+        &self,
+        target_id: &str,
+        target_namespace: &NetworkNamespace,
+        warp_namespace: &NetworkNamespace,
+        subnet: ExternalIpNetwork,
+        routes: Vec<RouteEntry>,
+        association: Association,
+    ) -> Result<(), String> {
+        if let Some(ref forwarding) = self.forwarding {
+            forwarding
+                .lock()
+                .await
+                .attach_target(warp_namespace, target_id, &[subnet])
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+
+        if let Association::AlreadyProgrammed { programmed_by } = association {
+            debug!(
+                "Namespace of target {} is already routed by {}",
+                target_id, programmed_by
+            );
+            return Ok(());
+        }
+
+        // With route precedence overriding, the routes replace Docker's with the same
+        // metric, which are put back once unrouted
+        let displaced = self.displaced_routes(target_namespace, &routes).await;
+        let inode = target_namespace
+            .inode
+            .expect("associated namespaces have an inode");
+        {
+            let mut state = self.state.lock().expect("router state poisoned");
+            let basis = state
+                .targets
+                .get(target_id)
+                .map(|target| target.basis.clone())
+                .expect("targets are recorded before being programmed");
+            state.programmed.insert(
+                inode,
+                ProgrammedRoutes {
+                    namespace: target_namespace.clone(),
+                    routes: Vec::new(),
+                    blocked: false,
+                    displaced,
+                    basis,
+                },
+            );
+        }
+
+        // Each route is recorded before it is added, so that it is removed on
+        // unrouting even if adding it was interrupted; a failed one is dropped again
+        for route in routes {
+            self.record_programmed(inode, |programmed| programmed.routes.push(route.clone()));
+            if let Err(e) = self.route_manager.add_route(target_namespace, &route).await {
+                self.record_programmed(inode, |programmed| {
+                    programmed.routes.retain(|programmed| *programmed != route)
+                });
+                return Err(e.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Update the routes recorded as programmed in a namespace
+    fn record_programmed(&self, inode: u64, update: impl FnOnce(&mut ProgrammedRoutes)) {
+        let mut state = self.state.lock().expect("router state poisoned");
+        if let Some(programmed) = state.programmed.get_mut(&inode) {
+            update(programmed);
+        }
+    }
+
+    /// Remove the routes of a target, if it was the last in its namespace, and
+    /// detach it from its warp's forwarding.
+    ///
+    /// The target's or the warp's namespace may be gone already, e.g. after they
+    /// stopped, so failures are only logged.
+    async fn unroute_target(&self, target_id: &str) {
+        let (target, programmed) = {
+            let mut state = self.state.lock().expect("router state poisoned");
+            let Some(target) = state.targets.remove(target_id) else {
+                return;
+            };
+            let released = match target.warp_id {
+                Some(_) => state.registry.release(&target.namespace),
+                None => true,
+            };
+            let last = released
+                && !state
+                    .targets
+                    .values()
+                    .any(|other| other.namespace.inode == target.namespace.inode);
+            let programmed = target
+                .namespace
+                .inode
+                .filter(|_| last)
+                .and_then(|inode| state.programmed.remove(&inode));
+            (target, programmed)
+        };
+
+        if let Some(programmed) = programmed {
+            for route in &programmed.routes {
+                let result = if programmed.blocked {
+                    self.route_manager
+                        .remove_blackhole_route(&programmed.namespace, &blackhole(route))
+                        .await
+                } else {
+                    self.route_manager
+                        .remove_route(&programmed.namespace, route)
+                        .await
+                };
+                if let Err(e) = result {
+                    warn!("Failed to remove route of target {}: {}", target_id, e);
+                }
+            }
+            for route in &programmed.displaced {
+                if let Err(e) = self
+                    .route_manager
+                    .add_route(&programmed.namespace, route)
+                    .await
+                {
+                    warn!("Failed to restore route of target {}: {}", target_id, e);
+                }
+            }
+        }
+
+        if let (Some(warp_id), Some(forwarding)) = (target.warp_id, &self.forwarding) {
+            if let Err(e) = forwarding
+                .lock()
+                .await
+                .detach_target(&warp_id, target_id)
+                .await
+            {
+                warn!(
+                    "Failed to detach target {} from forwarding of warp {}: {}",
+                    target_id, warp_id, e
+                );
+            }
+        }
+        debug!("Unrouted target {}", target_id);
+    }
+
+    /// Forget the forwarding of a warp set up in a namespace the warp no longer
+    /// has, e.g. after it restarted, so that it is set up again in the new one
+    async fn release_restarted_warp(&self, warp_namespace: &NetworkNamespace) {
+        let Some(ref forwarding) = self.forwarding else {
+            return;
+        };
+        let mut forwarding = forwarding.lock().await;
+        let warp_id = &warp_namespace.container_id;
+        if forwarding
+            .namespace_inode(warp_id)
+            .is_some_and(|inode| Some(inode) != warp_namespace.inode)
+        {
+            forwarding.release_warp(warp_id);
+            debug!("Released forwarding of restarted warp {}", warp_id);
+        }
+    }
+
+    /// Let go of a container that stopped or is gone: forwarding set up in it as
+    /// a warp, its netlink connection and its namespace handle, which would keep
+    /// the namespace alive
+    async fn release_container(&self, container_id: &str) {
+        if let Some(ref forwarding) = self.forwarding {
+            forwarding.lock().await.release_warp(container_id);
+        }
+        self.route_manager.release_container(container_id);
+        self.namespaces.release_container(container_id);
+        debug!("Released container {}", container_id);
+    }
+
+    async fn block_target(&self, target_id: &str) -> Result<(), HandlerError> {
+        let namespace = {
+            let state = self.state.lock().expect("router state poisoned");
+            state
+                .targets
+                .get(target_id)
+                .map(|target| target.namespace.clone())
+        };
+        // Whether warp routes are installed, which the blackholes take over from
+        let mut routed = true;
+        let namespace = match namespace {
+            Some(namespace) => namespace,
+            None => {
+                let namespace = self.namespace(target_id).await?;
+                let basis = RouteBasis::blocked();
+                let routes = self
+                    .desired_routes(&basis)
+                    .map_err(|e| failed(&format!("Invalid routes for target {}", target_id), e))?;
+                let inode = namespace.inode.ok_or_else(|| {
+                    failed(
+                        &format!("Cannot block target {}", target_id),
+                        "namespace inode is unknown",
+                    )
+                })?;
+
+                let mut state = self.state.lock().expect("router state poisoned");
+                if let Entry::Vacant(entry) = state.programmed.entry(inode) {
+                    routed = false;
+                    entry.insert(ProgrammedRoutes {
+                        namespace: namespace.clone(),
+                        routes: routes.clone(),
+                        blocked: false,
+                        displaced: Vec::new(),
+                        basis: basis.clone(),
+                    });
+                }
+                state.targets.insert(
+                    target_id.to_string(),
+                    RoutedTarget {
+                        namespace: namespace.clone(),
+                        warp_id: None,
+                        warp_inode: None,
+                        routes,
+                        basis,
+                        complete: false,
+                    },
+                );
+                namespace
+            }
+        };
+
+        let programmed = {
+            let mut state = self.state.lock().expect("router state poisoned");
+            namespace
+                .inode
+                .and_then(|inode| state.programmed.get_mut(&inode))
+                .map(|programmed| {
+                    let blocked = std::mem::replace(&mut programmed.blocked, true);
+                    (
+                        programmed.namespace.clone(),
+                        programmed.routes.clone(),
+                        blocked,
+                    )
+                })
+        };
+        let Some((namespace, routes, blocked)) = programmed else {
+            return Ok(());
+        };
+
+        // Blackholes replace existing routes in place, so blocking again is harmless
+        if !blocked {
+            let blackholes: Vec<RouteEntry> = routes.iter().map(blackhole).collect();
+            let displaced = self.displaced_routes(&namespace, &blackholes).await;
+            let mut state = self.state.lock().expect("router state poisoned");
+            if let Some(programmed) = namespace
+                .inode
+                .and_then(|inode| state.programmed.get_mut(&inode))
+            {
+                // Routes the warp routes replaced stay displaced too
+                for route in displaced {
+                    if !programmed.displaced.contains(&route) {
+                        programmed.displaced.push(route);
+                    }
+                }
+            }
+        }
+        for route in &routes {
+            self.route_manager
+                .add_blackhole_route(&namespace, &blackhole(route))
+                .await
+                .map_err(|e| failed(&format!("Failed to block target {}", target_id), e))?;
+        }
+
+        // Warp routes with another metric than the blackholes' were not replaced
+        if !blocked && routed {
+            for route in routes
+                .iter()
+                .filter(|route| route.metric != Some(BLACKHOLE_ROUTE_METRIC))
+            {
+                if let Err(e) = self.route_manager.remove_route(&namespace, route).await {
+                    warn!(
+                        "Failed to remove route of blocked target {}: {}",
+                        target_id, e
+                    );
+                }
+            }
+        }
+        info!("Blocked target {}", target_id);
+        Ok(())
+    }
+
+    /// Find the existing routes the given routes would replace: routes to the
+    /// same destinations with the same metric, through another gateway, such as
+    /// Docker's default route
+    async fn displaced_routes(
+        &self,
+        namespace: &NetworkNamespace,
+        routes: &[RouteEntry],
+    ) -> Vec<RouteEntry> {
+        match self.route_manager.list_routes(namespace).await {
+            Ok(existing) => existing
+                .into_iter()
+                .filter(|existing| {
+                    routes.iter().any(|route| {
+                        route.destination == existing.destination
+                            && route.metric.unwrap_or(DOCKER_ROUTE_METRIC)
+                                == existing.metric.unwrap_or(DOCKER_ROUTE_METRIC)
+                            && route.gateway != existing.gateway
+                    })
+                })
+                .collect(),
+            Err(e) => {
+                warn!(
+                    "Failed to list routes of container {}, the ones replaced won't be restored: {}",
+                    namespace.container_id, e
+                );
+                Vec::new()
+            }
+        }
+    }
+
+    async fn namespace(&self, container_id: &str) -> Result<NetworkNamespace, HandlerError> {
+        self.namespaces
+            .get_container_namespace(container_id)
+            .await
+            .map_err(|e| {
+                failed(
+                    &format!("No network namespace for container {}", container_id),
+                    e,
+                )
+            })
+    }
+}
+
+impl<D, R> TargetRouter for NetlinkTargetRouter<D, R>
+where
+    D: DockerClient + Send + Sync + 'static,
+    R: RouteManager + Send + Sync + 'static,
+{
+    fn route(
+        &self,
+        target: TargetContainerInfo,
+        warp: WarpContainerInfo,
+    ) -> Pin<Box<dyn Future<Output = Result<(), HandlerError>> + Send + '_>> {
+        Box::pin(self.route_target(target, warp))
+    }
+
+    fn unroute(
+        &self,
+        target_id: String,
+    ) -> Pin<Box<dyn Future<Output = Result<(), HandlerError>> + Send + '_>> {
+        Box::pin(async move {
+            self.unroute_target(&target_id).await;
+            Ok(())
+        })
+    }
+
+    fn block(
+        &self,
+        target_id: String,
+    ) -> Pin<Box<dyn Future<Output = Result<(), HandlerError>> + Send + '_>> {
+        Box::pin(async move { self.block_target(&target_id).await })
+    }
+
+    fn routed_through(&self, warp_id: &str) -> Vec<String> {
+        let state = self.state.lock().expect("router state poisoned");
+        let mut targets: Vec<String> = state
+            .targets
+            .iter()
+            .filter(|(_, target)| target.warp_id.as_deref() == Some(warp_id))
+            .map(|(id, _)| id.clone())
+            .collect();
+        targets.sort();
+        targets
+    }
+
+    fn release(
+        &self,
+        container_id: String,
+    ) -> Pin<Box<dyn Future<Output = Result<(), HandlerError>> + Send + '_>> {
+        Box::pin(async move {
+            self.release_container(&container_id).await;
+            Ok(())
+        })
+    }
+}
+
+impl<D, R> HostRouteUpdater for NetlinkTargetRouter<D, R>
+where
+    D: DockerClient + Send + Sync + 'static,
+    R: RouteManager + Send + Sync + 'static,
+{
+    /// Recalculate the routes of routed targets with the hostname's new addresses,
+    /// and add and remove the host routes, or blackholes of blocked targets, in the
+    /// namespaces they are programmed in.
+    ///
+    /// All namespaces are updated even if some fail; the first failure is returned.
+    async fn update_host_routes(
+        &self,
+        hostname: &str,
+        changes: &AddressChanges,
+    ) -> Result<(), RouteError> {
+        let mut result = Ok(());
+        let updates = {
+            let mut state = self.state.lock().expect("router state poisoned");
+            let state = &mut *state;
+            let addresses = state
+                .hostname_addresses
+                .entry(hostname.to_string())
+                .or_default();
+            for address in &changes.removed {
+                addresses.remove(address);
+            }
+            addresses.extend(&changes.added);
+
+            for (target_id, target) in state.targets.iter_mut() {
+                match self.calculate_routes(&target.basis, &state.hostname_addresses) {
+                    Ok(routes) => target.routes = routes,
+                    Err(e) => {
+                        warn!(
+                            "Failed to recalculate routes of target {} for {}: {}",
+                            target_id, hostname, e
+                        );
+                        result = result.and(Err(e));
+                    }
+                }
+            }
+            let mut updates = Vec::new();
+            for programmed in state.programmed.values_mut() {
+                let routes =
+                    match self.calculate_routes(&programmed.basis, &state.hostname_addresses) {
+                        Ok(routes) => routes,
+                        Err(e) => {
+                            warn!(
+                                "Failed to recalculate host routes of {} in container {}: {}",
+                                hostname, programmed.namespace.container_id, e
+                            );
+                            result = result.and(Err(e));
+                            continue;
+                        }
+                    };
+                let removed: Vec<RouteEntry> = programmed
+                    .routes
+                    .iter()
+                    .filter(|route| !routes.contains(route))
+                    .cloned()
+                    .collect();
+                let added: Vec<RouteEntry> = routes
+                    .iter()
+                    .filter(|route| !programmed.routes.contains(route))
+                    .cloned()
+                    .collect();
+                programmed.routes = routes;
+                if !removed.is_empty() || !added.is_empty() {
+                    updates.push((
+                        programmed.namespace.clone(),
+                        programmed.blocked,
+                        removed,
+                        added,
+                    ));
+                }
+            }
+            updates
+        };
+
+        for (namespace, blocked, removed, added) in updates {
+            for route in &removed {
+                let removal = if blocked {
+                    self.route_manager
+                        .remove_blackhole_route(&namespace, &blackhole(route))
+                        .await
+                } else {
+                    self.route_manager.remove_route(&namespace, route).await
+                };
+                if let Err(e) = removal {
+                    warn!(
+                        "Failed to remove host route of {} in container {}: {}",
+                        hostname, namespace.container_id, e
+                    );
+                    result = result.and(Err(e));
+                }
+            }
+            for route in &added {
+                let addition = if blocked {
+                    self.route_manager
+                        .add_blackhole_route(&namespace, &blackhole(route))
+                        .await
+                } else {
+                    self.route_manager.add_route(&namespace, route).await
+                };
+                if let Err(e) = addition {
+                    warn!(
+                        "Failed to add host route of {} in container {}: {}",
+                        hostname, namespace.container_id, e
+                    );
+                    result = result.and(Err(e));
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Get the blackhole standing in for a route while its target is blocked
+fn blackhole(route: &RouteEntry) -> RouteEntry {
+    RouteEntry {
+        metric: Some(BLACKHOLE_ROUTE_METRIC),
+        ..route.clone()
+    }
+}
+
+/// Check whether a CIDR rule's destination is an IPv4 network
+fn rule_is_ipv4(rule: &RoutingRule) -> Result<bool, RouteError> {
+    rule.destination
+        .trim()
+        .parse::<ExternalIpNetwork>()
+        .map(|network| network.is_ipv4())
+        .map_err(|e| RouteError::InvalidRoute(format!("{}: {}", rule.destination, e)))
+}
+
+fn failed(context: &str, e: impl Display) -> HandlerError {
+    HandlerError::ExecutionFailed(format!("{}: {}", context, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::docker::{ContainerInfo, ContainerState};
+    use crate::error::DockerError;
+    use crate::routing::IpNetwork;
+    use std::time::Duration;
+
+    struct MockDockerClient;
+
+    impl DockerClient for MockDockerClient {
+        async fn list_containers(&self, _all: bool) -> Result<Vec<ContainerInfo>, DockerError> {
+            Ok(vec![])
+        }
+
+        async fn inspect_container(&self, id: &str) -> Result<ContainerInfo, DockerError> {
+            Err(DockerError::ContainerNotFound {
+                container_id: id.to_string(),
+            })
+        }
+
+        async fn get_container_networks(&self, _id: &str) -> Result<Vec<NetworkInfo>, DockerError> {
+            Ok(vec![])
+        }
+    }
+
+    /// Route manager recording the routes it was asked to add and remove
+    #[derive(Default)]
+    struct RecordingRouteManager {
+        calls: Mutex<Vec<String>>,
+        /// Routes listed in every namespace
+        existing: Mutex<Vec<RouteEntry>>,
+        /// Destination whose next addition never completes, like a stuck netlink request
+        stall: Mutex<Option<IpNetwork>>,
+    }
+
+    impl RecordingRouteManager {
+        fn record(&self, operation: &str, route: &RouteEntry) {
+            let (addr, prefix) = (route.destination.addr(), route.destination.prefix());
+            self.calls.lock().unwrap().push(format!(
+                "{} {}/{} metric {:?}",
+                operation, addr, prefix, route.metric
+            ));
+        }
+
+        fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+    }
+
+    impl RouteManager for RecordingRouteManager {
+        async fn add_route(
+            &self,
+            _namespace: &NetworkNamespace,
+            route: &RouteEntry,
+        ) -> Result<(), RouteError> {
+            self.record("add", route);
+            let stalled = {
+                let mut stall = self.stall.lock().unwrap();
+                stall.take_if(|destination| *destination == route.destination)
+            };
+            if stalled.is_some() {
+                std::future::pending::<()>().await;
+            }
+            Ok(())
+        }
+
+        async fn remove_route(
+            &self,
+            _namespace: &NetworkNamespace,
+            route: &RouteEntry,
+        ) -> Result<(), RouteError> {
+            self.record("remove", route);
+            Ok(())
+        }
+
+        async fn list_routes(
+            &self,
+            _namespace: &NetworkNamespace,
+        ) -> Result<Vec<RouteEntry>, RouteError> {
+            Ok(self.existing.lock().unwrap().clone())
+        }
+
+        async fn add_blackhole_route(
+            &self,
+            _namespace: &NetworkNamespace,
+            route: &RouteEntry,
+        ) -> Result<(), RouteError> {
+            self.record("blackhole", route);
+            Ok(())
+        }
+
+        async fn remove_blackhole_route(
+            &self,
+            _namespace: &NetworkNamespace,
+            route: &RouteEntry,
+        ) -> Result<(), RouteError> {
+            self.record("unblackhole", route);
+            Ok(())
+        }
+
+        fn release_container(&self, container_id: &str) {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("release {}", container_id));
+        }
+    }
+
+    fn rules(destinations: &[&str]) -> Vec<RoutingRule> {
+        destinations
+            .iter()
+            .map(|destination| RoutingRule {
+                destination: destination.to_string(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn router(destinations: &[&str]) -> NetlinkTargetRouter<MockDockerClient> {
+        let namespaces = Arc::new(NamespaceManager::new(MockDockerClient));
+        let route_manager = Arc::new(RtNetlinkRouteManager::new(Arc::clone(&namespaces)));
+        NetlinkTargetRouter::new(namespaces, route_manager, rules(destinations))
+    }
+
+    fn recording_router(
+        destinations: &[&str],
+    ) -> (
+        NetlinkTargetRouter<MockDockerClient, RecordingRouteManager>,
+        Arc<RecordingRouteManager>,
+    ) {
+        let namespaces = Arc::new(NamespaceManager::new(MockDockerClient));
+        let route_manager = Arc::new(RecordingRouteManager::default());
+        let router =
+            NetlinkTargetRouter::new(namespaces, Arc::clone(&route_manager), rules(destinations));
+        (router, route_manager)
+    }
+
+    /// Try routing a target as `route_target` does once namespaces and gateway are known
+    async fn try_route_in(
+        router: &NetlinkTargetRouter<MockDockerClient, RecordingRouteManager>,
+        target_namespace: &NetworkNamespace,
+        warp_namespace: &NetworkNamespace,
+        gateway: &str,
+    ) -> Result<bool, String> {
+        let basis = RouteBasis::through(
+            gateway.parse().unwrap(),
+            &target(vec![]),
+            &warp(vec![], None),
+        );
+        let routes = router.desired_routes(&basis).unwrap();
+        router
+            .route_through(
+                &target_namespace.container_id,
+                target_namespace,
+                warp_namespace,
+                "172.20.0.5/16".parse().unwrap(),
+                routes,
+                basis,
+            )
+            .await
+    }
+
+    /// Route a target as `route_target` does once namespaces and gateway are known
+    async fn route_in(
+        router: &NetlinkTargetRouter<MockDockerClient, RecordingRouteManager>,
+        target_namespace: &NetworkNamespace,
+        warp_namespace: &NetworkNamespace,
+        gateway: &str,
+    ) {
+        try_route_in(router, target_namespace, warp_namespace, gateway)
+            .await
+            .unwrap();
+    }
+
+    fn namespace(container_id: &str, inode: u64) -> NetworkNamespace {
+        NetworkNamespace {
+            path: format!("/proc/{}/ns/net", inode),
+            container_id: container_id.to_string(),
+            inode: Some(inode),
+            handle: None,
+        }
+    }
+
+    fn network(name: &str, ip: &str, subnet: &str) -> NetworkInfo {
+        NetworkInfo {
+            name: name.to_string(),
+            ip_address: ip.parse().unwrap(),
+            gateway: None,
+            subnet: subnet.parse().unwrap(),
+        }
+    }
+
+    fn container(id: &str, networks: Vec<NetworkInfo>) -> ContainerInfo {
+        ContainerInfo {
+            id: id.to_string(),
+            name: id.to_string(),
+            labels: HashMap::new(),
+            networks,
+            state: ContainerState::Running,
+            pid: Some(1234),
+            network_mode: None,
+            sandbox_key: None,
+            started_at: None,
+        }
+    }
+
+    fn target(networks: Vec<NetworkInfo>) -> TargetContainerInfo {
+        TargetContainerInfo {
+            container: container("target", networks),
+            warp_target: "warp".to_string(),
+            route_metric: None,
+        }
+    }
+
+    fn warp(networks: Vec<NetworkInfo>, target_network: Option<&str>) -> WarpContainerInfo {
+        WarpContainerInfo {
+            container: container("warp", networks),
+            target_network: target_network.map(str::to_string),
+            route_attributes: RouteAttributes::default(),
+            selector: None,
+        }
+    }
+
+    #[test]
+    fn test_shared_network_picks_warp_address() {
+        let router = router(&["0.0.0.0/0"]);
+        let target = target(vec![
+            network("backend", "172.20.0.5", "172.20.0.5/16"),
+            network("frontend", "172.21.0.5", "172.21.0.5/16"),
+        ]);
+        let warp = warp(
+            vec![
+                network("frontend", "172.21.0.2", "172.21.0.2/16"),
+                network("backend", "172.20.0.2", "172.20.0.2/16"),
+                network("vpn", "10.8.0.2", "10.8.0.2/24"),
+            ],
+            Some("backend"),
+        );
+
+        let (gateway, subnet) = router.shared_network(&target, &warp).unwrap();
+        assert_eq!(gateway, "172.20.0.2".parse::<IpAddr>().unwrap());
+        assert_eq!(
+            subnet,
+            "172.20.0.5/16".parse::<ExternalIpNetwork>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_shared_network_requires_common_network() {
+        let router = router(&["0.0.0.0/0"]);
+        let unshared = warp(vec![network("vpn", "10.8.0.2", "10.8.0.2/24")], None);
+        assert!(router
+            .shared_network(
+                &target(vec![network("backend", "172.20.0.5", "172.20.0.5/16")]),
+                &unshared
+            )
+            .is_err());
+
+        // Several shared networks need a preference
+        let ambiguous = warp(
+            vec![
+                network("backend", "172.20.0.2", "172.20.0.2/16"),
+                network("vpn", "10.8.0.2", "10.8.0.2/24"),
+            ],
+            None,
+        );
+        assert!(router
+            .shared_network(
+                &target(vec![
+                    network("backend", "172.20.0.5", "172.20.0.5/16"),
+                    network("vpn", "10.8.0.5", "10.8.0.5/24"),
+                ]),
+                &ambiguous
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_desired_routes_skip_other_family_and_hostnames() {
+        let router = router(&["0.0.0.0/0", "::/0", "example.com", "10.0.0.0/8"]);
+        let mut target = target(vec![]);
+        target.route_metric = Some(42);
+        let warp = warp(vec![], None);
+
+        let routes = router
+            .desired_routes(&RouteBasis::through(
+                "172.20.0.2".parse().unwrap(),
+                &target,
+                &warp,
+            ))
+            .unwrap();
+        let destinations: Vec<IpNetwork> = routes.iter().map(|r| r.destination.clone()).collect();
+        assert_eq!(
+            destinations,
+            vec![
+                IpNetwork::new_v4(Ipv4Addr::UNSPECIFIED, 0),
+                IpNetwork::new_v4(Ipv4Addr::new(10, 0, 0, 0), 8),
+            ]
+        );
+        assert!(routes.iter().all(|r| r.metric == Some(42)));
+        assert!(routes
+            .iter()
+            .all(|r| r.gateway == "172.20.0.2".parse::<IpAddr>().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_configured_metrics_reach_route_manager() {
+        let config = AppConfig {
+            route_precedence: "override".to_string(),
+            route_metric_default: 0,
+            route_metric_network: 120,
+            routing_rules: rules(&["0.0.0.0/0", "10.0.0.0/8"]),
+            ..Default::default()
+        };
+        config.validate().unwrap();
+
+        let (router, routes) = recording_router(&["0.0.0.0/0", "10.0.0.0/8"]);
+        let router =
+            router.with_metric_policy(config.route_metrics(), config.route_precedence().unwrap());
+        route_in(
+            &router,
+            &namespace("target", 10),
+            &namespace("warp", 20),
+            "172.20.0.2",
+        )
+        .await;
+
+        assert_eq!(
+            routes.calls(),
+            vec![
+                "add 0.0.0.0/0 metric Some(0)",
+                "add 10.0.0.0/8 metric Some(120)"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_blackholes_take_precedence_over_docker_routes() {
+        let (router, routes) = recording_router(&["0.0.0.0/0", "10.0.0.0/8"]);
+        let docker_default = RouteEntry {
+            destination: IpNetwork::new_v4(Ipv4Addr::UNSPECIFIED, 0),
+            gateway: "172.20.0.1".parse().unwrap(),
+            interface: Some("eth0".to_string()),
+            metric: None,
+            attributes: RouteAttributes::default(),
+        };
+        *routes.existing.lock().unwrap() = vec![docker_default];
+        route_in(
+            &router,
+            &namespace("target", 10),
+            &namespace("warp", 20),
+            "172.20.0.2",
+        )
+        .await;
+
+        // Blackholes with Docker's metric replace its default route, and the warp
+        // routes they don't replace are removed
+        router.block("target".to_string()).await.unwrap();
+        router.block("target".to_string()).await.unwrap();
+        assert_eq!(
+            routes.calls()[2..],
+            [
+                "blackhole 0.0.0.0/0 metric Some(0)",
+                "blackhole 10.0.0.0/8 metric Some(0)",
+                "remove 0.0.0.0/0 metric Some(200)",
+                "remove 10.0.0.0/8 metric Some(100)",
+                "blackhole 0.0.0.0/0 metric Some(0)",
+                "blackhole 10.0.0.0/8 metric Some(0)",
+            ]
+        );
+
+        // Unblocking puts Docker's default route back
+        router.unroute("target".to_string()).await.unwrap();
+        assert_eq!(
+            routes.calls()[8..],
+            [
+                "unblackhole 0.0.0.0/0 metric Some(0)",
+                "unblackhole 10.0.0.0/8 metric Some(0)",
+                "add 0.0.0.0/0 metric None",
+            ]
+        );
+        assert!(router.state.lock().unwrap().programmed.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_overriding_routes_restore_docker_routes() {
+        let (router, routes) = recording_router(&["0.0.0.0/0"]);
+        let router = router.with_metric_policy(
+            RouteMetrics {
+                default_route: DOCKER_ROUTE_METRIC,
+                ..Default::default()
+            },
+            RoutePrecedence::Override,
+        );
+        let docker_default = RouteEntry {
+            destination: IpNetwork::new_v4(Ipv4Addr::UNSPECIFIED, 0),
+            gateway: "172.20.0.1".parse().unwrap(),
+            interface: Some("eth0".to_string()),
+            metric: None,
+            attributes: RouteAttributes::default(),
+        };
+        *routes.existing.lock().unwrap() = vec![docker_default.clone()];
+
+        // The warp route replaces Docker's default route, which comes back once unrouted
+        route_in(
+            &router,
+            &namespace("target", 10),
+            &namespace("warp", 20),
+            "172.20.0.2",
+        )
+        .await;
+        assert_eq!(
+            router.state.lock().unwrap().programmed[&10].displaced,
+            vec![docker_default]
+        );
+        router.unroute("target".to_string()).await.unwrap();
+        assert_eq!(
+            routes.calls(),
+            vec![
+                "add 0.0.0.0/0 metric Some(0)",
+                "remove 0.0.0.0/0 metric Some(0)",
+                "add 0.0.0.0/0 metric None",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_routing_is_undone_by_next_attempt() {
+        let (router, routes) = recording_router(&["0.0.0.0/0", "10.0.0.0/8"]);
+        let target_namespace = namespace("target", 10);
+        let warp_namespace = namespace("warp", 20);
+        *routes.stall.lock().unwrap() = Some(IpNetwork::new_v4(Ipv4Addr::new(10, 0, 0, 0), 8));
+
+        // The fan-out's timeout drops the attempt while the second route is being added
+        let attempt = try_route_in(&router, &target_namespace, &warp_namespace, "172.20.0.2");
+        assert!(tokio::time::timeout(Duration::from_millis(20), attempt)
+            .await
+            .is_err());
+
+        // The next reconcile removes what was added and routes the target
+        assert!(
+            try_route_in(&router, &target_namespace, &warp_namespace, "172.20.0.2")
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            routes.calls(),
+            vec![
+                "add 0.0.0.0/0 metric Some(200)",
+                "add 10.0.0.0/8 metric Some(100)",
+                "remove 0.0.0.0/0 metric Some(200)",
+                "remove 10.0.0.0/8 metric Some(100)",
+                "add 0.0.0.0/0 metric Some(200)",
+                "add 10.0.0.0/8 metric Some(100)",
+            ]
+        );
+        assert!(
+            !try_route_in(&router, &target_namespace, &warp_namespace, "172.20.0.2")
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shared_namespace_keeps_routes_of_first_target() {
+        let (router, routes) = recording_router(&["0.0.0.0/0"]);
+        let shared = namespace("web", 10);
+        let warp_namespace = namespace("warp", 20);
+        route_in(&router, &shared, &warp_namespace, "172.20.0.2").await;
+
+        // A sidecar in the same namespace asking for another metric is routed by
+        // the routes already there, and the difference is reported
+        let mut sidecar = target(vec![]);
+        sidecar.route_metric = Some(42);
+        let basis =
+            RouteBasis::through("172.20.0.2".parse().unwrap(), &sidecar, &warp(vec![], None));
+        let sidecar_routes = router.desired_routes(&basis).unwrap();
+        let sidecar_namespace = NetworkNamespace {
+            container_id: "sidecar".to_string(),
+            ..shared.clone()
+        };
+        assert!(router.differs_from_programmed(&sidecar_namespace, &sidecar_routes));
+        router
+            .route_through(
+                "sidecar",
+                &sidecar_namespace,
+                &warp_namespace,
+                "172.20.0.5/16".parse().unwrap(),
+                sidecar_routes,
+                basis,
+            )
+            .await
+            .unwrap();
+        assert_eq!(routes.calls(), vec!["add 0.0.0.0/0 metric Some(200)"]);
+
+        // Matching routes aren't reported
+        let same = router.state.lock().unwrap().targets["web"].routes.clone();
+        assert!(!router.differs_from_programmed(&sidecar_namespace, &same));
+    }
+
+    #[tokio::test]
+    async fn test_restarted_warp_is_not_routed() {
+        let (router, _) = recording_router(&["0.0.0.0/0"]);
+        let target_namespace = namespace("target", 10);
+        route_in(
+            &router,
+            &target_namespace,
+            &namespace("warp", 20),
+            "172.20.0.2",
+        )
+        .await;
+        let routes = router.state.lock().unwrap().targets["target"]
+            .routes
+            .clone();
+
+        assert!(router.is_routed("target", &target_namespace, &namespace("warp", 20), &routes));
+        // Restarted with the same address, the warp has a new namespace to set up
+        assert!(!router.is_routed("target", &target_namespace, &namespace("warp", 21), &routes));
+    }
+
+    /// Docker client reporting containers running in this process's namespace
+    struct OwnNamespaceDockerClient;
+
+    impl DockerClient for OwnNamespaceDockerClient {
+        async fn list_containers(&self, _all: bool) -> Result<Vec<ContainerInfo>, DockerError> {
+            Ok(vec![])
+        }
+
+        async fn inspect_container(&self, id: &str) -> Result<ContainerInfo, DockerError> {
+            let mut container = container(id, vec![]);
+            container.pid = Some(std::process::id() as i64);
+            Ok(container)
+        }
+
+        async fn get_container_networks(&self, _id: &str) -> Result<Vec<NetworkInfo>, DockerError> {
+            Ok(vec![])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_release_closes_namespace_handle_and_connection() {
+        let namespaces = Arc::new(
+            NamespaceManager::new(OwnNamespaceDockerClient).with_allow_host_namespace(true),
+        );
+        let route_manager = Arc::new(RecordingRouteManager::default());
+        let router = NetlinkTargetRouter::new(
+            Arc::clone(&namespaces),
+            Arc::clone(&route_manager),
+            rules(&["0.0.0.0/0"]),
+        );
+        router.namespace("app").await.unwrap();
+        router.namespace("other").await.unwrap();
+
+        router.release("app".to_string()).await.unwrap();
+        // Only the released container's handle was closed
+        assert!(!namespaces.release_container("app"));
+        assert!(namespaces.release_container("other"));
+        assert_eq!(route_manager.calls(), vec!["release app"]);
+    }
+
+    #[tokio::test]
+    async fn test_hostname_addresses_update_programmed_routes() {
+        let (router, routes) = recording_router(&["10.0.0.0/8", "api.example.com"]);
+        let warp_namespace = namespace("warp", 20);
+        route_in(
+            &router,
+            &namespace("web", 10),
+            &warp_namespace,
+            "172.20.0.2",
+        )
+        .await;
+        route_in(&router, &namespace("db", 11), &warp_namespace, "172.20.0.2").await;
+        router.block("db".to_string()).await.unwrap();
+        routes.calls.lock().unwrap().clear();
+
+        // Addresses of the gateway's family are routed, or blackholed while blocked
+        let changes = AddressChanges {
+            added: vec![
+                "203.0.113.1".parse().unwrap(),
+                "2001:db8::1".parse().unwrap(),
+            ],
+            removed: vec![],
+        };
+        router
+            .update_host_routes("api.example.com", &changes)
+            .await
+            .unwrap();
+        let mut calls = routes.calls();
+        calls.sort();
+        assert_eq!(
+            calls,
+            vec![
+                "add 203.0.113.1/32 metric Some(50)",
+                "blackhole 203.0.113.1/32 metric Some(0)",
+            ]
+        );
+
+        // Routed targets stay routed with the new routes
+        let basis = RouteBasis::through(
+            "172.20.0.2".parse().unwrap(),
+            &target(vec![]),
+            &warp(vec![], None),
+        );
+        let desired = router.desired_routes(&basis).unwrap();
+        assert_eq!(desired.len(), 2);
+        assert!(router.is_routed("web", &namespace("web", 10), &warp_namespace, &desired));
+
+        routes.calls.lock().unwrap().clear();
+        let changes = AddressChanges {
+            added: vec!["203.0.113.2".parse().unwrap()],
+            removed: vec!["203.0.113.1".parse().unwrap()],
+        };
+        router
+            .update_host_routes("api.example.com", &changes)
+            .await
+            .unwrap();
+        let mut calls = routes.calls();
+        calls.sort();
+        assert_eq!(
+            calls,
+            vec![
+                "add 203.0.113.2/32 metric Some(50)",
+                "blackhole 203.0.113.2/32 metric Some(0)",
+                "remove 203.0.113.1/32 metric Some(50)",
+                "unblackhole 203.0.113.1/32 metric Some(0)",
+            ]
+        );
+
+        // Unrouting removes the host routes too
+        routes.calls.lock().unwrap().clear();
+        router.unroute("web".to_string()).await.unwrap();
+        assert_eq!(
+            routes.calls(),
+            vec![
+                "remove 10.0.0.0/8 metric Some(100)",
+                "remove 203.0.113.2/32 metric Some(50)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_blocked_routes_cover_both_families() {
+        let router = router(&["0.0.0.0/0", "::/0", "example.com"]);
+        let routes = router.desired_routes(&RouteBasis::blocked()).unwrap();
+
+        assert_eq!(routes.len(), 2);
+        assert_eq!(
+            routes[0].destination,
+            IpNetwork::new_v4(Ipv4Addr::UNSPECIFIED, 0)
+        );
+        assert_eq!(
+            routes[1].destination,
+            IpNetwork::new_v6(Ipv6Addr::UNSPECIFIED, 0)
+        );
+        assert!(routes.iter().all(|r| r.metric.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_unroute_unknown_target_is_noop() {
+        let router = router(&["0.0.0.0/0"]);
+        router.unroute("missing".to_string()).await.unwrap();
+        assert!(router.state.lock().unwrap().targets.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_route_fails_without_namespace() {
+        let router = router(&["0.0.0.0/0"]);
+        let shared = network("backend", "172.20.0.5", "172.20.0.5/16");
+        let result = router
+            .route(target(vec![shared.clone()]), warp(vec![shared], None))
+            .await;
+
+        assert!(matches!(result, Err(HandlerError::ExecutionFailed(_))));
+        let state = router.state.lock().unwrap();
+        assert!(state.targets.is_empty());
+        assert!(state.programmed.is_empty());
+    }
+}
@@ -1,7 +1,11 @@
 //! Routing rule calculation and validation
 
+use crate::config::RoutingRule;
 use crate::error::RouteError;
-use crate::routing::{IpNetwork, RouteEntry};
+use crate::routing::{
+    validate_metric, IpNetwork, RouteAttributes, RouteEntry, RouteMetrics, RoutePrecedence,
+    MIN_ROUTE_MTU_V6,
+};
 use ipnetwork::IpNetwork as ExternalIpNetwork;
 use std::collections::HashMap;
 use std::net::IpAddr;
@@ -10,21 +14,113 @@ use std::net::IpAddr;
 pub struct RoutingRuleCalculator {
     /// Track routes by container ID for cleanup purposes
     container_routes: HashMap<String, Vec<RouteEntry>>,
+    metrics: RouteMetrics,
+    precedence: RoutePrecedence,
 }
 
 impl RoutingRuleCalculator {
     pub fn new() -> Self {
         Self {
             container_routes: HashMap::new(),
+            metrics: RouteMetrics::default(),
+            precedence: RoutePrecedence::default(),
         }
     }
 
+    /// Set the global route metrics and the precedence policy they are validated against
+    pub fn with_metric_policy(
+        mut self,
+        metrics: RouteMetrics,
+        precedence: RoutePrecedence,
+    ) -> Self {
+        self.metrics = metrics;
+        self.precedence = precedence;
+        self
+    }
+
     /// Calculate routes from target container to warp container
     pub fn calculate_routes(
         &self,
         destination_cidr: &str,
         gateway_ip: IpAddr,
         interface: Option<String>,
+    ) -> Result<Vec<RouteEntry>, RouteError> {
+        self.calculate_routes_with_attributes(
+            destination_cidr,
+            gateway_ip,
+            interface,
+            None,
+            RouteAttributes::default(),
+        )
+    }
+
+    /// Calculate routes for a configured routing rule.
+    ///
+    /// Route attributes set through warp container labels take precedence over
+    /// the ones configured on the rule. The metric is taken from the target
+    /// container's label, then the rule, then the global metrics.
+    pub fn calculate_rule_routes(
+        &self,
+        rule: &RoutingRule,
+        gateway_ip: IpAddr,
+        interface: Option<String>,
+        target_metric: Option<u32>,
+        warp_attributes: &RouteAttributes,
+    ) -> Result<Vec<RouteEntry>, RouteError> {
+        let attributes = warp_attributes.clone().or(&rule.route_attributes()?);
+        self.calculate_routes_with_attributes(
+            &rule.destination,
+            gateway_ip,
+            interface,
+            target_metric.or(rule.metric),
+            attributes,
+        )
+    }
+
+    /// Calculate host routes for the resolved addresses of a hostname rule.
+    ///
+    /// Addresses of a different IP family than the gateway are skipped. Metric
+    /// and attributes follow the same precedence as [`Self::calculate_rule_routes`].
+    pub fn calculate_hostname_routes(
+        &self,
+        rule: &RoutingRule,
+        addresses: &[IpAddr],
+        gateway_ip: IpAddr,
+        interface: Option<String>,
+        target_metric: Option<u32>,
+        warp_attributes: &RouteAttributes,
+    ) -> Result<Vec<RouteEntry>, RouteError> {
+        let attributes = warp_attributes.clone().or(&rule.route_attributes()?);
+        let mut routes = Vec::new();
+
+        for address in addresses {
+            let prefix = match (address, gateway_ip) {
+                (IpAddr::V4(_), IpAddr::V4(_)) => 32,
+                (IpAddr::V6(_), IpAddr::V6(_)) => 128,
+                _ => continue,
+            };
+            routes.extend(self.calculate_routes_with_attributes(
+                &format!("{}/{}", address, prefix),
+                gateway_ip,
+                interface.clone(),
+                target_metric.or(rule.metric),
+                attributes.clone(),
+            )?);
+        }
+
+        Ok(routes)
+    }
+
+    /// Calculate routes from target container to warp container with route attributes.
+    ///
+    /// Without an explicit metric, the global metric for the kind of destination is used.
+    pub fn calculate_routes_with_attributes(
+        &self,
+        destination_cidr: &str,
+        gateway_ip: IpAddr,
+        interface: Option<String>,
+        metric: Option<u32>,
+        attributes: RouteAttributes,
     ) -> Result<Vec<RouteEntry>, RouteError> {
         let network = destination_cidr
             .parse::<ExternalIpNetwork>()
@@ -41,11 +137,13 @@ impl RoutingRuleCalculator {
             },
         };
 
+        let metric = metric.unwrap_or_else(|| self.metrics.for_destination(&ip_network));
         let route = RouteEntry {
             destination: ip_network,
             gateway: gateway_ip,
             interface,
-            metric: Some(100), // Default metric
+            metric: Some(metric),
+            attributes,
         };
 
         // Validate the route before returning
@@ -130,11 +228,28 @@ impl RoutingRuleCalculator {
             }
         }
 
-        // Validate metric
+        // Validate metric against the route precedence policy
         if let Some(metric) = route.metric {
-            if metric == 0 {
+            validate_metric(self.precedence, metric, route.destination.prefix() == 0)?;
+        }
+
+        // Validate route attributes
+        let attributes = &route.attributes;
+        attributes.validate()?;
+
+        if let (IpNetwork::V6 { .. }, Some(mtu)) = (&route.destination, attributes.mtu) {
+            if mtu < MIN_ROUTE_MTU_V6 {
+                return Err(RouteError::InvalidRoute(format!(
+                    "IPv6 route MTU {} is below the minimum of {}",
+                    mtu, MIN_ROUTE_MTU_V6
+                )));
+            }
+        }
+
+        if let Some(prefsrc) = attributes.prefsrc {
+            if prefsrc.is_ipv4() != route.gateway.is_ipv4() {
                 return Err(RouteError::InvalidRoute(
-                    "Route metric cannot be zero".to_string(),
+                    "IP version mismatch between gateway and preferred source".to_string(),
                 ));
             }
         }
@@ -189,7 +304,8 @@ impl RoutingRuleCalculator {
             destination,
             gateway: gateway_ip,
             interface: None,
-            metric: Some(200), // Lower priority than specific routes
+            metric: Some(self.metrics.default_route),
+            attributes: RouteAttributes::default(),
         };
 
         self.validate_route(&route)?;
@@ -217,7 +333,8 @@ impl RoutingRuleCalculator {
             destination,
             gateway: gateway_ip,
             interface: None,
-            metric: Some(50), // Higher priority than network routes
+            metric: Some(self.metrics.host),
+            attributes: RouteAttributes::default(),
         };
 
         self.validate_route(&route)?;
@@ -234,6 +351,7 @@ impl Default for RoutingRuleCalculator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::routing::{RouteProtocol, RouteScope};
     use std::net::{Ipv4Addr, Ipv6Addr};
 
     #[test]
@@ -287,6 +405,7 @@ mod tests {
             gateway: IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
             interface: None,
             metric: None,
+            attributes: RouteAttributes::default(),
         };
 
         let result = calculator.validate_route(&route);
@@ -308,6 +427,7 @@ mod tests {
             gateway: IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)),
             interface: None,
             metric: None,
+            attributes: RouteAttributes::default(),
         };
 
         let result = calculator.validate_route(&route);
@@ -392,6 +512,267 @@ mod tests {
         assert_eq!(route.metric, Some(50));
     }
 
+    #[test]
+    fn test_calculate_rule_routes_with_attributes() {
+        let calculator = RoutingRuleCalculator::new();
+        let gateway = IpAddr::V4(Ipv4Addr::new(172, 17, 0, 2));
+        let rule = RoutingRule {
+            destination: "0.0.0.0/0".to_string(),
+            mtu: Some(1500),
+            advmss: Some(1360),
+            scope: Some("universe".to_string()),
+            route_protocol: Some("boot".to_string()),
+            ..Default::default()
+        };
+
+        // Warp label values take precedence over rule values
+        let warp_attributes = RouteAttributes {
+            mtu: Some(1400),
+            onlink: Some(true),
+            ..Default::default()
+        };
+
+        let routes = calculator
+            .calculate_rule_routes(&rule, gateway, None, None, &warp_attributes)
+            .unwrap();
+
+        assert_eq!(routes.len(), 1);
+        let attributes = &routes[0].attributes;
+        assert_eq!(attributes.mtu, Some(1400));
+        assert_eq!(attributes.advmss, Some(1360));
+        assert_eq!(attributes.scope, Some(RouteScope::Universe));
+        assert_eq!(attributes.onlink, Some(true));
+        assert_eq!(attributes.protocol, Some(RouteProtocol::Boot));
+        assert_eq!(attributes.prefsrc, None);
+    }
+
+    #[test]
+    fn test_warp_label_disables_rule_onlink() {
+        let calculator = RoutingRuleCalculator::new();
+        let gateway = IpAddr::V4(Ipv4Addr::new(172, 17, 0, 2));
+        let rule = RoutingRule {
+            destination: "10.0.0.0/8".to_string(),
+            onlink: Some(true),
+            ..Default::default()
+        };
+
+        let mut warp_attributes = RouteAttributes::default();
+        warp_attributes.set("onlink", "false").unwrap();
+        let routes = calculator
+            .calculate_rule_routes(&rule, gateway, None, None, &warp_attributes)
+            .unwrap();
+        assert_eq!(routes[0].attributes.onlink, Some(false));
+
+        // Without a label the rule's value applies
+        let routes = calculator
+            .calculate_rule_routes(&rule, gateway, None, None, &RouteAttributes::default())
+            .unwrap();
+        assert_eq!(routes[0].attributes.onlink, Some(true));
+    }
+
+    #[test]
+    fn test_calculate_routes_with_metric_policy() {
+        let gateway = IpAddr::V4(Ipv4Addr::new(172, 17, 0, 2));
+        let metrics = RouteMetrics {
+            network: 10,
+            default_route: 0,
+            host: 5,
+        };
+        let calculator =
+            RoutingRuleCalculator::new().with_metric_policy(metrics, RoutePrecedence::Override);
+
+        // Global metrics by kind of destination
+        let routes = calculator
+            .calculate_routes("0.0.0.0/0", gateway, None)
+            .unwrap();
+        assert_eq!(routes[0].metric, Some(0));
+        let routes = calculator
+            .calculate_routes("10.0.0.0/8", gateway, None)
+            .unwrap();
+        assert_eq!(routes[0].metric, Some(10));
+        let routes = calculator
+            .calculate_routes("10.1.2.3/32", gateway, None)
+            .unwrap();
+        assert_eq!(routes[0].metric, Some(5));
+
+        // Target label metric takes precedence over the rule metric
+        let rule = RoutingRule {
+            destination: "10.0.0.0/8".to_string(),
+            metric: Some(20),
+            ..Default::default()
+        };
+        let defaults = RouteAttributes::default();
+        let routes = calculator
+            .calculate_rule_routes(&rule, gateway, None, None, &defaults)
+            .unwrap();
+        assert_eq!(routes[0].metric, Some(20));
+        let routes = calculator
+            .calculate_rule_routes(&rule, gateway, None, Some(30), &defaults)
+            .unwrap();
+        assert_eq!(routes[0].metric, Some(30));
+
+        // Overriding Docker's default route requires metric 0
+        let rule = RoutingRule {
+            destination: "0.0.0.0/0".to_string(),
+            ..Default::default()
+        };
+        let result = calculator.calculate_rule_routes(&rule, gateway, None, Some(100), &defaults);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Default route metric"));
+    }
+
+    #[test]
+    fn test_calculate_hostname_routes() {
+        let calculator = RoutingRuleCalculator::new();
+        let gateway = IpAddr::V4(Ipv4Addr::new(172, 17, 0, 2));
+        let rule = RoutingRule {
+            destination: "api.example.com".to_string(),
+            mtu: Some(1400),
+            ..Default::default()
+        };
+        let addresses = [
+            IpAddr::V4(Ipv4Addr::new(203, 0, 113, 10)),
+            IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 10)),
+            IpAddr::V4(Ipv4Addr::new(203, 0, 113, 11)),
+        ];
+
+        let routes = calculator
+            .calculate_hostname_routes(
+                &rule,
+                &addresses,
+                gateway,
+                Some("eth0".to_string()),
+                None,
+                &RouteAttributes::default(),
+            )
+            .unwrap();
+
+        // The IPv6 address is skipped for an IPv4 gateway
+        assert_eq!(routes.len(), 2);
+        assert_eq!(
+            routes[0].destination,
+            IpNetwork::new_v4(Ipv4Addr::new(203, 0, 113, 10), 32)
+        );
+        assert_eq!(
+            routes[1].destination,
+            IpNetwork::new_v4(Ipv4Addr::new(203, 0, 113, 11), 32)
+        );
+        assert_eq!(routes[0].metric, Some(50));
+        assert_eq!(routes[0].interface, Some("eth0".to_string()));
+        assert_eq!(routes[0].attributes.mtu, Some(1400));
+
+        let routes = calculator
+            .calculate_hostname_routes(
+                &rule,
+                &addresses,
+                gateway,
+                None,
+                Some(30),
+                &RouteAttributes::default(),
+            )
+            .unwrap();
+        assert_eq!(routes[1].metric, Some(30));
+    }
+
+    #[test]
+    fn test_validate_route_zero_metric() {
+        let calculator = RoutingRuleCalculator::new();
+        let gateway = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1));
+
+        // Metric 0 collides with Docker's routes under the default precedence
+        let result = calculator.calculate_routes_with_attributes(
+            "10.0.0.0/8",
+            gateway,
+            None,
+            Some(0),
+            RouteAttributes::default(),
+        );
+        assert!(result.unwrap_err().to_string().contains("used by Docker"));
+
+        let calculator = RoutingRuleCalculator::new()
+            .with_metric_policy(RouteMetrics::default(), RoutePrecedence::Override);
+        let result = calculator.calculate_routes_with_attributes(
+            "10.0.0.0/8",
+            gateway,
+            None,
+            Some(0),
+            RouteAttributes::default(),
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_validate_route_attributes() {
+        let calculator = RoutingRuleCalculator::new();
+        let gateway = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1));
+
+        let result = calculator.calculate_routes_with_attributes(
+            "10.0.0.0/8",
+            gateway,
+            None,
+            None,
+            RouteAttributes {
+                prefsrc: Some(IpAddr::V6(Ipv6Addr::LOCALHOST)),
+                ..Default::default()
+            },
+        );
+        assert!(result.unwrap_err().to_string().contains("preferred source"));
+
+        let result = calculator.calculate_routes_with_attributes(
+            "10.0.0.0/8",
+            gateway,
+            None,
+            None,
+            RouteAttributes {
+                mtu: Some(20),
+                ..Default::default()
+            },
+        );
+        assert!(result.is_err());
+
+        let gateway_v6 = IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1));
+        let result = calculator.calculate_routes_with_attributes(
+            "2001:db8::/32",
+            gateway_v6,
+            None,
+            None,
+            RouteAttributes {
+                mtu: Some(1200),
+                ..Default::default()
+            },
+        );
+        assert!(result.unwrap_err().to_string().contains("IPv6 route MTU"));
+    }
+
+    #[test]
+    fn test_route_attributes_parsing() {
+        let mut attributes = RouteAttributes::default();
+        attributes.set("mtu", "1280").unwrap();
+        attributes.set("advmss", "1240").unwrap();
+        attributes.set("scope", "link").unwrap();
+        attributes.set("onlink", "").unwrap();
+        attributes.set("src", "10.0.0.2").unwrap();
+        attributes.set("proto", "42").unwrap();
+
+        assert_eq!(attributes.mtu, Some(1280));
+        assert_eq!(attributes.advmss, Some(1240));
+        assert_eq!(attributes.scope, Some(RouteScope::Link));
+        assert_eq!(attributes.onlink, Some(true));
+        assert_eq!(
+            attributes.prefsrc,
+            Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)))
+        );
+        assert_eq!(attributes.protocol, Some(RouteProtocol::Other(42)));
+
+        assert!(attributes.set("mtu", "large").is_err());
+        assert!(attributes.set("scope", "galaxy").is_err());
+        assert!(attributes.set("protocol", "bgp4").is_err());
+        assert!(attributes.set("onlink", "maybe").is_err());
+        assert!(attributes.set("color", "blue").is_err());
+    }
+
     #[test]
     fn test_routes_conflict_detection() {
         let calculator = RoutingRuleCalculator::new();
@@ -406,6 +787,7 @@ mod tests {
             gateway: gateway1,
             interface: None,
             metric: None,
+            attributes: RouteAttributes::default(),
         };
 
         let route2 = RouteEntry {
@@ -416,6 +798,7 @@ mod tests {
             gateway: gateway2,
             interface: None,
             metric: None,
+            attributes: RouteAttributes::default(),
         };
 
         let routes = vec![route1, route2];